@@ -0,0 +1,70 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Compare gathering the fixed-nonce CTR columns one by one with `ColumnBuffer`
+//!
+//! Uses the ciphertexts of challenge 20, run with `cargo bench --bench columns`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use cryptopals::attacks::ColumnBuffer;
+use cryptopals::cipher::{Aes128Ctr, StreamCipher};
+use cryptopals::crypto::HexString;
+use cryptopals::helper;
+
+const CHALLENGE20_FILE: &str = "https://cryptopals.com/static/challenge-data/20.txt";
+
+fn challenge20_ciphertexts() -> Vec<Vec<u8>> {
+    let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+    helper::read_from_url(CHALLENGE20_FILE).unwrap()
+        .lines()
+        .map(|line| ctr.apply_keystream(&line.base64_decode().unwrap()))
+        .collect()
+}
+
+/// One pass over all the ciphertexts per column
+fn gather_columns(ciphertexts: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let len = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+    (0..len)
+        .map(|i| ciphertexts.iter().filter_map(|ciphertext| ciphertext.get(i).copied()).collect())
+        .collect()
+}
+
+/// Sum of the bytes of every column, the cheapest use of the columns
+fn checksum<'a>(columns: impl Iterator<Item = &'a [u8]>) -> u64 {
+    columns.map(|column| column.iter().map(|&byte| u64::from(byte)).sum::<u64>()).sum()
+}
+
+fn bench_transpose(c: &mut Criterion) {
+    let ciphertexts = challenge20_ciphertexts();
+    let mut buffer = ColumnBuffer::new();
+
+    let mut group = c.benchmark_group("fixed-nonce columns");
+    group.bench_function("gather per column", |b| {
+        b.iter(|| checksum(gather_columns(black_box(&ciphertexts)).iter().map(Vec::as_slice)))
+    });
+    group.bench_function("column buffer", |b| {
+        b.iter(|| checksum(ColumnBuffer::from_rows(black_box(&ciphertexts)).columns()))
+    });
+    group.bench_function("reused column buffer", |b| {
+        b.iter(|| {
+            buffer.fill(black_box(&ciphertexts));
+            checksum(buffer.columns())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_transpose);
+criterion_main!(benches);
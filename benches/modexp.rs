@@ -0,0 +1,61 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Compare the naive square and multiply modexp with the Montgomery one
+//!
+//! Run with `cargo bench --bench modexp`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use cryptopals::math::{self, Montgomery, MontgomeryU128};
+use cryptopals::params;
+use num_bigint::BigUint;
+
+fn bench_u128(c: &mut Criterion) {
+    let modulus: u128 = (1 << 127) - 1;
+    let montgomery = MontgomeryU128::new(modulus);
+    let (base, exp) = (0x1234_5678_9abc_def0_u128, modulus - 2);
+
+    let mut group = c.benchmark_group("modexp u128");
+    group.bench_function("square and multiply", |b| {
+        b.iter(|| math::modexp_u128(black_box(base), black_box(exp), modulus))
+    });
+    group.bench_function("montgomery", |b| {
+        b.iter(|| montgomery.modexp(black_box(base), black_box(exp)))
+    });
+    group.finish();
+}
+
+fn bench_biguint(c: &mut Criterion) {
+    let modulus = params::dh::nist_1536();
+    let montgomery = Montgomery::new(&modulus);
+    let base = BigUint::from(2u32);
+    let exp = &modulus - 2u32;
+
+    let mut group = c.benchmark_group("modexp 1536 bits");
+    group.sample_size(20);
+    group.bench_function("square and multiply", |b| {
+        b.iter(|| math::modexp(black_box(&base), black_box(&exp), &modulus))
+    });
+    group.bench_function("montgomery", |b| {
+        b.iter(|| montgomery.modexp(black_box(&base), black_box(&exp)))
+    });
+    group.bench_function("num-bigint modpow", |b| {
+        b.iter(|| black_box(&base).modpow(black_box(&exp), &modulus))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_u128, bench_biguint);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Compare the crate's XOR and Hamming distance with byte by byte loops
+//!
+//! Uses the ciphertext of challenge 6. Run with `cargo bench --bench xor`, and
+//! with `--features simd` to measure the SSE2 and AVX2 paths.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use cryptopals::crypto::{BytesCrypto, HexString};
+use cryptopals::helper;
+
+const CHALLENGE6_FILE: &str = "https://cryptopals.com/static/challenge-data/6.txt";
+
+fn scalar_hamming(a: &[u8], b: &[u8]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// Normalized Hamming distance of every pair of adjacent blocks, for every key size of challenge 6
+fn keysize_scan<F: Fn(&[u8], &[u8]) -> u32>(ciphertext: &[u8], hamming: F) -> Vec<f64> {
+    (2..42)
+        .map(|keysize| {
+            let blocks: Vec<&[u8]> = ciphertext.chunks_exact(keysize).collect();
+            let total: u32 = blocks.windows(2).map(|pair| hamming(pair[0], pair[1])).sum();
+            total as f64 / ((blocks.len() - 1) * keysize) as f64
+        })
+        .collect()
+}
+
+fn bench_xor(c: &mut Criterion) {
+    let ciphertext = helper::read_from_url(CHALLENGE6_FILE).unwrap().base64_decode().unwrap();
+    let key: Vec<u8> = ciphertext.iter().rev().copied().collect();
+
+    let mut group = c.benchmark_group("challenge 6 keysize scan");
+    group.bench_function("byte by byte", |b| {
+        b.iter(|| keysize_scan(black_box(&ciphertext), scalar_hamming))
+    });
+    group.bench_function("hamming_distance", |b| {
+        b.iter(|| keysize_scan(black_box(&ciphertext), |x, y| x.hamming_distance(y).unwrap()))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("xor challenge 6");
+    group.bench_function("byte by byte", |b| {
+        b.iter(|| black_box(&ciphertext).iter().zip(&key).map(|(x, y)| x ^ y).collect::<Vec<u8>>())
+    });
+    group.bench_function("xor", |b| {
+        b.iter(|| black_box(&ciphertext).xor(&key))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_xor);
+criterion_main!(benches);
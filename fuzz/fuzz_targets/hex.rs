@@ -0,0 +1,34 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Hexadecimal decoding never panics, and round-trips what it accepts.
+
+#![no_main]
+
+use cryptopals::crypto::{BytesCrypto, HexString};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = text.hex2string();
+        let _ = text.hex2bytes_lenient();
+        if let Ok(bytes) = text.hex2bytes() {
+            assert_eq!(bytes.bytes2hex(), text.to_ascii_lowercase());
+        }
+    }
+    if !data.is_empty() {
+        assert_eq!(data.bytes2hex().hex2bytes().unwrap(), data);
+    }
+});
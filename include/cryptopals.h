@@ -0,0 +1,56 @@
+/*
+ * Copyright 2020 Farzad FARID <farzy@farzy.org>
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * C interface of the cryptopals library, built with the `ffi` feature.
+ *
+ * The functions write to buffers owned by the caller and return the number
+ * of bytes written, or one of the negative errors below, as an intptr_t
+ * like the isize of Rust. Text is written without a trailing NUL.
+ */
+
+#ifndef CRYPTOPALS_H
+#define CRYPTOPALS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Null pointer, malformed hex or Base64, or nothing to decrypt */
+#define CRYPTOPALS_ERROR_INVALID (-1)
+/* The output buffer is too small */
+#define CRYPTOPALS_ERROR_BUFFER (-2)
+
+/* Lowercase hex, 2 * input_len bytes */
+intptr_t cryptopals_hex_encode(const uint8_t *input, size_t input_len, uint8_t *output, size_t output_len);
+/* Bytes of a hex text, input_len / 2 bytes */
+intptr_t cryptopals_hex_decode(const uint8_t *input, size_t input_len, uint8_t *output, size_t output_len);
+/* Padded standard Base64, 4 * ceil(input_len / 3) bytes */
+intptr_t cryptopals_base64_encode(const uint8_t *input, size_t input_len, uint8_t *output, size_t output_len);
+/* Bytes of a Base64 text, at most 3 * input_len / 4 bytes */
+intptr_t cryptopals_base64_decode(const uint8_t *input, size_t input_len, uint8_t *output, size_t output_len);
+/* Key and plaintext of a single-byte XOR ciphertext, input_len bytes */
+intptr_t cryptopals_break_single_byte_xor(const uint8_t *input, size_t input_len, uint8_t *key,
+                                          uint8_t *output, size_t output_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CRYPTOPALS_H */
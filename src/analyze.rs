@@ -0,0 +1,273 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standalone analysis and maintenance commands
+
+use cryptopals::{crypto, english, keygen, keysize, outln, scoring, selftest};
+use cryptopals::bias::KeystreamBias;
+use cryptopals::cache::Cache;
+use cryptopals::cipher::{Aes128Ctr, StreamCipher};
+use cryptopals::config::Config;
+use cryptopals::crypto::HexString;
+use cryptopals::output::Context;
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+const KEYSIZE_RANGE: Range<usize> = 2..42;
+const TABLE_ROWS: usize = 10;
+/// Length of the repeated sequences of the Kasiski examination
+const KASISKI_LEN: usize = 3;
+const BAR_WIDTH: usize = 40;
+const BIAS_KEYSTREAM_LEN: usize = 32;
+const SAMPLE_SIZES: [usize; 5] = [1_000, 4_000, 16_000, 64_000, 256_000];
+const SAMPLE_RUNS: u32 = 20;
+/// Single-byte XOR ciphertext of challenge 3
+const SINGLE_XOR_CIPHERTEXT: &str = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
+
+/// Compare the rankings of every key size estimator on a ciphertext file
+///
+/// The file is Base64 decoded if possible, like the challenge data files,
+/// and used as raw bytes otherwise.
+pub fn keysize(ctx: &Context, path: &str) -> Result<(), Box<dyn Error>> {
+    ctx.section(&format!("Key size analysis of {}", path));
+
+    let raw = fs::read(path)?;
+    let ciphertext = match String::from_utf8(raw.clone()).map(|text| text.trim().base64_decode()) {
+        Ok(Ok(bytes)) => {
+            outln!(ctx, "Read {} bytes of Base64 encoded ciphertext", bytes.len());
+            bytes
+        }
+        _ => {
+            outln!(ctx, "Read {} bytes of raw ciphertext", raw.len());
+            raw
+        }
+    };
+
+    let estimators = keysize::estimators();
+    let rankings: Vec<Vec<(usize, f64)>> = estimators.iter()
+        .map(|estimator| estimator.rank(&ciphertext, KEYSIZE_RANGE))
+        .collect();
+
+    // Rankings side by side
+    ctx.subsection("Rankings, best first");
+    let width = estimators.iter().map(|e| e.name().len()).max().unwrap_or(0).max(14);
+    let mut header = String::from("rank");
+    let mut rule = String::from("----");
+    for estimator in &estimators {
+        header += &format!(" | {:width$}", estimator.name(), width = width);
+        rule += &format!("-+-{}", "-".repeat(width));
+    }
+    outln!(ctx, "{}", header);
+    outln!(ctx, "{}", rule);
+    for row in 0..TABLE_ROWS {
+        let mut line = format!("{:4}", row + 1);
+        for ranking in &rankings {
+            let cell = ranking.get(row)
+                .map(|(k, s)| format!("{:3} ({:.3})", k, s))
+                .unwrap_or_default();
+            line += &format!(" | {:width$}", cell, width = width);
+        }
+        outln!(ctx, "{}", line);
+    }
+
+    // Score of every key size for each estimator, a shorter bar is better
+    for (estimator, ranking) in estimators.iter().zip(&rankings) {
+        ctx.subsection(&format!("Scores of {}", estimator.name()));
+        let max = ranking.iter().map(|(_, s)| *s).fold(0.0, f64::max);
+        let mut by_keysize = ranking.clone();
+        by_keysize.sort_by_key(|(k, _)| *k);
+        for (k, s) in by_keysize {
+            let len = if max > 0.0 { (s / max * BAR_WIDTH as f64).round() as usize } else { 0 };
+            let rank = ranking.iter().position(|(rk, _)| *rk == k).unwrap() + 1;
+            outln!(ctx, "{:3} | {:bar_width$} {:.3}{}",
+                     k, "#".repeat(len), s,
+                     if rank <= 3 { format!(" <- #{}", rank) } else { String::new() },
+                     bar_width = BAR_WIDTH);
+        }
+    }
+
+    ctx.subsection("Kasiski examination");
+    let proposals = keysize::kasiski(&ciphertext, KASISKI_LEN, KEYSIZE_RANGE);
+    if proposals.is_empty() {
+        outln!(ctx, "No sequence of {} bytes repeated three times", KASISKI_LEN);
+    }
+    for (k, votes) in proposals.iter().take(TABLE_ROWS) {
+        outln!(ctx, "{:3} | {} sequences", k, votes);
+    }
+
+    // Summary
+    ctx.subsection("Best key size per estimator");
+    for (estimator, ranking) in estimators.iter().zip(&rankings) {
+        match ranking.first() {
+            Some((k, s)) => outln!(ctx, "{}: {} ({:.3})", estimator.name(), k, s),
+            None => outln!(ctx, "{}: ciphertext too short", estimator.name()),
+        }
+    }
+    let bests: Vec<usize> = rankings.iter().filter_map(|r| r.first().map(|(k, _)| *k)).collect();
+    if bests.windows(2).all(|w| w[0] == w[1]) {
+        outln!(ctx, "\nAll estimators agree.");
+    } else {
+        outln!(ctx, "\nEstimators disagree, compare the rankings above.");
+    }
+
+    Ok(())
+}
+
+/// Show the keystream byte biases of AES-CTR under random keys
+///
+/// A good stream cipher shows no position whose deviation stands out,
+/// which makes it a baseline for the biased ciphers.
+pub fn bias(ctx: &Context, samples: usize) -> Result<(), Box<dyn Error>> {
+    ctx.section(&format!("Keystream bias of AES-CTR over {} samples", samples));
+
+    let stats = KeystreamBias::collect(|| {
+        let mut rng = rand::thread_rng();
+        Aes128Ctr::new(&keygen::aes_key(&mut rng), keygen::ctr_nonce(&mut rng)).unwrap().keystream(BIAS_KEYSTREAM_LEN)
+    }, samples, BIAS_KEYSTREAM_LEN);
+
+    outln!(ctx, "{}", stats.to_string().trim_end());
+
+    Ok(())
+}
+
+/// Print the n-gram model of the English corpus as JSON
+///
+/// Nothing else is printed, so that the output can be redirected to a file.
+pub fn export_model(n: usize) -> Result<(), Box<dyn Error>> {
+    println!("{}", english::get_english_model(n)?.export_json()?);
+
+    Ok(())
+}
+
+/// Measure the accuracy lost by sampling the English corpus
+///
+/// For each sample size, the sampled frequencies are compared to those of the
+/// whole corpus, and used to break the single-byte XOR of challenge 3.
+pub fn corpus_sample(ctx: &Context) -> Result<(), Box<dyn Error>> {
+    ctx.section("Corpus sampling");
+
+    let corpus = english::get_gutenberg_corpus(english::GUTENBERG_CORPUS_URL)?;
+    let start = Instant::now();
+    let full = english::calc_frequencies(&corpus);
+    outln!(ctx, "Whole corpus: {} bytes in {:.1?}", corpus.len(), start.elapsed());
+
+    let ciphertext = SINGLE_XOR_CIPHERTEXT.hex2bytes()?;
+    let (_, expected_key, _, _) = crypto::decrypt_english(&ciphertext, &full);
+
+    ctx.subsection(&format!("Average of {} samples per size", SAMPLE_RUNS));
+    outln!(ctx, "{:>7} | {:>10} | {:>8} | challenge 3 key", "sample", "time", "distance");
+    let mut rng = rand::thread_rng();
+    for &size in &SAMPLE_SIZES {
+        let mut elapsed = Duration::default();
+        let mut distance = 0.0;
+        let mut found = 0;
+        for _ in 0..SAMPLE_RUNS {
+            let start = Instant::now();
+            let sampled = english::sample_frequencies(&corpus, size, &mut rng);
+            elapsed += start.elapsed();
+            distance += english::euclidean_distance(&full, &sampled);
+            if crypto::decrypt_english(&ciphertext, &sampled).1 == expected_key {
+                found += 1;
+            }
+        }
+        outln!(ctx, "{:7} | {:10.1?} | {:8.5} | {}/{}",
+                 size, elapsed / SAMPLE_RUNS, distance / SAMPLE_RUNS as f64, found, SAMPLE_RUNS);
+    }
+
+    Ok(())
+}
+
+/// Show what the cache of challenge inputs holds
+pub fn cache_stats(ctx: &Context) -> Result<(), Box<dyn Error>> {
+    let cache = Cache::default();
+    ctx.section(&format!("Cache {}", cache.dir().display()));
+
+    let stats = cache.stats()?;
+    outln!(ctx, "URLs:    {}", stats.urls);
+    outln!(ctx, "Entries: {} ({} bytes)", stats.entries, stats.bytes);
+    outln!(ctx, "Orphans: {}", stats.orphans);
+    outln!(ctx, "Missing: {}", stats.missing);
+
+    ctx.subsection("Index");
+    for (url, entry) in cache.index()? {
+        outln!(ctx, "{} {:8} {}", &entry.digest[..16], entry.size, url);
+    }
+
+    Ok(())
+}
+
+/// Empty the cache of challenge inputs
+pub fn cache_clean(ctx: &Context) -> Result<(), Box<dyn Error>> {
+    let cache = Cache::default();
+    let removed = cache.clean()?;
+    outln!(ctx, "Removed {} entries from {}", removed, cache.dir().display());
+
+    Ok(())
+}
+
+/// Calibrate the English scorers and save their ensemble weights
+///
+/// Every scorer, n-grams included, runs over the bundled English and
+/// non-English samples, the suggested weights go to the configuration file
+/// read by `decrypt_english`.
+pub fn calibrate(ctx: &Context) -> Result<(), Box<dyn Error>> {
+    ctx.section("English scorer calibration");
+
+    let corpus_freq = english::get_english_frequency(&english::CorpusSource::default())?;
+    let ngrams = scoring::Ngrams::english()?;
+    let samples = scoring::labeled_samples();
+    let english_count = samples.iter().filter(|(_, english)| *english).count();
+    outln!(ctx, "Samples: {} English, {} other", english_count, samples.len() - english_count);
+
+    let calibration = scoring::calibrate(&corpus_freq, Some(&ngrams), &samples);
+    outln!(ctx, "{:>10} | {:>10} | {:>9} | {:>6} | weight", "scorer", "threshold", "precision", "recall");
+    for report in &calibration.scorers {
+        outln!(ctx, "{:>10} | {:10.4} | {:9.3} | {:6.3} | {:.4}", report.name, report.threshold,
+                 report.precision, report.recall, calibration.weights[&report.name]);
+    }
+    let ensemble = &calibration.ensemble;
+    outln!(ctx, "{:>10} | {:10.4} | {:9.3} | {:6.3} |", ensemble.name, ensemble.threshold,
+             ensemble.precision, ensemble.recall);
+
+    let mut config = Config::load()?;
+    config.scoring_weights = calibration.weights;
+    config.save()?;
+    outln!(ctx, "\nWeights saved to {}", Config::path().display());
+
+    Ok(())
+}
+
+/// Run the self-test and print its pass/fail matrix
+pub fn self_test(ctx: &Context) -> Result<(), Box<dyn Error>> {
+    ctx.section("Self-test");
+
+    let reports = selftest::run_all();
+    outln!(ctx, "{:>8} | {:<18} | result | time", "area", "check");
+    for report in &reports {
+        let result = if report.passed() { "pass" } else { "FAIL" };
+        outln!(ctx, "{:>8} | {:<18} | {:<6} | {:.1?}", report.area, report.name, result, report.elapsed);
+    }
+    let failures: Vec<_> = reports.iter().filter(|report| !report.passed()).collect();
+    for report in &failures {
+        outln!(ctx, "{} / {}: {}", report.area, report.name, report.failure.as_deref().unwrap_or_default());
+    }
+    if !failures.is_empty() {
+        return Err(format!("{} of {} checks failed", failures.len(), reports.len()).into());
+    }
+    outln!(ctx, "\nAll {} checks passed", reports.len());
+
+    Ok(())
+}
@@ -0,0 +1,556 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Statistical breaks of XOR ciphers, and their key refinement
+//!
+//! Breaking a repeating-key XOR or a fixed-nonce CTR column by column often
+//! gets a key byte or two wrong, when a column is too short for its frequency
+//! score to be meaningful. Scoring the whole plaintext fixes these bytes.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+use crate::{blocks, crypto, english};
+use crate::crypto::BytesCrypto;
+use crate::keysize::{AverageBlocksHamming, FirstBlocksHamming, KeysizeEstimator};
+use crate::scoring::{Scorer, Words};
+
+/// Passes of `refine_key` over the whole key, at most
+const MAX_REFINE_PASSES: usize = 4;
+
+/// Weight of the common words in the refined repeating-key XOR score, only
+/// enough to decide between keys whose plaintexts have the same frequencies
+const WORDS_TIE_BREAK: f64 = 1e-9;
+
+/// Best key sizes of each Hamming estimator tried by `break_repeating_key_xor`
+const KEYSIZE_CANDIDATES: usize = 3;
+
+/// Score a plaintext against an English frequency table, lower is better
+///
+/// Plaintexts which are not valid UTF-8 get an infinite score.
+pub fn english_score(corpus_freq: &[f64], plaintext: &[u8]) -> f64 {
+    match std::str::from_utf8(plaintext) {
+        Ok(text) => english::euclidean_distance(corpus_freq, &english::calc_frequencies(text)),
+        Err(_) => f64::INFINITY,
+    }
+}
+
+/// Improve a key by trying every value of each of its bytes
+///
+/// `cipher` decrypts with a candidate key and `scorer` rates the plaintext,
+/// lower is better. A byte is changed when the change improves the score, and
+/// passes over the key are repeated until nothing improves.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::attacks;
+///
+/// let ciphertext = [b'a' ^ 1, b'b' ^ 2];
+/// let cipher = |key: &[u8]| vec![ciphertext[0] ^ key[0], ciphertext[1] ^ key[1]];
+/// // Distance to "ab"
+/// let scorer = |plaintext: &[u8]| plaintext.iter().zip(b"ab").filter(|(a, b)| a != b).count() as f64;
+///
+/// assert_eq!(vec![1, 2], attacks::refine_key(cipher, &[1, 7], scorer));
+/// ```
+pub fn refine_key<C, S>(cipher: C, key: &[u8], scorer: S) -> Vec<u8>
+    where C: Fn(&[u8]) -> Vec<u8>, S: Fn(&[u8]) -> f64
+{
+    let mut key = key.to_vec();
+    let mut best_score = scorer(&cipher(&key));
+    for _ in 0..MAX_REFINE_PASSES {
+        let mut improved = false;
+        for position in 0..key.len() {
+            let original = key[position];
+            let mut best_byte = original;
+            for byte in 0..=255u8 {
+                if byte == original {
+                    continue;
+                }
+                key[position] = byte;
+                let score = scorer(&cipher(&key));
+                if score < best_score {
+                    best_score = score;
+                    best_byte = byte;
+                }
+            }
+            key[position] = best_byte;
+            if best_byte != original {
+                debug!("Key byte {} refined from {:#04x} to {:#04x}", position, original, best_byte);
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    key
+}
+
+/// XOR a text with a repeating key
+pub fn repeating_xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.xor_repeating(key)
+}
+
+/// Bytes of rows of unequal lengths stored column after column
+///
+/// Column i holds the byte i of every row long enough, in row order. All the
+/// columns share one contiguous buffer, filled in a single pass over the rows,
+/// instead of one gather loop over all the rows per column.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::attacks::ColumnBuffer;
+///
+/// let columns = ColumnBuffer::from_rows(&[b"abc".to_vec(), b"d".to_vec(), b"ef".to_vec()]);
+/// assert_eq!(3, columns.len());
+/// assert_eq!(b"ade", columns.column(0));
+/// assert_eq!(b"c", columns.column(2));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ColumnBuffer {
+    bytes: Vec<u8>,
+    /// Start of every column in `bytes`, followed by the end of the last one
+    starts: Vec<usize>,
+}
+
+impl ColumnBuffer {
+    /// Empty buffer, see `fill`
+    pub fn new() -> Self {
+        ColumnBuffer::default()
+    }
+
+    /// Transpose rows into a new buffer
+    pub fn from_rows<T: AsRef<[u8]>>(rows: &[T]) -> Self {
+        let mut columns = ColumnBuffer::new();
+        columns.fill(rows);
+        columns
+    }
+
+    /// Replace the content with the transposed rows, reusing the allocations
+    pub fn fill<T: AsRef<[u8]>>(&mut self, rows: &[T]) {
+        let len = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+        // Column i has one byte per row longer than i
+        self.starts.clear();
+        self.starts.resize(len + 1, 0);
+        for row in rows {
+            for count in &mut self.starts[1..=row.as_ref().len()] {
+                *count += 1;
+            }
+        }
+        for i in 1..=len {
+            self.starts[i] += self.starts[i - 1];
+        }
+        self.bytes.clear();
+        self.bytes.resize(self.starts[len], 0);
+        // Next free position of every column
+        let mut next = self.starts[..len].to_vec();
+        for row in rows {
+            for (position, &byte) in next.iter_mut().zip(row.as_ref()) {
+                self.bytes[*position] = byte;
+                *position += 1;
+            }
+        }
+    }
+
+    /// Number of columns, the length of the longest row
+    pub fn len(&self) -> usize {
+        self.starts.len().saturating_sub(1)
+    }
+
+    /// No rows, or only empty ones
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes of column `index`
+    pub fn column(&self, index: usize) -> &[u8] {
+        &self.bytes[self.starts[index]..self.starts[index + 1]]
+    }
+
+    /// All the columns in order
+    pub fn columns(&self) -> impl Iterator<Item = &[u8]> + Clone {
+        self.starts.windows(2).map(move |bounds| &self.bytes[bounds[0]..bounds[1]])
+    }
+}
+
+/// Break every column of bytes as a single-byte XOR
+fn break_columns<'a>(columns: impl Iterator<Item = &'a [u8]>, corpus_freq: &[f64]) -> Vec<u8> {
+    columns
+        .map(|column| crypto::decrypt_english(column, corpus_freq).1)
+        .collect()
+}
+
+/// Find the key of a repeating-key XOR ciphertext, knowing its size
+///
+/// Byte i of the key is the single-byte XOR key of the ciphertext bytes at
+/// positions i, i + keysize, ... With `refine`, the key is then improved by
+/// `refine_key` on the whole plaintext, common words breaking the ties.
+pub fn break_repeating_xor(ciphertext: &[u8], keysize: usize, corpus_freq: &[f64], refine: bool) -> Vec<u8> {
+    let columns = blocks::transpose(ciphertext, keysize);
+    let key = break_columns(columns.iter().map(Vec::as_slice), corpus_freq);
+    if !refine {
+        return key;
+    }
+    let scorer = english_scorer(corpus_freq);
+    refine_key(|key| repeating_xor(ciphertext, key), &key, |plaintext| -scorer(plaintext))
+}
+
+/// Opposite of `english_score`, with common words breaking the ties
+///
+/// This is the scorer of challenge 6 for `break_repeating_key_xor`, higher is
+/// more English.
+pub fn english_scorer(corpus_freq: &[f64]) -> impl Fn(&[u8]) -> f64 + '_ {
+    move |plaintext| WORDS_TIE_BREAK * Words.score(plaintext).max(0.0) - english_score(corpus_freq, plaintext)
+}
+
+/// Break a repeating-key XOR ciphertext, returning the key and the plaintext
+///
+/// The key sizes tried are the best ones of the Hamming distance estimators
+/// in `keysizes`, or all of them when the ciphertext is too short for the
+/// estimators. For each key size, every column is broken as a single-byte XOR
+/// and the key is refined on the whole plaintext. The plaintext `scorer` rates
+/// best wins, and a key found with a multiple of its size is shortened to its
+/// period.
+///
+/// `scorer` rates both the columns and the whole plaintexts, so it should be
+/// based on character frequencies, like `english_scorer`.
+///
+/// Fixed-nonce CTR ciphertexts truncated to the shortest one and concatenated
+/// are a repeating-key XOR whose key size is the length of the shortest one.
+pub fn break_repeating_key_xor<S: Scorer + ?Sized>(ciphertext: &[u8], keysizes: Range<usize>, scorer: &S)
+    -> (Vec<u8>, Vec<u8>)
+{
+    let keysizes = keysizes.start.max(1)..keysizes.end;
+    let estimators: [&dyn KeysizeEstimator; 2] = [&FirstBlocksHamming, &AverageBlocksHamming { blocks: 4 }];
+    let mut candidates: BTreeSet<usize> = estimators.iter()
+        .flat_map(|estimator| estimator.rank(ciphertext, keysizes.clone()).into_iter().take(KEYSIZE_CANDIDATES))
+        .map(|(keysize, _)| keysize)
+        .collect();
+    if candidates.is_empty() {
+        candidates.extend(keysizes);
+    }
+    debug!("Trying key sizes {:?}", candidates);
+
+    let mut best = (Vec::new(), ciphertext.to_vec(), f64::NEG_INFINITY);
+    for keysize in candidates {
+        let columns = blocks::transpose(ciphertext, keysize);
+        let key: Vec<u8> = columns.iter()
+            .map(|column| crypto::decrypt_text(column, scorer).1)
+            .collect();
+        let key = refine_key(|key| repeating_xor(ciphertext, key), &key,
+                             |plaintext| -scorer.score(plaintext));
+        let plaintext = repeating_xor(ciphertext, &key);
+        let score = scorer.score(&plaintext);
+        debug!("Key size {} scores {}", keysize, score);
+        if score > best.2 {
+            best = (key, plaintext, score);
+        }
+    }
+    let period = key_period(&best.0);
+    best.0.truncate(period);
+    (best.0, best.1)
+}
+
+/// Length of the shortest prefix a key repeats
+fn key_period(key: &[u8]) -> usize {
+    (1..key.len())
+        .find(|&period| key.len().is_multiple_of(period) && key[period..] == key[..key.len() - period])
+        .unwrap_or(key.len())
+}
+
+/// Find the keystream shared by CTR ciphertexts encrypted with the same key and nonce
+///
+/// Keystream byte i is the single-byte XOR key of the byte i of all the
+/// ciphertexts long enough. With `refine`, the keystream is then improved by
+/// `refine_key` on all the plaintexts together.
+pub fn break_fixed_nonce(ciphertexts: &[Vec<u8>], corpus_freq: &[f64], refine: bool) -> Vec<u8> {
+    let columns = ColumnBuffer::from_rows(ciphertexts);
+    let keystream = break_columns(columns.columns(), corpus_freq);
+    if !refine {
+        return keystream;
+    }
+    let decrypt_all = |keystream: &[u8]| {
+        let mut plaintexts = Vec::new();
+        for ciphertext in ciphertexts {
+            plaintexts.extend(ciphertext.iter().zip(keystream).map(|(a, b)| a ^ b));
+            plaintexts.push(b'\n');
+        }
+        plaintexts
+    };
+    refine_key(decrypt_all, &keystream, |plaintexts| english_score(corpus_freq, plaintexts))
+}
+
+/// Plaintext recovered statistically, with the confidence of every byte
+///
+/// A confidence is between 0, when another key byte scores as well, and 1,
+/// when the key byte stands out from all the others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Partial {
+    pub plaintext: Vec<u8>,
+    pub confidence: Vec<f64>,
+}
+
+impl Partial {
+    /// Plaintext with the bytes less confident than `threshold` shown as `?`
+    ///
+    /// Other bytes outside of printable ASCII are shown as `.`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::attacks::Partial;
+    ///
+    /// let partial = Partial { plaintext: b"Hi!\x01".to_vec(), confidence: vec![0.9, 0.1, 0.5, 0.9] };
+    /// assert_eq!("H?!.", partial.render(0.5));
+    /// ```
+    pub fn render(&self, threshold: f64) -> String {
+        self.plaintext.iter().zip(&self.confidence)
+            .map(|(&byte, &confidence)| match byte {
+                _ if confidence < threshold => '?',
+                b' '..=b'~' | b'\n' => byte as char,
+                _ => '.',
+            })
+            .collect()
+    }
+
+    /// Share of the bytes at least as confident as `threshold`
+    pub fn coverage(&self, threshold: f64) -> f64 {
+        if self.confidence.is_empty() {
+            return 0.0;
+        }
+        self.confidence.iter().filter(|&&confidence| confidence >= threshold).count() as f64
+            / self.confidence.len() as f64
+    }
+}
+
+/// Add the plaintext bytes of a column, XORed with `key`, to ASCII counts
+///
+/// Letters are counted in uppercase, like `english::calc_byte_frequencies`.
+fn count_column(counts: &mut [i64; 128], column: &[u8], key: u8, sign: i64) {
+    for &byte in column {
+        let byte = byte ^ key;
+        if byte.is_ascii() {
+            counts[byte.to_ascii_uppercase() as usize] += sign;
+        }
+    }
+}
+
+/// Distance of ASCII counts to the corpus, see `english_score`
+fn counts_score(corpus_freq: &[f64], counts: &[i64; 128]) -> f64 {
+    let total: i64 = counts.iter().sum();
+    let frequencies: Vec<f64> = counts.iter()
+        .map(|&count| if total == 0 { 0.0 } else { count as f64 / total as f64 })
+        .collect();
+    english::euclidean_distance(corpus_freq, &frequencies)
+}
+
+/// Confidence of every byte of a key, scored on the whole plaintext
+///
+/// Column i holds the ciphertext bytes XORed with key byte i. Every value of
+/// a key byte is tried with the rest of the plaintext unchanged, so a byte
+/// fixed by `refine_key` is judged the way it was chosen. The confidence is
+/// the margin of the key byte over the runner-up value, relative to the
+/// margin over the median value: 0 when another value scores as well.
+fn key_confidence<'a, I>(columns: I, key: &[u8], corpus_freq: &[f64]) -> Vec<f64>
+    where I: Iterator<Item = &'a [u8]> + Clone
+{
+    let mut counts = [0i64; 128];
+    for (column, &byte) in columns.clone().zip(key) {
+        count_column(&mut counts, column, byte, 1);
+    }
+    columns.zip(key)
+        .map(|(column, &byte)| {
+            let mut rest = counts;
+            count_column(&mut rest, column, byte, -1);
+            let scores: Vec<f64> = (0..=255u8)
+                .map(|value| {
+                    let mut counts = rest;
+                    count_column(&mut counts, column, value, 1);
+                    counts_score(corpus_freq, &counts)
+                })
+                .collect();
+            let score = scores[byte as usize];
+            let runner_up = scores.iter().enumerate()
+                .filter(|&(value, _)| value != byte as usize)
+                .map(|(_, &score)| score)
+                .fold(f64::INFINITY, f64::min);
+            let mut sorted = scores.clone();
+            sorted.sort_by(f64::total_cmp);
+            let median = sorted[sorted.len() / 2];
+            if score >= runner_up {
+                return 0.0;
+            }
+            ((runner_up - score) / (median - score)).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// Plaintext of a repeating-key XOR with the confidence of every byte
+///
+/// Plaintext byte i gets the confidence of key byte i modulo the key size.
+pub fn repeating_xor_confidence(ciphertext: &[u8], key: &[u8], corpus_freq: &[f64]) -> Partial {
+    if key.is_empty() {
+        return Partial { plaintext: ciphertext.to_vec(), confidence: vec![0.0; ciphertext.len()] };
+    }
+    let columns = blocks::transpose(ciphertext, key.len());
+    let key_confidence = key_confidence(columns.iter().map(Vec::as_slice), key, corpus_freq);
+    Partial {
+        plaintext: repeating_xor(ciphertext, key),
+        confidence: key_confidence.iter().cycle().take(ciphertext.len()).copied().collect(),
+    }
+}
+
+/// Plaintexts of fixed-nonce CTR ciphertexts with the confidence of every byte
+///
+/// Byte i of every plaintext gets the confidence of the keystream byte i,
+/// bytes beyond the keystream are left encrypted with a zero confidence.
+pub fn fixed_nonce_confidence(ciphertexts: &[Vec<u8>], keystream: &[u8], corpus_freq: &[f64]) -> Vec<Partial> {
+    let columns = ColumnBuffer::from_rows(ciphertexts);
+    let key_confidence = key_confidence(columns.columns(), keystream, corpus_freq);
+    ciphertexts.iter()
+        .map(|ciphertext| Partial {
+            plaintext: ciphertext.iter().zip(keystream.iter().chain(std::iter::repeat(&0)))
+                .map(|(a, b)| a ^ b)
+                .collect(),
+            confidence: (0..ciphertext.len())
+                .map(|i| key_confidence.get(i).copied().unwrap_or(0.0))
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cipher::{Aes128Ctr, StreamCipher};
+
+    const TEXT: &str = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+        incredulity, it was the season of Light, it was the season of Darkness, it was the spring \
+        of hope, it was the winter of despair, we had everything before us, we had nothing before \
+        us, we were all going direct to Heaven, we were all going direct the other way - in short, \
+        the period was so far like the present period, that some of its noisiest authorities \
+        insisted on its being received, for good or for evil, in the superlative degree of \
+        comparison only.";
+
+    fn corpus() -> Vec<f64> {
+        english::calc_frequencies(TEXT)
+    }
+
+    #[test]
+    fn refine_fixes_wrong_bytes() {
+        let key = b"Terminator X: Bring the noise";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        let mut wrong_key = key.to_vec();
+        wrong_key[3] ^= 0x20;
+        wrong_key[17] = b'#';
+        let refined = refine_key(|key| repeating_xor(&ciphertext, key), &wrong_key,
+                                 |plaintext| english_score(&corpus(), plaintext));
+        assert_eq!(key.to_vec(), refined);
+    }
+
+    #[test]
+    fn refine_keeps_right_key() {
+        let key = b"ICE";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        let refined = refine_key(|key| repeating_xor(&ciphertext, key), key,
+                                 |plaintext| english_score(&corpus(), plaintext));
+        assert_eq!(key.to_vec(), refined);
+    }
+
+    #[test]
+    fn repeating_xor_pipeline() {
+        let key = b"Terminator X: Bring the noise";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        assert_eq!(key.to_vec(), break_repeating_xor(&ciphertext, key.len(), &corpus(), true));
+    }
+
+    #[test]
+    fn repeating_key_xor_unknown_keysize() {
+        let key = b"Vanilla";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        let corpus = corpus();
+        let (found, plaintext) = break_repeating_key_xor(&ciphertext, 2..42, &english_scorer(&corpus));
+        assert_eq!(key.to_vec(), found);
+        assert_eq!(TEXT.as_bytes(), &plaintext[..]);
+    }
+
+    #[test]
+    fn fixed_nonce_as_repeating_key_xor() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+        let truncated: Vec<u8> = TEXT.as_bytes().chunks(20)
+            .filter(|line| line.len() == 20)
+            .flat_map(|line| ctr.apply_keystream(line))
+            .collect();
+        let corpus = corpus();
+        let (keystream, _) = break_repeating_key_xor(&truncated, 20..21, &english_scorer(&corpus));
+        assert_eq!(ctr.keystream(20), keystream);
+    }
+
+    #[test]
+    fn column_buffer_transpose() {
+        let rows = [b"abcd".to_vec(), vec![], b"ef".to_vec(), b"ghijk".to_vec()];
+        let mut columns = ColumnBuffer::from_rows(&rows);
+        assert_eq!(5, columns.len());
+        let naive: Vec<Vec<u8>> = (0..5)
+            .map(|i| rows.iter().filter_map(|row| row.get(i).copied()).collect())
+            .collect();
+        assert!(columns.columns().eq(naive.iter().map(Vec::as_slice)));
+
+        columns.fill(&[b"x"]);
+        assert_eq!(b"x", columns.column(0));
+        columns.fill::<Vec<u8>>(&[]);
+        assert!(columns.is_empty());
+        assert_eq!(0, columns.columns().count());
+    }
+
+    #[test]
+    fn confidence_markers() {
+        let key = b"ICE";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        let partial = repeating_xor_confidence(&ciphertext, key, &corpus());
+        assert_eq!(TEXT.as_bytes(), &partial.plaintext[..]);
+        assert!(partial.coverage(0.1) > 0.99, "coverage {}", partial.coverage(0.1));
+
+        // A wrong key byte is never trusted
+        let partial = repeating_xor_confidence(&ciphertext, b"IC#", &corpus());
+        assert_eq!(0.0, partial.confidence[2]);
+        assert!(partial.render(0.1).starts_with("It?wa? t"));
+    }
+
+    #[test]
+    fn refined_key_confidence() {
+        let key = b"Terminator X: Bring the noise";
+        let ciphertext = repeating_xor(TEXT.as_bytes(), key);
+        let refined = break_repeating_xor(&ciphertext, key.len(), &corpus(), true);
+        let partial = repeating_xor_confidence(&ciphertext, &refined, &corpus());
+        assert!(partial.confidence.iter().all(|&confidence| confidence > 0.0), "{:?}", partial.confidence);
+    }
+
+    #[test]
+    fn fixed_nonce_pipeline() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+        let ciphertexts: Vec<Vec<u8>> = TEXT.as_bytes().chunks(60)
+            .map(|line| ctr.apply_keystream(line))
+            .collect();
+        let expected = ctr.keystream(60);
+        let right = |keystream: Vec<u8>| keystream.iter().zip(&expected).filter(|(a, b)| a == b).count();
+        let raw = right(break_fixed_nonce(&ciphertexts, &corpus(), false));
+        let refined = right(break_fixed_nonce(&ciphertexts, &corpus(), true));
+        // About 11 lines per column, too few for some of them
+        assert!(refined > raw, "{} bytes right after refinement, {} before", refined, raw);
+    }
+}
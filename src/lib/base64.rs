@@ -0,0 +1,356 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Streaming Base64 adapters over `Read` and `Write`
+//!
+//! `HexString::base64_decode` and `BytesCrypto::base64_encode` need the whole
+//! text in memory. The adapters below encode or decode large files chunk by
+//! chunk, with the same alphabets and the same handling of line breaks.
+
+use std::io::{self, Read, Write};
+use crate::crypto::{Alphabet, BytesCrypto, DecodeConfig};
+
+/// Size of the chunks read from the underlying reader
+const READ_CHUNK: usize = 4096;
+
+/// Decode the Base64 text of a reader
+///
+/// Line breaks are skipped, padding is only accepted at the end of the text.
+/// Malformed input returns an `InvalidData` error.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use cryptopals::base64::Base64Reader;
+///
+/// let mut decoded = Vec::new();
+/// Base64Reader::new("SGVsbG8s\nIHdvcmxkIQ==\n".as_bytes()).read_to_end(&mut decoded).unwrap();
+/// assert_eq!(b"Hello, world!", &decoded[..]);
+/// ```
+pub struct Base64Reader<R: Read> {
+    inner: R,
+    config: DecodeConfig,
+    /// Sextets of the quartet being read
+    quartet: [u8; 4],
+    filled: usize,
+    /// Padding characters seen, no data may follow them
+    padding: usize,
+    /// Decoded bytes not returned yet, from `offset`
+    decoded: Vec<u8>,
+    offset: usize,
+    /// Characters read, line breaks excluded
+    position: usize,
+    eof: bool,
+}
+
+impl<R: Read> Base64Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Base64Reader {
+            inner,
+            config: DecodeConfig::default(),
+            quartet: [0; 4],
+            filled: 0,
+            padding: 0,
+            decoded: Vec::new(),
+            offset: 0,
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Decode text written with another alphabet than the standard one
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.config.alphabet = alphabet;
+        self
+    }
+
+    /// Decode with the options of `config`, see `HexString::base64_decode_config`
+    pub fn with_config(mut self, config: DecodeConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Return the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn push(&mut self, byte: u8) -> io::Result<()> {
+        if byte == b'\n' || byte == b'\r' {
+            return Ok(());
+        }
+        let sextet = match (byte, self.config.alphabet.sextet(byte)) {
+            // Equal sign only authorized at end of a quartet
+            (b'=', _) if self.filled >= 2 => {
+                self.padding += 1;
+                0
+            }
+            (_, Some(sextet)) if self.padding == 0 => sextet,
+            _ => return Err(invalid_data(format!(
+                "invalid byte '{}' (0x{:X}) at position {} in Base64 string",
+                byte as char, byte, self.position))),
+        };
+        self.position += 1;
+        self.quartet[self.filled] = sextet;
+        self.filled += 1;
+        if self.filled == 4 {
+            self.decode_quartet();
+        }
+        Ok(())
+    }
+
+    /// Decode the quartet, its missing sextets and padding being zeros
+    fn decode_quartet(&mut self) {
+        let q = self.quartet;
+        let bytes = [
+            q[0] << 2 | (q[1] & 0b00110000) >> 4,
+            (q[1] & 0b00001111) << 4 | (q[2] & 0b00111100) >> 2,
+            (q[2] & 0b00000011) << 6 | q[3],
+        ];
+        let missing = self.padding + 4 - self.filled;
+        self.decoded.extend_from_slice(&bytes[..3 - missing]);
+        self.quartet = [0; 4];
+        self.filled = 0;
+    }
+
+    /// Decode the last quartet, if the text may end without all its padding
+    fn finish_unpadded(&mut self) -> io::Result<()> {
+        match self.filled {
+            0 => Ok(()),
+            2 | 3 if !self.config.require_padding => {
+                self.decode_quartet();
+                Ok(())
+            }
+            _ => Err(invalid_data(format!("invalid Base64 length: {}", self.position))),
+        }
+    }
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset == self.decoded.len() {
+            self.decoded.clear();
+            self.offset = 0;
+            if self.eof {
+                return Ok(0);
+            }
+            let mut chunk = [0; READ_CHUNK];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.eof = true;
+                self.finish_unpadded()?;
+            }
+            for &byte in &chunk[..n] {
+                self.push(byte)?;
+            }
+        }
+        let n = buf.len().min(self.decoded.len() - self.offset);
+        buf[..n].copy_from_slice(&self.decoded[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+/// Encode everything written as Base64 to a writer
+///
+/// The last one or two bytes are only encoded, with padding, by `finish`, or
+/// when the writer is dropped, ignoring errors.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use cryptopals::base64::Base64Writer;
+///
+/// let mut writer = Base64Writer::new(Vec::new());
+/// writer.write_all(b"Hello, ").unwrap();
+/// writer.write_all(b"world!").unwrap();
+/// assert_eq!(b"SGVsbG8sIHdvcmxkIQ==", &writer.finish().unwrap()[..]);
+/// ```
+pub struct Base64Writer<W: Write> {
+    /// `None` once finished
+    inner: Option<W>,
+    alphabet: Alphabet,
+    /// Bytes waiting for a complete triplet
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Base64Writer { inner: Some(inner), alphabet: Alphabet::Standard, pending: Vec::with_capacity(3) }
+    }
+
+    /// Encode with another alphabet than the standard one
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Encode the last bytes and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_pending()?;
+        Ok(self.inner.take().expect("writer already finished"))
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.write_all(self.pending.base64_encode_with(self.alphabet).as_bytes())?;
+            self.pending.clear();
+            inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = self.inner.as_mut().expect("writer already finished");
+        // Complete the pending triplet first, then encode whole triplets only
+        let missing = (3 - self.pending.len()).min(buf.len());
+        self.pending.extend_from_slice(&buf[..missing]);
+        if self.pending.len() < 3 {
+            return Ok(buf.len());
+        }
+        let rest = &buf[missing..];
+        let whole = rest.len() - rest.len() % 3;
+        inner.write_all(self.pending.base64_encode_with(self.alphabet).as_bytes())?;
+        inner.write_all(rest[..whole].base64_encode_with(self.alphabet).as_bytes())?;
+        self.pending.clear();
+        self.pending.extend_from_slice(&rest[whole..]);
+        Ok(buf.len())
+    }
+
+    /// Flush the underlying writer, the pending bytes stay until `finish`
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for Base64Writer<W> {
+    fn drop(&mut self) {
+        let _ = self.write_pending();
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::HexString;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn stream_round_trip(bytes in prop::collection::vec(any::<u8>(), 0..300), chunk in 1..10usize) {
+            let mut writer = Base64Writer::new(Vec::new());
+            for part in bytes.chunks(chunk) {
+                writer.write_all(part).unwrap();
+            }
+            let encoded = String::from_utf8(writer.finish().unwrap()).unwrap();
+            prop_assert_eq!(&bytes, &decode(&encoded).unwrap());
+        }
+
+        #[test]
+        fn stream_never_panics(text in "[A-Za-z0-9+/=\r\n]{0,16}|\\PC*") {
+            let _ = decode(&text);
+        }
+    }
+
+    /// Reader returning at most 3 bytes per call
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn decode(text: &str) -> io::Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        Base64Reader::new(Trickle(text.as_bytes())).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn stream_matches_in_memory() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for len in [0, 1, 2, 3, 4, 5, 999, 1000] {
+            let mut writer = Base64Writer::new(Vec::new());
+            for chunk in data[..len].chunks(7) {
+                writer.write_all(chunk).unwrap();
+            }
+            let encoded = String::from_utf8(writer.finish().unwrap()).unwrap();
+            assert_eq!(data[..len].base64_encode(), encoded);
+            assert_eq!(&data[..len], &decode(&encoded).unwrap()[..]);
+        }
+
+        let wrapped = "SGVsbG8s\r\nIHdvcmxk\r\nIQ==";
+        assert_eq!(wrapped.base64_decode().unwrap(), decode(wrapped).unwrap());
+    }
+
+    #[test]
+    fn url_safe_stream() {
+        let data = [0xfb, 0xff, 0xbf, 0x3e];
+        let mut writer = Base64Writer::new(Vec::new()).with_alphabet(Alphabet::UrlSafe);
+        writer.write_all(&data).unwrap();
+        let encoded = writer.finish().unwrap();
+        assert_eq!(b"-_-_Pg==", &encoded[..]);
+
+        let mut decoded = Vec::new();
+        Base64Reader::new(&encoded[..]).with_alphabet(Alphabet::UrlSafe).read_to_end(&mut decoded).unwrap();
+        assert_eq!(data, &decoded[..]);
+        assert!(Base64Reader::new(&encoded[..]).read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn unpadded_stream() {
+        let config = DecodeConfig::new().with_optional_padding();
+        for text in ["QUJD", "QUI", "QQ", "QQ=", "SGVsbG8s\nIHdvcmxk\nIQ\n"] {
+            let mut decoded = Vec::new();
+            Base64Reader::new(Trickle(text.as_bytes())).with_config(config).read_to_end(&mut decoded).unwrap();
+            assert_eq!(text.base64_decode_config(&config).unwrap(), decoded, "{}", text);
+            assert!(decode(text).is_err() || text == "QUJD", "{}", text);
+        }
+        let mut decoded = Vec::new();
+        assert!(Base64Reader::new("QUJDQ".as_bytes()).with_config(config).read_to_end(&mut decoded).is_err());
+        assert!("QUJDQ".base64_decode_config(&config).is_err());
+    }
+
+    #[test]
+    fn dropped_writer_pads() {
+        let mut encoded = Vec::new();
+        Base64Writer::new(&mut encoded).write_all(b"A").unwrap();
+        assert_eq!(b"QQ==", &encoded[..]);
+    }
+
+    #[test]
+    fn invalid_stream() {
+        for text in ["QUJ", "QU=D", "Q===", "QQ==QUJD", "QU*D"] {
+            let error = decode(text).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Keystream bias statistics
+//!
+//! Generate many keystreams with random keys and count the bytes found at each
+//! position. A perfect stream cipher gives each byte value a 1/256 probability
+//! everywhere, RC4 famously does not.
+
+use std::fmt;
+use crate::stats::ByteHistogram;
+
+/// Byte histograms of the keystream, one per position
+pub struct KeystreamBias {
+    histograms: Vec<ByteHistogram>,
+}
+
+/// A byte value which shows up more or less often than it should at a position
+#[derive(Debug, PartialEq)]
+pub struct Bias {
+    pub position: usize,
+    pub byte: u8,
+    pub frequency: f64,
+    /// Frequency relative to the uniform 1/256, 0 means no bias
+    pub deviation: f64,
+}
+
+impl KeystreamBias {
+    /// Collect `samples` keystreams of `len` bytes from a keystream generator
+    ///
+    /// The generator should use a new random key for each call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::bias::KeystreamBias;
+    ///
+    /// // A terrible keystream, always starting with a zero byte
+    /// let mut counter = 0u8;
+    /// let stats = KeystreamBias::collect(|| {
+    ///     counter = counter.wrapping_add(1);
+    ///     vec![0, counter]
+    /// }, 256, 2);
+    ///
+    /// assert_eq!(1.0, stats.frequency(0, 0));
+    /// assert_eq!(0, stats.most_biased(0).byte);
+    /// assert_eq!(0.0, stats.max_deviation(1));
+    /// ```
+    pub fn collect<F: FnMut() -> Vec<u8>>(mut keystream: F, samples: usize, len: usize) -> KeystreamBias {
+        let mut histograms = vec![ByteHistogram::new(); len];
+        for _ in 0..samples {
+            let stream = keystream();
+            for (histogram, &byte) in histograms.iter_mut().zip(stream.iter()) {
+                histogram.add(byte);
+            }
+        }
+        KeystreamBias { histograms }
+    }
+
+    /// Number of keystream positions
+    pub fn len(&self) -> usize {
+        self.histograms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histograms.is_empty()
+    }
+
+    /// Histogram of the bytes seen at a position
+    pub fn histogram(&self, position: usize) -> &ByteHistogram {
+        &self.histograms[position]
+    }
+
+    /// Frequency of a byte at a position
+    pub fn frequency(&self, position: usize, byte: u8) -> f64 {
+        self.histograms[position].frequency(byte)
+    }
+
+    /// Bias of a byte at a position
+    pub fn bias(&self, position: usize, byte: u8) -> Bias {
+        let frequency = self.frequency(position, byte);
+        Bias { position, byte, frequency, deviation: frequency * 256.0 - 1.0 }
+    }
+
+    /// Most frequent byte at a position
+    pub fn most_biased(&self, position: usize) -> Bias {
+        let byte = (0..=255u8)
+            .max_by_key(|&byte| self.histograms[position].count(byte))
+            .unwrap();
+        self.bias(position, byte)
+    }
+
+    /// Largest deviation from the uniform distribution at a position, in either direction
+    ///
+    /// Compare it with the value observed for a good cipher and the same number of
+    /// samples to tell whether a keystream is distinguishable from random.
+    pub fn max_deviation(&self, position: usize) -> f64 {
+        (0..=255u8)
+            .map(|byte| self.bias(position, byte).deviation.abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Most frequent byte of every position, the table needed by bias attacks
+    pub fn bias_table(&self) -> Vec<Bias> {
+        (0..self.len()).map(|position| self.most_biased(position)).collect()
+    }
+}
+
+impl fmt::Display for KeystreamBias {
+    /// Display the most frequent byte of each position
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "position | byte | frequency | deviation")?;
+        for bias in self.bias_table() {
+            writeln!(f, "{:8} | 0x{:02x} | {:9.6} | {:+9.4}",
+                     bias.position + 1, bias.byte, bias.frequency, bias.deviation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uniform() {
+        let mut next = 0u8;
+        let stats = KeystreamBias::collect(|| {
+            next = next.wrapping_add(1);
+            vec![next; 3]
+        }, 512, 3);
+        assert_eq!(3, stats.len());
+        for position in 0..3 {
+            assert_eq!(0.0, stats.max_deviation(position));
+            assert_eq!(2, stats.histogram(position).count(42));
+        }
+    }
+
+    #[test]
+    fn biased_position() {
+        let mut next = 0u8;
+        let stats = KeystreamBias::collect(|| {
+            next = next.wrapping_add(1);
+            vec![next, if next & 1 == 0 { 0xf0 } else { next }]
+        }, 256, 2);
+        let bias = stats.most_biased(1);
+        assert_eq!(0xf0, bias.byte);
+        assert_eq!(0.5, bias.frequency);
+        assert!(stats.max_deviation(1) > 100.0);
+    }
+
+    #[test]
+    fn short_keystreams() {
+        let stats = KeystreamBias::collect(|| vec![1], 10, 2);
+        assert_eq!(10, stats.histogram(0).total());
+        assert_eq!(0, stats.histogram(1).total());
+        assert_eq!(0.0, stats.frequency(1, 1));
+    }
+
+    #[test]
+    fn display() {
+        let stats = KeystreamBias::collect(|| vec![0x42], 4, 1);
+        assert_eq!(
+            "position | byte | frequency | deviation\n       1 | 0x42 |  1.000000 | +255.0000\n",
+            stats.to_string()
+        );
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Bit manipulation of byte strings
+//!
+//! Bit `i` is bit `i % 8` of byte `i / 8`, counting from the least
+//! significant bit, so that flipping bit `8 * j + k` XORs byte `j` with
+//! `1 << k`, as the bitflipping attacks do.
+
+/// Number of bits set
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::bits;
+///
+/// assert_eq!(3, bits::hamming_weight(&[0b101, 0x80]));
+/// ```
+pub fn hamming_weight(data: &[u8]) -> u32 {
+    data.iter().map(|b| b.count_ones()).sum()
+}
+
+/// Value of bit `index`
+///
+/// # Panics:
+///
+/// The function panics if `index` is past the end of `data`, like the other
+/// bit accessors.
+pub fn get_bit(data: &[u8], index: usize) -> bool {
+    (data[index / 8] >> (index % 8)) & 1 == 1
+}
+
+pub fn set_bit(data: &mut [u8], index: usize, value: bool) {
+    if value {
+        data[index / 8] |= 1 << (index % 8);
+    } else {
+        data[index / 8] &= !(1 << (index % 8));
+    }
+}
+
+/// Invert bit `index`
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::bits;
+///
+/// let mut block = *b";admin=false";
+/// bits::flip_bit(&mut block, 8 * 6);
+/// assert_eq!(b";admin<false", &block);
+/// ```
+pub fn flip_bit(data: &mut [u8], index: usize) {
+    data[index / 8] ^= 1 << (index % 8);
+}
+
+/// Iterate over the bits of `data`, in index order
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::bits;
+///
+/// let bits: Vec<bool> = bits::iter(&[0b0000_0110]).collect();
+/// assert_eq!(vec![false, true, true, false, false, false, false, false], bits);
+/// ```
+pub fn iter(data: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    (0..data.len() * 8).map(move |index| get_bit(data, index))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_get_flip() {
+        let mut data = [0u8; 3];
+        set_bit(&mut data, 9, true);
+        assert_eq!([0, 2, 0], data);
+        assert!(get_bit(&data, 9));
+        flip_bit(&mut data, 9);
+        flip_bit(&mut data, 23);
+        assert_eq!([0, 0, 0x80], data);
+        set_bit(&mut data, 23, false);
+        assert_eq!(0, hamming_weight(&data));
+        assert_eq!(24, iter(&data).count());
+    }
+
+    #[test]
+    fn iter_matches_get() {
+        let data = [0xa5, 0x3c];
+        assert_eq!(hamming_weight(&data) as usize, iter(&data).filter(|&b| b).count());
+        assert!(iter(&data).enumerate().all(|(i, b)| b == get_bit(&data, i)));
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Blocks, columns and block-aligned padding of byte arrays
+//!
+//! Repeating-key XOR attacks read a ciphertext column by column, block
+//! cipher attacks block by block; both go through these helpers.
+//!
+//! PKCS#7 padding is removed either by `pkcs7_unpad`, which tells why the
+//! padding is invalid as fast as it can, like the servers attacked by padding
+//! oracles, or by `pkcs7_unpad_ct`, which takes the same time and gives the
+//! same error for every invalid padding.
+
+use alloc::borrow::ToOwned;
+use alloc::{format, vec};
+use alloc::vec::Vec;
+use crate::error::{CryptopalsError, Result};
+
+/// Split data into `keysize` columns, column i holding the bytes i, i + keysize, ...
+///
+/// The first `data.len() % keysize` columns are one byte longer than the
+/// others. A zero `keysize` gives no column.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(vec![b"adg".to_vec(), b"be".to_vec(), b"cf".to_vec()], blocks::transpose(b"abcdefg", 3));
+/// ```
+pub fn transpose(data: &[u8], keysize: usize) -> Vec<Vec<u8>> {
+    if keysize == 0 {
+        return Vec::new();
+    }
+    let mut columns = vec![Vec::with_capacity(data.len() / keysize + 1); keysize];
+    for (index, &byte) in data.iter().enumerate() {
+        columns[index % keysize].push(byte);
+    }
+    columns
+}
+
+/// Block `n` of `block_size` bytes, the last one may be shorter
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(Some(&b"YELLOW SUBMARINE"[..]), blocks::nth_block(b"0123456789abcdefYELLOW SUBMARINE!", 16, 1));
+/// assert_eq!(Some(&b"!"[..]), blocks::nth_block(b"0123456789abcdefYELLOW SUBMARINE!", 16, 2));
+/// assert_eq!(None, blocks::nth_block(b"0123456789abcdef", 16, 1));
+/// ```
+///
+/// # Panics:
+///
+/// The function panics if `block_size` is 0, like `slice::chunks`.
+pub fn nth_block(data: &[u8], block_size: usize, n: usize) -> Option<&[u8]> {
+    data.chunks(block_size).nth(n)
+}
+
+/// Number of blocks of `block_size` bytes needed for `len` bytes
+pub fn block_count(len: usize, block_size: usize) -> usize {
+    len.div_ceil(block_size)
+}
+
+/// Bytes missing after `len` bytes to reach a block boundary, 0 on a boundary
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(6, blocks::padding_len(10, 16));
+/// assert_eq!(0, blocks::padding_len(32, 16));
+/// ```
+pub fn padding_len(len: usize, block_size: usize) -> usize {
+    block_count(len, block_size) * block_size - len
+}
+
+/// Append `byte` until the data ends on a block boundary
+///
+/// Unlike PKCS#7, data already ending on a boundary is not padded.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(b"YELLOW SUB\0\0\0\0\0\0".to_vec(), blocks::pad_to_block(b"YELLOW SUB", 16, 0));
+/// assert_eq!(b"YELLOW SUBMARINE".to_vec(), blocks::pad_to_block(b"YELLOW SUBMARINE", 16, 0));
+/// ```
+pub fn pad_to_block(data: &[u8], block_size: usize, byte: u8) -> Vec<u8> {
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + padding_len(data.len(), block_size), byte);
+    padded
+}
+
+/// Pad data with PKCS#7: append n bytes of value n, a whole block on a boundary
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec(), blocks::pkcs7_pad(b"YELLOW SUBMARINE", 20));
+/// assert_eq!(vec![4; 4], blocks::pkcs7_pad(b"", 4));
+/// ```
+///
+/// # Panics:
+///
+/// The function panics if `block_size` is 0 or does not fit in a byte.
+pub fn pkcs7_pad(data: &[u8], block_size: usize) -> Vec<u8> {
+    assert!((1..=255).contains(&block_size), "invalid PKCS#7 block size {}", block_size);
+    let pad = block_size - data.len() % block_size;
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + pad, pad as u8);
+    padded
+}
+
+/// Remove the PKCS#7 padding of decrypted data, checking it byte by byte
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(b"ICE ICE BABY", blocks::pkcs7_unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16).unwrap());
+/// assert!(blocks::pkcs7_unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16).is_err());
+/// assert!(blocks::pkcs7_unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16).is_err());
+/// ```
+pub fn pkcs7_unpad(data: &[u8], block_size: usize) -> Result<&[u8]> {
+    if data.is_empty() || block_size == 0 || !data.len().is_multiple_of(block_size) {
+        return Err(CryptopalsError::Padding(format!("{} bytes are not whole blocks of {} bytes", data.len(), block_size)));
+    }
+    let pad = data[data.len() - 1] as usize;
+    if pad == 0 || pad > block_size {
+        return Err(CryptopalsError::Padding(format!("invalid padding length {}", pad)));
+    }
+    let start = data.len() - pad;
+    if let Some(position) = data[start..].iter().position(|&byte| byte as usize != pad) {
+        return Err(CryptopalsError::Padding(format!("invalid padding byte at {}", start + position)));
+    }
+    Ok(&data[..start])
+}
+
+/// Remove the PKCS#7 padding of decrypted data without leaking why it is invalid
+///
+/// The whole last block is read whatever its content, with masks instead of
+/// data-dependent branches and early exits, and every invalid padding gives
+/// the same error. Only the lengths of the data and of a valid padding are
+/// public.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::blocks;
+///
+/// assert_eq!(b"ICE ICE BABY", blocks::pkcs7_unpad_ct(b"ICE ICE BABY\x04\x04\x04\x04", 16).unwrap());
+/// assert!(blocks::pkcs7_unpad_ct(b"ICE ICE BABY\x05\x05\x05\x05", 16).is_err());
+/// ```
+pub fn pkcs7_unpad_ct(data: &[u8], block_size: usize) -> Result<&[u8]> {
+    let invalid = || CryptopalsError::Padding("invalid padding".to_owned());
+    if data.is_empty() || block_size == 0 || block_size > 255 || !data.len().is_multiple_of(block_size) {
+        return Err(invalid());
+    }
+    let last_block = &data[data.len() - block_size..];
+    let pad = last_block[block_size - 1];
+    // 0xff while every check passes
+    let mut valid = !ct_is_zero(pad) & ct_lt(pad as u32, block_size as u32 + 1);
+    for (i, &byte) in last_block.iter().rev().enumerate() {
+        let in_padding = ct_lt(i as u32, pad as u32);
+        valid &= !in_padding | ct_is_zero(byte ^ pad);
+    }
+    if core::hint::black_box(valid) != 0xff {
+        return Err(invalid());
+    }
+    Ok(&data[..data.len() - pad as usize])
+}
+
+/// 0xff if `a < b`, 0 otherwise, for values below 2^31
+fn ct_lt(a: u32, b: u32) -> u8 {
+    (a.wrapping_sub(b) >> 31) as u8 * 0xff
+}
+
+/// 0xff if `x` is 0, 0 otherwise
+fn ct_is_zero(x: u8) -> u8 {
+    ((x as u32).wrapping_sub(1) >> 31) as u8 * 0xff
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transpose_non_ascii() {
+        let data: Vec<u8> = (0..=255).collect();
+        let columns = transpose(&data, 7);
+        assert_eq!(7, columns.len());
+        assert_eq!(37, columns[0].len());
+        assert_eq!(36, columns[6].len());
+        assert_eq!(Some(&0xfc), columns[0].last());
+        assert!(transpose(&data, 0).is_empty());
+        assert_eq!(vec![Vec::<u8>::new(); 3], transpose(b"", 3));
+    }
+
+    #[test]
+    fn block_arithmetic() {
+        assert_eq!(0, block_count(0, 16));
+        assert_eq!(2, block_count(17, 16));
+        assert_eq!(0, padding_len(0, 16));
+        assert_eq!(15, padding_len(17, 16));
+        assert!(pad_to_block(b"", 16, 0).is_empty());
+    }
+
+    #[test]
+    fn pad_and_unpad() {
+        for len in 0..=40 {
+            let data = vec![0x41; len];
+            let padded = pkcs7_pad(&data, 16);
+            assert!(padded.len().is_multiple_of(16) && padded.len() > len);
+            assert_eq!(&data[..], pkcs7_unpad(&padded, 16).unwrap());
+        }
+        assert_eq!(vec![255; 255], pkcs7_pad(b"", 255));
+    }
+
+    #[test]
+    fn unpad_variants_agree() {
+        let mut block = *b"YELLOW SUBMARINE";
+        for last in 0..=255u8 {
+            for filler in [0x01, 0x02, 0x03, 0x10, last] {
+                block[13] = filler;
+                block[14] = filler;
+                block[15] = last;
+                let strict = pkcs7_unpad(&block, 16).ok();
+                assert_eq!(strict, pkcs7_unpad_ct(&block, 16).ok(), "{:?}", block);
+            }
+        }
+        assert_eq!(Some(&b""[..]), pkcs7_unpad_ct(&[16; 16], 16).ok());
+        assert!(pkcs7_unpad_ct(b"", 16).is_err());
+        assert!(pkcs7_unpad_ct(&[1; 17], 16).is_err());
+        assert!(pkcs7_unpad(&[1; 17], 16).is_err());
+    }
+}
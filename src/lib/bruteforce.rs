@@ -0,0 +1,143 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Parallel exhaustive search
+//!
+//! The candidates are handed out to the workers in chunks, in increasing
+//! order, and the search stops as soon as no chunk can hold a smaller match
+//! than the best one found so far.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+/// Candidates handed out at once, large enough to make the atomics negligible
+const CHUNK_SIZE: u64 = 1024;
+
+/// Search a range of candidates over several threads
+pub struct WorkerPool {
+    concurrency: usize,
+}
+
+impl WorkerPool {
+    /// As many workers as there are CPUs
+    pub fn new() -> WorkerPool {
+        let concurrency = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        WorkerPool { concurrency }
+    }
+
+    /// Limit the number of workers, 1 searches sequentially
+    pub fn with_concurrency(mut self, concurrency: usize) -> WorkerPool {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Smallest candidate accepted by `check`, None if there is none
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::bruteforce::WorkerPool;
+    ///
+    /// let pool = WorkerPool::new().with_concurrency(4);
+    /// assert_eq!(Some(1 << 20), pool.find_first(0..1 << 24, |x| x * x >= 1 << 40));
+    /// assert_eq!(None, pool.find_first(0..1000, |x| x > 1000));
+    /// ```
+    pub fn find_first<F>(&self, candidates: Range<u64>, check: F) -> Option<u64>
+        where F: Fn(u64) -> bool + Sync
+    {
+        if self.concurrency <= 1 {
+            return candidates.into_iter().find(|&x| check(x));
+        }
+
+        // Offset of the next chunk from the start, which cannot overflow like the candidates
+        let next = AtomicU64::new(0);
+        let len = candidates.end.saturating_sub(candidates.start);
+        // u64::MAX until a match is found
+        let best = AtomicU64::new(u64::MAX);
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                scope.spawn(|| loop {
+                    let offset = next.fetch_add(CHUNK_SIZE, Ordering::Relaxed);
+                    let start = candidates.start + offset.min(len);
+                    if offset >= len || start > best.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let end = start + CHUNK_SIZE.min(len - offset);
+                    for x in start..end {
+                        if x > best.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        if check(x) {
+                            best.fetch_min(x, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        match best.into_inner() {
+            u64::MAX => None,
+            x => Some(x),
+        }
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        WorkerPool::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn smallest_match() {
+        // Several matches spread over different chunks
+        let pool = WorkerPool::new().with_concurrency(8);
+        assert_eq!(Some(5000), pool.find_first(0..100_000, |x| x % 5000 == 0 && x > 0));
+        assert_eq!(Some(10), pool.find_first(10..20, |_| true));
+        assert_eq!(None, pool.find_first(10..10, |_| true));
+        assert_eq!(Some(u64::MAX - 1), pool.find_first(u64::MAX - 3..u64::MAX, |x| x == u64::MAX - 1));
+    }
+
+    #[test]
+    fn early_cancellation() {
+        let checked = AtomicUsize::new(0);
+        let pool = WorkerPool::new().with_concurrency(4);
+        let found = pool.find_first(0..1 << 40, |x| {
+            checked.fetch_add(1, Ordering::Relaxed);
+            x >= 3000
+        });
+        assert_eq!(Some(3000), found);
+        // Every candidate below the match, then at most one more per worker,
+        // however the threads are scheduled
+        assert!(checked.load(Ordering::Relaxed) <= 3000 + 4);
+    }
+
+    #[test]
+    fn sequential() {
+        let pool = WorkerPool::new().with_concurrency(0);
+        assert_eq!(1, pool.concurrency());
+        assert_eq!(Some(7), pool.find_first(0..100, |x| x * x > 42));
+    }
+}
@@ -0,0 +1,519 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Content-addressed cache of the challenge inputs
+//!
+//! Each downloaded body, text or binary, is stored once, in a file named
+//! after its SHA-256 digest, and an index record per URL, named after the
+//! digest of the URL, gives the digest of its body. Writers of different
+//! URLs never touch the same record, so concurrent runs keep each other's
+//! entries, and a corrupted record only loses its own URL. The digest is checked on every read, so a corrupted entry is
+//! downloaded again. Entries can also expire after a time to live.
+//!
+//! Files are written next to their destination under a unique name, then
+//! renamed, so that concurrent runs never see each other's partial writes.
+
+use std::collections::BTreeMap;
+use std::{env, error};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Environment variable overriding the cache directory
+pub const CACHE_DIR_ENV: &str = "CRYPTOPALS_CACHE_DIR";
+
+/// Directory of the index records
+const INDEX_DIR: &str = "index";
+const INDEX_EXTENSION: &str = "json";
+/// Single index of the older caches, only removed by `clean`
+const LEGACY_INDEX_FILE: &str = "index.json";
+const ENTRY_EXTENSION: &str = "txt";
+
+/// Index record of a cached URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Hex SHA-256 digest of the body, which names its file
+    pub digest: String,
+    pub size: u64,
+    /// Download time, in seconds since the Unix epoch
+    pub fetched: u64,
+}
+
+/// Index record file of a URL
+#[derive(Serialize, Deserialize)]
+struct IndexRecord {
+    url: String,
+    #[serde(flatten)]
+    entry: IndexEntry,
+}
+
+/// Summary of the cache contents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// URLs in the index
+    pub urls: usize,
+    /// Stored bodies, shared by the URLs with the same content
+    pub entries: usize,
+    pub bytes: u64,
+    /// Stored bodies no URL refers to
+    pub orphans: usize,
+    /// URLs whose body is missing
+    pub missing: usize,
+}
+
+/// Location of the cache
+///
+/// The default is the directory in `CRYPTOPALS_CACHE_DIR`, or else the cache
+/// directory of the platform: `$XDG_CACHE_HOME/cryptopals` or
+/// `~/.cache/cryptopals` on Linux, `~/Library/Caches/org.farzy.cryptopals` on
+/// macOS and `%LOCALAPPDATA%\farzy\cryptopals\cache` on Windows.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::cache::CacheConfig;
+///
+/// let cache = CacheConfig::default().with_dir("/tmp/inputs").build();
+/// assert_eq!(std::path::Path::new("/tmp/inputs"), cache.dir());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl CacheConfig {
+    /// Cache directory of the platform, or of the temporary directory if there is none
+    pub fn platform() -> CacheConfig {
+        let dir = ProjectDirs::from("org", "farzy", "cryptopals")
+            .map(|dirs| dirs.cache_dir().to_path_buf())
+            .unwrap_or_else(|| env::temp_dir().join("cryptopals-cache"));
+        CacheConfig { dir, ttl: None }
+    }
+
+    /// `CRYPTOPALS_CACHE_DIR` if set and not empty, the platform directory otherwise
+    pub fn from_env() -> CacheConfig {
+        CacheConfig::resolve(env::var_os(CACHE_DIR_ENV).map(PathBuf::from))
+    }
+
+    fn resolve(dir: Option<PathBuf>) -> CacheConfig {
+        match dir {
+            Some(dir) if !dir.as_os_str().is_empty() => CacheConfig { dir, ttl: None },
+            _ => CacheConfig::platform(),
+        }
+    }
+
+    pub fn with_dir<P: AsRef<Path>>(mut self, dir: P) -> CacheConfig {
+        self.dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Expire the entries fetched more than `ttl` ago, they never expire by default
+    pub fn with_ttl(mut self, ttl: Duration) -> CacheConfig {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn build(&self) -> Cache {
+        let cache = Cache::new(&self.dir);
+        match self.ttl {
+            Some(ttl) => cache.with_ttl(ttl),
+            None => cache,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> CacheConfig {
+        CacheConfig::from_env()
+    }
+}
+
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl Default for Cache {
+    /// Cache in the directory of `CacheConfig::default`
+    fn default() -> Cache {
+        CacheConfig::default().build()
+    }
+}
+
+impl Cache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Cache {
+        Cache { dir: dir.as_ref().to_path_buf(), ttl: None }
+    }
+
+    /// Expire the entries fetched more than `ttl` ago
+    pub fn with_ttl(mut self, ttl: Duration) -> Cache {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn digest(body: &[u8]) -> String {
+        Sha256::digest(body).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn entry_path(&self, digest: &str) -> PathBuf {
+        self.dir.join(digest).with_extension(ENTRY_EXTENSION)
+    }
+
+    /// URL to entry mapping, empty if there is no index yet
+    ///
+    /// Corrupted records are skipped, the next `put` of their URL replaces them.
+    pub fn index(&self) -> Result<BTreeMap<String, IndexEntry>> {
+        let mut index = BTreeMap::new();
+        let dir = self.dir.join(INDEX_DIR);
+        if !dir.exists() {
+            return Ok(index);
+        }
+        for dir_entry in fs::read_dir(dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|extension| extension == INDEX_EXTENSION) {
+                match Self::read_record(&path) {
+                    Some(record) => {
+                        index.insert(record.url, record.entry);
+                    }
+                    None => warn!("Index record {} is corrupted", path.display()),
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    fn record_path(&self, url: &str) -> PathBuf {
+        self.dir.join(INDEX_DIR).join(Self::digest(url.as_bytes())).with_extension(INDEX_EXTENSION)
+    }
+
+    fn read_record(path: &Path) -> Option<IndexRecord> {
+        serde_json::from_slice(&fs::read(path).ok()?).ok()
+    }
+
+    /// Index record of a URL
+    fn entry(&self, url: &str) -> Option<IndexEntry> {
+        Self::read_record(&self.record_path(url))
+            .filter(|record| record.url == url)
+            .map(|record| record.entry)
+    }
+
+    /// Write a file next to its destination then rename it, never leaving a truncated file
+    ///
+    /// The temporary name is unique to the process and the call, so that
+    /// concurrent writers don't mix their contents.
+    fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+        let temp_path = path.with_extension(format!(
+            "{}.{}.tmp", process::id(), WRITES.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&temp_path, contents)?;
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Replace the index record of a URL, leaving the others alone
+    fn save_entry(&self, url: &str, entry: IndexEntry) -> Result<()> {
+        fs::create_dir_all(self.dir.join(INDEX_DIR))?;
+        let record = IndexRecord { url: url.to_owned(), entry };
+        Self::write_atomic(&self.record_path(url), &serde_json::to_vec_pretty(&record)?)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+    }
+
+    /// Index record of a URL, `None` if missing or expired
+    fn fresh_entry(&self, url: &str) -> Option<IndexEntry> {
+        let entry = self.entry(url)?;
+        if let Some(ttl) = self.ttl {
+            if Self::now().saturating_sub(entry.fetched) > ttl.as_secs() {
+                debug!("Cache entry of {} expired", url);
+                return None;
+            }
+        }
+        Some(entry)
+    }
+
+    /// Digest of the cached body of a URL, without reading the body
+    ///
+    /// The body itself is only checked against it by `get`.
+    pub fn body_digest(&self, url: &str) -> Option<String> {
+        self.fresh_entry(url).map(|entry| entry.digest)
+    }
+
+    /// Cached text body of a URL
+    ///
+    /// Returns `None` if the URL is not cached, if its entry expired, if its
+    /// body does not match its digest any more, or if it is not UTF-8 text.
+    pub fn get(&self, url: &str) -> Option<String> {
+        String::from_utf8(self.get_bytes(url)?).ok()
+    }
+
+    /// Cached body of a URL, see `get`
+    pub fn get_bytes(&self, url: &str) -> Option<Vec<u8>> {
+        let entry = self.fresh_entry(url)?;
+        let body = fs::read(self.entry_path(&entry.digest)).ok()?;
+        if Self::digest(&body) != entry.digest {
+            warn!("Cache entry {} of {} is corrupted", entry.digest, url);
+            return None;
+        }
+        Some(body)
+    }
+
+    /// Store the text body of a URL
+    pub fn put(&self, url: &str, body: &str) -> Result<()> {
+        self.put_bytes(url, body.as_bytes())
+    }
+
+    /// Store the body of a URL, text or binary
+    pub fn put_bytes(&self, url: &str, body: &[u8]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let digest = Self::digest(body);
+        // Rewritten even if present, a corrupted body would otherwise stay
+        Self::write_atomic(&self.entry_path(&digest), body)?;
+        self.save_entry(url, IndexEntry { digest, size: body.len() as u64, fetched: Self::now() })
+    }
+
+    /// Digests of the bodies stored in the cache directory
+    fn stored_digests(&self) -> Result<Vec<String>> {
+        let mut digests = Vec::new();
+        if !self.dir.exists() {
+            return Ok(digests);
+        }
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|extension| extension == ENTRY_EXTENSION) {
+                if let Some(stem) = path.file_stem() {
+                    digests.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(digests)
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let index = self.index()?;
+        let mut stats = CacheStats { urls: index.len(), ..CacheStats::default() };
+        let stored = self.stored_digests()?;
+        for digest in &stored {
+            stats.entries += 1;
+            stats.bytes += fs::metadata(self.entry_path(digest))?.len();
+            if !index.values().any(|entry| entry.digest == *digest) {
+                stats.orphans += 1;
+            }
+        }
+        stats.missing = index.values().filter(|entry| !stored.contains(&entry.digest)).count();
+        Ok(stats)
+    }
+
+    /// Remove every cached body and the index, return the number of bodies removed
+    pub fn clean(&self) -> Result<usize> {
+        let stored = self.stored_digests()?;
+        for digest in &stored {
+            fs::remove_file(self.entry_path(digest))?;
+        }
+        let index = self.dir.join(INDEX_DIR);
+        if index.exists() {
+            fs::remove_dir_all(index)?;
+        }
+        let legacy_index = self.dir.join(LEGACY_INDEX_FILE);
+        if legacy_index.exists() {
+            fs::remove_file(legacy_index)?;
+        }
+        Ok(stored.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_cache(name: &str) -> Cache {
+        let cache = Cache::new(std::env::temp_dir().join(format!("cryptopals-cache-{}-{}", name, std::process::id())));
+        cache.clean().unwrap();
+        cache
+    }
+
+    #[test]
+    fn config_resolution() {
+        assert_eq!(Path::new("/tmp/a"), CacheConfig::resolve(Some(PathBuf::from("/tmp/a"))).dir());
+        assert_eq!(CacheConfig::platform(), CacheConfig::resolve(Some(PathBuf::new())));
+        assert_eq!(CacheConfig::platform(), CacheConfig::resolve(None));
+    }
+
+    #[test]
+    fn put_and_get() {
+        let cache = temp_cache("get");
+        assert_eq!(None, cache.get("https://example.com/a"));
+        cache.put("https://example.com/a", "some text").unwrap();
+        assert_eq!(Some("some text".to_owned()), cache.get("https://example.com/a"));
+        assert_eq!(None, cache.get("https://example.com/b"));
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn binary_body() {
+        let cache = temp_cache("binary");
+        let body = [0x00, 0xff, 0xfe, 0x80, 0x0a];
+        cache.put_bytes("https://example.com/a", &body).unwrap();
+        assert_eq!(Some(body.to_vec()), cache.get_bytes("https://example.com/a"));
+        // Not UTF-8, so not a text body
+        assert_eq!(None, cache.get("https://example.com/a"));
+        cache.put("https://example.com/b", "some text").unwrap();
+        assert_eq!(Some(b"some text".to_vec()), cache.get_bytes("https://example.com/b"));
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn shared_content() {
+        let cache = temp_cache("shared");
+        cache.put("https://example.com/a", "same text").unwrap();
+        cache.put("https://example.com/b", "same text").unwrap();
+        cache.put("https://example.com/c", "other text").unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!(CacheStats { urls: 3, entries: 2, bytes: 19, orphans: 0, missing: 0 }, stats);
+
+        // A URL whose content changed leaves its old body behind
+        cache.put("https://example.com/c", "new text").unwrap();
+        assert_eq!(1, cache.stats().unwrap().orphans);
+        assert_eq!(3, cache.clean().unwrap());
+        assert_eq!(CacheStats::default(), cache.stats().unwrap());
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn corrupted_entry() {
+        let cache = temp_cache("corrupted");
+        cache.put("https://example.com/a", "some text").unwrap();
+        let digest = cache.index().unwrap()["https://example.com/a"].digest.clone();
+        fs::write(cache.entry_path(&digest), "tampered").unwrap();
+        assert_eq!(None, cache.get("https://example.com/a"));
+        assert_eq!(None, cache.get_bytes("https://example.com/a"));
+        fs::remove_file(cache.entry_path(&digest)).unwrap();
+        assert_eq!(1, cache.stats().unwrap().missing);
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn repaired_entry() {
+        let cache = temp_cache("repaired");
+        cache.put("https://example.com/a", "some text").unwrap();
+        let digest = cache.index().unwrap()["https://example.com/a"].digest.clone();
+        fs::write(cache.entry_path(&digest), "some t").unwrap();
+        assert_eq!(None, cache.get("https://example.com/a"));
+
+        // Downloading again replaces the truncated body
+        cache.put("https://example.com/a", "some text").unwrap();
+        assert_eq!(Some("some text".to_owned()), cache.get("https://example.com/a"));
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn corrupted_index_record() {
+        let cache = temp_cache("record");
+        cache.put("https://example.com/a", "some text").unwrap();
+        cache.put("https://example.com/b", "other text").unwrap();
+        fs::write(cache.record_path("https://example.com/a"), "{").unwrap();
+        assert_eq!(None, cache.get("https://example.com/a"));
+        assert_eq!(vec!["https://example.com/b"], cache.index().unwrap().keys().collect::<Vec<_>>());
+
+        // The next put replaces the record
+        cache.put("https://example.com/a", "some text").unwrap();
+        assert_eq!(Some("some text".to_owned()), cache.get("https://example.com/a"));
+        assert_eq!(2, cache.index().unwrap().len());
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn concurrent_puts() {
+        let cache = temp_cache("concurrent");
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for j in 0..10 {
+                        cache.put(&format!("https://example.com/{}/{}", i, j), &format!("body {} {}", i, j)).unwrap();
+                    }
+                });
+            }
+        });
+        // No put lost another one's entry
+        assert_eq!(80, cache.index().unwrap().len());
+        assert_eq!(Some("body 3 7".to_owned()), cache.get("https://example.com/3/7"));
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn expiry() {
+        let cache = temp_cache("expiry");
+        cache.put("https://example.com/a", "some text").unwrap();
+        let mut entry = cache.entry("https://example.com/a").unwrap();
+        entry.fetched -= 7200;
+        cache.save_entry("https://example.com/a", entry).unwrap();
+
+        let fresh = Cache::new(cache.dir()).with_ttl(Duration::from_secs(86400));
+        assert_eq!(Some("some text".to_owned()), fresh.get("https://example.com/a"));
+        let expired = CacheConfig::default().with_dir(cache.dir()).with_ttl(Duration::from_secs(3600)).build();
+        assert_eq!(None, expired.get("https://example.com/a"));
+        cache.clean().unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn atomic_writes() {
+        let cache = temp_cache("atomic");
+        fs::create_dir_all(cache.dir()).unwrap();
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    let path = cache.dir().join("shared.txt");
+                    Cache::write_atomic(&path, i.to_string().repeat(10000).as_bytes()).unwrap();
+                });
+            }
+        });
+        // One of the writes, whole, and no temporary file left behind
+        let contents = fs::read_to_string(cache.dir().join("shared.txt")).unwrap();
+        assert_eq!(10000, contents.len());
+        assert!(contents.chars().all(|c| contents.starts_with(c)));
+        assert_eq!(1, fs::read_dir(cache.dir()).unwrap().count());
+        fs::remove_file(cache.dir().join("shared.txt")).unwrap();
+        fs::remove_dir(cache.dir()).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CBC-MAC computation and block stitching
+
+use std::error;
+use crate::blocks;
+use crate::crypto::{Aes128Cbc, BytesCrypto};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// AES block size
+pub const BLOCK_SIZE: usize = 16;
+
+/// Compute the AES-128 CBC-MAC of a message
+///
+/// The message is PKCS#7 padded and the MAC is the last ciphertext block.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::cbcmac;
+///
+/// let mac = cbcmac::cbc_mac(b"YELLOW SUBMARINE", &[0; 16], b"alert('MZA who was that?');\n").unwrap();
+/// assert_eq!(16, mac.len());
+/// ```
+pub fn cbc_mac(key: &[u8], iv: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+    let ciphertext = Aes128Cbc::new(key, iv)?.encrypt(message);
+    Ok(ciphertext[(ciphertext.len() - BLOCK_SIZE)..].to_vec())
+}
+
+/// Pad a message with PKCS#7, the way CBC-MAC does before encrypting it
+pub fn pkcs7_pad(message: &[u8]) -> Vec<u8> {
+    blocks::pkcs7_pad(message, BLOCK_SIZE)
+}
+
+/// Glue a prefix in front of a target message without changing the target's CBC-MAC
+///
+/// `prefix_mac` is the CBC-MAC of `prefix` and `target` must have been MACed with a
+/// zero IV. The padded prefix leaves the CBC state at `prefix_mac`, so XORing it into
+/// the target's first block brings the chain back to the state the target started from.
+///
+/// # Panics:
+///
+/// The function panics if the target is shorter than one block.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::cbcmac;
+///
+/// let key = b"YELLOW SUBMARINE";
+/// let iv = [0; 16];
+/// let target = b"the original and genuine message";
+/// let prefix = b"something else";
+///
+/// let prefix_mac = cbcmac::cbc_mac(key, &iv, prefix).unwrap();
+/// let forged = cbcmac::stitch(prefix, &prefix_mac, target);
+/// assert_eq!(cbcmac::cbc_mac(key, &iv, target).unwrap(), cbcmac::cbc_mac(key, &iv, &forged).unwrap());
+/// ```
+pub fn stitch(prefix: &[u8], prefix_mac: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut forged = pkcs7_pad(prefix);
+    assert!(target.len() >= BLOCK_SIZE, "target must be at least one block long");
+    forged.extend(target[..BLOCK_SIZE].xor(prefix_mac));
+    forged.extend_from_slice(&target[BLOCK_SIZE..]);
+    forged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::HexString;
+
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+    const IV: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+
+    #[test]
+    fn challenge50_hash() {
+        assert_eq!(
+            "296b8d7cb78a243dda4d0a61d33bbdd1".hex2bytes().unwrap(),
+            cbc_mac(KEY, &IV, b"alert('MZA who was that?');\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn pad_full_block() {
+        assert_eq!(vec![16; 16], pkcs7_pad(b""));
+        assert_eq!(32, pkcs7_pad(&[0; 16]).len());
+        assert_eq!(b"YELLOW\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a\x0a".to_vec(), pkcs7_pad(b"YELLOW"));
+    }
+
+    #[test]
+    fn stitch_keeps_mac() {
+        let target = b"alert('MZA who was that?');\n";
+        let prefix = b"alert('Ayo, the Wu is back!');//";
+        let forged = stitch(prefix, &cbc_mac(KEY, &IV, prefix).unwrap(), target);
+        assert!(forged.starts_with(prefix));
+        assert_eq!(cbc_mac(KEY, &IV, target).unwrap(), cbc_mac(KEY, &IV, &forged).unwrap());
+    }
+
+    #[test]
+    fn bad_key_length() {
+        assert!(cbc_mac(b"short", &IV, b"message").is_err());
+    }
+}
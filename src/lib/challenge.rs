@@ -0,0 +1,180 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Common interface of the challenge solutions
+//!
+//! Challenges narrate their progress to a `Context`, and return what they
+//! recovered so that tests can check the answers without parsing the output.
+
+use std::error::Error;
+use serde::{Serialize, Serializer};
+use crate::crypto::BytesCrypto;
+use crate::output::Context;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Serialize optional bytes as a hexadecimal string
+fn serialize_hex<S: Serializer>(bytes: &Option<Vec<u8>>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&bytes.bytes2hex()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Answer of a challenge
+///
+/// Serialized with the key and the plaintext in hexadecimal.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChallengeOutcome {
+    /// Recovered or given key
+    #[serde(serialize_with = "serialize_hex")]
+    pub key: Option<Vec<u8>>,
+    /// Recovered plaintext
+    #[serde(serialize_with = "serialize_hex")]
+    pub plaintext: Option<Vec<u8>>,
+    /// Any other answer, e.g. an encoded output or the index of a ciphertext
+    pub answer: Option<String>,
+    /// Score of the recovered plaintext, its meaning depends on the challenge
+    pub score: Option<f64>,
+}
+
+impl ChallengeOutcome {
+    pub fn with_key(mut self, key: &[u8]) -> ChallengeOutcome {
+        self.key = Some(key.to_vec());
+        self
+    }
+
+    pub fn with_plaintext(mut self, plaintext: &[u8]) -> ChallengeOutcome {
+        self.plaintext = Some(plaintext.to_vec());
+        self
+    }
+
+    pub fn with_answer(mut self, answer: &str) -> ChallengeOutcome {
+        self.answer = Some(answer.to_owned());
+        self
+    }
+
+    pub fn with_score(mut self, score: f64) -> ChallengeOutcome {
+        self.score = Some(score);
+        self
+    }
+
+    /// Plaintext as text, None if missing or not UTF-8
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::challenge::ChallengeOutcome;
+    ///
+    /// let outcome = ChallengeOutcome::default().with_key(b"ICE").with_plaintext(b"Burning 'em");
+    /// assert_eq!(Some("Burning 'em"), outcome.text());
+    /// ```
+    pub fn text(&self) -> Option<&str> {
+        self.plaintext.as_ref().and_then(|plaintext| std::str::from_utf8(plaintext).ok())
+    }
+}
+
+/// A solved challenge
+pub trait Challenge {
+    /// Number of the challenge on cryptopals.com
+    fn number(&self) -> u32;
+
+    /// Solve the challenge, narrating to `ctx`
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome>;
+}
+
+/// Run a challenge, narrating to the context
+pub type Runner = fn(&Context) -> Result<ChallengeOutcome>;
+
+/// Entry of the challenge registry, built by `register_challenges!`
+#[derive(Clone, Copy)]
+pub struct Registration {
+    pub set: u32,
+    /// Number of the challenge on cryptopals.com
+    pub number: u32,
+    pub title: &'static str,
+    pub runner: Runner,
+}
+
+impl Registration {
+    /// Solve the challenge, narrating to `ctx`
+    pub fn run(&self, ctx: &Context) -> Result<ChallengeOutcome> {
+        (self.runner)(ctx)
+    }
+}
+
+/// Declare the challenge modules of a set and register them in its `CHALLENGES`
+///
+/// Each entry gives the number, the module and either the `Challenge` of the
+/// module, or its solving function followed by `()`, and the title.
+/// Attributes such as `#[cfg]` apply to the module and its entry. A test
+/// checks that every `Challenge` has the number it is registered under.
+///
+/// # Examples
+///
+/// ```ignore
+/// cryptopals::register_challenges! {
+///     set 1;
+///     1 => challenge1::Challenge1, "Convert hex to base64";
+///     2 => challenge2::Challenge2, "Fixed XOR";
+/// }
+///
+/// cryptopals::register_challenges! {
+///     set 8;
+///     57 => challenge57::main(), "Diffie-Hellman Revisited: Small Subgroup Confinement";
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_challenges {
+    (set $set:literal; $($(#[$attr:meta])* $number:literal => $module:ident::$solution:ident, $title:literal;)*) => {
+        $($(#[$attr])* mod $module;)*
+
+        /// Challenges of the set, in order
+        pub const CHALLENGES: &[$crate::challenge::Registration] = &[$(
+            $(#[$attr])*
+            $crate::challenge::Registration {
+                set: $set,
+                number: $number,
+                title: $title,
+                runner: |ctx| $crate::challenge::Challenge::run(&$module::$solution, ctx),
+            },
+        )*];
+
+        #[cfg(test)]
+        mod registration_test {
+            #[test]
+            fn numbering() {
+                $(
+                    $(#[$attr])*
+                    assert_eq!($number, $crate::challenge::Challenge::number(&super::$module::$solution));
+                )*
+            }
+        }
+    };
+    (set $set:literal; $($(#[$attr:meta])* $number:literal => $module:ident::$function:ident(), $title:literal;)*) => {
+        $($(#[$attr])* mod $module;)*
+
+        /// Challenges of the set, in order
+        pub const CHALLENGES: &[$crate::challenge::Registration] = &[$(
+            $(#[$attr])*
+            $crate::challenge::Registration {
+                set: $set,
+                number: $number,
+                title: $title,
+                runner: $module::$function,
+            },
+        )*];
+    };
+}
@@ -0,0 +1,511 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Stream ciphers: AES in CTR mode and RC4
+
+use std::error;
+use aes::Aes128;
+use block_modes::{BlockMode, Ecb};
+use block_modes::block_padding::NoPadding;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// AES block size
+pub const BLOCK_SIZE: usize = 16;
+
+/// Cipher which XORs the data with a keystream
+///
+/// Every call starts the keystream from the same position, so that the same
+/// cipher encrypts and decrypts.
+pub trait StreamCipher {
+    /// Generate `len` bytes of keystream
+    fn keystream(&self, len: usize) -> Vec<u8>;
+
+    /// Encrypt or decrypt data, both operations are the same
+    fn apply_keystream(&self, data: &[u8]) -> Vec<u8> {
+        data.iter().zip(self.keystream(data.len()))
+            .map(|(a, b)| a ^ b)
+            .collect()
+    }
+
+    /// Replace the plaintext of `ciphertext` from byte `offset` with `newtext`,
+    /// without decrypting the rest
+    ///
+    /// The ciphertext grows if `newtext` goes past its end. The keystream is
+    /// generated up to the end of the edit, unless the cipher can seek.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::cipher::{Rc4, StreamCipher};
+    ///
+    /// let rc4 = Rc4::new(b"Key").unwrap();
+    /// let ciphertext = rc4.apply_keystream(b"Play that funky music");
+    /// let edited = rc4.edit(&ciphertext, 5, b"this").unwrap();
+    /// assert_eq!(b"Play this funky music".to_vec(), rc4.apply_keystream(&edited));
+    /// ```
+    fn edit(&self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        if offset > ciphertext.len() {
+            return Err(format!("edit offset {} past the end of a {} bytes ciphertext", offset, ciphertext.len()).into());
+        }
+        let keystream = self.keystream(offset + newtext.len());
+        Ok(splice(ciphertext, offset, newtext.iter().zip(&keystream[offset..]).map(|(a, b)| a ^ b)))
+    }
+}
+
+/// Replace the bytes of `data` from `offset` with `new`, growing it if needed
+fn splice(data: &[u8], offset: usize, new: impl Iterator<Item = u8>) -> Vec<u8> {
+    let mut spliced = data[..offset].to_vec();
+    spliced.extend(new);
+    if let Some(rest) = data.get(spliced.len()..) {
+        spliced.extend_from_slice(rest);
+    }
+    spliced
+}
+
+/// Layout of the counter blocks of CTR mode
+///
+/// Implementations disagree on where the nonce goes and how the counter is
+/// encoded, the layout must match the tool which produced the ciphertext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrLayout {
+    /// cryptopals: a 64-bit little endian nonce, then a 64-bit little endian block counter from 0
+    LittleEndian64 { nonce: u64 },
+    /// A 128-bit big endian counter starting at the IV, as OpenSSL's `aes-128-ctr` and NIST SP 800-38A
+    BigEndian128 { iv: [u8; BLOCK_SIZE] },
+    /// A 96-bit nonce, then a 32-bit big endian counter starting at `initial`,
+    /// 1 for RFC 3686 and 2 for GCM
+    Nonce96 { nonce: [u8; 12], initial: u32 },
+    /// Any other nonce size, counter size, endianness and starting value
+    Custom(CounterFormat),
+}
+
+/// Byte order of a CTR counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Counter block made of a nonce followed by a counter filling the rest of the block
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::cipher::{CounterFormat, CtrLayout, Endianness};
+///
+/// // A 32-bit nonce and a 96-bit little endian counter starting at 5
+/// let format = CounterFormat::new(&[0xaa; 4]).unwrap().with_endianness(Endianness::Little).with_initial(5);
+/// assert_eq!(12, format.counter_size());
+/// let block = CtrLayout::Custom(format).counter_block(1);
+/// assert_eq!([0xaa, 0xaa, 0xaa, 0xaa, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], block);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterFormat {
+    nonce: [u8; BLOCK_SIZE],
+    nonce_len: usize,
+    endianness: Endianness,
+    initial: u128,
+}
+
+impl CounterFormat {
+    /// Format with a big endian counter from 0, the nonce must leave at least one byte for the counter
+    pub fn new(nonce: &[u8]) -> Result<CounterFormat> {
+        if nonce.len() >= BLOCK_SIZE {
+            return Err(format!("a {} bytes nonce leaves no room for the counter", nonce.len()).into());
+        }
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..nonce.len()].copy_from_slice(nonce);
+        Ok(CounterFormat { nonce: block, nonce_len: nonce.len(), endianness: Endianness::Big, initial: 0 })
+    }
+
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Value of the counter in the first block, reduced to the counter size
+    pub fn with_initial(mut self, initial: u128) -> Self {
+        self.initial = initial & self.counter_mask();
+        self
+    }
+
+    /// Counter size in bytes
+    pub fn counter_size(&self) -> usize {
+        BLOCK_SIZE - self.nonce_len
+    }
+
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce[..self.nonce_len]
+    }
+
+    fn counter_mask(&self) -> u128 {
+        u128::MAX >> (8 * self.nonce_len)
+    }
+
+    /// Counter block number `index`, the counter wraps around within its size
+    pub fn counter_block(&self, index: u64) -> [u8; BLOCK_SIZE] {
+        let counter = self.initial.wrapping_add(index as u128) & self.counter_mask();
+        let mut block = self.nonce;
+        let size = self.counter_size();
+        match self.endianness {
+            Endianness::Little => block[self.nonce_len..].copy_from_slice(&counter.to_le_bytes()[..size]),
+            Endianness::Big => block[self.nonce_len..].copy_from_slice(&counter.to_be_bytes()[BLOCK_SIZE - size..]),
+        }
+        block
+    }
+}
+
+impl CtrLayout {
+    /// Counter block number `index`
+    ///
+    /// The counters wrap around silently, like most implementations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::cipher::CtrLayout;
+    ///
+    /// let layout = CtrLayout::BigEndian128 { iv: [0xff; 16] };
+    /// assert_eq!([0; 16], layout.counter_block(1));
+    /// ```
+    pub fn counter_block(&self, index: u64) -> [u8; BLOCK_SIZE] {
+        let mut block = [0u8; BLOCK_SIZE];
+        match *self {
+            CtrLayout::LittleEndian64 { nonce } => {
+                block[..8].copy_from_slice(&nonce.to_le_bytes());
+                block[8..].copy_from_slice(&index.to_le_bytes());
+            }
+            CtrLayout::BigEndian128 { iv } => {
+                block = u128::from_be_bytes(iv).wrapping_add(index as u128).to_be_bytes();
+            }
+            CtrLayout::Nonce96 { nonce, initial } => {
+                block[..12].copy_from_slice(&nonce);
+                block[12..].copy_from_slice(&initial.wrapping_add(index as u32).to_be_bytes());
+            }
+            CtrLayout::Custom(format) => block = format.counter_block(index),
+        }
+        block
+    }
+}
+
+/// AES-128 in CTR mode, with the cryptopals keystream format by default
+///
+/// Each keystream block is the encryption of a 64-bit little endian nonce
+/// followed by a 64-bit little endian block counter, unless another
+/// `CtrLayout` is chosen. Every call starts the keystream at the position
+/// set by `seek`, the beginning by default.
+#[derive(Clone)]
+pub struct Aes128Ctr {
+    key: Vec<u8>,
+    layout: CtrLayout,
+    /// Offset in bytes of the keystream
+    position: u64,
+}
+
+impl Aes128Ctr {
+    /// Create a CTR cipher, the key must be 16 bytes long
+    pub fn new(key: &[u8], nonce: u64) -> Result<Aes128Ctr> {
+        Aes128Ctr::with_layout(key, CtrLayout::LittleEndian64 { nonce })
+    }
+
+    /// Create a CTR cipher with another layout of the counter blocks
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::cipher::{Aes128Ctr, CtrLayout, StreamCipher};
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// // echo -n Hello | openssl enc -aes-128-ctr -K 59454c4c4f57205355424d4152494e45 -iv 00000000000000000000000000000000
+    /// let ctr = Aes128Ctr::with_layout(b"YELLOW SUBMARINE", CtrLayout::BigEndian128 { iv: [0; 16] }).unwrap();
+    /// let ciphertext = "3eb4a727c0".hex2bytes().unwrap();
+    /// assert_eq!(b"Hello".to_vec(), ctr.apply_keystream(&ciphertext));
+    /// ```
+    pub fn with_layout(key: &[u8], layout: CtrLayout) -> Result<Aes128Ctr> {
+        if key.len() != BLOCK_SIZE {
+            return Err(format!("invalid AES-128 key length: {}", key.len()).into());
+        }
+        Ok(Aes128Ctr { key: key.to_vec(), layout, position: 0 })
+    }
+
+    /// Layout of the counter blocks
+    pub fn layout(&self) -> CtrLayout {
+        self.layout
+    }
+
+    /// Start the keystream at byte `offset`, to read or write the middle of a ciphertext
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::cipher::{Aes128Ctr, StreamCipher};
+    ///
+    /// let mut ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+    /// let keystream = ctr.keystream(40);
+    /// ctr.seek(13);
+    /// assert_eq!(keystream[13..], ctr.keystream(27)[..]);
+    /// ```
+    pub fn seek(&mut self, offset: u64) {
+        self.position = offset;
+    }
+
+    /// Offset of the keystream set by `seek`
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl StreamCipher for Aes128Ctr {
+    /// Generate `len` bytes of keystream from the current position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::cipher::{Aes128Ctr, StreamCipher};
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+    /// let ciphertext = "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==".base64_decode().unwrap();
+    /// assert_eq!(
+    ///     "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ".as_bytes(),
+    ///     &ctr.apply_keystream(&ciphertext)[..]
+    /// );
+    /// ```
+    fn keystream(&self, len: usize) -> Vec<u8> {
+        let first = self.position / BLOCK_SIZE as u64;
+        let skip = (self.position % BLOCK_SIZE as u64) as usize;
+        let blocks = (skip + len).div_ceil(BLOCK_SIZE);
+        let mut stream = Vec::with_capacity(blocks * BLOCK_SIZE);
+        for index in first..first + blocks as u64 {
+            stream.extend_from_slice(&self.layout.counter_block(index));
+        }
+        // The key length was checked by the constructor
+        let ecb = Ecb::<Aes128, NoPadding>::new_var(&self.key, &[]).unwrap();
+        let stream_len = stream.len();
+        ecb.encrypt(&mut stream, stream_len).unwrap();
+        stream.drain(..skip);
+        stream.truncate(len);
+        stream
+    }
+
+    /// Seek to `offset` instead of generating the keystream before it
+    ///
+    /// The ciphertext must start at the beginning of the keystream, whatever
+    /// the position of this cipher.
+    fn edit(&self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        if offset > ciphertext.len() {
+            return Err(format!("edit offset {} past the end of a {} bytes ciphertext", offset, ciphertext.len()).into());
+        }
+        let mut ctr = self.clone();
+        ctr.seek(offset as u64);
+        Ok(splice(ciphertext, offset, ctr.apply_keystream(newtext).into_iter()))
+    }
+}
+
+/// RC4 stream cipher
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::cipher::{Rc4, StreamCipher};
+/// use cryptopals::crypto::BytesCrypto;
+///
+/// let rc4 = Rc4::new(b"Key").unwrap();
+/// assert_eq!("bbf316e8d940af0ad3", rc4.apply_keystream(b"Plaintext").bytes2hex());
+/// ```
+pub struct Rc4 {
+    /// Permutation after the key schedule
+    state: [u8; 256],
+}
+
+impl Rc4 {
+    /// Run the key schedule, the key must be 1 to 256 bytes long
+    pub fn new(key: &[u8]) -> Result<Rc4> {
+        if key.is_empty() || key.len() > 256 {
+            return Err(format!("invalid RC4 key length: {}", key.len()).into());
+        }
+        let mut state = [0u8; 256];
+        for (i, s) in state.iter_mut().enumerate() {
+            *s = i as u8;
+        }
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
+        }
+        Ok(Rc4 { state })
+    }
+}
+
+impl StreamCipher for Rc4 {
+    fn keystream(&self, len: usize) -> Vec<u8> {
+        let mut state = self.state;
+        let (mut i, mut j) = (0u8, 0u8);
+        (0..len).map(|_| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{BytesCrypto, HexString};
+
+    #[test]
+    fn roundtrip() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 42).unwrap();
+        let plaintext = b"Some text which is longer than a single block of AES";
+        let ciphertext = ctr.apply_keystream(plaintext);
+        assert_eq!(plaintext.len(), ciphertext.len());
+        assert_eq!(plaintext.to_vec(), ctr.apply_keystream(&ciphertext));
+    }
+
+    #[test]
+    fn keystream_length() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+        assert_eq!(0, ctr.keystream(0).len());
+        assert_eq!(17, ctr.keystream(17).len());
+        assert_eq!(ctr.keystream(16), ctr.keystream(17)[..16].to_vec());
+    }
+
+    #[test]
+    fn seek_across_blocks() {
+        let mut ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 7).unwrap();
+        let keystream = ctr.keystream(64);
+        for offset in [0, 1, 15, 16, 17, 40] {
+            ctr.seek(offset as u64);
+            assert_eq!(offset as u64, ctr.position());
+            assert_eq!(keystream[offset..], ctr.keystream(64 - offset)[..]);
+        }
+        assert!(ctr.keystream(0).is_empty());
+    }
+
+    #[test]
+    fn edit_bounds() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0).unwrap();
+        let ciphertext = ctr.apply_keystream(b"short");
+        let grown = ctr.edit(&ciphertext, 3, b"rter than that").unwrap();
+        assert_eq!(b"shorter than that".to_vec(), ctr.apply_keystream(&grown));
+        assert_eq!(ciphertext, ctr.edit(&ciphertext, 5, b"").unwrap());
+        assert!(ctr.edit(&ciphertext, 6, b"x").is_err());
+    }
+
+    /// Edit through the trait, whatever the cipher
+    fn check_edit<C: StreamCipher>(cipher: &C) {
+        let ciphertext = cipher.apply_keystream(b"Play that funky music");
+        let edited = cipher.edit(&ciphertext, 5, b"this").unwrap();
+        assert_eq!(b"Play this funky music".to_vec(), cipher.apply_keystream(&edited));
+        let grown = cipher.edit(&ciphertext, 21, b", white boy").unwrap();
+        assert_eq!(b"Play that funky music, white boy".to_vec(), cipher.apply_keystream(&grown));
+        assert!(cipher.edit(&ciphertext, 22, b"").is_err());
+    }
+
+    #[test]
+    fn edit_stream_ciphers() {
+        check_edit(&Aes128Ctr::new(b"YELLOW SUBMARINE", 3).unwrap());
+        check_edit(&Rc4::new(b"Key").unwrap());
+        check_edit(&crate::mt19937::Mt19937Cipher::new(4242));
+    }
+
+    #[test]
+    fn bad_key() {
+        assert!(Aes128Ctr::new(b"YELLOW", 0).is_err());
+    }
+
+    #[test]
+    fn cryptopals_layout() {
+        let ctr = Aes128Ctr::new(b"YELLOW SUBMARINE", 0x0102).unwrap();
+        assert_eq!(CtrLayout::LittleEndian64 { nonce: 0x0102 }, ctr.layout());
+        assert_eq!("02010000000000000300000000000000", ctr.layout().counter_block(3).bytes2hex());
+    }
+
+    #[test]
+    fn big_endian_layout() {
+        // NIST SP 800-38A, F.5.1 CTR-AES128.Encrypt
+        let key = "2b7e151628aed2a6abf7158809cf4f3c".hex2bytes().unwrap();
+        let mut iv = [0u8; BLOCK_SIZE];
+        iv.copy_from_slice(&"f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff".hex2bytes().unwrap());
+        let ctr = Aes128Ctr::with_layout(&key, CtrLayout::BigEndian128 { iv }).unwrap();
+        let plaintext = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51".hex2bytes().unwrap();
+        assert_eq!(
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff",
+            ctr.apply_keystream(&plaintext).bytes2hex()
+        );
+        // The carry propagates across the whole block
+        assert_eq!("f0f1f2f3f4f5f6f7f8f9fafbfcfdff00", ctr.layout().counter_block(1).bytes2hex());
+    }
+
+    #[test]
+    fn nonce96_layout() {
+        // RFC 3686, test vector #1
+        let key = "ae6852f8121067cc4bf7a5765577f39e".hex2bytes().unwrap();
+        let layout = CtrLayout::Nonce96 { nonce: [0, 0, 0, 0x30, 0, 0, 0, 0, 0, 0, 0, 0], initial: 1 };
+        let ctr = Aes128Ctr::with_layout(&key, layout).unwrap();
+        assert_eq!("e4095d4fb7a7b3792d6175a3261311b8", ctr.apply_keystream(b"Single block msg").bytes2hex());
+        // Only the last 32 bits count
+        let layout = CtrLayout::Nonce96 { nonce: [0xff; 12], initial: u32::MAX };
+        assert_eq!("ffffffffffffffffffffffff00000000", layout.counter_block(1).bytes2hex());
+    }
+
+    #[test]
+    fn custom_layouts() {
+        let nonce = 0x0102_0304_0506_0708u64;
+        let cryptopals = CounterFormat::new(&nonce.to_le_bytes()).unwrap().with_endianness(Endianness::Little);
+        let mut iv = [0u8; BLOCK_SIZE];
+        iv[15] = 0xfe;
+        let nist = CounterFormat::new(&[]).unwrap().with_initial(u128::from_be_bytes(iv));
+        let rfc3686 = CounterFormat::new(&[7; 12]).unwrap().with_initial(1);
+        for index in [0, 1, 2, u32::MAX as u64, u64::MAX] {
+            assert_eq!(CtrLayout::LittleEndian64 { nonce }.counter_block(index), cryptopals.counter_block(index));
+            assert_eq!(CtrLayout::BigEndian128 { iv }.counter_block(index), nist.counter_block(index));
+            assert_eq!(CtrLayout::Nonce96 { nonce: [7; 12], initial: 1 }.counter_block(index), rfc3686.counter_block(index));
+        }
+        // An 8-bit counter wraps around without touching the nonce
+        let format = CounterFormat::new(&[0xff; 15]).unwrap().with_initial(0x1ff);
+        assert_eq!(0xff, format.counter_block(0)[15]);
+        assert_eq!([0xff; 15][..], format.counter_block(1)[..15]);
+        assert_eq!(0, format.counter_block(1)[15]);
+        assert!(CounterFormat::new(&[0; 16]).is_err());
+    }
+
+    #[test]
+    fn rc4_vectors() {
+        assert_eq!("1021bf0420", Rc4::new(b"Wiki").unwrap().apply_keystream(b"pedia").bytes2hex());
+        assert_eq!(
+            "45a01f645fc35b383552544b9bf5",
+            Rc4::new(b"Secret").unwrap().apply_keystream(b"Attack at dawn").bytes2hex()
+        );
+    }
+
+    #[test]
+    fn rc4_rfc6229() {
+        // RFC 6229, 40-bit key, keystream offsets 0 and 16
+        let keystream = Rc4::new(&[1, 2, 3, 4, 5]).unwrap().keystream(32);
+        assert_eq!("b2396305f03dc027ccc3524a0a1118a8", keystream[..16].bytes2hex());
+        assert_eq!("6982944f18fc82d589c403a47a0d0919", keystream[16..].bytes2hex());
+    }
+
+    #[test]
+    fn rc4_bad_key() {
+        assert!(Rc4::new(b"").is_err());
+        assert!(Rc4::new(&[0; 257]).is_err());
+        assert!(Rc4::new(&[0; 256]).is_ok());
+    }
+}
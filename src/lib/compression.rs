@@ -0,0 +1,236 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Compression ratio side channel (CRIME) oracle and attack
+
+use std::error;
+use std::io::Write;
+use flate2::Compression;
+use rand::RngCore;
+use flate2::write::DeflateEncoder;
+use crate::cipher::{Aes128Ctr, StreamCipher};
+use crate::crypto::Aes128Cbc;
+use crate::keygen;
+use crate::oracle::ParallelOracle;
+use crate::timebox::{NoProgress, Progress};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Session id hidden in the requests of challenge 51
+pub const SESSION_ID: &str = "TmV2ZXIgcmV2ZWFsIHRoZSBXdS1UYW5nIFNlY3JldCE=";
+
+/// Name of the cookie holding the session id
+pub const COOKIE_NAME: &str = "sessionid=";
+
+/// Characters a session id can be made of, plus the end of line which ends it
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/=\n";
+
+/// Known text right before the session id in the request
+const ANCHOR: &str = "\nCookie: sessionid=";
+
+/// Length of the known text repeated in the payload
+///
+/// A match of 19 bytes and its extension by a right guess use the same DEFLATE
+/// length code (19 to 22), so the guess only changes the cost of a literal and
+/// not the Huffman table.
+const TAIL_LEN: usize = 19;
+
+/// Cipher used to encrypt the compressed request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Stream cipher, the ciphertext length is the compressed length
+    Ctr,
+    /// Block cipher, the ciphertext length is rounded to the block size
+    Cbc,
+}
+
+/// Oracle returning the length of a compressed then encrypted request
+pub struct CompressionOracle {
+    mode: Mode,
+    session_id: String,
+}
+
+impl CompressionOracle {
+    pub fn new(mode: Mode, session_id: &str) -> CompressionOracle {
+        CompressionOracle { mode, session_id: session_id.to_owned() }
+    }
+
+    /// Build the HTTP request embedding the session id and the attacker's payload
+    pub fn format_request(&self, payload: &[u8]) -> Vec<u8> {
+        let mut request = format!(
+            "POST / HTTP/1.1\nHost: hapless.com\nCookie: {}{}\nContent-Length: {}\n",
+            COOKIE_NAME, self.session_id, payload.len()
+        ).into_bytes();
+        request.extend_from_slice(payload);
+        request
+    }
+
+    /// Compress and encrypt the request under a fresh random key, return the ciphertext length
+    pub fn length(&self, payload: &[u8]) -> usize {
+        self.length_with_rng(payload, &mut rand::thread_rng())
+    }
+
+    /// Same as `length`, drawing the key from `rng`
+    pub fn length_with_rng<R: RngCore + ?Sized>(&self, payload: &[u8], rng: &mut R) -> usize {
+        let compressed = compress(&self.format_request(payload));
+        let key = keygen::aes_key(rng);
+        match self.mode {
+            Mode::Ctr => {
+                let nonce = keygen::ctr_nonce(rng);
+                Aes128Ctr::new(&key, nonce).unwrap().apply_keystream(&compressed).len()
+            }
+            Mode::Cbc => {
+                let iv = keygen::iv(rng);
+                Aes128Cbc::new(&key, &iv).unwrap().encrypt(&compressed).len()
+            }
+        }
+    }
+}
+
+/// Compress data with DEFLATE
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::compression;
+///
+/// assert!(compression::compress(&[b'A'; 100]).len() < 100);
+/// ```
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec cannot fail
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Recover the session id byte by byte from the ciphertext lengths
+///
+/// The payload repeats the end of the known request text followed by a guess,
+/// the right guess compresses better against the real cookie. The payload is
+/// preceded by a growing padding of distinct non-ASCII bytes, which compress badly:
+/// with a block cipher, the lengths only differ once the padding pushes the
+/// compressed request right before a block boundary.
+///
+/// The guesses for a given padding are independent, they are sent in parallel.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::compression::{self, CompressionOracle, Mode};
+///
+/// let oracle = CompressionOracle::new(Mode::Ctr, "SGVsbG8=");
+/// let session_id = compression::recover_session_id(|p| oracle.length(p)).unwrap();
+/// assert_eq!("SGVsbG8=", session_id);
+/// ```
+pub fn recover_session_id<F: Fn(&[u8]) -> usize + Sync>(oracle: F) -> Result<String> {
+    recover_session_id_with_progress(oracle, &NoProgress)
+}
+
+/// Same as `recover_session_id`, reporting the oracle queries and each recovered character
+pub fn recover_session_id_with_progress<F, P>(oracle: F, progress: &P) -> Result<String>
+    where F: Fn(&[u8]) -> usize + Sync, P: Progress + ?Sized
+{
+    let oracle = ParallelOracle::new(|payload: &Vec<u8>| oracle(payload));
+    let mut queries = 0;
+    let padding: Vec<u8> = (0x80..=0xff).collect();
+    let mut known = ANCHOR.as_bytes().to_vec();
+
+    loop {
+        let tail = &known[(known.len() - TAIL_LEN)..];
+        let mut candidates = ALPHABET.to_vec();
+        for padding_len in 0..padding.len() {
+            let payloads: Vec<Vec<u8>> = candidates.iter()
+                .map(|&c| {
+                    let mut payload = padding[..padding_len].to_vec();
+                    payload.extend_from_slice(tail);
+                    payload.push(c);
+                    payload
+                })
+                .collect();
+            let lengths = oracle.query_all(&payloads);
+            queries += payloads.len() as u64;
+            progress.step(queries, None);
+            let min = *lengths.iter().min().unwrap();
+            candidates = candidates.iter().zip(lengths)
+                .filter(|(_, len)| *len == min)
+                .map(|(&c, _)| c)
+                .collect();
+            if candidates.len() == 1 {
+                break;
+            }
+        }
+        if candidates.len() != 1 {
+            return Err(format!(
+                "cannot decide the byte following {:?}", String::from_utf8_lossy(&known)).into());
+        }
+        if candidates[0] == b'\n' {
+            break;
+        }
+        progress.recovered(known.len() - ANCHOR.len(), &candidates);
+        known.push(candidates[0]);
+        debug!("Recovered so far: {:?}", String::from_utf8_lossy(&known[ANCHOR.len()..]));
+        if known.len() > ANCHOR.len() + 256 {
+            return Err("session id too long, the attack went astray".into());
+        }
+    }
+
+    Ok(String::from_utf8(known[ANCHOR.len()..].to_vec())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cipher::BLOCK_SIZE;
+    use crate::timebox::ProgressCounter;
+
+    #[test]
+    fn request_format() {
+        let oracle = CompressionOracle::new(Mode::Ctr, "abc");
+        assert_eq!(
+            b"POST / HTTP/1.1\nHost: hapless.com\nCookie: sessionid=abc\nContent-Length: 3\nxyz".to_vec(),
+            oracle.format_request(b"xyz")
+        );
+    }
+
+    #[test]
+    fn cbc_length_is_block_aligned() {
+        let oracle = CompressionOracle::new(Mode::Cbc, SESSION_ID);
+        assert_eq!(0, oracle.length(b"anything") % BLOCK_SIZE);
+    }
+
+    #[test]
+    fn attack_ctr() {
+        let oracle = CompressionOracle::new(Mode::Ctr, SESSION_ID);
+        assert_eq!(SESSION_ID, recover_session_id(|p| oracle.length(p)).unwrap());
+    }
+
+    #[test]
+    fn progress_reports() {
+        let oracle = CompressionOracle::new(Mode::Ctr, "SGVsbG8=");
+        let counter = ProgressCounter::default();
+        assert_eq!("SGVsbG8=", recover_session_id_with_progress(|p| oracle.length(p), &counter).unwrap());
+        let recovered: Vec<(usize, Vec<u8>)> = b"SGVsbG8=".iter().enumerate().map(|(i, &c)| (i, vec![c])).collect();
+        assert_eq!(recovered, counter.recovered_bytes());
+        // At least one query per candidate character, for each character and the end of line
+        let (queries, _) = counter.last_step().unwrap();
+        assert!(queries >= 9 * ALPHABET.len() as u64);
+    }
+
+    #[test]
+    fn attack_cbc() {
+        let oracle = CompressionOracle::new(Mode::Cbc, SESSION_ID);
+        assert_eq!(SESSION_ID, recover_session_id(|p| oracle.length(p)).unwrap());
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! User configuration, read from a JSON file
+//!
+//! The file is `cryptopals.json` in the current directory, or the path in the
+//! `CRYPTOPALS_CONFIG` environment variable. Missing fields keep their defaults.
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Environment variable overriding the configuration file path
+pub const CONFIG_ENV: &str = "CRYPTOPALS_CONFIG";
+
+/// Configuration file used when `CRYPTOPALS_CONFIG` is not set
+pub const DEFAULT_CONFIG_FILE: &str = "cryptopals.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Weight of each English scorer in the ensemble, by scorer name
+    pub scoring_weights: BTreeMap<String, f64>,
+}
+
+impl Default for Config {
+    /// Only the Euclidean distance to the corpus frequencies
+    fn default() -> Config {
+        let mut scoring_weights = BTreeMap::new();
+        scoring_weights.insert("euclidean".to_owned(), 1.0);
+        Config { scoring_weights }
+    }
+}
+
+impl Config {
+    /// Path of the configuration file
+    pub fn path() -> PathBuf {
+        std::env::var_os(CONFIG_ENV).map_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE), PathBuf::from)
+    }
+
+    /// Read the configuration file, or return the defaults if there is none
+    pub fn load() -> Result<Config> {
+        match fs::read_to_string(Self::path()) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    /// Write the configuration file, through a temporary file
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_fields() {
+        assert_eq!(Config::default(), serde_json::from_str::<Config>("{}").unwrap());
+        let config: Config = serde_json::from_str(r#"{"scoring_weights": {"pearson": 0.5}}"#).unwrap();
+        assert_eq!(Some(&0.5), config.scoring_weights.get("pearson"));
+        assert_eq!(None, config.scoring_weights.get("euclidean"));
+    }
+}
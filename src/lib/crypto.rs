@@ -13,334 +13,456 @@
 // limitations under the License.
 
 //! String, cryptographic and mathematical functions
-
-use std::{error, fmt};
-use std::fmt::Write;
-use std::char;
-use std::cell::RefCell;
-use std::rc::Rc;
+//!
+//! The hex, Base64, XOR and AES-128 primitives are in `primitives`, which
+//! builds without std, and are re-exported here.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 use crate::{english, stats};
-
-const BASE64_ALPHABET: [char; 65] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '+', '/', '='
-];
-
-
-// Create a custom error and boxing dyn errors
-
-type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
-
-#[derive(Debug, PartialEq)]
-struct InvalidHexString;
-
-impl fmt::Display for InvalidHexString {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid hexadecimal string")
+use crate::config::Config;
+use crate::language::Language;
+use crate::scoring::{Ensemble, Ngrams, Scorer};
+use crate::error::Result;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub use crate::primitives::{
+    Aes128Cbc, Aes128Ecb, Alphabet, BytesCrypto, DecodeConfig, HexFormat, HexString,
+    AES_BLOCK_SIZE, MIME_LINE_WIDTH, ct_eq, xor_with_key_into,
+};
+
+/// Compare two byte arrays byte by byte, stopping at the first difference
+///
+/// Sleeping `delay` after each equal byte makes the leak measurable, like the
+/// `insecure_compare` of the timing attack challenges.
+pub fn leaky_eq(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return false;
+        }
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
     }
+    true
 }
 
-impl error::Error for InvalidHexString {}
-
-/// Add hexadecimal string manipulation to strings.
-pub trait HexString {
-    fn hex2bytes(&self) -> Result<Vec<u8>>;
-    fn hex2string(&self) -> Result<String>;
-    fn base64_decode(&self) -> Result<Vec<u8>>;
+/// How a MAC-verifying oracle compares the expected tag with the received one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagComparison {
+    /// Early exit comparison, see `leaky_eq`
+    Leaky { delay: Duration },
+    /// See `ct_eq`
+    ConstantTime,
 }
 
-impl HexString for str {
-    /// Convert a hex string to an array of bytes
+impl TagComparison {
+    /// Whether `tag` is the `expected` tag
     ///
     /// # Examples
     ///
     /// ```
-    /// use cryptopals::crypto::HexString;
+    /// use std::time::Duration;
+    /// use cryptopals::crypto::TagComparison;
     ///
-    /// assert_eq!(vec![65], "41".hex2bytes().unwrap());
-    /// assert_eq!(vec![16, 32, 48], "102030".hex2bytes().unwrap());
-    /// assert!("1020ZZ".hex2bytes().is_err());
+    /// let leaky = TagComparison::Leaky { delay: Duration::from_millis(5) };
+    /// for comparison in [leaky, TagComparison::ConstantTime] {
+    ///     assert!(comparison.verify(b"expected", b"expected"));
+    ///     assert!(!comparison.verify(b"expected", b"received"));
+    /// }
     /// ```
-    fn hex2bytes(&self) -> Result<Vec<u8>> {
-        let l = self.len();
-        if l == 0 || (l & 0b1) == 1 {
-            return Err(Box::new(InvalidHexString));
+    pub fn verify(&self, expected: &[u8], tag: &[u8]) -> bool {
+        match *self {
+            TagComparison::Leaky { delay } => leaky_eq(expected, tag, delay),
+            TagComparison::ConstantTime => ct_eq(expected, tag),
         }
-        (0..l)
-            .step_by(2)
-            .map(|i|
-                u8::from_str_radix(&self[i..i + 2], 16)
-                    .map_err(|e| e.into())) // Converts to Box
-            .collect()
     }
+}
 
-    /// Convert a hex string to a string
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use cryptopals::crypto::HexString;
-    ///
-    /// assert_eq!("A".to_owned(), "41".hex2string().unwrap());
-    /// assert_eq!("the kid don't play", "746865206b696420646f6e277420706c6179".hex2string().unwrap());
-    /// assert!("1020ZZ".hex2string().is_err());
-    /// ```
-    fn hex2string(&self) -> Result<String> {
-        let l = self.len();
-        if l == 0 || (l & 0b1) == 1 {
-            return Err(Box::new(InvalidHexString));
-        }
-        let mut s = String::with_capacity(l / 2);
-        for i in (0..l).step_by(2) {
-            let c = u8::from_str_radix(&self[i..i + 2], 16)? as char;
-            s.push(c);
-        }
-        Ok(s)
-    }
+/// Probability that two bytes drawn from the input without replacement are equal
+///
+/// About 0.065 for English text and 1/256 for random bytes. XORing every
+/// byte with the same key does not change it. Inputs shorter than two bytes
+/// return 0.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::crypto;
+///
+/// assert_eq!(1.0, crypto::index_of_coincidence(b"aaaa"));
+/// assert_eq!(2.0 / 12.0, crypto::index_of_coincidence(b"aabc"));
+/// assert_eq!(0.0, crypto::index_of_coincidence(b"a"));
+/// ```
+pub fn index_of_coincidence(input_bytes: &[u8]) -> f64 {
+    let len = input_bytes.len() as f64;
+    if len < 2.0 {
+        return 0.0;
+    }
+    let histogram = stats::ByteHistogram::from_bytes(input_bytes);
+    let pairs: f64 = (0..=255u8)
+        .map(|byte| histogram.count(byte) as f64)
+        .map(|count| count * (count - 1.0))
+        .sum();
+    pairs / (len * (len - 1.0))
+}
 
-    /// Decode a Base64 string to a byte array
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use cryptopals::crypto::HexString;
-    ///
-    /// assert_eq!(String::from("QUJD").base64_decode().unwrap(), vec![65, 66, 67]);
-    /// assert_eq!("QUJD".base64_decode().unwrap(), &[65, 66, 67]);
-    /// assert_eq!("SGVsbG8sIHdvcmxkIQ==".base64_decode().unwrap(), "Hello, world!".as_bytes());
-    /// ```
-    ///
-    /// # References
-    ///
-    /// This code is inspired by [this article](https://levelup.gitconnected.com/implementing-base64-in-rust-34ef6db1e73a).
-    fn base64_decode(&self) -> Result<Vec<u8>> {
-        let mut padding_count = 0;
-        // We need interior mutability here because we both update and read the byte array's
-        // length in the same expression, but not at the same time.
-        let b64_length = Rc::new(RefCell::new(self.len()));
-        let b64_bytes: Result<Vec<u8>> = self
-            .bytes()
-            // Remove return chars all the while adjusting array length
-            .filter(|&b| {
-                if b != b'\n' && b != b'\r' {
-                    true
-                } else {
-                    *b64_length.borrow_mut() -= 1;
-                    false
-                }
-            })
-            .enumerate()
-            .map(| (index, byte)| {
-                match byte {
-                    // A to Z => 0 to 25
-                    65..=90 => Ok(byte - 65),
-                    // a to z => 26 to 51
-                    97..=122 => Ok(byte - 97 + 26),
-                    // 0 to 9 => 52 to 61
-                    48..=57 => Ok(byte + 4),
-                    // + => 62
-                    43 => Ok(62),
-                    // / => 63
-                    47 => Ok(63),
-                    // = => 0
-                    61 => {
-                        // Equal sign only authorized at end of string
-                        if index >= *b64_length.borrow() - 2 {
-                            padding_count += 1;
-                            Ok(0)
-                        } else {
-                            Err(format!("invalid byte '=' at position {} in Base64 string", index).into())
-                        }
-                    },
-                    _ => Err(format!("invalid byte '{}' (0x{:X}) at position {} in Base64 string", byte as char, byte, index).into())
-                }
-            })
-            .collect();
-        if b64_bytes.is_err() {
-            return b64_bytes;
-        }
-        if *b64_length.borrow() % 4 != 0 {
-            return Err(format!("invalid Base64 length: {}", *b64_length.borrow()).into());
-        }
-        let mut bytes = b64_bytes
-            .unwrap()
-            .chunks(4)
-            .map(|quartet| {
-                let b1 = quartet[0] << 2                | (quartet[1] & 0b00110000) >> 4;
-                let b2 = (quartet[1] & 0b00001111) << 4 | (quartet[2] & 0b00111100) >> 2;
-                let b3 = (quartet[2] & 0b00000011) << 6 | quartet[3];
-                vec![b1, b2, b3]
-            })
-            .flatten()
-            .collect::<Vec<u8>>();
-        // Remove extra bytes created by the padding
-        bytes.resize(bytes.len() - padding_count, 0);
-        Ok(bytes)
-    }
+/// A plaintext of a single-byte XOR ciphertext, with its scores
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub key: u8,
+    pub plaintext: String,
+    /// Euclidean distance to the corpus frequencies, lower is better
+    pub euclidean: f64,
+    /// Pearson correlation with the corpus frequencies, higher is better
+    pub pearson: f64,
+    /// Weighted score of the configured scorers, higher is better
+    pub ensemble: f64,
+    /// Share of common English words, which breaks ensemble ties
+    pub words: f64,
 }
 
-/// Add hexadecimal strings, base64 and xor functions to arrays of bytes.
-pub trait BytesCrypto {
-    fn bytes2hex(&self) -> String;
-    fn base64_encode(&self) -> String;
-    fn xor(&self, other: &[u8]) -> Vec<u8>;
-    fn hamming_distance(&self, other: &[u8]) -> u32;
+/// Every valid plaintext of a single-byte XOR ciphertext, best first
+///
+/// Keys giving invalid UTF-8, or text without letter frequencies, are
+/// skipped. Candidates are ranked by ensemble score, then by share of common
+/// words, then by key. With the `parallel` feature, keys are scored on all
+/// the cores, with the same ranking.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::{crypto, english};
+///
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// let ciphertext: Vec<u8> = b"a lazy dog".iter().map(|byte| byte ^ 42).collect();
+/// let candidates = crypto::rank_candidates(&ciphertext, &corpus_freq);
+/// assert_eq!((42, "a lazy dog"), (candidates[0].key, candidates[0].plaintext.as_str()));
+/// ```
+pub fn rank_candidates(input_bytes: &[u8], corpus_freq: &[f64]) -> Vec<Candidate> {
+    let ensemble = Ensemble::new(scoring_weights(), corpus_freq, scoring_ngrams());
+    let keys = 0u8..=255;
+    #[cfg(feature = "parallel")]
+    let keys = keys.into_par_iter();
+    let mut candidates: Vec<Candidate> = keys
+        .filter_map(|xor| score_candidate(input_bytes, corpus_freq, &ensemble, xor))
+        .collect();
+    // Stable, ties keep the smallest key; common words break ensemble ties,
+    // e.g. between keys flipping the case of letters
+    candidates.sort_by(|a, b| b.ensemble.total_cmp(&a.ensemble).then(b.words.total_cmp(&a.words)));
+    candidates
 }
 
-impl BytesCrypto for [u8] {
-    /// Convert an array of bytes to a hex string
-     ///
-     /// # Examples
-     ///
-     /// ```
-     /// use cryptopals::crypto::BytesCrypto;
-     ///
-     /// assert_eq!("41".to_owned(), vec![65].bytes2hex());
-     /// assert_eq!(
-     ///    "48656c6c6f2c20776f726c6421".to_owned(),
-     ///    vec![72, 101, 108, 108, 111, 44, 32, 119, 111, 114, 108, 100, 33].bytes2hex());
-     /// ```
-    fn bytes2hex(&self) -> String {
-        let mut s = String::with_capacity(self.len() * 2);
-        for b in self {
-            write!(&mut s, "{:02x}", b).unwrap();
+/// Decrypt with one key, reject invalid strings and compute the letter
+/// frequencies and their distance to our English corpus
+fn score_candidate(input_bytes: &[u8], corpus_freq: &[f64], ensemble: &Ensemble, xor: u8) -> Option<Candidate> {
+    let xored_input: Vec<_> = input_bytes.iter()
+        .map(|byte| *byte ^ xor)
+        .collect();
+    let xored_string = match String::from_utf8(xored_input) {
+        Ok(xored_string) => xored_string,
+        Err(_) => {
+            debug!("input xor {} is an invalid string!", xor);
+            return None;
         }
-        s
-    }
+    };
+    let xored_freq = english::calc_frequencies(&xored_string);
+
+    // Without any ASCII character the frequencies are all zero
+    let pearson = match stats::pearson_correlation(corpus_freq, &xored_freq) {
+        Some(score) => score,
+        None => {
+            debug!("input xor {} has no letter frequencies, invalid!", xor);
+            return None;
+        }
+    };
+    let euclidean = english::euclidean_distance(corpus_freq, &xored_freq);
+    let ensemble = ensemble.score_str(&xored_string);
+    let words = english::common_word_share(&xored_string);
 
-    /// Convert an array of bytes to Base64
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use cryptopals::crypto::BytesCrypto;
-    ///
-    /// assert_eq!(String::from("QUJD"), [65, 66, 67].base64_encode());
-    /// assert_eq!(String::from("QUJD"), vec![65, 66, 67].base64_encode());
-    /// assert_eq!(String::from("SGVsbG8sIHdvcmxkIQ=="), "Hello, world!".as_bytes().base64_encode());
-    /// ```
-    ///
-    /// # References
-    ///
-    /// This code is inspired by [this article](https://levelup.gitconnected.com/implementing-base64-in-rust-34ef6db1e73a).
-    fn base64_encode(&self) -> String {
-        self
-            .chunks(3)
-            .map(|chunk| {
-                match chunk.len() {
-                    1 => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4, 64, 64],
-                    2 => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4 | (chunk[1] & 0b11110000) >> 4, (chunk[1] & 0b00001111) << 2, 64],
-                    _ => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4 | (chunk[1] & 0b11110000) >> 4, (chunk[1] & 0b00001111) << 2 | (chunk[2] & 0b11000000) >> 6, chunk[2] & 0b00111111],
-                }.iter()
-                    .map(|x| BASE64_ALPHABET[*x as usize])
-                    .collect::<String>()
-            })
-            .collect::<Vec<String>>()
-            .join("")
-    }
-
-    /// XOR two equal length arrays of bytes
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use cryptopals::crypto::BytesCrypto;
-    ///
-    /// assert_eq!(vec![0], vec![0].xor(&vec![0]));
-    /// assert_eq!(
-    ///    vec![0b11111111, 0b01101100],
-    ///    vec![0b10101010, 0b11111111].xor(&vec![0b01010101, 0b10010011])
-    /// );
-    /// ```
-    fn xor(&self, other: &[u8]) -> Vec<u8> {
-        self.iter().zip(other.iter())
-            .map(|(&x, &y)| x ^ y)
-            .collect()
-    }
+    debug!("input xor {} = '{}'", xor, xored_string);
+    debug!(" - Euclidean score: {}", euclidean);
+    debug!(" - Pearson: {}", pearson);
+    debug!(" - Ensemble: {}", ensemble);
 
-    /// Compute the Hamming distance between two byte arrays
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use cryptopals::crypto::BytesCrypto;
-    ///
-    /// assert_eq!(
-    ///            37,
-    ///            "this is a test".as_bytes()
-    ///                .hamming_distance("wokka wokka!!!".as_bytes()));
-    /// ```
-    fn hamming_distance(&self, other: &[u8]) -> u32 {
-        assert_eq!(self.len(), other.len(), "bytes arrays differ in size");
+    Some(Candidate { key: xor, plaintext: xored_string, euclidean, pearson, ensemble, words })
+}
 
-        self.iter().zip(other.iter())
-            .map(|(a, b)| (a ^ b).count_ones())
-            .sum::<u32>()
+/// A plaintext of a single-byte XOR ciphertext kept as bytes, see `rank_byte_candidates`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteCandidate {
+    pub key: u8,
+    pub plaintext: Vec<u8>,
+    /// Euclidean distance of the ASCII frequencies to the corpus, lower is better
+    pub euclidean: f64,
+    /// Pearson correlation of the ASCII frequencies with the corpus, higher is better
+    pub pearson: f64,
+}
+
+impl ByteCandidate {
+    /// Plaintext for display, invalid UTF-8 sequences replaced by `�`
+    pub fn lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.plaintext)
     }
 }
 
+/// Every plaintext of a single-byte XOR ciphertext with some ASCII, best first
+///
+/// Unlike `rank_candidates`, plaintexts are never converted to strings, so
+/// that binary or Latin-1 plaintexts are not skipped: only their ASCII bytes
+/// are scored, see `english::calc_byte_frequencies`. Keys leaving no ASCII
+/// letter frequencies are skipped. Candidates are ranked by Euclidean
+/// distance, ties keep the smallest key.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::{crypto, english};
+///
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// // Latin-1 is not valid UTF-8
+/// let ciphertext: Vec<u8> = b"caf\xe9 au lait".iter().map(|byte| byte ^ 42).collect();
+/// let candidates = crypto::rank_byte_candidates(&ciphertext, &corpus_freq);
+/// assert_eq!(42, candidates[0].key);
+/// assert_eq!("caf\u{fffd} au lait", candidates[0].lossy());
+/// ```
+pub fn rank_byte_candidates(input_bytes: &[u8], corpus_freq: &[f64]) -> Vec<ByteCandidate> {
+    let mut candidates: Vec<ByteCandidate> = (0u8..=255)
+        .filter_map(|xor| {
+            let plaintext: Vec<u8> = input_bytes.iter().map(|byte| byte ^ xor).collect();
+            let frequencies = english::calc_byte_frequencies(&plaintext);
+            let pearson = match stats::pearson_correlation(corpus_freq, &frequencies) {
+                Some(score) => score,
+                None => {
+                    debug!("input xor {} has no letter frequencies, invalid!", xor);
+                    return None;
+                }
+            };
+            let euclidean = english::euclidean_distance(corpus_freq, &frequencies);
+            Some(ByteCandidate { key: xor, plaintext, euclidean, pearson })
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.euclidean.total_cmp(&b.euclidean));
+    candidates
+}
+
+/// Decrypt a XORed plaintext which may not be valid UTF-8, see `rank_byte_candidates`
+///
+/// Returns `None` if no key leaves any ASCII letter frequencies.
+pub fn decrypt_bytes(input_bytes: &[u8], corpus_freq: &[f64]) -> Option<ByteCandidate> {
+    rank_byte_candidates(input_bytes, corpus_freq).into_iter().next()
+}
 
-/// Decrypt a XORed text using a frequency table
+/// Decrypt a single-byte XORed text with any scorer, return the text, the key and its score
+///
+/// Ties keep the smallest key. `decrypt_english` ranks the keys with the
+/// ensemble of the configured scorers instead.
 ///
 /// # Examples:
 ///
 /// ```
 /// use cryptopals::{crypto, english};
+/// use cryptopals::scoring::ChiSquared;
 ///
-/// let corpus_frequency: Vec<f64> = english::get_english_frequency().unwrap();
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// let ciphertext: Vec<u8> = b"a lazy dog".iter().map(|byte| byte ^ 42).collect();
+/// let (text, key, _) = crypto::decrypt_text(&ciphertext, &ChiSquared::new(&corpus_freq));
+/// assert_eq!((42, "a lazy dog"), (key, text.as_str()));
 ///
-/// let (text, key, euclidean_score, pearson_score) = crypto::decrypt_text("SHRDLU".as_bytes(),
-///                                                                 &corpus_frequency);
+/// // Or a closure
+/// let spaces = |plaintext: &[u8]| plaintext.iter().filter(|&&byte| byte == b' ').count() as f64;
+/// assert_eq!(42, crypto::decrypt_text(&ciphertext, &spaces).1);
 /// ```
-pub fn decrypt_text(input_bytes: &[u8], corpus_freq: &[f64]) -> (String, u8, f64, f64) {
-    let mut best_euclidean_score = f64::INFINITY;
-    let mut best_pearson_score = f64::NEG_INFINITY;
-    let mut best_xor = 0;
-    let mut best_string = String::new();
-
-    // Test all values from 0 to 255 as XOR, reject invalid strings and compute
-    // letter frequencies and Euclidean distance to our English corpus.
-    // Keep the winner.
+pub fn decrypt_text<S: Scorer + ?Sized>(input_bytes: &[u8], scorer: &S) -> (String, u8, f64) {
+    let (mut best_key, mut best_score) = (0, f64::NEG_INFINITY);
+    let mut plaintext = Vec::with_capacity(input_bytes.len());
     for xor in 0u8..=255 {
-        let xored_input: Vec<_> = input_bytes.iter()
-            .map(|byte| *byte ^ xor)
-            .collect();
-        if let Ok(xored_string) = String::from_utf8(xored_input) {
-            let xored_freq = english::calc_frequencies(&xored_string);
-
-            let euclidean_score = english::euclidean_distance(&corpus_freq, &xored_freq);
-
-            let pearson_score = stats::covariance(&corpus_freq, &xored_freq)
-                / stats::std_dev(&corpus_freq)
-                / stats::std_dev(&xored_freq);
-
-            debug!("input xor {} = '{}'", xor, xored_string);
-            debug!(" - Euclidean score: {}", euclidean_score);
-            debug!(" - Pearson: {}", pearson_score);
-
-            if euclidean_score < best_euclidean_score {
-                best_euclidean_score = euclidean_score;
-                best_xor = xor;
-                best_string = xored_string;
-                debug!(" - Best Euclidean score!");
-            }
-            if pearson_score > best_pearson_score {
-                best_pearson_score = pearson_score;
-                debug!(" - Best Pearson score!");
-            }
-        } else {
-            debug!("input xor {} is an invalid string!", xor);
+        xor_with_key_into(input_bytes, &[xor], &mut plaintext);
+        let score = scorer.score(&plaintext);
+        debug!("input xor {} scores {}", xor, score);
+        if score > best_score {
+            best_key = xor;
+            best_score = score;
         }
     }
+    xor_with_key_into(input_bytes, &[best_key], &mut plaintext);
+    (String::from_utf8_lossy(&plaintext).into_owned(), best_key, best_score)
+}
+
+/// Decrypt a XORed English text using a frequency table and the configured scorers
+///
+/// Returns the best candidate of `rank_candidates`, with the best Euclidean
+/// and Pearson scores of all the candidates.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::{crypto, english};
+///
+/// let corpus_frequency: Vec<f64> = english::get_english_frequency(&english::CorpusSource::default()).unwrap();
+///
+/// let (text, key, euclidean_score, pearson_score) = crypto::decrypt_english("SHRDLU".as_bytes(),
+///                                                                    &corpus_frequency);
+/// ```
+pub fn decrypt_english(input_bytes: &[u8], corpus_freq: &[f64]) -> (String, u8, f64, f64) {
+    let candidates = rank_candidates(input_bytes, corpus_freq);
+    let best_euclidean_score = candidates.iter().map(|c| c.euclidean).fold(f64::INFINITY, f64::min);
+    let best_pearson_score = candidates.iter().map(|c| c.pearson).fold(f64::NEG_INFINITY, f64::max);
+    match candidates.into_iter().next() {
+        Some(best) => (best.plaintext, best.key, best_euclidean_score, best_pearson_score),
+        None => (String::new(), 0, best_euclidean_score, best_pearson_score),
+    }
+}
+
+/// Decrypt a XORed text written in `language`, see `decrypt_english`
+///
+/// The frequency table of the language is computed once per process.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::crypto;
+/// use cryptopals::language::Language;
+///
+/// let (text, key, _, _) = crypto::decrypt_text_in("SHRDLU".as_bytes(), Language::German).unwrap();
+/// ```
+pub fn decrypt_text_in(input_bytes: &[u8], language: Language) -> Result<(String, u8, f64, f64)> {
+    static TABLES: OnceLock<Mutex<HashMap<Language, Arc<Vec<f64>>>>> = OnceLock::new();
+    let tables = TABLES.get_or_init(Default::default);
+    // The lock is only held to look up and store the tables, never while loading or decrypting
+    let cached = tables.lock().unwrap_or_else(|e| e.into_inner()).get(&language).cloned();
+    let corpus_freq = match cached {
+        Some(table) => table,
+        None => {
+            // Threads missing the same table may all load it, the first one stored wins
+            let table = Arc::new(language.frequencies()?);
+            tables.lock().unwrap_or_else(|e| e.into_inner()).entry(language).or_insert(table).clone()
+        }
+    };
+    Ok(decrypt_english(input_bytes, &corpus_freq))
+}
+
+/// Ensemble score of every single-byte XOR key, best first, see `rank_candidates`
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::{crypto, english};
+///
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// let ciphertext: Vec<u8> = b"a lazy dog".iter().map(|byte| byte ^ 42).collect();
+/// assert_eq!(42, crypto::score_keys(&ciphertext, &corpus_freq)[0].0);
+/// ```
+pub fn score_keys(input_bytes: &[u8], corpus_freq: &[f64]) -> Vec<(u8, f64)> {
+    rank_candidates(input_bytes, corpus_freq).into_iter()
+        .map(|candidate| (candidate.key, candidate.ensemble))
+        .collect()
+}
+
+/// Scorer weights of the configuration file, read once
+fn scoring_weights() -> &'static BTreeMap<String, f64> {
+    static WEIGHTS: OnceLock<BTreeMap<String, f64>> = OnceLock::new();
+    WEIGHTS.get_or_init(|| match Config::load() {
+        Ok(config) => config.scoring_weights,
+        Err(e) => {
+            warn!("invalid configuration file {}: {}", Config::path().display(), e);
+            Config::default().scoring_weights
+        }
+    })
+}
 
-    (best_string, best_xor, best_euclidean_score, best_pearson_score)
+/// English n-gram tables, only built if the configuration weighs them
+fn scoring_ngrams() -> Option<&'static Ngrams> {
+    static NGRAMS: OnceLock<Option<Ngrams>> = OnceLock::new();
+    if !scoring_weights().contains_key("ngrams") {
+        return None;
+    }
+    NGRAMS.get_or_init(|| match Ngrams::english() {
+        Ok(ngrams) => Some(ngrams),
+        Err(e) => {
+            warn!("n-gram tables unavailable, left out of the ensemble: {}", e);
+            None
+        }
+    }).as_ref()
 }
 
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::error::CryptopalsError;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn hex_round_trip(bytes in prop::collection::vec(any::<u8>(), 1..100)) {
+            prop_assert_eq!(&bytes, &bytes.bytes2hex().hex2bytes().unwrap());
+            let upper = bytes.bytes2hex_with(&HexFormat::new().with_uppercase());
+            prop_assert_eq!(&bytes, &upper.hex2bytes().unwrap());
+            let grouped = bytes.bytes2hex_with(&HexFormat::new().with_groups(4, " "));
+            prop_assert_eq!(&bytes, &grouped.hex2bytes_lenient().unwrap());
+        }
+
+        #[test]
+        fn hex_never_panics(text in "\\PC*") {
+            if let Ok(bytes) = text.hex2bytes() {
+                prop_assert_eq!(bytes.bytes2hex(), text.to_ascii_lowercase());
+            }
+            let _ = text.hex2bytes_lenient();
+            let _ = text.hex2string();
+        }
+
+        #[test]
+        fn base64_round_trip(bytes in prop::collection::vec(any::<u8>(), 0..300)) {
+            prop_assert_eq!(&bytes, &bytes.base64_encode().base64_decode().unwrap());
+            let url_safe = bytes.base64_encode_with(Alphabet::UrlSafe);
+            prop_assert_eq!(&bytes, &url_safe.base64_decode_with(Alphabet::UrlSafe).unwrap());
+            let wrapped = bytes.base64_encode_wrapped(MIME_LINE_WIDTH);
+            prop_assert_eq!(&bytes, &wrapped.base64_decode().unwrap());
+            let unpadded = bytes.base64_encode().trim_end_matches('=').to_owned();
+            let config = DecodeConfig::new().with_optional_padding();
+            prop_assert_eq!(&bytes, &unpadded.base64_decode_config(&config).unwrap());
+        }
+
+        #[test]
+        fn base64_never_panics(text in "\\PC*", alphabet_text in "[A-Za-z0-9+/=\r\n]{0,12}") {
+            for text in [&text, &alphabet_text] {
+                let _ = text.base64_decode();
+                let _ = text.base64_decode_config(&DecodeConfig::new().with_optional_padding());
+            }
+        }
+
+        /// Whatever the decoder accepts is the canonical encoding of its output
+        #[test]
+        fn base64_accepts_canonical_only(text in "[A-Za-z0-9+/=]{0,12}") {
+            if let Ok(bytes) = text.base64_decode() {
+                let canonical = bytes.base64_encode();
+                // Unused low bits of the last sextet are not checked
+                prop_assert_eq!(canonical.len(), text.len());
+                prop_assert_eq!(canonical.trim_end_matches('=').len(), text.trim_end_matches('=').len());
+            }
+        }
+    }
+
+    #[test]
+    fn latin1_plaintext() {
+        let corpus_freq = english::calc_frequencies("It was the best of times, it was the worst of times, \
+            it was the age of wisdom, it was the age of foolishness");
+        let plaintext = b"No se puede ir a la ca\xf1ada sin el se\xf1or, dijo el ni\xf1o";
+        let ciphertext: Vec<u8> = plaintext.iter().map(|byte| byte ^ 0x17).collect();
+        // The right key gives invalid UTF-8
+        assert!(rank_candidates(&ciphertext, &corpus_freq).iter().all(|candidate| candidate.key != 0x17));
+
+        let best = decrypt_bytes(&ciphertext, &corpus_freq).unwrap();
+        assert_eq!((0x17, &plaintext[..]), (best.key, &best.plaintext[..]));
+        assert!(best.lossy().starts_with("No se puede ir a la ca\u{fffd}ada"));
+    }
 
     #[test]
     fn hex1() {
@@ -369,6 +491,57 @@ mod test {
                    "48656c6c6f2c20776f726c6421".hex2bytes().unwrap());
     }
 
+    #[test]
+    fn hex_lenient() {
+        assert_eq!(vec![0x0a, 0xbc], "0x0A:BC\r\n".hex2bytes_lenient().unwrap());
+        assert!("  \n".hex2bytes_lenient().is_err());
+        assert!("d\u{e9}ad".hex2bytes_lenient().is_err());
+        assert!("de:a:d".hex2bytes_lenient().is_err());
+    }
+
+    #[test]
+    fn hex_format_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let formats = [
+            HexFormat::new(),
+            HexFormat::new().with_uppercase().with_prefix(),
+            HexFormat::new().with_groups(1, ":"),
+            HexFormat::new().with_groups(16, "\n").with_prefix(),
+        ];
+        for format in &formats {
+            assert_eq!(bytes, bytes.bytes2hex_with(format).hex2bytes_lenient().unwrap(), "{:?}", format);
+        }
+        assert_eq!("", [].bytes2hex_with(&HexFormat::new().with_prefix()));
+        assert_eq!("0x01 0x02", [1, 2].bytes2hex_with(&HexFormat::new().with_prefix().with_groups(1, " ")));
+    }
+
+    #[test]
+    fn leaky_eq_leaks() {
+        let delay = Duration::from_millis(2);
+        let start = std::time::Instant::now();
+        assert!(!leaky_eq(b"abcdefgh", b"abcdefgX", delay));
+        // Sleeps may last longer, never shorter
+        assert!(start.elapsed() >= 7 * delay);
+        assert!(leaky_eq(b"", b"", delay));
+        assert!(!ct_eq(b"abc", b""));
+    }
+
+    #[test]
+    fn aes_wrappers() {
+        assert!(Aes128Ecb::new(b"YELLOW").is_err());
+        assert!(Aes128Cbc::new(b"YELLOW SUBMARINE", &[0; 8]).is_err());
+        let ecb = Aes128Ecb::new(b"YELLOW SUBMARINE").unwrap();
+        let cbc = Aes128Cbc::new(b"YELLOW SUBMARINE", &[0; 16]).unwrap();
+        // A zero IV makes the first CBC block an ECB block
+        assert_eq!(ecb.encrypt(b"")[..], cbc.encrypt(b"")[..]);
+        assert_eq!(16, cbc.encrypt(b"").len());
+        assert_eq!(Vec::<u8>::new(), ecb.decrypt(&ecb.encrypt(b"")).unwrap());
+        assert!(matches!(ecb.decrypt(b""), Err(CryptopalsError::Padding(_))));
+        let mut tampered = cbc.encrypt(b"YELLOW SUB");
+        tampered[15] ^= 0x01;
+        assert!(matches!(cbc.decrypt(&tampered), Err(CryptopalsError::Padding(_))));
+    }
+
     #[test]
     fn hex2str_short() {
         assert_eq!("A".to_owned(), "41".hex2string().unwrap());
@@ -481,6 +654,16 @@ mod test {
     }
 
 
+    #[test]
+    fn base64_wrapped_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let wrapped = data.base64_encode_wrapped(MIME_LINE_WIDTH);
+        assert!(wrapped.split("\r\n").all(|line| line.len() <= MIME_LINE_WIDTH));
+        assert_eq!(data.base64_encode().len().div_ceil(MIME_LINE_WIDTH), wrapped.split("\r\n").count());
+        assert_eq!(data, wrapped.base64_decode().unwrap());
+        assert_eq!("", [].base64_encode_wrapped(MIME_LINE_WIDTH));
+    }
+
     #[test]
     fn base64_1_byte() {
         assert_eq!(String::from("QQ=="), [65].base64_encode())
@@ -543,15 +726,32 @@ mod test {
         assert_eq!(
             37,
             "this is a test".as_bytes()
-                .hamming_distance("wokka wokka!!!".as_bytes()));
+                .hamming_distance("wokka wokka!!!".as_bytes()).unwrap());
     }
 
     #[test]
-    #[should_panic]
     fn hamming_different_len() {
-        assert_eq!(
-            37,
-            "this is a test".as_bytes()
-                .hamming_distance("wokka wokka".as_bytes()));
+        assert!("this is a test".as_bytes()
+            .hamming_distance("wokka wokka".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn normalized_hamming_pairs() {
+        // Adjacent pairs, not the first block against the others
+        let blocks = b"this is a testwokka wokka!!!this is a test";
+        assert_eq!(37.0 / 14.0, blocks.normalized_hamming(14, 3).unwrap());
+        assert_eq!(37.0 / 14.0, blocks.normalized_hamming(14, 2).unwrap());
+        assert!(blocks.normalized_hamming(0, 2).is_err());
+        assert!(blocks.normalized_hamming(14, 1).is_err());
+    }
+
+    #[test]
+    fn decrypt_english_empty_input() {
+        // Every candidate has zero letter frequencies and is rejected
+        let corpus_freq = english::calc_frequencies("the quick brown fox");
+        let (text, _, euclidean, pearson) = decrypt_english(b"", &corpus_freq);
+        assert_eq!("", text);
+        assert_eq!(f64::INFINITY, euclidean);
+        assert_eq!(f64::NEG_INFINITY, pearson);
     }
 }
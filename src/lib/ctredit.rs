@@ -0,0 +1,117 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Random access read/write AES-CTR and its break (challenge 25)
+//!
+//! An API that edits the plaintext of a CTR ciphertext in place encrypts the
+//! new text with the same keystream bytes. Writing zeros gives the keystream
+//! away, and with it the original plaintext. Any `StreamCipher` is as weak
+//! as CTR here.
+
+use std::error;
+use rand::RngCore;
+use crate::cipher::{Aes128Ctr, StreamCipher};
+use crate::crypto::BytesCrypto;
+use crate::keygen;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Encryption under a secret key, with an exposed `edit` function
+pub struct EditOracle<C = Aes128Ctr> {
+    cipher: C,
+}
+
+impl Default for EditOracle {
+    fn default() -> Self {
+        EditOracle::new()
+    }
+}
+
+impl EditOracle {
+    /// AES-CTR oracle with a random key and nonce
+    pub fn new() -> EditOracle {
+        EditOracle::with_rng(&mut rand::thread_rng())
+    }
+
+    /// Same as `new`, drawing the key and nonce from `rng`
+    pub fn with_rng<R: RngCore + ?Sized>(rng: &mut R) -> EditOracle {
+        let ctr = Aes128Ctr::new(&keygen::aes_key(rng), keygen::ctr_nonce(rng)).unwrap();
+        EditOracle { cipher: ctr }
+    }
+}
+
+impl<C: StreamCipher> EditOracle<C> {
+    /// Oracle hiding another stream cipher
+    pub fn with_cipher(cipher: C) -> EditOracle<C> {
+        EditOracle { cipher }
+    }
+
+    /// Encrypt a plaintext from the beginning of the keystream
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        self.cipher.apply_keystream(plaintext)
+    }
+
+    /// Replace the plaintext of `ciphertext` from byte `offset` with `newtext`
+    pub fn edit(&self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        self.cipher.edit(ciphertext, offset, newtext)
+    }
+}
+
+/// Recover the plaintext of a CTR ciphertext through an edit function
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::ctredit::{self, EditOracle};
+///
+/// let oracle = EditOracle::new();
+/// let ciphertext = oracle.encrypt(b"I'm back and I'm ringin' the bell");
+/// let plaintext = ctredit::recover_plaintext(&ciphertext, |c, offset, newtext| oracle.edit(c, offset, newtext)).unwrap();
+/// assert_eq!(b"I'm back and I'm ringin' the bell".to_vec(), plaintext);
+/// ```
+pub fn recover_plaintext<F>(ciphertext: &[u8], edit: F) -> Result<Vec<u8>>
+    where F: Fn(&[u8], usize, &[u8]) -> Result<Vec<u8>>
+{
+    let keystream = edit(ciphertext, 0, &vec![0; ciphertext.len()])?;
+    if keystream.len() != ciphertext.len() {
+        return Err("the edit function changed the ciphertext length".into());
+    }
+    Ok(ciphertext.xor(&keystream))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cipher::Rc4;
+
+    #[test]
+    fn recover_edited() {
+        let oracle = EditOracle::new();
+        let plaintext: Vec<u8> = (0..=255).collect();
+        let ciphertext = oracle.encrypt(&plaintext);
+        let edit = |c: &[u8], offset, newtext: &[u8]| oracle.edit(c, offset, newtext);
+        assert_eq!(plaintext, recover_plaintext(&ciphertext, edit).unwrap());
+        assert_eq!(Vec::<u8>::new(), recover_plaintext(b"", edit).unwrap());
+        assert!(recover_plaintext(&ciphertext, |c, _, _| Ok(c[1..].to_vec())).is_err());
+    }
+
+    #[test]
+    fn recover_rc4() {
+        let oracle = EditOracle::with_cipher(Rc4::new(b"secret").unwrap());
+        let ciphertext = oracle.encrypt(b"any stream cipher");
+        let edit = |c: &[u8], offset, newtext: &[u8]| oracle.edit(c, offset, newtext);
+        assert_eq!(b"any stream cipher".to_vec(), recover_plaintext(&ciphertext, edit).unwrap());
+    }
+}
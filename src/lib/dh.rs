@@ -0,0 +1,319 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Diffie-Hellman key exchange and the small subgroup confinement attack
+//!
+//! If p - 1 has small factors r, an element h of order r confines the shared
+//! secret h^x to r values. A peer MACing a message with it leaks x mod r, and
+//! enough of these residues give x with the CRT.
+
+use hmac::{Hmac, Mac};
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use rand::Rng;
+use sha2::Sha256;
+use std::error;
+use crate::dlog::Kangaroo;
+use crate::{math, params};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Message MACed by Bob in challenge 57
+pub const BOB_MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// Upper bound of the factors the attack brute-forces
+pub const FACTOR_BOUND: u64 = 1 << 16;
+
+/// Kangaroo runs before giving up
+const KANGAROO_RETRIES: u32 = 4;
+
+/// Group parameters: a prime p, and a generator g of a subgroup of prime order q
+#[derive(Debug, Clone, PartialEq)]
+pub struct DhParams {
+    pub p: BigUint,
+    pub g: BigUint,
+    pub q: BigUint,
+}
+
+impl DhParams {
+    /// Parameters of challenge 58, where (p - 1) / q only has a few small factors
+    pub fn challenge58() -> DhParams {
+        params::dh::challenge58()
+    }
+
+    /// Parameters of challenge 57, where (p - 1) / q has many small factors
+    pub fn challenge57() -> DhParams {
+        params::dh::challenge57()
+    }
+
+    /// Generate parameters of `p_bits` bits with a `q_bits` subgroup and a smooth cofactor
+    ///
+    /// (p - 1) / q is built from distinct primes below `factor_bound`, whose
+    /// product has more than `smooth_bits` bits, completed by a random number up
+    /// to the size of p. With `smooth_bits` at least `q_bits`, the small subgroups
+    /// reveal the whole private key.
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if p is not large enough to hold q and the small factors,
+    /// or if the primes below `factor_bound` cannot make `smooth_bits` bits, e.g.
+    /// when `factor_bound` is at most 3 and 2 is the only one.
+    pub fn generate_smooth(p_bits: u64, q_bits: u64, smooth_bits: u64, factor_bound: u64) -> DhParams {
+        let mut rng = rand::thread_rng();
+        let primes = math::primes_below(factor_bound);
+        let all_primes: BigUint = primes.iter().map(|&prime| BigUint::from(prime)).product();
+        assert!(all_primes.bits() > smooth_bits, "not enough primes below {} for the small factors", factor_bound);
+        let q = math::random_prime(q_bits);
+        loop {
+            // p - 1 must be even, and the small factors must cover q
+            let mut smooth = BigUint::from(2u32);
+            while smooth.bits() <= smooth_bits {
+                let prime = primes[rng.gen_range(1..primes.len())];
+                if !(&smooth % prime).is_zero() {
+                    smooth *= prime;
+                }
+            }
+            // Cofactor such that p has exactly p_bits bits
+            let base = &q * &smooth;
+            assert!(base.bits() < p_bits, "p is too small for the subgroups");
+            let low = ((BigUint::one() << (p_bits - 1)) + &base - 1u32) / &base;
+            let high = (BigUint::one() << p_bits) / &base;
+            let p = base * rng.gen_biguint_range(&low, &high) + 1u32;
+            if !math::is_probable_prime(&p, 40) {
+                continue;
+            }
+            let g = element_of_order(&p, &q);
+            return DhParams { p, g, q };
+        }
+    }
+
+    /// Random private key in [1, q)
+    pub fn generate_private(&self) -> BigUint {
+        rand::thread_rng().gen_biguint_range(&BigUint::one(), &self.q)
+    }
+
+    /// Public key g^private mod p
+    pub fn public_key(&self, private: &BigUint) -> BigUint {
+        self.g.modpow(private, &self.p)
+    }
+
+    /// Check that a public key is an element of the subgroup of order q
+    ///
+    /// 1 and p - 1 are rejected first, they confine the secret to one or two values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::dh::DhParams;
+    /// use num_bigint::BigUint;
+    ///
+    /// let params = DhParams::challenge57();
+    /// assert!(params.validate_public(&params.g).is_ok());
+    /// assert!(params.validate_public(&BigUint::from(1u32)).is_err());
+    /// ```
+    pub fn validate_public(&self, public: &BigUint) -> Result<()> {
+        if public <= &BigUint::one() || public >= &(&self.p - 1u32) {
+            return Err(format!("public key out of range: {}", public).into());
+        }
+        if !public.modpow(&self.q, &self.p).is_one() {
+            return Err(format!("public key outside of the subgroup of order q: {}", public).into());
+        }
+        Ok(())
+    }
+}
+
+/// Random element of order `order` of the multiplicative group modulo the prime `p`
+///
+/// `order` must be a prime dividing p - 1.
+pub fn element_of_order(p: &BigUint, order: &BigUint) -> BigUint {
+    let exponent = (p - 1u32) / order;
+    let two = BigUint::from(2u32);
+    loop {
+        let h = rand::thread_rng().gen_biguint_range(&two, p).modpow(&exponent, p);
+        if !h.is_one() {
+            return h;
+        }
+    }
+}
+
+/// HMAC-SHA256 of a message, keyed by a shared secret
+pub fn mac(secret: &BigUint, message: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(&secret.to_bytes_be()).unwrap();
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// A peer answering any public key with a MACed message, unless it uses `respond_validated`
+pub struct Bob {
+    params: DhParams,
+    private: BigUint,
+}
+
+impl Bob {
+    pub fn new(params: &DhParams) -> Bob {
+        Bob { params: params.clone(), private: params.generate_private() }
+    }
+
+    pub fn public_key(&self) -> BigUint {
+        self.params.public_key(&self.private)
+    }
+
+    /// Derive the shared secret from the other public key, return (message, MAC)
+    pub fn respond(&self, public: &BigUint) -> (Vec<u8>, Vec<u8>) {
+        let secret = public.modpow(&self.private, &self.params.p);
+        (BOB_MESSAGE.to_vec(), mac(&secret, BOB_MESSAGE))
+    }
+
+    /// Like `respond`, after checking the other public key
+    pub fn respond_validated(&self, public: &BigUint) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.params.validate_public(public)?;
+        Ok(self.respond(public))
+    }
+
+    /// Bob's private key, to check the attack
+    pub fn private_key(&self) -> &BigUint {
+        &self.private
+    }
+}
+
+/// Learn a peer's private key modulo the small factors of (p - 1) / q
+///
+/// `respond` sends a public key to the peer and returns its (message, MAC). The
+/// factors below `FACTOR_BOUND` are used until their product exceeds q. Returns
+/// the private key modulo that product, and the product.
+pub fn confine<F>(params: &DhParams, respond: F) -> Option<(BigUint, BigUint)>
+    where F: Fn(&BigUint) -> (Vec<u8>, Vec<u8>)
+{
+    let cofactor = (&params.p - 1u32) / &params.q;
+    let mut congruences = Vec::new();
+    let mut product = BigUint::one();
+    for r in math::small_factors(&cofactor, FACTOR_BOUND) {
+        if product > params.q {
+            break;
+        }
+        let h = element_of_order(&params.p, &BigUint::from(r));
+        let (message, tag) = respond(&h);
+
+        // The shared secret is one of h^0, ..., h^(r-1)
+        let mut secret = BigUint::one();
+        let residue = (0..r).find(|_| {
+            let found = mac(&secret, &message) == tag;
+            secret = &secret * &h % &params.p;
+            found
+        })?;
+        debug!("x = {} mod {}", residue, r);
+        congruences.push((BigUint::from(residue), BigUint::from(r)));
+        product *= r;
+    }
+    math::crt(&congruences)
+}
+
+/// Recover a peer's private key by confining its shared secret to small subgroups
+///
+/// Returns `None` if the small factors of (p - 1) / q below `FACTOR_BOUND` do
+/// not multiply to more than q.
+pub fn small_subgroup_attack<F>(params: &DhParams, respond: F) -> Option<BigUint>
+    where F: Fn(&BigUint) -> (Vec<u8>, Vec<u8>)
+{
+    let (x, modulus) = confine(params, respond)?;
+    if modulus <= params.q {
+        return None;
+    }
+    Some(x)
+}
+
+/// Recover a peer's private key from partial confinement and its public key
+///
+/// Knowing x = n mod r, the private key is x = n + m * r with m in [0, (q - 1) / r],
+/// so y * g^-n = (g^r)^m and the kangaroo finds m.
+pub fn confine_and_catch<F>(params: &DhParams, public: &BigUint, respond: F) -> Option<BigUint>
+    where F: Fn(&BigUint) -> (Vec<u8>, Vec<u8>)
+{
+    let (n, r) = confine(params, respond)?;
+    if r > params.q {
+        return Some(n);
+    }
+    let g_inv = math::modinv(&params.g, &params.p)?;
+    let y = public * g_inv.modpow(&n, &params.p) % &params.p;
+    let g = params.g.modpow(&r, &params.p);
+
+    // The wild kangaroo can miss the trap, retry with other jump sizes
+    let kangaroo = Kangaroo::new(&BigUint::zero(), &((&params.q - 1u32) / &r));
+    let k = kangaroo.k();
+    let m = (k..(k + KANGAROO_RETRIES).min(64))
+        .find_map(|k| kangaroo.clone().with_k(k).catch(&params.p, &g, &y))?;
+    Some(n + m * r)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn challenge57_params() {
+        let params = DhParams::challenge57();
+        assert!(params.g.modpow(&params.q, &params.p).is_one());
+        assert!(((&params.p - 1u32) % &params.q).is_zero());
+    }
+
+    #[test]
+    fn challenge58_params() {
+        let params = DhParams::challenge58();
+        assert!(params.g.modpow(&params.q, &params.p).is_one());
+    }
+
+    #[test]
+    #[should_panic = "not enough primes below 3"]
+    fn smooth_without_odd_primes() {
+        DhParams::generate_smooth(128, 48, 24, 3);
+    }
+
+    #[test]
+    fn catch_generated_params() {
+        // Too few small factors to cover q, the kangaroo finds the rest
+        let params = DhParams::generate_smooth(128, 48, 24, 1 << 8);
+        let bob = Bob::new(&params);
+        assert_eq!(
+            Some(bob.private_key().clone()),
+            confine_and_catch(&params, &bob.public_key(), |h| bob.respond(h))
+        );
+    }
+
+    #[test]
+    fn generated_params() {
+        let params = DhParams::generate_smooth(256, 64, 64, 1 << 10);
+        assert_eq!(256, params.p.bits());
+        assert_eq!(64, params.q.bits());
+        assert!(params.g.modpow(&params.q, &params.p).is_one());
+        assert!(!params.g.is_one());
+    }
+
+    #[test]
+    fn shared_secret() {
+        let params = DhParams::generate_smooth(256, 64, 64, 1 << 10);
+        let bob = Bob::new(&params);
+        let alice = params.generate_private();
+        let secret = bob.public_key().modpow(&alice, &params.p);
+        assert_eq!((BOB_MESSAGE.to_vec(), mac(&secret, BOB_MESSAGE)), bob.respond(&params.public_key(&alice)));
+    }
+
+    #[test]
+    fn attack_generated_params() {
+        let params = DhParams::generate_smooth(256, 64, 64, 1 << 10);
+        let bob = Bob::new(&params);
+        assert_eq!(Some(bob.private_key().clone()), small_subgroup_attack(&params, |h| bob.respond(h)));
+    }
+}
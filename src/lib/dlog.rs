@@ -0,0 +1,248 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Discrete logarithms in Z_p* and other cyclic groups
+//!
+//! Pollard's kangaroo (lambda) algorithm finds an index known to lie in an
+//! interval [a, b] in O(sqrt(b - a)) multiplications. A tame kangaroo jumps from
+//! g^b and sets a trap where it stops, a wild kangaroo jumps from y and falls into
+//! the trap if its path merges with the tame one.
+//!
+//! When the order of the group is smooth, Pohlig-Hellman finds any index from
+//! its residues modulo each factor, brute-forced in the subgroup of that order.
+
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use crate::math;
+
+/// A group the kangaroo can jump in, written multiplicatively
+pub trait Group {
+    type Element: Clone + PartialEq;
+
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    fn pow(&self, g: &Self::Element, n: &BigUint) -> Self::Element;
+
+    /// Pseudo-random jump index in [0, k) of a kangaroo standing on `element`
+    fn jump(&self, element: &Self::Element, k: u32) -> usize;
+}
+
+/// The multiplicative group Z_p*
+pub struct Multiplicative<'a> {
+    pub p: &'a BigUint,
+}
+
+impl Group for Multiplicative<'_> {
+    type Element = BigUint;
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.p
+    }
+
+    fn pow(&self, g: &BigUint, n: &BigUint) -> BigUint {
+        g.modpow(n, self.p)
+    }
+
+    fn jump(&self, element: &BigUint, k: u32) -> usize {
+        (element % k).to_usize().unwrap()
+    }
+}
+
+/// Tunable parameters of the kangaroo algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kangaroo {
+    a: BigUint,
+    b: BigUint,
+    /// Jumps are powers of two below 2^k
+    k: u32,
+    /// Number of jumps of the tame kangaroo
+    tame_jumps: u64,
+}
+
+impl Kangaroo {
+    /// Parameters for an index in [a, b]
+    ///
+    /// The average jump is chosen close to sqrt(b - a) / 2, and the tame kangaroo
+    /// makes 4 times as many jumps.
+    pub fn new(a: &BigUint, b: &BigUint) -> Kangaroo {
+        let target = ((b - a).sqrt() / 2u32).to_f64().unwrap_or(f64::MAX);
+        let mut k = 1;
+        while k < 63 && Self::mean_jump_of(k) < target {
+            k += 1;
+        }
+        Kangaroo { a: a.clone(), b: b.clone(), k, tame_jumps: 0 }.with_k(k)
+    }
+
+    /// Use jumps below 2^k, with the matching default number of tame jumps
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if `k` is not in 1..64.
+    pub fn with_k(mut self, k: u32) -> Kangaroo {
+        assert!((1..64).contains(&k), "k must be in 1..64");
+        self.k = k;
+        self.tame_jumps = (4.0 * Self::mean_jump_of(k)).ceil() as u64;
+        self
+    }
+
+    /// Set the number of jumps of the tame kangaroo
+    pub fn with_tame_jumps(mut self, tame_jumps: u64) -> Kangaroo {
+        self.tame_jumps = tame_jumps;
+        self
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    pub fn tame_jumps(&self) -> u64 {
+        self.tame_jumps
+    }
+
+    fn mean_jump_of(k: u32) -> f64 {
+        ((1u64 << k) - 1) as f64 / k as f64
+    }
+
+    /// Find x in [a, b] such that y = g^x mod p
+    ///
+    /// The search is probabilistic: `None` means that the wild kangaroo missed
+    /// the trap, either because x is not in the interval or by bad luck.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::dlog::Kangaroo;
+    /// use num_bigint::BigUint;
+    ///
+    /// let p = BigUint::from(1_000_000_007u32);
+    /// let g = BigUint::from(5u32);
+    /// let x = BigUint::from(123_456u32);
+    /// let y = g.modpow(&x, &p);
+    /// let kangaroo = Kangaroo::new(&BigUint::from(100_000u32), &BigUint::from(200_000u32));
+    /// assert_eq!(Some(x), kangaroo.catch(&p, &g, &y));
+    /// ```
+    pub fn catch(&self, p: &BigUint, g: &BigUint, y: &BigUint) -> Option<BigUint> {
+        self.catch_in(&Multiplicative { p }, &(g % p), &(y % p))
+    }
+
+    /// Find x in [a, b] such that y = g^x in any group
+    pub fn catch_in<G: Group>(&self, group: &G, g: &G::Element, y: &G::Element) -> Option<BigUint> {
+        // g^(2^i) for all the jump sizes
+        let mut steps = Vec::with_capacity(self.k as usize);
+        let mut step = g.clone();
+        for _ in 0..self.k {
+            let next = group.mul(&step, &step);
+            steps.push(std::mem::replace(&mut step, next));
+        }
+
+        // Tame kangaroo, from g^b
+        let mut tame_distance = BigUint::zero();
+        let mut tame = group.pow(g, &self.b);
+        for _ in 0..self.tame_jumps {
+            let jump = group.jump(&tame, self.k);
+            tame_distance += 1u64 << jump;
+            tame = group.mul(&tame, &steps[jump]);
+        }
+        debug!("Trap set at g^(b + {})", tame_distance);
+
+        // Wild kangaroo, from y, until it has passed the trap
+        let limit = &self.b - &self.a + &tame_distance;
+        let mut wild_distance = BigUint::zero();
+        let mut wild = y.clone();
+        while wild_distance <= limit {
+            if wild == tame {
+                return Some(&self.b + tame_distance - wild_distance);
+            }
+            let jump = group.jump(&wild, self.k);
+            wild_distance += 1u64 << jump;
+            wild = group.mul(&wild, &steps[jump]);
+        }
+        None
+    }
+}
+
+/// Find x such that y = g^x mod p with Pohlig-Hellman
+///
+/// `factors` are the distinct primes whose product is p - 1. Returns x modulo
+/// p - 1, or `None` if y is not a power of g.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::dlog;
+/// use num_bigint::BigUint;
+///
+/// // 1019 - 1 = 2 * 509
+/// let p = BigUint::from(1019u32);
+/// let g = BigUint::from(2u32);
+/// let y = g.modpow(&BigUint::from(777u32), &p);
+/// assert_eq!(Some(BigUint::from(777u32)), dlog::pohlig_hellman(&p, &g, &y, &[2, 509]));
+/// ```
+pub fn pohlig_hellman(p: &BigUint, g: &BigUint, y: &BigUint, factors: &[u64]) -> Option<BigUint> {
+    let order = p - 1u32;
+    let mut congruences = Vec::with_capacity(factors.len());
+    for &r in factors {
+        // g^((p - 1) / r) generates the subgroup of order r, or is 1
+        let cofactor = &order / r;
+        let h = g.modpow(&cofactor, p);
+        let target = y.modpow(&cofactor, p);
+        let mut power = BigUint::one();
+        let residue = (0..r).find(|_| {
+            let found = power == target;
+            power = &power * &h % p;
+            found
+        })?;
+        congruences.push((BigUint::from(residue), BigUint::from(r)));
+    }
+    let (x, _) = math::crt(&congruences)?;
+    if g.modpow(&x, p) != y % p {
+        return None;
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn challenge58_group() -> (BigUint, BigUint) {
+        let params = crate::params::dh::challenge58();
+        (params.p, params.g)
+    }
+
+    #[test]
+    fn default_parameters() {
+        let kangaroo = Kangaroo::new(&BigUint::zero(), &BigUint::from(1u64 << 40));
+        // sqrt(2^40) / 2 = 2^19, and (2^24 - 1) / 24 is the first mean above it
+        assert_eq!(24, kangaroo.k());
+        assert_eq!(2796203, kangaroo.tame_jumps());
+        assert_eq!(1000, kangaroo.with_tame_jumps(1000).tame_jumps());
+    }
+
+    #[test]
+    fn challenge58_small_interval() {
+        let (p, g) = challenge58_group();
+        let y = BigUint::parse_bytes(b"7760073848032689505395005705677365876654629189298052775754597607446617558600394076764814236081991643094239886772481052254010323780165093955236429914607119", 10).unwrap();
+        let x = Kangaroo::new(&BigUint::zero(), &BigUint::from(1u32 << 20)).catch(&p, &g, &y).unwrap();
+        assert_eq!(y, g.modpow(&x, &p));
+    }
+
+    #[test]
+    fn out_of_interval() {
+        let (p, g) = challenge58_group();
+        let y = g.modpow(&BigUint::from(5000u32), &p);
+        assert_eq!(None, Kangaroo::new(&BigUint::from(10_000u32), &BigUint::from(20_000u32)).catch(&p, &g, &y));
+    }
+}
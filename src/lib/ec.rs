@@ -0,0 +1,801 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Elliptic curves over prime fields, ECDH and the invalid curve attack
+//!
+//! The addition formulas of a Weierstrass curve y^2 = x^3 + ax + b never use b,
+//! so a peer which does not check that a point is on its curve computes on
+//! whatever curve the point belongs to. Bogus curves with smooth orders then
+//! leak the private key modulo small primes, as in the small subgroup attack.
+//!
+//! The x-only Montgomery ladder cannot even check a point: any u is on the
+//! curve or on its quadratic twist, and a twist with small factors leaks the
+//! private key up to its sign, the kangaroo finding the rest.
+
+use std::collections::HashSet;
+use std::error;
+use hmac::{Hmac, Mac};
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use num_traits::{One, ToPrimitive, Zero};
+use sha2::Sha256;
+use crate::dh;
+use crate::dlog::{Group, Kangaroo};
+use crate::{math, params};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Message MACed by Bob in challenge 59
+pub const BOB_MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// Upper bound of the factors the attack brute-forces
+pub const FACTOR_BOUND: u64 = 1 << 16;
+
+/// Upper bound of the twist factors the twist attack brute-forces
+pub const TWIST_FACTOR_BOUND: u64 = 1 << 24;
+
+/// Random points tried to find a point of a given order
+const POINT_TRIES: usize = 16;
+
+/// Jump sizes tried after the default one when the kangaroo misses
+const KANGAROO_RETRIES: u32 = 4;
+
+/// A point of an elliptic curve, in affine coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Point {
+    /// The point at infinity, neutral element of the group
+    Infinity,
+    Affine { x: BigUint, y: BigUint },
+}
+
+impl Point {
+    pub fn new(x: BigUint, y: BigUint) -> Point {
+        Point::Affine { x, y }
+    }
+
+    /// Big endian x then y coordinates, empty for the point at infinity
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Point::Infinity => Vec::new(),
+            Point::Affine { x, y } => {
+                let mut bytes = x.to_bytes_be();
+                bytes.extend(y.to_bytes_be());
+                bytes
+            }
+        }
+    }
+}
+
+/// The curve y^2 = x^3 + ax + b over GF(p)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeierstrassCurve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+impl WeierstrassCurve {
+    /// Create a curve, `a` and `b` are reduced modulo p
+    pub fn new(p: &BigUint, a: &BigInt, b: &BigInt) -> WeierstrassCurve {
+        let modulus = BigInt::from(p.clone());
+        let reduce = |n: &BigInt| ((n % &modulus + &modulus) % &modulus).to_biguint().unwrap();
+        WeierstrassCurve { p: p.clone(), a: reduce(a), b: reduce(b) }
+    }
+
+    /// The same curve with another b, which the addition formulas cannot tell apart
+    pub fn with_b(&self, b: &BigInt) -> WeierstrassCurve {
+        WeierstrassCurve::new(&self.p, &BigInt::from(self.a.clone()), b)
+    }
+
+    /// x^3 + ax + b
+    fn rhs(&self, x: &BigUint) -> BigUint {
+        (x * x * x + &self.a * x + &self.b) % &self.p
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b % &self.p) % &self.p
+    }
+
+    fn div(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        // p is prime, every non-zero number is invertible
+        a * math::modinv(b, &self.p).unwrap() % &self.p
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        match point {
+            Point::Infinity => true,
+            Point::Affine { x, y } => y * y % &self.p == self.rhs(x),
+        }
+    }
+
+    pub fn neg(&self, point: &Point) -> Point {
+        match point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine { x, y } => Point::new(x.clone(), self.sub(&BigUint::zero(), y)),
+        }
+    }
+
+    pub fn add(&self, p1: &Point, p2: &Point) -> Point {
+        let (x1, y1, x2, y2) = match (p1, p2) {
+            (Point::Infinity, _) => return p2.clone(),
+            (_, Point::Infinity) => return p1.clone(),
+            (Point::Affine { x: x1, y: y1 }, Point::Affine { x: x2, y: y2 }) => (x1, y1, x2, y2),
+        };
+        if x1 == x2 {
+            return if (y1 + y2) % &self.p == BigUint::zero() {
+                Point::Infinity
+            } else {
+                self.double(p1)
+            };
+        }
+        let slope = self.div(&self.sub(y2, y1), &self.sub(x2, x1));
+        self.line_point(&slope, x1, y1, x2)
+    }
+
+    pub fn double(&self, point: &Point) -> Point {
+        match point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine { x, y } => {
+                if y.is_zero() {
+                    return Point::Infinity;
+                }
+                let slope = self.div(&(3u32 * x * x + &self.a), &(2u32 * y));
+                self.line_point(&slope, x, y, x)
+            }
+        }
+    }
+
+    /// Third point of the line of slope `slope` through (x1, y1) and x2, reflected
+    fn line_point(&self, slope: &BigUint, x1: &BigUint, y1: &BigUint, x2: &BigUint) -> Point {
+        let x3 = self.sub(&self.sub(&(slope * slope), x1), x2);
+        let y3 = self.sub(&(slope * self.sub(x1, &x3)), y1);
+        Point::new(x3, y3)
+    }
+
+    /// Compute k * point with double and add
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::ec::{EcParams, Point};
+    ///
+    /// let params = EcParams::challenge59();
+    /// assert_eq!(Point::Infinity, params.curve.scalar_mul(&params.base, &params.order));
+    /// ```
+    pub fn scalar_mul(&self, point: &Point, k: &BigUint) -> Point {
+        let mut result = Point::Infinity;
+        for i in (0..k.bits()).rev() {
+            result = self.double(&result);
+            if k.bit(i) {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+
+    /// Random point of the curve, other than the point at infinity
+    pub fn random_point(&self) -> Point {
+        let mut rng = rand::thread_rng();
+        loop {
+            let x = rng.gen_biguint_below(&self.p);
+            if let Some(y) = math::sqrt_mod(&self.rhs(&x), &self.p) {
+                return Point::new(x, y);
+            }
+        }
+    }
+}
+
+/// The group of points, for the kangaroo
+impl Group for WeierstrassCurve {
+    type Element = Point;
+
+    fn mul(&self, a: &Point, b: &Point) -> Point {
+        self.add(a, b)
+    }
+
+    fn pow(&self, g: &Point, n: &BigUint) -> Point {
+        self.scalar_mul(g, n)
+    }
+
+    fn jump(&self, element: &Point, k: u32) -> usize {
+        match element {
+            Point::Infinity => 0,
+            Point::Affine { x, .. } => (x % k).to_usize().unwrap(),
+        }
+    }
+}
+
+/// A curve and a base point of prime order
+#[derive(Debug, Clone, PartialEq)]
+pub struct EcParams {
+    pub curve: WeierstrassCurve,
+    pub base: Point,
+    pub order: BigUint,
+}
+
+impl EcParams {
+    /// Curve of challenge 59
+    pub fn challenge59() -> EcParams {
+        params::ec::challenge59()
+    }
+
+    /// Curves differing from the one of challenge 59 only by b, with their orders
+    ///
+    /// The orders only hold for that curve, so this takes no parameters.
+    pub fn challenge59_bogus_curves() -> Vec<(WeierstrassCurve, BigUint)> {
+        params::ec::challenge59_bogus_curves()
+    }
+
+    /// Random private key in [1, order)
+    pub fn generate_private(&self) -> BigUint {
+        rand::thread_rng().gen_biguint_range(&BigUint::one(), &self.order)
+    }
+
+    pub fn public_key(&self, private: &BigUint) -> Point {
+        self.curve.scalar_mul(&self.base, private)
+    }
+
+    /// Check that a public key is a point of the curve in the subgroup of the base point
+    ///
+    /// The coordinates are range checked first: the addition formulas assume
+    /// reduced coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::ec::{EcParams, Point};
+    ///
+    /// let params = EcParams::challenge59();
+    /// assert!(params.validate_public(&params.base).is_ok());
+    /// assert!(params.validate_public(&Point::Infinity).is_err());
+    /// ```
+    pub fn validate_public(&self, public: &Point) -> Result<()> {
+        let (x, y) = match public {
+            Point::Infinity => return Err("public key at infinity".into()),
+            Point::Affine { x, y } => (x, y),
+        };
+        if x >= &self.curve.p || y >= &self.curve.p {
+            return Err("public key coordinates out of range".into());
+        }
+        if !self.curve.contains(public) {
+            return Err("public key not on the curve".into());
+        }
+        if self.curve.scalar_mul(public, &self.order) != Point::Infinity {
+            return Err("public key outside of the subgroup of the base point".into());
+        }
+        Ok(())
+    }
+}
+
+/// HMAC-SHA256 of a message, keyed by a shared point
+pub fn mac(secret: &Point, message: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(&secret.to_bytes()).unwrap();
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// A peer answering any point with a MACed message, unless it uses `respond_validated`
+pub struct Bob {
+    params: EcParams,
+    private: BigUint,
+}
+
+impl Bob {
+    pub fn new(params: &EcParams) -> Bob {
+        Bob { params: params.clone(), private: params.generate_private() }
+    }
+
+    pub fn public_key(&self) -> Point {
+        self.params.public_key(&self.private)
+    }
+
+    /// Derive the shared point from the other public key, return (message, MAC)
+    pub fn respond(&self, public: &Point) -> (Vec<u8>, Vec<u8>) {
+        let secret = self.params.curve.scalar_mul(public, &self.private);
+        (BOB_MESSAGE.to_vec(), mac(&secret, BOB_MESSAGE))
+    }
+
+    /// Like `respond`, after checking the other public key
+    pub fn respond_validated(&self, public: &Point) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.params.validate_public(public)?;
+        Ok(self.respond(public))
+    }
+
+    /// Bob's private key, to check the attack
+    pub fn private_key(&self) -> &BigUint {
+        &self.private
+    }
+}
+
+/// Recover a peer's private key with points of small order on bogus curves
+///
+/// `bogus_curves` lists curves sharing `a` with the real one, and their orders.
+/// Returns `None` if their distinct small factors below `FACTOR_BOUND` do not
+/// multiply to more than the order of the base point.
+pub fn invalid_curve_attack<F>(params: &EcParams, bogus_curves: &[(WeierstrassCurve, BigUint)], respond: F)
+                               -> Option<BigUint>
+    where F: Fn(&Point) -> (Vec<u8>, Vec<u8>)
+{
+    let mut used = HashSet::new();
+    let mut congruences = Vec::new();
+    let mut product = BigUint::one();
+    for (curve, order) in bogus_curves {
+        for r in math::small_factors(order, FACTOR_BOUND) {
+            if product > params.order {
+                break;
+            }
+            if used.contains(&r) {
+                continue;
+            }
+            // Point of order r on the bogus curve. If r^2 divides the order, the
+            // group may not be cyclic and the cofactor kills every point.
+            let cofactor = order / r;
+            let h = match (0..POINT_TRIES)
+                .map(|_| curve.scalar_mul(&curve.random_point(), &cofactor))
+                .find(|h| *h != Point::Infinity) {
+                Some(h) => h,
+                None => continue,
+            };
+            used.insert(r);
+            let (message, tag) = respond(&h);
+
+            // The shared point is one of 0, h, ..., (r-1)h
+            let mut secret = Point::Infinity;
+            let residue = (0..r).find(|_| {
+                let found = mac(&secret, &message) == tag;
+                secret = curve.add(&secret, &h);
+                found
+            })?;
+            debug!("x = {} mod {}", residue, r);
+            congruences.push((BigUint::from(residue), BigUint::from(r)));
+            product *= r;
+        }
+    }
+    if product <= params.order {
+        return None;
+    }
+    math::crt(&congruences).map(|(x, _)| x)
+}
+
+/// The Montgomery curve Bv^2 = u^3 + Au^2 + u over GF(p)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MontgomeryCurve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+impl MontgomeryCurve {
+    pub fn new(p: &BigUint, a: &BigUint, b: &BigUint) -> MontgomeryCurve {
+        MontgomeryCurve { p: p.clone(), a: a % p, b: b % p }
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b % &self.p) % &self.p
+    }
+
+    /// a / b, or 0 if b is 0 so that the point at infinity maps to 0
+    fn div(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        math::modinv(b, &self.p).map_or_else(BigUint::zero, |inverse| a * inverse % &self.p)
+    }
+
+    /// v^2 for a given u
+    fn v_square(&self, u: &BigUint) -> BigUint {
+        self.div(&((u * u * u + &self.a * u * u + u) % &self.p), &self.b)
+    }
+
+    /// Whether u is the coordinate of a point of the curve rather than of its twist
+    pub fn contains(&self, u: &BigUint) -> bool {
+        math::sqrt_mod(&self.v_square(u), &self.p).is_some()
+    }
+
+    /// u coordinate of k * P from the u coordinate of P, with the Montgomery ladder
+    ///
+    /// The ladder works the same whether u is on the curve or on its twist, and
+    /// the point at infinity maps to 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::ec::MontgomeryParams;
+    /// use num_bigint::BigUint;
+    ///
+    /// let params = MontgomeryParams::challenge60();
+    /// assert_eq!(BigUint::from(0u32), params.curve.ladder(&params.base, &params.order));
+    /// ```
+    pub fn ladder(&self, u: &BigUint, k: &BigUint) -> BigUint {
+        let (mut u2, mut w2) = (BigUint::one(), BigUint::zero());
+        let (mut u3, mut w3) = (u % &self.p, BigUint::one());
+        for i in (0..self.p.bits().max(k.bits())).rev() {
+            // (u2 : w2) is the u of nP and (u3 : w3) the u of (n + 1)P
+            if k.bit(i) {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+            let sum = self.sub(&(&u2 * &u3), &(&w2 * &w3));
+            let difference = self.sub(&(&u2 * &w3), &(&w2 * &u3));
+            u3 = &sum * &sum % &self.p;
+            w3 = u * &difference * &difference % &self.p;
+            let square = self.sub(&(&u2 * &u2), &(&w2 * &w2));
+            let next_w2 = 4u32 * &u2 * &w2 * (&u2 * &u2 + &self.a * &u2 * &w2 + &w2 * &w2) % &self.p;
+            u2 = &square * &square % &self.p;
+            w2 = next_w2;
+            if k.bit(i) {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+        }
+        self.div(&u2, &w2)
+    }
+
+    /// u coordinates of 0, P, 2P, 3P... with x-only doublings and differential additions
+    pub fn multiples<'a>(&'a self, u: &'a BigUint) -> impl Iterator<Item = BigUint> + 'a {
+        let mut n = 0u64;
+        let (mut before, mut current) = (BigUint::zero(), BigUint::zero());
+        std::iter::from_fn(move || {
+            let next = match n {
+                0 => BigUint::zero(),
+                1 => u.clone(),
+                // u(2P) = (u^2 - 1)^2 / 4u(u^2 + Au + 1)
+                2 => {
+                    let square = self.sub(&(u * u), &BigUint::one());
+                    self.div(&(&square * &square), &(4u32 * u * (u * u + &self.a * u + 1u32)))
+                }
+                // u((n + 1)P) = (u(nP) u - 1)^2 / u((n - 1)P) (u(nP) - u)^2
+                _ => {
+                    let product = self.sub(&(&current * u), &BigUint::one());
+                    let difference = self.sub(&current, u);
+                    self.div(&(&product * &product), &(&before * &difference * &difference))
+                }
+            };
+            n += 1;
+            before = std::mem::replace(&mut current, next.clone());
+            Some(next)
+        })
+    }
+
+    /// The equivalent Weierstrass curve, with x = u / B + A / 3B and y = v / B
+    pub fn to_weierstrass(&self) -> WeierstrassCurve {
+        let (a, b) = (BigInt::from(self.a.clone()), BigInt::from(self.b.clone()));
+        let p = BigInt::from(self.p.clone());
+        let div = |n: BigInt, d: BigInt| {
+            let n = ((n % &p + &p) % &p).to_biguint().unwrap();
+            let d = ((d % &p + &p) % &p).to_biguint().unwrap();
+            BigInt::from(self.div(&n, &d))
+        };
+        WeierstrassCurve::new(
+            &self.p,
+            &div(3 - &a * &a, 3 * &b * &b),
+            &div(2 * &a * &a * &a - 9 * &a, 27 * &b * &b * &b),
+        )
+    }
+
+    /// One of the two points of the Weierstrass curve whose u coordinate is `u`
+    ///
+    /// Returns `None` if u is on the twist.
+    pub fn to_weierstrass_point(&self, u: &BigUint) -> Option<Point> {
+        let v = math::sqrt_mod(&self.v_square(u), &self.p)?;
+        let three_b = 3u32 * &self.b % &self.p;
+        Some(Point::new(
+            self.div(&(3u32 * u + &self.a), &three_b),
+            self.div(&v, &self.b),
+        ))
+    }
+}
+
+/// A Montgomery curve, the u coordinate of a base point of prime order and the curve order
+#[derive(Debug, Clone, PartialEq)]
+pub struct MontgomeryParams {
+    pub curve: MontgomeryCurve,
+    pub base: BigUint,
+    pub order: BigUint,
+    pub curve_order: BigUint,
+}
+
+impl MontgomeryParams {
+    /// Curve of challenge 60, the curve of challenge 59 in Montgomery form
+    pub fn challenge60() -> MontgomeryParams {
+        params::ec::challenge60()
+    }
+
+    /// Order of the quadratic twist, 2p + 2 - n since the curve and its twist
+    /// together have two points for each of the p values of u, plus two at infinity
+    pub fn twist_order(&self) -> BigUint {
+        2u32 * &self.curve.p + 2u32 - &self.curve_order
+    }
+
+    /// Random u on the twist
+    pub fn random_twist_u(&self) -> BigUint {
+        let mut rng = rand::thread_rng();
+        loop {
+            let u = rng.gen_biguint_below(&self.curve.p);
+            if !self.curve.contains(&u) {
+                return u;
+            }
+        }
+    }
+
+    /// The same group on the equivalent Weierstrass curve
+    pub fn to_weierstrass(&self) -> EcParams {
+        EcParams {
+            curve: self.curve.to_weierstrass(),
+            base: self.curve.to_weierstrass_point(&self.base).unwrap(),
+            order: self.order.clone(),
+        }
+    }
+
+    /// Random private key in [1, order)
+    pub fn generate_private(&self) -> BigUint {
+        rand::thread_rng().gen_biguint_range(&BigUint::one(), &self.order)
+    }
+
+    /// u coordinate of the public key
+    pub fn public_key(&self, private: &BigUint) -> BigUint {
+        self.curve.ladder(&self.base, private)
+    }
+
+    /// Check that a public u is on the curve, not its twist, and in the subgroup of the base
+    ///
+    /// u = 0 is the point of order 2, and the ladder maps the multiples of the
+    /// order of any point of the subgroup to 0.
+    pub fn validate_public(&self, public: &BigUint) -> Result<()> {
+        if public.is_zero() || public >= &self.curve.p {
+            return Err(format!("public u out of range: {}", public).into());
+        }
+        if !self.curve.contains(public) {
+            return Err(format!("public u on the twist: {}", public).into());
+        }
+        if !self.curve.ladder(public, &self.order).is_zero() {
+            return Err(format!("public u outside of the subgroup of the base point: {}", public).into());
+        }
+        Ok(())
+    }
+}
+
+/// A peer using the x-only ladder, which cannot tell a twist u from a curve u
+/// unless it uses `respond_validated`
+pub struct LadderBob {
+    params: MontgomeryParams,
+    private: BigUint,
+}
+
+impl LadderBob {
+    pub fn new(params: &MontgomeryParams) -> LadderBob {
+        LadderBob { params: params.clone(), private: params.generate_private() }
+    }
+
+    pub fn public_key(&self) -> BigUint {
+        self.params.public_key(&self.private)
+    }
+
+    /// Derive the shared u from the other public u, return (message, MAC)
+    pub fn respond(&self, public: &BigUint) -> (Vec<u8>, Vec<u8>) {
+        let secret = self.params.curve.ladder(public, &self.private);
+        (BOB_MESSAGE.to_vec(), dh::mac(&secret, BOB_MESSAGE))
+    }
+
+    /// Like `respond`, after checking the other public u
+    pub fn respond_validated(&self, public: &BigUint) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.params.validate_public(public)?;
+        Ok(self.respond(public))
+    }
+
+    /// Bob's private key, to check the attack
+    pub fn private_key(&self) -> &BigUint {
+        &self.private
+    }
+}
+
+/// u of a point of order `order` on the twist, which must divide the twist order
+fn twist_u_of_order(params: &MontgomeryParams, order: &BigUint) -> Option<BigUint> {
+    let cofactor = params.twist_order() / order;
+    (0..POINT_TRIES)
+        .map(|_| params.curve.ladder(&params.random_twist_u(), &cofactor))
+        .find(|u| !u.is_zero())
+}
+
+/// Learn a peer's private key modulo the odd factors of the twist order below `factor_bound`
+///
+/// u(kP) = u(-kP), so each factor r only gives x = ±k mod r. Before adding r,
+/// a twist point of order m * r, with m the product of the previous factors,
+/// tells which of the two signs of k matches the sign already chosen. Returns c
+/// and the product of the factors with x = ±c modulo that product.
+pub fn twist_residues<F>(params: &MontgomeryParams, factor_bound: u64, respond: F) -> Option<(BigUint, BigUint)>
+    where F: Fn(&BigUint) -> (Vec<u8>, Vec<u8>)
+{
+    let twist_order = params.twist_order();
+    let mut residue = BigUint::zero();
+    let mut modulus = BigUint::one();
+    for r in math::small_factors(&twist_order, factor_bound) {
+        if r == 2 {
+            continue;
+        }
+        let h = twist_u_of_order(params, &BigUint::from(r))?;
+        let (message, tag) = respond(&h);
+
+        // The shared u is the one of 0, h, ..., ((r-1)/2)h
+        let k = params.curve.multiples(&h)
+            .take((r / 2 + 1) as usize)
+            .position(|u| dh::mac(&u, &message) == tag)?;
+        debug!("x = ±{} mod {}", k, r);
+        let r = BigUint::from(r);
+        let k = BigUint::from(k);
+        let product = &modulus * &r;
+        let candidates: Vec<BigUint> = [k.clone(), (&r - &k) % &r].iter()
+            .map(|k| math::crt(&[(residue.clone(), modulus.clone()), (k.clone(), r.clone())]).unwrap().0)
+            .collect();
+        // The candidates are the same up to sign if k = 0 or c = 0 mod m
+        residue = if candidates[0] == candidates[1] || ((&candidates[0] + &candidates[1]) % &product).is_zero() {
+            candidates[0].clone()
+        } else {
+            // A point of order dividing m * r only gives the same u for both
+            // candidates if its order misses some of the factors
+            (0..POINT_TRIES).find_map(|_| {
+                let h = twist_u_of_order(params, &product)?;
+                let (message, tag) = respond(&h);
+                let matches: Vec<bool> = candidates.iter()
+                    .map(|c| dh::mac(&params.curve.ladder(&h, c), &message) == tag)
+                    .collect();
+                match matches[..] {
+                    [true, false] => Some(candidates[0].clone()),
+                    [false, true] => Some(candidates[1].clone()),
+                    _ => None,
+                }
+            })?
+        };
+        modulus = product;
+    }
+    Some((residue, modulus))
+}
+
+/// Recover a peer's private key from the twist residues and its public u
+///
+/// With x = ±c mod m, and since the public u lifts to ±xG on the Weierstrass
+/// curve, the lifted point is (s + n * m)G with s = ±c and n in [-q / m - 1, q / m].
+/// The kangaroo finds n for one of the two values of s. Both x and q - x give
+/// the same public u and shared secrets, so either of them may be returned.
+pub fn twist_attack<F>(params: &MontgomeryParams, public: &BigUint, factor_bound: u64, respond: F)
+                       -> Option<BigUint>
+    where F: Fn(&BigUint) -> (Vec<u8>, Vec<u8>)
+{
+    let (c, m) = twist_residues(params, factor_bound, respond)?;
+    let signs = [c.clone(), (&m - &c) % &m];
+    if m > params.order {
+        return signs.iter().find(|&x| params.public_key(x) == *public).cloned();
+    }
+
+    let weierstrass = params.to_weierstrass();
+    let curve = &weierstrass.curve;
+    let y = params.curve.to_weierstrass_point(public)?;
+    let bound = &params.order / &m + 1u32;
+    let g = curve.scalar_mul(&weierstrass.base, &m);
+    let shift = curve.scalar_mul(&g, &bound);
+    let kangaroo = Kangaroo::new(&BigUint::zero(), &(2u32 * &bound));
+    let k = kangaroo.k();
+    for s in &signs {
+        // y - sG + bound * mG = (n + bound) * mG
+        let target = curve.add(&curve.add(&y, &curve.neg(&weierstrass.public_key(s))), &shift);
+        // The wild kangaroo can miss the trap, retry with other jump sizes
+        let n = match (k..(k + KANGAROO_RETRIES).min(64))
+            .find_map(|k| kangaroo.clone().with_k(k).catch_in(curve, &g, &target)) {
+            Some(n) => n,
+            None => continue,
+        };
+        // s + (n - bound) * m, reduced modulo q
+        let order = BigInt::from(params.order.clone());
+        let x = BigInt::from(s.clone()) + (BigInt::from(n) - BigInt::from(bound.clone())) * BigInt::from(m.clone());
+        let x = ((x % &order + &order) % &order).to_biguint().unwrap();
+        if params.public_key(&x) == *public {
+            return Some(x);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base_point() {
+        let params = EcParams::challenge59();
+        assert!(params.curve.contains(&params.base));
+        assert_eq!(Point::Infinity, params.curve.scalar_mul(&params.base, &params.order));
+        assert_eq!(params.base, params.curve.scalar_mul(&params.base, &(&params.order + 1u32)));
+    }
+
+    #[test]
+    fn group_law() {
+        let curve = EcParams::challenge59().curve;
+        let (p1, p2, p3) = (curve.random_point(), curve.random_point(), curve.random_point());
+        assert!(curve.contains(&p1));
+        assert_eq!(curve.double(&p1), curve.add(&p1, &p1));
+        assert_eq!(curve.add(&p1, &p2), curve.add(&p2, &p1));
+        assert_eq!(curve.add(&curve.add(&p1, &p2), &p3), curve.add(&p1, &curve.add(&p2, &p3)));
+        assert_eq!(Point::Infinity, curve.add(&p1, &curve.neg(&p1)));
+        assert_eq!(curve.double(&p1), curve.scalar_mul(&p1, &BigUint::from(2u32)));
+        assert_eq!(Point::Infinity, curve.scalar_mul(&p1, &BigUint::zero()));
+    }
+
+    #[test]
+    fn bogus_curve_orders() {
+        let params = EcParams::challenge59();
+        for (curve, order) in EcParams::challenge59_bogus_curves() {
+            let point = curve.random_point();
+            assert!(!params.curve.contains(&point));
+            assert_eq!(Point::Infinity, curve.scalar_mul(&point, &order));
+        }
+    }
+
+    #[test]
+    fn ecdh() {
+        let params = EcParams::challenge59();
+        let bob = Bob::new(&params);
+        let alice = params.generate_private();
+        let secret = params.curve.scalar_mul(&bob.public_key(), &alice);
+        assert_eq!((BOB_MESSAGE.to_vec(), mac(&secret, BOB_MESSAGE)), bob.respond(&params.public_key(&alice)));
+    }
+
+    #[test]
+    fn kangaroo_on_curve() {
+        let params = EcParams::challenge59();
+        let x = BigUint::from(123_456u32);
+        let kangaroo = Kangaroo::new(&BigUint::from(100_000u32), &BigUint::from(200_000u32));
+        let k = kangaroo.k();
+        let found = (k..k + KANGAROO_RETRIES)
+            .find_map(|k| kangaroo.clone().with_k(k).catch_in(&params.curve, &params.base, &params.public_key(&x)));
+        assert_eq!(Some(x), found);
+    }
+
+    #[test]
+    fn montgomery_is_weierstrass() {
+        let params = MontgomeryParams::challenge60();
+        let weierstrass = params.to_weierstrass();
+        assert_eq!(EcParams::challenge59().curve, weierstrass.curve);
+        let k = params.generate_private();
+        let lifted = params.curve.to_weierstrass_point(&params.public_key(&k)).unwrap();
+        let public = weierstrass.public_key(&k);
+        assert!(lifted == public || lifted == weierstrass.curve.neg(&public));
+    }
+
+    #[test]
+    fn ladder_multiples() {
+        let params = MontgomeryParams::challenge60();
+        let u = params.random_twist_u();
+        for (k, multiple) in params.curve.multiples(&u).enumerate().take(8) {
+            assert_eq!(params.curve.ladder(&u, &BigUint::from(k)), multiple);
+        }
+    }
+
+    #[test]
+    fn twist_order() {
+        let params = MontgomeryParams::challenge60();
+        assert!(params.curve.contains(&params.base));
+        let u = params.random_twist_u();
+        assert!(!params.curve.contains(&u));
+        assert!(params.curve.ladder(&u, &params.twist_order()).is_zero());
+        assert_eq!(vec![2, 11, 107, 197], math::small_factors(&params.twist_order(), 1 << 10));
+    }
+
+    #[test]
+    fn residues_up_to_sign() {
+        let params = MontgomeryParams::challenge60();
+        let bob = LadderBob::new(&params);
+        let (c, m) = twist_residues(&params, 1 << 10, |h| bob.respond(h)).unwrap();
+        assert_eq!(BigUint::from(11u32 * 107 * 197), m);
+        let x = bob.private_key() % &m;
+        assert!(x == c || x == (&m - &c) % &m);
+    }
+}
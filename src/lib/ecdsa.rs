@@ -0,0 +1,268 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! ECDSA, duplicate signature key selection and biased nonces
+//!
+//! A signature (r, s) verifies when u1 * G + u2 * Q has the x coordinate r. The
+//! verifier trusts the generator G as much as the public key Q, so anyone can
+//! pick a new private key d' and a generator G' with u1 * G' + u2 * d' * G' equal
+//! to that same point, and claim the signature as theirs.
+//!
+//! Nonces must also be uniform: a few known bits of each nonce turn enough
+//! signatures into a lattice whose short vectors reveal the private key.
+
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use sha2::{Digest, Sha256};
+use crate::ec::{EcParams, Point};
+use crate::{lattice, math};
+
+/// An ECDSA signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+/// SHA-256 of the message, truncated to the bit length of the order
+pub fn hash(message: &[u8], order: &BigUint) -> BigUint {
+    let digest = BigUint::from_bytes_be(&Sha256::digest(message));
+    let excess = 256u64.saturating_sub(order.bits());
+    digest >> excess
+}
+
+/// x coordinate of a point modulo the order, 0 for the point at infinity
+fn x_mod(point: &Point, order: &BigUint) -> BigUint {
+    match point {
+        Point::Infinity => BigUint::zero(),
+        Point::Affine { x, .. } => x % order,
+    }
+}
+
+/// Sign a message with a private key
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::ec::EcParams;
+/// use cryptopals::ecdsa;
+///
+/// let params = EcParams::challenge59();
+/// let private = params.generate_private();
+/// let signature = ecdsa::sign(&params, &private, b"hi mom");
+/// assert!(ecdsa::verify(&params, &params.public_key(&private), b"hi mom", &signature));
+/// assert!(!ecdsa::verify(&params, &params.public_key(&private), b"hi dad", &signature));
+/// ```
+pub fn sign(params: &EcParams, private: &BigUint, message: &[u8]) -> Signature {
+    let mut rng = rand::thread_rng();
+    sign_with_nonces(params, private, message, || rng.gen_biguint_range(&BigUint::one(), &params.order))
+}
+
+/// Sign with nonces whose `zero_bits` low bits are zero, as a biased generator would
+pub fn sign_biased(params: &EcParams, private: &BigUint, message: &[u8], zero_bits: u64) -> Signature {
+    let mut rng = rand::thread_rng();
+    let high = (&params.order - 1u32) >> zero_bits;
+    sign_with_nonces(params, private, message, || rng.gen_biguint_range(&BigUint::one(), &(&high + 1u32)) << zero_bits)
+}
+
+/// Sign with the first usable nonce returned by `nonce`, in [1, order)
+fn sign_with_nonces<F>(params: &EcParams, private: &BigUint, message: &[u8], mut nonce: F) -> Signature
+    where F: FnMut() -> BigUint
+{
+    let n = &params.order;
+    let h = hash(message, n);
+    loop {
+        let k = nonce();
+        let r = x_mod(&params.public_key(&k), n);
+        if r.is_zero() {
+            continue;
+        }
+        // n is prime, k is invertible
+        let k_inv = math::modinv(&k, n).unwrap();
+        let s = k_inv * (&h + private * &r) % n;
+        if !s.is_zero() {
+            return Signature { r, s };
+        }
+    }
+}
+
+/// The scalars u1 = H(m) / s and u2 = r / s of the verification
+///
+/// Returns `None` if r or s are out of range.
+fn scalars(order: &BigUint, message: &[u8], signature: &Signature) -> Option<(BigUint, BigUint)> {
+    let Signature { r, s } = signature;
+    if r.is_zero() || r >= order || s.is_zero() || s >= order {
+        return None;
+    }
+    let w = math::modinv(s, order)?;
+    Some((hash(message, order) * &w % order, r * &w % order))
+}
+
+/// u1 * G + u2 * Q, the point whose x coordinate a valid signature carries
+fn signed_point(params: &EcParams, public: &Point, u1: &BigUint, u2: &BigUint) -> Point {
+    params.curve.add(&params.curve.scalar_mul(&params.base, u1), &params.curve.scalar_mul(public, u2))
+}
+
+pub fn verify(params: &EcParams, public: &Point, message: &[u8], signature: &Signature) -> bool {
+    match scalars(&params.order, message, signature) {
+        Some((u1, u2)) => {
+            let point = signed_point(params, public, &u1, &u2);
+            point != Point::Infinity && x_mod(&point, &params.order) == signature.r
+        }
+        None => false,
+    }
+}
+
+/// Like `verify`, after checking that the public key is a point of the base point subgroup
+pub fn verify_validated(params: &EcParams, public: &Point, message: &[u8], signature: &Signature) -> bool {
+    params.validate_public(public).is_ok() && verify(params, public, message, signature)
+}
+
+/// Craft a generator and a key pair under which an existing signature verifies
+///
+/// With R = u1 * G + u2 * Q and a random d', t = u1 + u2 * d' and G' = t^-1 * R
+/// give u1 * G' + u2 * d' * G' = R. Returns the parameters with G', d' and
+/// Q' = d' * G', or `None` if the signature does not verify in the first place.
+pub fn duplicate_key(params: &EcParams, public: &Point, message: &[u8], signature: &Signature)
+                     -> Option<(EcParams, BigUint, Point)> {
+    if !verify(params, public, message, signature) {
+        return None;
+    }
+    let n = &params.order;
+    let (u1, u2) = scalars(n, message, signature)?;
+    let point = signed_point(params, public, &u1, &u2);
+    loop {
+        let private = params.generate_private();
+        // t = 0 has no inverse, draw another key
+        let t = match math::modinv(&((&u1 + &u2 * &private) % n), n) {
+            Some(t) => t,
+            None => continue,
+        };
+        let forged = EcParams {
+            curve: params.curve.clone(),
+            base: params.curve.scalar_mul(&point, &t),
+            order: n.clone(),
+        };
+        let public = forged.public_key(&private);
+        return Some((forged, private, public));
+    }
+}
+
+/// Recover a private key from signatures whose nonces have `zero_bits` low zero bits
+///
+/// With k = 2^l * b, s = (H(m) + d * r) / k gives b = u + d * t mod q, where
+/// t = r / (s * 2^l), u = H(m) / (s * 2^l) and b < q / 2^l. Scaled by 2^l, the
+/// lattice spanned by the q * 2^l * e_i, (2^l * t_1, ..., 2^l * t_n, 1, 0) and
+/// (2^l * u_1, ..., 2^l * u_n, 0, q) contains the short vector
+/// (2^l * b_1, ..., 2^l * b_n, d, q), which LLL finds with enough signatures.
+pub fn biased_nonce_attack(params: &EcParams, public: &Point, signatures: &[(Vec<u8>, Signature)], zero_bits: u64)
+                           -> Option<BigUint> {
+    let q = &params.order;
+    let n = signatures.len();
+    let scale = BigUint::one() << zero_bits;
+    let mut t_row = vec![BigInt::zero(); n + 2];
+    let mut u_row = vec![BigInt::zero(); n + 2];
+    for (i, (message, signature)) in signatures.iter().enumerate() {
+        let inverse = math::modinv(&(&signature.s * &scale % q), q)?;
+        t_row[i] = BigInt::from(&signature.r * &inverse % q * &scale);
+        u_row[i] = BigInt::from(hash(message, q) * &inverse % q * &scale);
+    }
+    t_row[n] = BigInt::one();
+    u_row[n + 1] = BigInt::from(q.clone());
+
+    let mut basis: Vec<Vec<BigInt>> = (0..n)
+        .map(|i| {
+            let mut row = vec![BigInt::zero(); n + 2];
+            row[i] = BigInt::from(q * &scale);
+            row
+        })
+        .collect();
+    basis.push(t_row);
+    basis.push(u_row);
+
+    let q_int = BigInt::from(q.clone());
+    lattice::lll(basis).iter()
+        .filter_map(|row| {
+            // (…, d, q) or its opposite
+            let d = if row[n + 1] == q_int {
+                row[n].clone()
+            } else if row[n + 1] == -&q_int {
+                -&row[n]
+            } else {
+                return None;
+            };
+            ((d % &q_int + &q_int) % &q_int).to_biguint()
+        })
+        .find(|d| params.public_key(d) == *public)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+    #[test]
+    fn truncated_hash() {
+        let params = EcParams::challenge59();
+        assert!(hash(MESSAGE, &params.order).bits() <= params.order.bits());
+        assert_eq!(BigUint::from_bytes_be(&Sha256::digest(MESSAGE)), hash(MESSAGE, &(BigUint::one() << 300)));
+    }
+
+    #[test]
+    fn bad_signatures() {
+        let params = EcParams::challenge59();
+        let private = params.generate_private();
+        let public = params.public_key(&private);
+        let signature = sign(&params, &private, MESSAGE);
+        assert!(verify(&params, &public, MESSAGE, &signature));
+        let zero = Signature { r: BigUint::zero(), s: signature.s.clone() };
+        assert!(!verify(&params, &public, MESSAGE, &zero));
+        let large = Signature { r: signature.r.clone(), s: &signature.s + &params.order };
+        assert!(!verify(&params, &public, MESSAGE, &large));
+        assert!(!verify(&params, &params.public_key(&(private + 1u32)), MESSAGE, &signature));
+    }
+
+    #[test]
+    fn duplicate_signature_key() {
+        let params = EcParams::challenge59();
+        let private = params.generate_private();
+        let public = params.public_key(&private);
+        let signature = sign(&params, &private, MESSAGE);
+        let (forged, forged_private, forged_public) = duplicate_key(&params, &public, MESSAGE, &signature).unwrap();
+        assert_ne!(private, forged_private);
+        assert!(forged.curve.contains(&forged.base));
+        assert!(verify(&forged, &forged_public, MESSAGE, &signature));
+        assert!(!verify(&forged, &forged_public, b"another message", &signature));
+    }
+
+    #[test]
+    fn biased_nonces() {
+        let params = EcParams::challenge59();
+        let private = params.generate_private();
+        let public = params.public_key(&private);
+        // 16 known bits per nonce, fewer signatures than the 8 bits of challenge 62
+        let signatures: Vec<(Vec<u8>, Signature)> = (0..12)
+            .map(|i| {
+                let message = format!("message {}", i).into_bytes();
+                let signature = sign_biased(&params, &private, &message, 16);
+                (message, signature)
+            })
+            .collect();
+        assert!(signatures.iter().all(|(message, signature)| verify(&params, &public, message, signature)));
+        assert_eq!(Some(private), biased_nonce_attack(&params, &public, &signatures, 16));
+        assert_eq!(None, biased_nonce_attack(&params, &public, &signatures[..2], 16));
+    }
+}
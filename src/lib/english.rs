@@ -14,13 +14,51 @@
 
 //! Text / Corpus manipulation functions
 
-use std::error;
-use crate::helper;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::{helper, stats};
+#[cfg(feature = "net")]
+use crate::cache::Cache;
+use crate::error::{CryptopalsError, Result};
 
 
 /// Alice in Wonderland in text format from Project Gutenberg
 pub const GUTENBERG_CORPUS_URL: &str = "https://www.gutenberg.org/files/11/11-0.txt";
 
+/// The most common English words, in lowercase, for `scoring::Words`
+pub const COMMON_WORDS: &[&str] = &[
+    "the", "of", "and", "to", "a", "in", "is", "it", "you", "that", "he", "was", "for", "on", "are",
+    "with", "as", "i", "his", "they", "be", "at", "one", "have", "this", "from", "or", "had", "by",
+    "not", "word", "but", "what", "some", "we", "can", "out", "other", "were", "all", "there",
+    "when", "up", "use", "your", "how", "said", "an", "each", "she", "which", "do", "their", "time",
+    "if", "will", "way", "about", "many", "then", "them", "write", "would", "like", "so", "these",
+    "her", "long", "make", "thing", "see", "him", "two", "has", "look", "more", "day", "could",
+    "go", "come", "did", "number", "sound", "no", "most", "people", "my", "over", "know", "water",
+    "than", "call", "first", "who", "may", "down", "side", "been", "now", "find", "any", "new",
+    "work", "part", "take", "get", "place", "made", "live", "where", "after", "back", "little",
+    "only", "round", "man", "year", "came", "show", "every", "good", "me", "give", "our", "under",
+    "name", "very", "through", "just", "form", "sentence", "great", "think", "say", "help", "low",
+    "line", "differ", "turn", "cause", "much", "mean", "before", "move", "right", "boy", "old",
+    "too", "same", "tell", "does", "set", "three", "want", "air", "well", "also", "play", "small",
+    "end", "put", "home", "read", "hand", "port", "large", "spell", "add", "even", "land", "here",
+    "must", "big", "high", "such", "follow", "act", "why", "ask", "men", "change", "went", "light",
+    "kind", "off", "need", "house", "picture", "try", "us", "again", "animal", "point", "mother",
+    "world", "near", "build", "self", "earth", "father", "head", "stand", "own", "page", "should",
+    "country", "found", "answer", "school", "grow", "study", "still", "learn", "plant", "cover",
+    "food", "sun", "four", "between", "state", "keep", "eye", "never", "last", "let", "thought",
+    "city", "tree", "cross", "farm", "hard", "start", "might", "story", "saw", "far", "sea", "draw",
+    "left", "late", "run", "don't", "while", "press", "close", "night", "real", "life", "few",
+    "north", "i'm", "it's", "that's",
+];
+
+/// Environment variable holding the size of the corpus sample, see `get_english_frequency`
+pub const CORPUS_SAMPLE_ENV: &str = "CRYPTOPALS_CORPUS_SAMPLE";
+
 /// Compute the characters frequency in a text
 ///
 /// The code only takes ASCII characters into consideration and ignore any other character.
@@ -43,16 +81,35 @@ pub const GUTENBERG_CORPUS_URL: &str = "https://www.gutenberg.org/files/11/11-0.
 /// assert_eq!(expected_freq, f);
 /// ```
 pub fn calc_frequencies(text: &str) -> Vec<f64> {
+    // All the bytes of the UTF-8 encoding of a non-ASCII character are non-ASCII
+    calc_byte_frequencies(text.as_bytes())
+}
+
+/// Compute the ASCII characters frequency of raw bytes, see `calc_frequencies`
+///
+/// Bytes outside of ASCII are ignored, so that plaintexts in another encoding
+/// than UTF-8, e.g. Latin-1, or with some binary data still get the frequencies
+/// of their ASCII letters.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english;
+///
+/// // "Forêt" in Latin-1
+/// assert_eq!(english::calc_frequencies("Fort"), english::calc_byte_frequencies(b"For\xeat"));
+/// ```
+pub fn calc_byte_frequencies(bytes: &[u8]) -> Vec<f64> {
     // Store characters and their frequency in order, defaulting to 0
     let mut frequencies: Vec<f64> = Vec::new();
     frequencies.resize(128, 0.0);
 
     let mut total = 0u32;
 
-    for c in text.chars() {
+    for &byte in bytes {
         // WARNING We ignore non-ASCII characters
-        if c.is_ascii() {
-            frequencies[c.to_ascii_uppercase() as usize] += 1.0;
+        if byte.is_ascii() {
+            frequencies[byte.to_ascii_uppercase() as usize] += 1.0;
             total += 1;
         }
     }
@@ -69,6 +126,45 @@ pub fn calc_frequencies(text: &str) -> Vec<f64> {
 }
 
 
+/// Estimate the characters frequency in a text from `sample_size` random positions
+///
+/// Positions are drawn with replacement. Non-ASCII characters are ignored as in
+/// `calc_frequencies`, since all the bytes of their UTF-8 encoding are non-ASCII.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english;
+///
+/// let f = english::sample_frequencies("aaaa", 100, &mut rand::thread_rng());
+/// assert_eq!(1.0, f['A' as usize]);
+/// ```
+pub fn sample_frequencies<R: Rng>(text: &str, sample_size: usize, rng: &mut R) -> Vec<f64> {
+    let mut frequencies: Vec<f64> = Vec::new();
+    frequencies.resize(128, 0.0);
+
+    let bytes = text.as_bytes();
+    if bytes.is_empty() {
+        return frequencies;
+    }
+
+    let mut total = 0u32;
+    for _ in 0..sample_size {
+        let byte = bytes[rng.gen_range(0..bytes.len())];
+        if byte.is_ascii() {
+            frequencies[byte.to_ascii_uppercase() as usize] += 1.0;
+            total += 1;
+        }
+    }
+
+    if total != 0 {
+        for item in frequencies.iter_mut() {
+            *item /= total as f64;
+        }
+    }
+    frequencies
+}
+
 /// Compute the Euclidean distance between two frequency series
 ///
 /// # Panics:
@@ -98,54 +194,577 @@ pub fn euclidean_distance(freq1: &[f64], freq2: &[f64]) -> f64 {
         .sqrt()
 }
 
+/// Expected frequency of a character missing from the corpus, see `chi_squared`
+pub const UNSEEN_FREQUENCY: f64 = 1e-4;
+
+/// Share of the words of a text found in `COMMON_WORDS`, 0 without any word
+///
+/// Words are the runs of letters and apostrophes, compared in lowercase.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english;
+///
+/// assert_eq!(0.6, english::common_word_share("The cat and THE hat"));
+/// assert_eq!(0.0, english::common_word_share("1234 !!"));
+/// ```
+pub fn common_word_share(text: &str) -> f64 {
+    static WORDS: OnceLock<HashSet<&str>> = OnceLock::new();
+    let common = WORDS.get_or_init(|| COMMON_WORDS.iter().copied().collect());
+
+    let words: Vec<String> = text.split(|c: char| !(c.is_alphabetic() || c == '\''))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        return 0.0;
+    }
+    words.iter().filter(|word| common.contains(word.as_str())).count() as f64 / words.len() as f64
+}
+
+/// Frequency of an n-gram missing from a model, see `FrequencyModel::log_likelihood`
+pub const UNSEEN_NGRAM_FREQUENCY: f64 = 1e-7;
+
+/// Chi-squared statistic of the characters of a text against a frequency table
+///
+/// Every ASCII character, in uppercase like `calc_frequencies`, is a category
+/// expected with its corpus frequency, all the other characters share one
+/// category. Categories missing from the corpus are expected with
+/// `UNSEEN_FREQUENCY`. Unlike `euclidean_distance`, the statistic weighs the
+/// deviations by the counts, so that short texts are not dominated by one
+/// frequent letter. Lower is more English, an empty text gets infinity.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english;
+///
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// assert!(english::chi_squared(&corpus_freq, "a lazy dog")
+///     < english::chi_squared(&corpus_freq, "zq#x!kw@ v"));
+/// ```
+pub fn chi_squared(corpus_freq: &[f64], text: &str) -> f64 {
+    let mut counts = [0.0; 129];
+    for c in text.chars() {
+        let category = if c.is_ascii() { c.to_ascii_uppercase() as usize } else { 128 };
+        counts[category] += 1.0;
+    }
+    let total: f64 = counts.iter().sum();
+    if total == 0.0 {
+        return f64::INFINITY;
+    }
+    let expected: Vec<f64> = (0..counts.len())
+        .map(|category| corpus_freq.get(category).copied().unwrap_or(0.0).max(UNSEEN_FREQUENCY) * total)
+        .collect();
+    stats::chi_squared(&counts, &expected)
+}
+
+
+/// Where to read an English corpus from
+#[derive(Clone, Debug, PartialEq)]
+pub enum CorpusSource {
+    /// Project Gutenberg book, downloaded once and cached
+    Gutenberg(String),
+    /// Local text file
+    File(PathBuf),
+    /// Text already in memory
+    Text(String),
+}
+
+impl Default for CorpusSource {
+    /// The book at `GUTENBERG_CORPUS_URL`
+    fn default() -> Self {
+        CorpusSource::Gutenberg(GUTENBERG_CORPUS_URL.to_owned())
+    }
+}
+
+impl CorpusSource {
+    /// Text of the corpus
+    pub fn load(&self) -> Result<String> {
+        match self {
+            CorpusSource::Gutenberg(url) => get_gutenberg_corpus(url),
+            CorpusSource::File(path) => corpus_from_file(path),
+            CorpusSource::Text(text) => Ok(corpus_from_str(text)),
+        }
+    }
+}
+
+/// Whether a line is a Project Gutenberg start marker
+///
+/// The markers changed over the years: `*** START OF THE PROJECT GUTENBERG
+/// EBOOK X ***`, `*** START OF THIS PROJECT GUTENBERG EBOOK X ***`, with or
+/// without spaces after the asterisks and in any case.
+fn is_start_marker(line: &str) -> bool {
+    let line = line.trim().to_ascii_uppercase();
+    line.starts_with("***") && line.trim_start_matches(['*', ' ']).starts_with("START OF")
+        && line.contains("PROJECT GUTENBERG")
+}
+
+/// Whether a line is a Project Gutenberg end marker, or the first line of an old license footer
+///
+/// Old books end with `End of the Project Gutenberg EBook of X` or `End of
+/// Project Gutenberg's X` before the marker, or instead of it.
+fn is_end_marker(line: &str) -> bool {
+    let line = line.trim().to_ascii_uppercase();
+    let text = line.trim_start_matches(['*', ' ']);
+    (line.starts_with("***") && text.starts_with("END OF") && line.contains("PROJECT GUTENBERG"))
+        || text.starts_with("END OF THE PROJECT GUTENBERG")
+        || text.starts_with("END OF PROJECT GUTENBERG")
+}
+
+/// Select the text of a Project Gutenberg book, between the line of its start marker and its license footer
+///
+/// Lines may end with CRLF or LF only.
+fn gutenberg_text(body: &str) -> Result<&str> {
+    let mut offset = 0;
+    let mut start_text = None;
+    for line in body.split_inclusive('\n') {
+        match start_text {
+            None if is_start_marker(line) => start_text = Some(offset + line.len()),
+            Some(start_text) if is_end_marker(line) => {
+                debug!("Body len: {}", body.len());
+                debug!("Start text: {}, end text: {}", start_text, offset);
+                return Ok(&body[start_text..offset]);
+            }
+            _ => (),
+        }
+        offset += line.len();
+    }
+    match start_text {
+        None => Err(CryptopalsError::Format("Gutenberg start marker not found".to_owned())),
+        Some(_) => Err(CryptopalsError::Format("Gutenberg end marker not found".to_owned())),
+    }
+}
 
 /// Read an English corpus from an URL
 ///
 /// The code supposes that the text is formatted in Project Gutenberg's
 /// style.
-pub fn get_gutenberg_corpus(url: &str) -> Result<String, Box<dyn error::Error>> {
-    const GUTENBERG_START_MARKER: &str = "*** START OF THE PROJECT GUTENBERG EBOOK";
-    const GUTENBERG_END_MARKER: &str = "*** END OF THE PROJECT GUTENBERG EBOOK";
-
+pub fn get_gutenberg_corpus(url: &str) -> Result<String> {
     debug!("Using {} as English corpus", url);
 
     let body = helper::read_from_url(&url)?;
+    Ok(gutenberg_text(&body)?.to_owned())
+}
 
-    // Select all text between the two markers, starting on a new line
-    let start_marker =
-        body.find(GUTENBERG_START_MARKER).ok_or("Gutenberg start marker not found")?;
-    let start_text =
-        start_marker
-            + body[start_marker..].find("\r\n").ok_or("Missing end of line")?
-            + 2;
-    let end_text =
-        body.find(GUTENBERG_END_MARKER).ok_or("Gutenberg end marker not found")?
-            - 1;
+/// Use a text as English corpus
+///
+/// A Project Gutenberg book is stripped of its license header and footer,
+/// any other text is used whole.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english;
+///
+/// assert_eq!("Some English text", english::corpus_from_str("Some English text"));
+/// ```
+pub fn corpus_from_str(text: &str) -> String {
+    gutenberg_text(text).unwrap_or(text).to_owned()
+}
+
+/// Read an English corpus from a local file, see `corpus_from_str`
+pub fn corpus_from_file<P: AsRef<Path>>(path: P) -> Result<String> {
+    debug!("Using {} as English corpus", path.as_ref().display());
+    Ok(corpus_from_str(&fs::read_to_string(path)?))
+}
 
-    debug!("Body len: {}", body.len());
-    debug!("Start text: {}, end text: {}", start_text, end_text);
+/// Prefix of the cache keys of the frequency tables, followed by the source of the corpus
+#[cfg(feature = "net")]
+const FREQUENCY_TABLE_KEY: &str = "cryptopals:frequencies:v2:";
 
-    Ok(body[start_text..=end_text].to_owned())
+/// Cache key of the frequency table of a corpus, `None` if it can't be known without reading the corpus
+///
+/// A Gutenberg book is identified by its URL and the digest of its cached
+/// body, a file by its path, modification time and size, so that neither is
+/// read to find its table. Texts in memory are not cached.
+#[cfg(feature = "net")]
+fn frequency_table_key(cache: &Cache, source: &CorpusSource) -> Option<String> {
+    match source {
+        CorpusSource::Gutenberg(url) => cache.body_digest(url)
+            .map(|digest| format!("{}url:{}:{}", FREQUENCY_TABLE_KEY, url, digest)),
+        CorpusSource::File(path) => {
+            let metadata = fs::metadata(path).ok()?;
+            let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+            Some(format!("{}file:{}:{}:{}", FREQUENCY_TABLE_KEY, path.display(), modified.as_nanos(), metadata.len()))
+        }
+        CorpusSource::Text(_) => None,
+    }
 }
 
-/// Return the text frequency for a standard English corpus
+/// Frequencies of a corpus, read from `cache` if they were computed before
+///
+/// The table is stored as JSON under a key naming the source of the corpus,
+/// see `frequency_table_key`, so the corpus is not even loaded when the table
+/// is cached, and an edited corpus gets a new table. A table which cannot be
+/// read or does not have 128 entries is computed again, and failing to store
+/// it is only logged.
 ///
 /// # Examples:
 ///
 /// ```
-/// use cryptopals::english;
+/// use cryptopals::cache::Cache;
+/// use cryptopals::english::{self, CorpusSource};
 ///
-/// let corpus_frequency: Vec<f64> = english::get_english_frequency().unwrap();
+/// let dir = std::env::temp_dir().join(format!("cryptopals-doc-{}", std::process::id()));
+/// let cache = Cache::new(&dir);
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("corpus.txt"), "Hello").unwrap();
+/// let source = CorpusSource::File(dir.join("corpus.txt"));
+/// let frequencies = english::cached_frequencies(&cache, &source).unwrap();
+/// assert_eq!(english::calc_frequencies("Hello"), frequencies);
+/// // Read from the cache
+/// assert_eq!(frequencies, english::cached_frequencies(&cache, &source).unwrap());
+/// # cache.clean().unwrap();
+/// # std::fs::remove_dir_all(&dir).unwrap();
 /// ```
-pub fn get_english_frequency() -> Result<Vec<f64>, Box<dyn error::Error>> {
+#[cfg(feature = "net")]
+pub fn cached_frequencies(cache: &Cache, source: &CorpusSource) -> Result<Vec<f64>> {
+    if let Some(key) = frequency_table_key(cache, source) {
+        let table: Option<Vec<f64>> = cache.get(&key).and_then(|json| serde_json::from_str(&json).ok());
+        match table {
+            Some(table) if table.len() == 128 => {
+                debug!("Read frequency table {} from cache {}", key, cache.dir().display());
+                return Ok(table);
+            }
+            Some(table) => warn!("Cached frequency table {} has {} entries, computing it again", key, table.len()),
+            None => {}
+        }
+    }
+    let table = calc_frequencies(&source.load()?);
+    // Loading a book caches it, so its key is known now
+    if let Some(key) = frequency_table_key(cache, source) {
+        let stored = serde_json::to_string(&table).map_err(|e| e.into()).and_then(|json| cache.put(&key, &json));
+        if let Err(e) = stored {
+            warn!("Cannot cache frequency table {}: {}", key, e);
+        }
+    }
+    Ok(table)
+}
+
+/// Return the text frequency of an English corpus
+///
+/// `CorpusSource::default()` is the standard English corpus. If
+/// `CRYPTOPALS_CORPUS_SAMPLE` is set to a number of characters, the
+/// frequencies are estimated from a random sample of that size instead of the
+/// whole text. `cargo run -- analyze-sample` measures the loss of accuracy.
+///
+/// The frequencies of a downloaded or local corpus are cached, see
+/// `cached_frequencies`.
+///
+/// # Examples:
+///
+/// ```
+/// use cryptopals::english::{self, CorpusSource};
+///
+/// let local_frequency = english::get_english_frequency(&CorpusSource::Text("Hello".to_owned())).unwrap();
+/// assert_eq!(0.4, local_frequency['L' as usize]);
+///
+/// let corpus_frequency: Vec<f64> = english::get_english_frequency(&CorpusSource::default()).unwrap();
+/// ```
+pub fn get_english_frequency(source: &CorpusSource) -> Result<Vec<f64>> {
+    match env::var(CORPUS_SAMPLE_ENV) {
+        Ok(size) => {
+            let size = size.parse()
+                .map_err(|_| CryptopalsError::Format(format!("{} must be a number of characters", CORPUS_SAMPLE_ENV)))?;
+            debug!("Sampling {} characters of the corpus", size);
+            Ok(sample_frequencies(&source.load()?, size, &mut rand::thread_rng()))
+        }
+        #[cfg(feature = "net")]
+        Err(_) => cached_frequencies(&Cache::default(), source),
+        #[cfg(not(feature = "net"))]
+        Err(_) => Ok(calc_frequencies(&source.load()?)),
+    }
+}
+
+/// Format version of exported frequency models
+///
+/// Increase it whenever the meaning of the exported fields changes.
+pub const MODEL_VERSION: u32 = 1;
+
+/// N-gram frequencies of a corpus, which can be shared as JSON
+///
+/// Only ASCII characters are counted, in uppercase, like `calc_frequencies`.
+/// Pinning an exported model makes results independent of the corpus
+/// downloaded at run time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyModel {
+    /// Format version, checked on import
+    pub version: u32,
+    /// Description of the corpus, e.g. its URL
+    pub source: String,
+    /// Length of the n-grams, 1 for single characters
+    pub n: usize,
+    /// Frequency of every n-gram found in the corpus
+    pub frequencies: BTreeMap<String, f64>,
+}
+
+impl FrequencyModel {
+    /// Count the n-grams of a text
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if `n` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::english::FrequencyModel;
+    ///
+    /// let model = FrequencyModel::build("example", "abab", 2);
+    /// assert_eq!(2.0 / 3.0, model.frequency("AB"));
+    /// assert_eq!(1.0 / 3.0, model.frequency("BA"));
+    /// ```
+    pub fn build(source: &str, text: &str, n: usize) -> FrequencyModel {
+        assert!(n > 0, "n-grams must be at least one character long");
+
+        let chars: Vec<char> = text.chars()
+            .filter(|c| c.is_ascii())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let mut frequencies = BTreeMap::new();
+        let mut total = 0u32;
+        for ngram in chars.windows(n) {
+            *frequencies.entry(ngram.iter().collect()).or_insert(0.0) += 1.0;
+            total += 1;
+        }
+        for frequency in frequencies.values_mut() {
+            *frequency /= total as f64;
+        }
+
+        FrequencyModel { version: MODEL_VERSION, source: source.to_owned(), n, frequencies }
+    }
+
+    /// Frequency of one n-gram, 0 if it never appeared
+    pub fn frequency(&self, ngram: &str) -> f64 {
+        self.frequencies.get(ngram).copied().unwrap_or(0.0)
+    }
+
+    /// Mean log10 frequency of the n-grams of a text, `None` if it is too short
+    ///
+    /// N-grams missing from the model, including those with non-ASCII
+    /// characters, count as `UNSEEN_NGRAM_FREQUENCY`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::english::FrequencyModel;
+    ///
+    /// let model = FrequencyModel::build("example", "the cat sat on the mat", 2);
+    /// assert!(model.log_likelihood("that").unwrap() > model.log_likelihood("qxzv").unwrap());
+    /// assert_eq!(None, model.log_likelihood("a"));
+    /// ```
+    pub fn log_likelihood(&self, text: &str) -> Option<f64> {
+        let chars: Vec<char> = text.chars().map(|c| c.to_ascii_uppercase()).collect();
+        if chars.len() < self.n {
+            return None;
+        }
+        let total: f64 = chars.windows(self.n)
+            .map(|ngram| self.frequency(&ngram.iter().collect::<String>()).max(UNSEEN_NGRAM_FREQUENCY).log10())
+            .sum();
+        Some(total / (chars.len() - self.n + 1) as f64)
+    }
+
+    /// Character frequencies indexed by ASCII code, in the format of `calc_frequencies`
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the model is not made of single characters.
+    pub fn character_frequencies(&self) -> Vec<f64> {
+        assert_eq!(1, self.n, "not a single character model");
+        (0..128u8).map(|c| self.frequency(&(c as char).to_string())).collect()
+    }
+
+    /// Export the model as JSON
+    pub fn export_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Import a model exported by `export_json`
+    ///
+    /// Models with a newer format version than `MODEL_VERSION` are refused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::english::FrequencyModel;
+    ///
+    /// let json = r#"{"version": 1, "source": "pinned", "n": 1, "frequencies": {"E": 0.6, "T": 0.4}}"#;
+    /// let model = FrequencyModel::import_json(json).unwrap();
+    /// assert_eq!(0.6, model.frequency("E"));
+    /// ```
+    pub fn import_json(json: &str) -> Result<FrequencyModel> {
+        let model: FrequencyModel = serde_json::from_str(json)?;
+        if model.version > MODEL_VERSION {
+            return Err(CryptopalsError::Format(format!("unsupported model version {}, expected at most {}",
+                                                       model.version, MODEL_VERSION)));
+        }
+        if model.n == 0 || model.frequencies.keys().any(|ngram| ngram.chars().count() != model.n) {
+            return Err(CryptopalsError::Format(format!("n-grams of the model are not {} characters long", model.n)));
+        }
+        Ok(model)
+    }
+}
+
+/// Build the n-gram model of the standard English corpus
+pub fn get_english_model(n: usize) -> Result<FrequencyModel> {
     let corpus = get_gutenberg_corpus(GUTENBERG_CORPUS_URL)?;
-    Ok(calc_frequencies(&corpus))
+    Ok(FrequencyModel::build(GUTENBERG_CORPUS_URL, &corpus, n))
+}
+
+/// Words of a corpus with their frequencies, to put spaces back in text
+///
+/// Each word keeps its most frequent spelling in the corpus, so that names
+/// and "I" get their capital letter back.
+pub struct WordList {
+    /// Log probability and usual spelling, indexed by the lowercase word
+    words: HashMap<String, (f64, String)>,
+    /// Number of words in the corpus
+    total: f64,
+    /// Length of the longest word
+    max_len: usize,
+}
+
+impl WordList {
+    /// Count the alphabetic words of a text
+    pub fn build(text: &str) -> WordList {
+        let mut spellings: HashMap<String, BTreeMap<&str, u32>> = HashMap::new();
+        let mut total = 0u32;
+        for word in text.split(|c: char| !c.is_ascii_alphabetic()).filter(|w| !w.is_empty()) {
+            *spellings.entry(word.to_ascii_lowercase()).or_default().entry(word).or_insert(0) += 1;
+            total += 1;
+        }
+        let total = total.max(1) as f64;
+        let max_len = spellings.keys().map(String::len).max().unwrap_or(0);
+        let words = spellings.into_iter()
+            .map(|(word, spellings)| {
+                let count: u32 = spellings.values().sum();
+                // Ties go to the lowercase spelling, the last one in ASCII order
+                let spelling = spellings.iter().max_by_key(|&(_, &n)| n).map(|(s, _)| s.to_string()).unwrap();
+                (word, ((count as f64 / total).ln(), spelling))
+            })
+            .collect();
+        WordList { words, total, max_len }
+    }
+
+    /// Log probability of a lowercase word, unknown words get less likely as they get longer
+    fn log_probability(&self, word: &str) -> f64 {
+        match self.words.get(word) {
+            Some((log_probability, _)) => *log_probability,
+            None => (10.0 / self.total).ln() - word.len() as f64 * 10f64.ln(),
+        }
+    }
+
+    /// Most likely split of a text into words, ignoring its case and non-letters
+    ///
+    /// Dynamic programming finds the split which maximizes the product of the
+    /// word probabilities, in time linear in the length of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::english::WordList;
+    ///
+    /// let words = WordList::build("Alice was beginning to get very tired of sitting by her sister");
+    /// assert_eq!(vec!["Alice", "was", "tired"], words.segment("ALICEWASTIRED"));
+    /// ```
+    pub fn segment(&self, text: &str) -> Vec<String> {
+        let letters: Vec<u8> = text.bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        // best[i]: log probability of the best split of letters[..i], and the start of its last word
+        let mut best = vec![(0.0, 0); letters.len() + 1];
+        for end in 1..=letters.len() {
+            // Unknown words longer than the known ones are split anyway
+            let longest = self.max_len.max(1).min(end);
+            best[end] = ((end - longest)..end)
+                .map(|start| {
+                    let word = std::str::from_utf8(&letters[start..end]).unwrap();
+                    (best[start].0 + self.log_probability(word), start)
+                })
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                .unwrap();
+        }
+
+        let mut words = Vec::new();
+        let mut end = letters.len();
+        while end > 0 {
+            let start = best[end].1;
+            let word = std::str::from_utf8(&letters[start..end]).unwrap();
+            words.push(self.words.get(word).map_or_else(|| word.to_owned(), |(_, spelling)| spelling.clone()));
+            end = start;
+        }
+        words.reverse();
+        words
+    }
+
+    /// Put back the spaces and the usual case of the words of a text, and
+    /// capitalize its first letter
+    ///
+    /// Meant for the output of solvers which only keep letters, like those of
+    /// Vigenère or substitution ciphers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::english::WordList;
+    ///
+    /// let words = WordList::build("she said I am late and the rabbit ran down the hole");
+    /// assert_eq!("I am late said the rabbit", words.restore("IAMLATESAIDTHERABBIT"));
+    /// ```
+    pub fn restore(&self, text: &str) -> String {
+        let restored = self.segment(text).join(" ");
+        let mut chars = restored.chars();
+        match chars.next() {
+            Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+            None => restored,
+        }
+    }
+}
+
+/// Build the word list of the standard English corpus
+pub fn get_english_wordlist() -> Result<WordList> {
+    Ok(WordList::build(&get_gutenberg_corpus(GUTENBERG_CORPUS_URL)?))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[cfg(feature = "net")]
+    #[test]
+    fn frequency_table_cache() {
+        let dir = std::env::temp_dir().join(format!("cryptopals-frequencies-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("corpus.txt");
+        fs::write(&path, "abc").unwrap();
+        let source = CorpusSource::File(path.clone());
+        assert_eq!(calc_frequencies("abc"), cached_frequencies(&cache, &source).unwrap());
+        let index = cache.index().unwrap();
+        assert_eq!(1, index.len());
+
+        // The stored table is read back instead of being computed, without reading the corpus
+        let key = index.keys().next().unwrap().clone();
+        assert!(key.starts_with(FREQUENCY_TABLE_KEY));
+        cache.put(&key, &serde_json::to_string(&vec![0.5; 128]).unwrap()).unwrap();
+        assert_eq!(vec![0.5; 128], cached_frequencies(&cache, &source).unwrap());
+
+        // A table of the wrong length is computed again
+        cache.put(&key, &serde_json::to_string(&vec![0.5; 3]).unwrap()).unwrap();
+        assert_eq!(calc_frequencies("abc"), cached_frequencies(&cache, &source).unwrap());
+
+        // Texts in memory are not cached
+        let text = CorpusSource::Text("abd".to_owned());
+        assert_eq!(calc_frequencies("abd"), cached_frequencies(&cache, &text).unwrap());
+        assert_eq!(1, cache.index().unwrap().len());
+
+        cache.clean().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     fn empty_freq() -> Vec<f64> {
         let mut expected_f: Vec<f64> = Vec::new();
         expected_f.resize(128, 0.0);
@@ -243,4 +862,97 @@ mod test {
     fn euclid_bad_size() {
         let _ = euclidean_distance(&vec![1.0, 2.0, 3.0, 4.0], &vec![1.0, 2.0, 3.0]);
     }
+
+    #[test]
+    fn model_matches_calc_frequencies() {
+        let text = "Hello, World! Forêt";
+        assert_eq!(calc_frequencies(text), FrequencyModel::build("test", text, 1).character_frequencies());
+    }
+
+    #[test]
+    fn model_json_roundtrip() {
+        let model = FrequencyModel::build("test", "The quick brown fox jumps over the lazy dog", 3);
+        let json = model.export_json().unwrap();
+        assert!(json.contains("\"version\": 1"));
+        assert_eq!(model, FrequencyModel::import_json(&json).unwrap());
+    }
+
+    #[test]
+    fn model_import_rejects() {
+        // Newer version
+        assert!(FrequencyModel::import_json(r#"{"version": 2, "source": "", "n": 1, "frequencies": {}}"#).is_err());
+        // N-gram of the wrong length
+        assert!(FrequencyModel::import_json(r#"{"version": 1, "source": "", "n": 2, "frequencies": {"A": 1.0}}"#).is_err());
+        // Missing header
+        assert!(FrequencyModel::import_json(r#"{"n": 1, "frequencies": {}}"#).is_err());
+    }
+
+    #[test]
+    fn sample_empty() {
+        assert_eq!(empty_freq(), sample_frequencies("", 100, &mut rand::thread_rng()));
+        assert_eq!(empty_freq(), sample_frequencies("abc", 0, &mut rand::thread_rng()));
+    }
+
+    #[test]
+    fn sample_close_to_full() {
+        use rand::SeedableRng;
+
+        let text = "The quick brown fox jumps over the lazy dog, thrice. ".repeat(100);
+        let full = calc_frequencies(&text);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let small = euclidean_distance(&full, &sample_frequencies(&text, 100, &mut rng));
+        let large = euclidean_distance(&full, &sample_frequencies(&text, 100_000, &mut rng));
+        assert!(large < small);
+        assert!(large < 0.01);
+    }
+
+    #[test]
+    fn local_corpus() {
+        let book = "License\r\n*** START OF THE PROJECT GUTENBERG EBOOK X ***\r\nThe tale.\n*** END OF THE PROJECT GUTENBERG EBOOK X ***";
+        assert_eq!("The tale.\n", corpus_from_str(book));
+        assert_eq!("No markers", corpus_from_str("No markers"));
+
+        let path = std::env::temp_dir().join(format!("cryptopals-corpus-{}.txt", std::process::id()));
+        fs::write(&path, book).unwrap();
+        let source = CorpusSource::File(path.clone());
+        assert_eq!("The tale.\n", source.load().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(source.load().is_err());
+    }
+
+    #[test]
+    fn gutenberg_markers() {
+        let books = [
+            "Header\r\n*** START OF THE PROJECT GUTENBERG EBOOK ALICE ***\r\n\r\nThe tale.\r\n*** END OF THE PROJECT GUTENBERG EBOOK ALICE ***\r\nLicense",
+            "Header\n*** START OF THIS PROJECT GUTENBERG EBOOK ALICE ***\n\nThe tale.\n*** END OF THIS PROJECT GUTENBERG EBOOK ALICE ***\n",
+            "Header\n***START OF THE PROJECT GUTENBERG EBOOK ALICE***\n\nThe tale.\n***END OF THE PROJECT GUTENBERG EBOOK ALICE***\n",
+            "Header\n*** Start of the Project Gutenberg EBook Alice ***\n\nThe tale.\nEnd of the Project Gutenberg EBook of Alice\n\n*** END OF THIS PROJECT GUTENBERG EBOOK ALICE ***\n",
+            "Header\n*** START OF THE PROJECT GUTENBERG EBOOK ALICE ***\n\nThe tale.\nEnd of Project Gutenberg's Alice, by Lewis Carroll\nLicense",
+        ];
+        for book in &books {
+            assert_eq!("The tale.", gutenberg_text(book).unwrap().trim(), "{:?}", book);
+        }
+    }
+
+    #[test]
+    fn gutenberg_missing_markers() {
+        assert!(gutenberg_text("The tale.\n*** END OF THE PROJECT GUTENBERG EBOOK X ***\n").is_err());
+        // A truncated download lacks the end marker
+        assert!(gutenberg_text("*** START OF THE PROJECT GUTENBERG EBOOK X ***\nThe ta").is_err());
+        // The end marker only counts after the start marker
+        let book = "*** END OF THE PROJECT GUTENBERG EBOOK X ***\n*** START OF THE PROJECT GUTENBERG EBOOK X ***\nThe tale.\n";
+        assert!(gutenberg_text(book).is_err());
+        // Mentions of the project in the text are not markers
+        assert!(!is_end_marker("The end of Project Gutenberg's history is near"));
+    }
+
+    #[test]
+    fn segment_words() {
+        let words = WordList::build("The Queen said to Alice: the cat is out of the bag. Off with their heads!");
+        assert_eq!(vec!["the", "Queen", "said", "Off", "with", "their", "heads"],
+                   words.segment("thequeensaid, OFFWITHTHEIRHEADS"));
+        assert_eq!("Alice said xyzzy", words.restore("alicesaidxyzzy"));
+        assert!(words.segment("").is_empty());
+        assert_eq!("", words.restore("!!"));
+    }
 }
@@ -0,0 +1,155 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Error type shared by the text, encoding and input helpers
+//!
+//! Callers can match on the kind of failure, and the underlying error, if
+//! any, is available through `source()`.
+
+use alloc::boxed::Box;
+use alloc::string::{FromUtf8Error, String};
+use core::{error, fmt};
+use core::num::ParseIntError;
+#[cfg(feature = "std")]
+use std::io;
+
+pub type Result<T> = core::result::Result<T, CryptopalsError>;
+
+#[derive(Debug)]
+pub enum CryptopalsError {
+    /// Hexadecimal string of odd length or with non-hexadecimal digits
+    Hex(Option<ParseIntError>),
+    /// Malformed Base64 string, with the description of the problem
+    Base64(String),
+    /// Invalid block padding, with the description of the problem
+    Padding(String),
+    /// Bytes which are not UTF-8 text
+    Utf8(FromUtf8Error),
+    /// Failure while downloading a challenge input
+    #[cfg(feature = "net")]
+    Network(reqwest::Error),
+    /// Challenge input missing from the cache while the network is disabled
+    Offline(String),
+    /// Failure of the download cache
+    Cache(Box<dyn error::Error>),
+    /// Other input or output failure
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// Malformed JSON data
+    #[cfg(feature = "std")]
+    Json(serde_json::Error),
+    /// Input which does not have the expected format
+    Format(String),
+    /// An attack did not recover its secret
+    Attack(String),
+}
+
+impl fmt::Display for CryptopalsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CryptopalsError::Hex(_) => write!(f, "invalid hexadecimal string"),
+            CryptopalsError::Base64(message) => write!(f, "{}", message),
+            CryptopalsError::Padding(message) => write!(f, "{}", message),
+            CryptopalsError::Utf8(e) => write!(f, "invalid UTF-8 text: {}", e),
+            #[cfg(feature = "net")]
+            CryptopalsError::Network(e) => write!(f, "network error: {}", e),
+            CryptopalsError::Offline(url) => write!(f, "{} is not in the cache and the network is disabled", url),
+            CryptopalsError::Cache(e) => write!(f, "cache error: {}", e),
+            #[cfg(feature = "std")]
+            CryptopalsError::Io(e) => write!(f, "input/output error: {}", e),
+            #[cfg(feature = "std")]
+            CryptopalsError::Json(e) => write!(f, "invalid JSON: {}", e),
+            CryptopalsError::Format(message) => write!(f, "{}", message),
+            CryptopalsError::Attack(message) => write!(f, "attack failed: {}", message),
+        }
+    }
+}
+
+impl error::Error for CryptopalsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CryptopalsError::Hex(Some(e)) => Some(e),
+            CryptopalsError::Utf8(e) => Some(e),
+            #[cfg(feature = "net")]
+            CryptopalsError::Network(e) => Some(e),
+            CryptopalsError::Cache(e) => Some(e.as_ref()),
+            #[cfg(feature = "std")]
+            CryptopalsError::Io(e) => Some(e),
+            #[cfg(feature = "std")]
+            CryptopalsError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIntError> for CryptopalsError {
+    fn from(e: ParseIntError) -> Self {
+        CryptopalsError::Hex(Some(e))
+    }
+}
+
+impl From<FromUtf8Error> for CryptopalsError {
+    fn from(e: FromUtf8Error) -> Self {
+        CryptopalsError::Utf8(e)
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<reqwest::Error> for CryptopalsError {
+    fn from(e: reqwest::Error) -> Self {
+        CryptopalsError::Network(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for CryptopalsError {
+    fn from(e: io::Error) -> Self {
+        CryptopalsError::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<serde_json::Error> for CryptopalsError {
+    fn from(e: serde_json::Error) -> Self {
+        CryptopalsError::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn source_chaining() {
+        let e: CryptopalsError = "zz".parse::<u8>().unwrap_err().into();
+        assert_eq!("invalid hexadecimal string", e.to_string());
+        assert_eq!("invalid digit found in string", e.source().unwrap().to_string());
+        assert!(CryptopalsError::Hex(None).source().is_none());
+
+        let e: CryptopalsError = String::from_utf8(vec![0xff]).unwrap_err().into();
+        assert!(matches!(e, CryptopalsError::Utf8(_)));
+        assert!(e.source().is_some());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn display_cause() {
+        let e: CryptopalsError = io::Error::new(io::ErrorKind::NotFound, "no such file").into();
+        assert_eq!("input/output error: no such file", e.to_string());
+        let e = CryptopalsError::Cache("disk full".into());
+        assert_eq!("cache error: disk full", e.to_string());
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! C interface to the hex and Base64 codecs and the single-byte XOR breaker
+//!
+//! Every function reads `input_len` bytes from `input` and writes to a
+//! buffer of `output_len` bytes owned by the caller, nothing is allocated
+//! for C. They return the number of bytes written, or a negative error:
+//! `CRYPTOPALS_ERROR_INVALID` for a null pointer or a malformed input, and
+//! `CRYPTOPALS_ERROR_BUFFER` when the output does not fit. Text is written
+//! without a trailing NUL. Null pointers are accepted with a length of 0.
+//! A panic is caught before it unwinds into C, and reported as
+//! `CRYPTOPALS_ERROR_INVALID`.
+//!
+//! The declarations are in `include/cryptopals.h`, build the library with:
+//!
+//! ```text
+//! cargo rustc --release --lib --no-default-features --features ffi --crate-type staticlib
+//! ```
+
+use std::{ptr, slice};
+use std::panic::{self, AssertUnwindSafe};
+use crate::{crypto, english, scoring};
+use crate::crypto::{BytesCrypto, HexString};
+
+/// Null pointer, malformed hex or Base64, or nothing to decrypt
+pub const CRYPTOPALS_ERROR_INVALID: isize = -1;
+/// The output buffer is too small
+pub const CRYPTOPALS_ERROR_BUFFER: isize = -2;
+
+/// Run the body of an exported function, a panic returns `CRYPTOPALS_ERROR_INVALID`
+///
+/// Unwinding out of an `extern "C"` function is undefined behaviour.
+fn guard<F: FnOnce() -> isize>(body: F) -> isize {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(CRYPTOPALS_ERROR_INVALID)
+}
+
+/// Borrow the input of C, `None` for a null pointer with a length
+unsafe fn borrow_input<'a>(input: *const u8, input_len: usize) -> Option<&'a [u8]> {
+    match (input.is_null(), input_len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(input, input_len)),
+    }
+}
+
+/// Copy `bytes` to the output of C, return the length written or an error
+unsafe fn write_output(bytes: &[u8], output: *mut u8, output_len: usize) -> isize {
+    if bytes.len() > output_len {
+        return CRYPTOPALS_ERROR_BUFFER;
+    }
+    if !bytes.is_empty() {
+        if output.is_null() {
+            return CRYPTOPALS_ERROR_INVALID;
+        }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), output, bytes.len());
+    }
+    bytes.len() as isize
+}
+
+/// Decode the ASCII text of C with `decode`
+unsafe fn decode<F>(text: *const u8, text_len: usize, out: *mut u8, out_len: usize, decode: F) -> isize
+    where F: Fn(&str) -> crate::error::Result<Vec<u8>> {
+    let bytes = match borrow_input(text, text_len).and_then(|bytes| std::str::from_utf8(bytes).ok()) {
+        Some(text) => decode(text),
+        None => return CRYPTOPALS_ERROR_INVALID,
+    };
+    match bytes {
+        Ok(bytes) => write_output(&bytes, out, out_len),
+        Err(_) => CRYPTOPALS_ERROR_INVALID,
+    }
+}
+
+/// Write the lowercase hex of `input`, `2 * input_len` bytes
+///
+/// # Safety
+///
+/// `input` and `output` must be valid for `input_len` and `output_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cryptopals_hex_encode(input: *const u8, input_len: usize,
+                                               output: *mut u8, output_len: usize) -> isize {
+    guard(|| {
+        match borrow_input(input, input_len) {
+            Some(bytes) => write_output(bytes.bytes2hex().as_bytes(), output, output_len),
+            None => CRYPTOPALS_ERROR_INVALID,
+        }
+    })
+}
+
+/// Write the bytes of the hex text `input`, `input_len / 2` bytes
+///
+/// An empty text is invalid, like in `HexString::hex2bytes`.
+///
+/// # Safety
+///
+/// `input` and `output` must be valid for `input_len` and `output_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cryptopals_hex_decode(input: *const u8, input_len: usize,
+                                               output: *mut u8, output_len: usize) -> isize {
+    guard(|| decode(input, input_len, output, output_len, |text| text.hex2bytes()))
+}
+
+/// Write the padded standard Base64 of `input`, `4 * ceil(input_len / 3)` bytes
+///
+/// # Safety
+///
+/// `input` and `output` must be valid for `input_len` and `output_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cryptopals_base64_encode(input: *const u8, input_len: usize,
+                                                  output: *mut u8, output_len: usize) -> isize {
+    guard(|| {
+        match borrow_input(input, input_len) {
+            Some(bytes) => write_output(bytes.base64_encode().as_bytes(), output, output_len),
+            None => CRYPTOPALS_ERROR_INVALID,
+        }
+    })
+}
+
+/// Write the bytes of the Base64 text `input`, at most `3 * input_len / 4` bytes
+///
+/// Line breaks are skipped, see `HexString::base64_decode`.
+///
+/// # Safety
+///
+/// `input` and `output` must be valid for `input_len` and `output_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cryptopals_base64_decode(input: *const u8, input_len: usize,
+                                                  output: *mut u8, output_len: usize) -> isize {
+    guard(|| decode(input, input_len, output, output_len, |text| text.base64_decode()))
+}
+
+/// Break a single-byte XOR ciphertext, write its key to `key` and its plaintext to `output`
+///
+/// The plaintext has `input_len` bytes. It is scored against the letter
+/// frequencies of the bundled English samples, see `crypto::decrypt_bytes`,
+/// so that nothing is downloaded.
+///
+/// # Safety
+///
+/// `input` and `output` must be valid for `input_len` and `output_len` bytes,
+/// and `key` for one byte.
+#[no_mangle]
+pub unsafe extern "C" fn cryptopals_break_single_byte_xor(input: *const u8, input_len: usize, key: *mut u8,
+                                                          output: *mut u8, output_len: usize) -> isize {
+    guard(|| {
+        let ciphertext = match borrow_input(input, input_len) {
+            Some(ciphertext) if !key.is_null() => ciphertext,
+            _ => return CRYPTOPALS_ERROR_INVALID,
+        };
+        if input_len > output_len {
+            return CRYPTOPALS_ERROR_BUFFER;
+        }
+        let corpus_freq = english::calc_frequencies(&scoring::ENGLISH_SAMPLES.join(" "));
+        match crypto::decrypt_bytes(ciphertext, &corpus_freq) {
+            Some(best) => {
+                *key = best.key;
+                write_output(&best.plaintext, output, output_len)
+            }
+            None => CRYPTOPALS_ERROR_INVALID,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Call a codec on `input` with an output buffer of `capacity` bytes
+    fn call(codec: unsafe extern "C" fn(*const u8, usize, *mut u8, usize) -> isize,
+            input: &[u8], capacity: usize) -> Result<Vec<u8>, isize> {
+        let mut buffer = vec![0; capacity];
+        let written = unsafe { codec(input.as_ptr(), input.len(), buffer.as_mut_ptr(), buffer.len()) };
+        if written < 0 {
+            return Err(written);
+        }
+        buffer.truncate(written as usize);
+        Ok(buffer)
+    }
+
+    #[test]
+    fn codecs() {
+        assert_eq!(b"49276d".to_vec(), call(cryptopals_hex_encode, b"I'm", 6).unwrap());
+        assert_eq!(b"I'm".to_vec(), call(cryptopals_hex_decode, b"49276D", 3).unwrap());
+        assert_eq!(b"SSdt".to_vec(), call(cryptopals_base64_encode, b"I'm", 10).unwrap());
+        assert_eq!(b"I'm".to_vec(), call(cryptopals_base64_decode, b"SSdt\n", 3).unwrap());
+        assert_eq!(Vec::<u8>::new(), call(cryptopals_base64_encode, b"", 0).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(CRYPTOPALS_ERROR_BUFFER), call(cryptopals_hex_encode, b"I'm", 5));
+        assert_eq!(Err(CRYPTOPALS_ERROR_INVALID), call(cryptopals_hex_decode, b"4927Z", 3));
+        assert_eq!(Err(CRYPTOPALS_ERROR_INVALID), call(cryptopals_base64_decode, b"\xffSdt", 3));
+        let mut buffer = [0; 4];
+        let written = unsafe { cryptopals_hex_decode(ptr::null(), 2, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(CRYPTOPALS_ERROR_INVALID, written);
+        assert_eq!(CRYPTOPALS_ERROR_INVALID, guard(|| panic!("unwinding into C")));
+    }
+
+    #[test]
+    fn break_single_byte_xor() {
+        let plaintext = scoring::ENGLISH_SAMPLES[3].as_bytes();
+        let ciphertext: Vec<u8> = plaintext.iter().map(|byte| byte ^ 0x58).collect();
+        let mut key = 0;
+        let mut buffer = vec![0; ciphertext.len()];
+        let written = unsafe {
+            cryptopals_break_single_byte_xor(ciphertext.as_ptr(), ciphertext.len(), &mut key,
+                                             buffer.as_mut_ptr(), buffer.len())
+        };
+        assert_eq!(ciphertext.len() as isize, written);
+        assert_eq!((0x58, plaintext), (key, &buffer[..]));
+        let written = unsafe {
+            cryptopals_break_single_byte_xor(ciphertext.as_ptr(), ciphertext.len(), &mut key,
+                                             buffer.as_mut_ptr(), buffer.len() - 1)
+        };
+        assert_eq!(CRYPTOPALS_ERROR_BUFFER, written);
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Malformed parameters for the hardening tests of the key exchanges
+//!
+//! Each generator returns the classic bad inputs of a handler: zero, one and
+//! the other fixed points, unreduced and oversized integers, elements outside
+//! the prime order subgroup, non-residues, twist and bogus curve points. The
+//! validated handlers must reject all of them, and `failures` reports the
+//! inputs they accept or panic on.
+
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use rand::Rng;
+use crate::dh::{self, DhParams};
+use crate::ec::{EcParams, MontgomeryParams, Point};
+use crate::ecdsa::Signature;
+use crate::math;
+
+/// Bound of the small factors used for elements of small order
+const SMALL_FACTOR_BOUND: u64 = 1 << 12;
+
+/// Up to `rounds` random integers of up to twice the bits of `p`, at least `p`
+fn oversized<R: Rng>(p: &BigUint, rng: &mut R, rounds: usize) -> Vec<BigUint> {
+    (0..rounds).map(|_| p + rng.gen_biguint(p.bits() * 2)).collect()
+}
+
+/// Malformed Diffie-Hellman public keys, with `rounds` values of each random kind
+pub fn dh_public_keys<R: Rng>(params: &DhParams, rng: &mut R, rounds: usize) -> Vec<BigUint> {
+    let p = &params.p;
+    let mut keys = vec![
+        BigUint::zero(),
+        BigUint::one(),
+        p - 1u32,
+        p.clone(),
+        p + 1u32,
+        p + &params.g,
+        p * 2u32,
+    ];
+    keys.extend(oversized(p, rng, rounds));
+
+    // Non-residues, whose order is even while q is odd
+    let half = (p - 1u32) / 2u32;
+    let mut non_residues = 0;
+    while non_residues < rounds {
+        let y = rng.gen_biguint_range(&BigUint::from(2u32), p);
+        if y.modpow(&half, p) == p - 1u32 {
+            keys.push(y);
+            non_residues += 1;
+        }
+    }
+    // Elements of the small subgroups, and random elements outside of q
+    let cofactor = (p - 1u32) / &params.q;
+    for factor in math::small_factors(&cofactor, SMALL_FACTOR_BOUND).into_iter().take(rounds) {
+        keys.push(dh::element_of_order(p, &BigUint::from(factor)));
+    }
+    for _ in 0..rounds {
+        keys.push(rng.gen_biguint_range(&BigUint::from(2u32), &(p - 1u32)));
+    }
+    keys
+}
+
+/// Malformed public points of a Weierstrass curve, with `rounds` points of each random kind
+pub fn ec_points<R: Rng>(params: &EcParams, rng: &mut R, rounds: usize) -> Vec<Point> {
+    let curve = &params.curve;
+    let p = &curve.p;
+    let (x, y) = match &params.base {
+        Point::Affine { x, y } => (x.clone(), y.clone()),
+        Point::Infinity => unreachable!("the base point is affine"),
+    };
+    let mut points = vec![
+        Point::Infinity,
+        Point::new(BigUint::zero(), BigUint::zero()),
+        Point::new(&x + p, y.clone()),
+        Point::new(x.clone(), &y + p),
+    ];
+    for big in oversized(p, rng, rounds) {
+        points.push(Point::new(big.clone(), y.clone()));
+        points.push(Point::new(x.clone(), big));
+    }
+    for _ in 0..rounds {
+        // Off the curve, and on another curve sharing a
+        points.push(Point::new(rng.gen_biguint_below(p), rng.gen_biguint_below(p)));
+        let b = BigInt::from(rng.gen_biguint_below(p));
+        points.push(curve.with_b(&b).random_point());
+        // On the curve, of small order
+        let small = curve.scalar_mul(&curve.random_point(), &params.order);
+        if small != Point::Infinity {
+            points.push(small);
+        }
+    }
+    points
+}
+
+/// Malformed public u coordinates of a Montgomery curve, with `rounds` values of each random kind
+pub fn montgomery_us<R: Rng>(params: &MontgomeryParams, rng: &mut R, rounds: usize) -> Vec<BigUint> {
+    let p = &params.curve.p;
+    let mut us = vec![BigUint::zero(), p.clone(), p + 1u32, p + &params.base];
+    us.extend(oversized(p, rng, rounds));
+    for _ in 0..rounds {
+        us.push(params.random_twist_u());
+        // Multiple of the order of a random curve point, of small order
+        let u = loop {
+            let u = rng.gen_biguint_below(p);
+            if params.curve.contains(&u) {
+                break u;
+            }
+        };
+        let small = params.curve.ladder(&u, &params.order);
+        if !small.is_zero() {
+            us.push(small);
+        }
+    }
+    us
+}
+
+/// Out of range ECDSA signatures, with `rounds` oversized ones
+pub fn ecdsa_signatures<R: Rng>(params: &EcParams, rng: &mut R, rounds: usize) -> Vec<Signature> {
+    let n = &params.order;
+    let one = BigUint::one();
+    let mut signatures = vec![
+        Signature { r: BigUint::zero(), s: BigUint::zero() },
+        Signature { r: BigUint::zero(), s: one.clone() },
+        Signature { r: one.clone(), s: BigUint::zero() },
+        Signature { r: n.clone(), s: one.clone() },
+        Signature { r: one.clone(), s: n.clone() },
+        Signature { r: n + 1u32, s: n + 1u32 },
+    ];
+    for (r, s) in oversized(n, rng, rounds).into_iter().zip(oversized(n, rng, rounds)) {
+        signatures.push(Signature { r: r.clone(), s: one.clone() });
+        signatures.push(Signature { r: one.clone(), s });
+    }
+    signatures
+}
+
+/// Describe the inputs that `reject` accepts or panics on
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::fuzz;
+///
+/// assert!(fuzz::failures(&[1, 2, 3], |&n| n > 0).is_empty());
+/// assert_eq!(vec!["accepted 0".to_string()], fuzz::failures(&[0, 1], |&n| n > 0));
+/// ```
+pub fn failures<T: Debug, F: Fn(&T) -> bool>(inputs: &[T], reject: F) -> Vec<String> {
+    inputs.iter().filter_map(|input| {
+        match panic::catch_unwind(AssertUnwindSafe(|| reject(input))) {
+            Ok(true) => None,
+            Ok(false) => Some(format!("accepted {:?}", input)),
+            Err(_) => Some(format!("panicked on {:?}", input)),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ec, ecdsa};
+
+    const ROUNDS: usize = 4;
+
+    #[test]
+    fn dh_rejects() {
+        let params = DhParams::challenge57();
+        let keys = dh_public_keys(&params, &mut rand::thread_rng(), ROUNDS);
+        let bob = dh::Bob::new(&params);
+        assert_eq!(Vec::<String>::new(), failures(&keys, |key| bob.respond_validated(key).is_err()));
+        assert!(bob.respond_validated(&params.public_key(&params.generate_private())).is_ok());
+    }
+
+    #[test]
+    fn ec_rejects() {
+        let params = EcParams::challenge59();
+        let points = ec_points(&params, &mut rand::thread_rng(), ROUNDS);
+        let bob = ec::Bob::new(&params);
+        assert_eq!(Vec::<String>::new(), failures(&points, |point| bob.respond_validated(point).is_err()));
+        assert!(bob.respond_validated(&params.public_key(&params.generate_private())).is_ok());
+    }
+
+    #[test]
+    fn montgomery_rejects() {
+        let params = MontgomeryParams::challenge60();
+        let us = montgomery_us(&params, &mut rand::thread_rng(), ROUNDS);
+        let bob = ec::LadderBob::new(&params);
+        assert_eq!(Vec::<String>::new(), failures(&us, |u| bob.respond_validated(u).is_err()));
+        assert!(bob.respond_validated(&params.public_key(&params.generate_private())).is_ok());
+    }
+
+    #[test]
+    fn ecdsa_rejects() {
+        let params = EcParams::challenge59();
+        let mut rng = rand::thread_rng();
+        let private = params.generate_private();
+        let public = params.public_key(&private);
+        let signatures = ecdsa_signatures(&params, &mut rng, ROUNDS);
+        assert_eq!(Vec::<String>::new(),
+                   failures(&signatures, |signature| !ecdsa::verify_validated(&params, &public, b"hi mom", signature)));
+
+        // A valid signature under a malformed public key
+        let signature = ecdsa::sign(&params, &private, b"hi mom");
+        let points = ec_points(&params, &mut rng, ROUNDS);
+        assert_eq!(Vec::<String>::new(),
+                   failures(&points, |point| !ecdsa::verify_validated(&params, point, b"hi mom", &signature)));
+        assert!(ecdsa::verify_validated(&params, &public, b"hi mom", &signature));
+    }
+
+    #[test]
+    fn unvalidated_accepts() {
+        // The plain handler answers, which is what the attacks exploit
+        let params = DhParams::challenge57();
+        let keys = dh_public_keys(&params, &mut rand::thread_rng(), 1);
+        let bob = dh::Bob::new(&params);
+        assert_eq!(keys.len(), failures(&keys, |key| bob.respond(key).1.is_empty()).len());
+
+        // Confined keys leave a shared secret of 0, 1 or p - 1 behind the MAC
+        let p = &params.p;
+        let confined: Vec<_> = [BigUint::zero(), BigUint::one(), p - 1u32].iter()
+            .map(|secret| dh::mac(secret, dh::BOB_MESSAGE))
+            .collect();
+        for key in &keys[..5] {
+            let (message, tag) = bob.respond(key);
+            assert_eq!(dh::BOB_MESSAGE, &message[..]);
+            assert!(confined.contains(&tag), "tag for {} leaks no confined secret", key);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! AES-GCM, and the forbidden attack on reused nonces
+//!
+//! A GCM tag evaluates a polynomial of the authentication key h, whose
+//! coefficients are the data blocks. Two messages sealed under the same nonce
+//! share the tag mask, so h is a root of the sum of their polynomials.
+//!
+//! With tags truncated to 32 bits, changing only the blocks multiplied by the
+//! h^(2^i) makes the tag error linear in h over GF(2), which lets a forger
+//! learn linear equations on h from each accepted forgery.
+
+use std::error;
+use aes::Aes128;
+use block_modes::{BlockMode, Ecb};
+use block_modes::block_padding::NoPadding;
+use rand::{Rng, RngCore};
+use crate::cipher::BLOCK_SIZE;
+use crate::keygen;
+use crate::gf128::{Gf128, Poly};
+use crate::gf2::{BitVec, Matrix};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Nonce size, the counter blocks append a 32-bit counter to it
+pub const NONCE_SIZE: usize = 12;
+
+/// GCM authentication tag
+pub type Tag = [u8; BLOCK_SIZE];
+
+/// Size of the truncated tags, in bytes
+pub const TRUNCATED_TAG_SIZE: usize = 4;
+
+const TRUNCATED_TAG_BITS: usize = TRUNCATED_TAG_SIZE * 8;
+
+/// AES-128 in Galois/Counter Mode
+pub struct Aes128Gcm {
+    key: Vec<u8>,
+    h: Gf128,
+}
+
+impl Aes128Gcm {
+    /// Create a GCM cipher, the key must be 16 bytes long
+    pub fn new(key: &[u8]) -> Result<Aes128Gcm> {
+        if key.len() != BLOCK_SIZE {
+            return Err(format!("invalid AES-128 key length: {}", key.len()).into());
+        }
+        let h = Gf128::from_block(&encrypt_blocks(key, vec![0; BLOCK_SIZE]));
+        Ok(Aes128Gcm { key: key.to_vec(), h })
+    }
+
+    /// Authentication key h, the encryption of the zero block
+    pub fn auth_key(&self) -> Gf128 {
+        self.h
+    }
+
+    /// Generate `len` bytes of keystream, starting at block `counter`
+    fn keystream(&self, nonce: &[u8; NONCE_SIZE], counter: u32, len: usize) -> Vec<u8> {
+        let blocks = len.div_ceil(BLOCK_SIZE) as u32;
+        let mut stream = Vec::with_capacity(blocks as usize * BLOCK_SIZE);
+        for i in 0..blocks {
+            stream.extend_from_slice(nonce);
+            stream.extend_from_slice(&counter.wrapping_add(i).to_be_bytes());
+        }
+        let mut stream = encrypt_blocks(&self.key, stream);
+        stream.truncate(len);
+        stream
+    }
+
+    /// Tag of a ciphertext and its associated data
+    fn tag(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> Tag {
+        let mask = Gf128::from_block(&self.keystream(nonce, 1, BLOCK_SIZE));
+        (ghash(self.h, aad, ciphertext) + mask).to_block()
+    }
+
+    /// Encrypt and authenticate, return the ciphertext and its tag
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gcm::Aes128Gcm;
+    ///
+    /// let gcm = Aes128Gcm::new(b"YELLOW SUBMARINE").unwrap();
+    /// let (ciphertext, tag) = gcm.encrypt(b"twelve bytes", b"Attack at dawn", b"header");
+    /// assert_eq!(b"Attack at dawn".to_vec(), gcm.decrypt(b"twelve bytes", &ciphertext, b"header", &tag).unwrap());
+    /// assert!(gcm.decrypt(b"twelve bytes", &ciphertext, b"footer", &tag).is_err());
+    /// ```
+    pub fn encrypt(&self, nonce: &[u8; NONCE_SIZE], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, Tag) {
+        let ciphertext: Vec<u8> = plaintext.iter().zip(self.keystream(nonce, 2, plaintext.len()))
+            .map(|(a, b)| a ^ b)
+            .collect();
+        let tag = self.tag(nonce, aad, &ciphertext);
+        (ciphertext, tag)
+    }
+
+    /// Check the tag and decrypt
+    pub fn decrypt(&self, nonce: &[u8; NONCE_SIZE], ciphertext: &[u8], aad: &[u8], tag: &Tag) -> Result<Vec<u8>> {
+        if self.tag(nonce, aad, ciphertext) != *tag {
+            return Err("invalid GCM tag".into());
+        }
+        Ok(ciphertext.iter().zip(self.keystream(nonce, 2, ciphertext.len()))
+            .map(|(a, b)| a ^ b)
+            .collect())
+    }
+}
+
+/// Encrypt whole blocks with AES-128 in ECB mode
+fn encrypt_blocks(key: &[u8], mut blocks: Vec<u8>) -> Vec<u8> {
+    // The key length is checked by the callers
+    let ecb = Ecb::<Aes128, NoPadding>::new_var(key, &[]).unwrap();
+    let len = blocks.len();
+    ecb.encrypt(&mut blocks, len).unwrap();
+    blocks
+}
+
+/// Blocks hashed by GHASH: the zero padded data, the zero padded ciphertext,
+/// then their lengths in bits
+pub fn ghash_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    let mut blocks: Vec<Gf128> = aad.chunks(BLOCK_SIZE)
+        .chain(ciphertext.chunks(BLOCK_SIZE))
+        .map(Gf128::from_block)
+        .collect();
+    let mut lengths = [0u8; BLOCK_SIZE];
+    lengths[..8].copy_from_slice(&(aad.len() as u64 * 8).to_be_bytes());
+    lengths[8..].copy_from_slice(&(ciphertext.len() as u64 * 8).to_be_bytes());
+    blocks.push(Gf128::from_block(&lengths));
+    blocks
+}
+
+/// GHASH of a ciphertext and its associated data
+pub fn ghash(h: Gf128, aad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(aad, ciphertext).into_iter()
+        .fold(Gf128::ZERO, |acc, block| (acc + block) * h)
+}
+
+/// Message sealed by GCM, as seen on the wire
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sealed {
+    pub aad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Tag,
+}
+
+/// Polynomial in h equal to the tag mask
+///
+/// With the GHASH blocks b1..bn, the tag is b1·h^n + … + bn·h + mask.
+fn tag_polynomial(message: &Sealed) -> Poly {
+    let mut coeffs = vec![Gf128::from_block(&message.tag)];
+    coeffs.extend(ghash_blocks(&message.aad, &message.ciphertext).into_iter().rev());
+    Poly::new(coeffs)
+}
+
+/// Recover the authentication key from messages sealed with the same nonce
+///
+/// The candidates are the roots of the polynomial of the first two messages,
+/// each further message removes those that don't cancel its polynomial too.
+pub fn forbidden_attack(messages: &[Sealed]) -> Vec<Gf128> {
+    if messages.len() < 2 {
+        return Vec::new();
+    }
+    let first = tag_polynomial(&messages[0]);
+    let mut candidates = first.add(&tag_polynomial(&messages[1])).roots();
+    for message in &messages[2..] {
+        let difference = first.add(&tag_polynomial(message));
+        candidates.retain(|&h| difference.eval(h) == Gf128::ZERO);
+    }
+    candidates
+}
+
+/// Tag of a forged ciphertext, under the nonce of a known message
+pub fn forge(h: Gf128, known: &Sealed, aad: &[u8], ciphertext: &[u8]) -> Tag {
+    let mask = Gf128::from_block(&known.tag) + ghash(h, &known.aad, &known.ciphertext);
+    (ghash(h, aad, ciphertext) + mask).to_block()
+}
+
+/// Receiver of a message authenticated by a truncated tag
+///
+/// Forgeries are submitted as differences XORed to ciphertext blocks. This is
+/// the same as sending the whole modified ciphertext, but the oracle can update
+/// the tag with the differences instead of hashing megabytes per forgery.
+pub struct TruncatedMacOracle {
+    gcm: Aes128Gcm,
+    ciphertext: Vec<u8>,
+    tag: Tag,
+    // h^(2^i), the factors of the blocks changed by the attack
+    squares: Vec<Gf128>,
+}
+
+impl TruncatedMacOracle {
+    /// Seal a random message of `blocks` blocks under a random key and nonce
+    pub fn new(blocks: usize) -> Result<TruncatedMacOracle> {
+        TruncatedMacOracle::with_rng(blocks, &mut rand::thread_rng())
+    }
+
+    /// Same as `new`, drawing the key, nonce and message from `rng`
+    pub fn with_rng<R: RngCore + ?Sized>(blocks: usize, rng: &mut R) -> Result<TruncatedMacOracle> {
+        let key = keygen::aes_key(rng);
+        let nonce = keygen::gcm_nonce(rng);
+        let plaintext = keygen::bytes(rng, blocks * BLOCK_SIZE);
+
+        let gcm = Aes128Gcm::new(&key)?;
+        let (ciphertext, tag) = gcm.encrypt(&nonce, &plaintext, b"");
+        let squares = std::iter::successors(Some(gcm.auth_key()), |&x| Some(x * x))
+            .take((blocks + 1).ilog2() as usize + 1)
+            .collect();
+        Ok(TruncatedMacOracle { gcm, ciphertext, tag, squares })
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Truncated tag of the message
+    pub fn tag(&self) -> &[u8] {
+        &self.tag[..TRUNCATED_TAG_SIZE]
+    }
+
+    /// Authentication key, to check the attack
+    pub fn auth_key(&self) -> Gf128 {
+        self.gcm.auth_key()
+    }
+
+    /// Check the truncated tag of the ciphertext modified by (block index, difference) pairs
+    pub fn accepts(&self, differences: &[(usize, Gf128)]) -> bool {
+        let blocks = self.ciphertext.len() / BLOCK_SIZE;
+        let error = differences.iter().fold(Gf128::ZERO, |acc, &(index, difference)| {
+            // The last ciphertext block is multiplied by h^2, before the lengths
+            let exponent = blocks + 1 - index;
+            let power = if exponent.is_power_of_two() {
+                self.squares[exponent.trailing_zeros() as usize]
+            } else {
+                self.gcm.auth_key().pow(exponent as u128)
+            };
+            acc + difference * power
+        });
+        (Gf128::from_block(&self.tag) + error).to_block()[..TRUNCATED_TAG_SIZE] == *self.tag()
+    }
+}
+
+/// Matrix A_d of the tag error A_d·h for the differences d_i of the blocks
+/// multiplied by h^(2^(i+1))
+fn error_matrix(differences: &BitVec, squarings: &[Matrix]) -> Matrix {
+    squarings.iter().enumerate()
+        .map(|(i, squaring)| Matrix::multiplication(differences.gf128_at(i * 128)).mul(squaring))
+        .fold(Matrix::zero(128, 128), |sum, term| sum.add(&term))
+}
+
+/// Recover the authentication key from truncated tag forgeries
+///
+/// The candidates for h are X·h', with the columns of X a basis of the kernel
+/// of the equations learned so far. Each round solves for the differences d
+/// that zero the first rows of A_d·X: the forgery then passes with a good
+/// probability, and the next rows of A_d give new equations on h.
+pub fn truncated_mac_attack(oracle: &TruncatedMacOracle) -> Option<Gf128> {
+    let blocks = oracle.ciphertext().len() / BLOCK_SIZE;
+    // Blocks multiplied by h^2, h^4, … that can be changed
+    let powers = (blocks + 1).ilog2() as usize;
+    let bits = powers * 128;
+    let squarings: Vec<Matrix> = std::iter::successors(Some(Matrix::squaring()), |s| Some(s.mul(&Matrix::squaring())))
+        .take(powers)
+        .collect();
+    let shifts: Vec<Matrix> = (0..128).map(|j| Matrix::multiplication(Gf128(1 << j))).collect();
+
+    let mut rng = rand::thread_rng();
+    let mut equations = Matrix::zero(0, 128);
+    let mut candidates = Matrix::identity(128);
+    while candidates.ncols() > 1 {
+        let dims = candidates.ncols();
+        // Keep a row free, an error that is always zero teaches nothing
+        let zero_rows = ((bits - 1) / dims).min(TRUNCATED_TAG_BITS - 1);
+        let mut dependencies = Matrix::zero(zero_rows * dims, bits);
+        for (i, squaring) in squarings.iter().enumerate() {
+            let squared = squaring.mul(&candidates);
+            for (j, shift) in shifts.iter().enumerate() {
+                let product = shift.mul(&squared);
+                for r in 0..zero_rows {
+                    for c in 0..dims {
+                        if product.get(r, c) {
+                            dependencies.set(r * dims + c, i * 128 + j, true);
+                        }
+                    }
+                }
+            }
+        }
+        let solutions = dependencies.kernel();
+        debug!("{} candidate dimensions, zeroing {} rows, {} solutions", dims, zero_rows, solutions.len());
+
+        let (differences, attempts) = (1..).find_map(|attempt| {
+            let mut differences = BitVec::zero(bits);
+            for solution in &solutions {
+                if rng.gen() {
+                    differences.xor(solution);
+                }
+            }
+            let changes: Vec<(usize, Gf128)> = (0..powers)
+                .map(|i| (blocks + 1 - (2 << i), differences.gf128_at(i * 128)))
+                .collect();
+            if !differences.is_zero() && oracle.accepts(&changes) {
+                Some((differences, attempt))
+            } else {
+                None
+            }
+        })?;
+
+        let error = error_matrix(&differences, &squarings);
+        for r in zero_rows..TRUNCATED_TAG_BITS {
+            equations.push_row(error.row(r).clone());
+        }
+        let kernel = equations.kernel();
+        if kernel.is_empty() {
+            return None;
+        }
+        candidates = Matrix::from_columns(128, &kernel);
+        debug!("forgery accepted after {} attempts, {} equations", attempts, equations.rank());
+    }
+    Some(candidates.column(0).gf128_at(0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{BytesCrypto, HexString};
+
+    #[test]
+    fn nist_zero_key() {
+        let gcm = Aes128Gcm::new(&[0; 16]).unwrap();
+        let (ciphertext, tag) = gcm.encrypt(&[0; 12], b"", b"");
+        assert!(ciphertext.is_empty());
+        assert_eq!("58e2fccefa7e3061367f1d57a4e7455a", tag.bytes2hex());
+        let (ciphertext, tag) = gcm.encrypt(&[0; 12], &[0; 16], b"");
+        assert_eq!("0388dace60b6a392f328c2b971b2fe78", ciphertext.bytes2hex());
+        assert_eq!("ab6e47d42cec13bdf53a67b21257bddf", tag.bytes2hex());
+    }
+
+    #[test]
+    fn nist_with_aad() {
+        let gcm = Aes128Gcm::new(&"feffe9928665731c6d6a8f9467308308".hex2bytes().unwrap()).unwrap();
+        let mut nonce = [0; NONCE_SIZE];
+        nonce.copy_from_slice(&"cafebabefacedbaddecaf888".hex2bytes().unwrap());
+        let plaintext = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39".hex2bytes().unwrap();
+        let aad = "feedfacedeadbeeffeedfacedeadbeefabaddad2".hex2bytes().unwrap();
+        let (ciphertext, tag) = gcm.encrypt(&nonce, &plaintext, &aad);
+        assert_eq!("42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                    21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091", ciphertext.bytes2hex());
+        assert_eq!("5bc94fbc3221a5db94fae95ae7121a47", tag.bytes2hex());
+    }
+
+    #[test]
+    fn nonce_reuse() {
+        let gcm = Aes128Gcm::new(b"YELLOW SUBMARINE").unwrap();
+        let seal = |plaintext: &[u8], aad: &[u8]| {
+            let (ciphertext, tag) = gcm.encrypt(b"reused nonce", plaintext, aad);
+            Sealed { aad: aad.to_vec(), ciphertext, tag }
+        };
+        let messages = vec![
+            seal(b"Transfer 100 dollars to Alice", b"id=1"),
+            seal(b"Transfer 250 dollars to Bob, today", b"id=2"),
+            seal(b"Nothing", b"id=3"),
+        ];
+        assert_eq!(vec![gcm.auth_key()], forbidden_attack(&messages));
+
+        let forged: Vec<u8> = b"Transfer 999".iter().zip(&messages[0].ciphertext)
+            .zip(b"Transfer 100")
+            .map(|((a, b), c)| a ^ b ^ c)
+            .collect();
+        let tag = forge(gcm.auth_key(), &messages[0], b"id=4", &forged);
+        assert_eq!(b"Transfer 999".to_vec(), gcm.decrypt(b"reused nonce", &forged, b"id=4", &tag).unwrap());
+    }
+
+    #[test]
+    fn truncated_oracle() {
+        let oracle = TruncatedMacOracle::new(15).unwrap();
+        assert_eq!(15 * BLOCK_SIZE, oracle.ciphertext().len());
+        assert_eq!(TRUNCATED_TAG_SIZE, oracle.tag().len());
+        assert!(oracle.accepts(&[]));
+        assert!(oracle.accepts(&[(3, Gf128::ONE), (3, Gf128::ONE)]));
+        assert!(!oracle.accepts(&[(3, Gf128::ONE)]));
+    }
+
+    #[test]
+    fn linear_error() {
+        let mut rng = rand::thread_rng();
+        let oracle = TruncatedMacOracle::new(7).unwrap();
+        let h = oracle.auth_key();
+        let squarings = vec![Matrix::squaring(), Matrix::squaring().mul(&Matrix::squaring())];
+        let mut differences = BitVec::zero(256);
+        for i in 0..256 {
+            differences.set(i, rng.gen());
+        }
+        // Blocks 6 and 4 are multiplied by h^2 and h^4
+        let error = differences.gf128_at(0) * h.pow(2) + differences.gf128_at(128) * h.pow(4);
+        assert_eq!(BitVec::from_gf128(error), error_matrix(&differences, &squarings).mul_vec(&BitVec::from_gf128(h)));
+    }
+}
@@ -0,0 +1,544 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Arithmetic in GF(2^128) with the GCM polynomial, and polynomials over it
+//!
+//! Products are carry-less multiplications reduced modulo x^128 + x^7 + x^2 +
+//! x + 1. Polynomials over the field support GCD, root finding and complete
+//! factorization, which the GCM nonce reuse attack relies on.
+//!
+//! Elements are stored with the coefficient of x^i in bit i. GCM blocks use
+//! the reversed bit order: the most significant bit of their first byte is
+//! the coefficient of x^0.
+
+use std::ops::{Add, Mul};
+use rand::Rng;
+
+/// x^128 = x^7 + x^2 + x + 1
+const REDUCTION: u128 = 0x87;
+
+/// Number of squarings of the Frobenius map, x -> x^(2^128) is the identity
+const FIELD_BITS: usize = 128;
+
+/// Element of GF(2^128)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Gf128(pub u128);
+
+impl Gf128 {
+    pub const ZERO: Gf128 = Gf128(0);
+    pub const ONE: Gf128 = Gf128(1);
+
+    /// Read a GCM block, shorter blocks are padded with zeroes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gf128::Gf128;
+    ///
+    /// assert_eq!(Gf128::ONE, Gf128::from_block(&[0x80]));
+    /// ```
+    pub fn from_block(block: &[u8]) -> Gf128 {
+        assert!(block.len() <= 16, "GCM blocks are at most 16 bytes");
+        let mut bytes = [0u8; 16];
+        bytes[..block.len()].copy_from_slice(block);
+        Gf128(u128::from_be_bytes(bytes).reverse_bits())
+    }
+
+    /// Write the element as a GCM block
+    pub fn to_block(self) -> [u8; 16] {
+        self.0.reverse_bits().to_be_bytes()
+    }
+
+    /// Random element
+    pub fn random<R: Rng>(rng: &mut R) -> Gf128 {
+        Gf128(rng.gen())
+    }
+
+    /// Raise to the power `exp`
+    pub fn pow(self, mut exp: u128) -> Gf128 {
+        let mut result = Gf128::ONE;
+        let mut base = self;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Square root, a^(2^127): squaring is a bijection in characteristic 2
+    pub fn sqrt(self) -> Gf128 {
+        self.pow(1 << 127)
+    }
+
+    /// Multiplicative inverse, a^(2^128 - 2)
+    pub fn inverse(self) -> Option<Gf128> {
+        if self == Gf128::ZERO {
+            None
+        } else {
+            Some(self.pow(u128::MAX - 1))
+        }
+    }
+}
+
+impl Add for Gf128 {
+    type Output = Gf128;
+
+    /// Addition and subtraction are both XOR
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, other: Gf128) -> Gf128 {
+        Gf128(self.0 ^ other.0)
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+
+    fn mul(self, other: Gf128) -> Gf128 {
+        let (high, low) = clmul(self.0, other.0);
+        reduce(high, low)
+    }
+}
+
+/// Carry-less product of two polynomials of GF(2)[x] of degree below 128, as (high, low) halves
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::gf128;
+///
+/// // (x + 1)^2 = x^2 + 1 and x^127 · x = x^128
+/// assert_eq!((0, 0b101), gf128::clmul(0b11, 0b11));
+/// assert_eq!((1, 0), gf128::clmul(1 << 127, 2));
+/// ```
+pub fn clmul(a: u128, b: u128) -> (u128, u128) {
+    let (mut high, mut low) = (0u128, 0u128);
+    for i in 0..128 {
+        if (b >> i) & 1 == 1 {
+            low ^= a << i;
+            if i > 0 {
+                high ^= a >> (128 - i);
+            }
+        }
+    }
+    (high, low)
+}
+
+/// Reduce a polynomial of degree below 256 modulo the GCM polynomial x^128 + x^7 + x^2 + x + 1
+///
+/// high · x^128 = high · (x^7 + x^2 + x + 1) overflows by at most 7 bits,
+/// whose own reduction fits in the low half.
+pub fn reduce(high: u128, low: u128) -> Gf128 {
+    let (overflow, folded) = clmul(high, REDUCTION);
+    let (_, refolded) = clmul(overflow, REDUCTION);
+    Gf128(low ^ folded ^ refolded)
+}
+
+/// Polynomial over GF(2^128)
+///
+/// The coefficients start with the constant term, and the leading one is never
+/// zero: the zero polynomial has no coefficients.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Poly(Vec<Gf128>);
+
+impl Poly {
+    /// Build a polynomial from its coefficients, constant term first
+    pub fn new(mut coeffs: Vec<Gf128>) -> Poly {
+        while coeffs.last() == Some(&Gf128::ZERO) {
+            coeffs.pop();
+        }
+        Poly(coeffs)
+    }
+
+    pub fn zero() -> Poly {
+        Poly(Vec::new())
+    }
+
+    pub fn one() -> Poly {
+        Poly(vec![Gf128::ONE])
+    }
+
+    /// The polynomial x
+    pub fn x() -> Poly {
+        Poly(vec![Gf128::ZERO, Gf128::ONE])
+    }
+
+    /// Coefficients, constant term first
+    pub fn coeffs(&self) -> &[Gf128] {
+        &self.0
+    }
+
+    /// Degree, none for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Evaluate at `x`
+    pub fn eval(&self, x: Gf128) -> Gf128 {
+        self.0.iter().rev().fold(Gf128::ZERO, |acc, &c| acc * x + c)
+    }
+
+    pub fn add(&self, other: &Poly) -> Poly {
+        let len = self.0.len().max(other.0.len());
+        let coeff = |p: &Poly, i: usize| p.0.get(i).copied().unwrap_or_default();
+        Poly::new((0..len).map(|i| coeff(self, i) + coeff(other, i)).collect())
+    }
+
+    pub fn mul(&self, other: &Poly) -> Poly {
+        if self.is_zero() || other.is_zero() {
+            return Poly::zero();
+        }
+        let mut coeffs = vec![Gf128::ZERO; self.0.len() + other.0.len() - 1];
+        for (i, &a) in self.0.iter().enumerate() {
+            for (j, &b) in other.0.iter().enumerate() {
+                coeffs[i + j] = coeffs[i + j] + a * b;
+            }
+        }
+        Poly::new(coeffs)
+    }
+
+    /// Quotient and remainder of the division by a non zero polynomial
+    pub fn div_rem(&self, divisor: &Poly) -> (Poly, Poly) {
+        let divisor_degree = divisor.degree().expect("division by the zero polynomial");
+        let lead_inverse = divisor.0[divisor_degree].inverse().unwrap();
+        let mut remainder = self.0.clone();
+        let mut quotient = vec![Gf128::ZERO; remainder.len().saturating_sub(divisor_degree)];
+        while remainder.len() > divisor_degree {
+            let shift = remainder.len() - 1 - divisor_degree;
+            let factor = *remainder.last().unwrap() * lead_inverse;
+            quotient[shift] = factor;
+            for (i, &c) in divisor.0.iter().enumerate() {
+                remainder[shift + i] = remainder[shift + i] + factor * c;
+            }
+            remainder.pop();
+        }
+        (Poly::new(quotient), Poly::new(remainder))
+    }
+
+    /// Same polynomial divided by its leading coefficient
+    pub fn monic(&self) -> Poly {
+        match self.0.last() {
+            None => Poly::zero(),
+            Some(lead) => {
+                let inverse = lead.inverse().unwrap();
+                Poly(self.0.iter().map(|&c| c * inverse).collect())
+            }
+        }
+    }
+
+    /// Monic greatest common divisor
+    pub fn gcd(&self, other: &Poly) -> Poly {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let remainder = a.div_rem(&b).1;
+            a = b;
+            b = remainder;
+        }
+        a.monic()
+    }
+
+    fn mul_mod(&self, other: &Poly, modulus: &Poly) -> Poly {
+        self.mul(other).div_rem(modulus).1
+    }
+
+    fn is_one(&self) -> bool {
+        self.0 == [Gf128::ONE]
+    }
+
+    /// Formal derivative, only the odd powers survive in characteristic 2
+    pub fn derivative(&self) -> Poly {
+        Poly::new(self.0.iter().enumerate().skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { Gf128::ZERO })
+            .collect())
+    }
+
+    /// Square root of a polynomial whose derivative is zero, a polynomial in x^2
+    fn sqrt(&self) -> Poly {
+        Poly::new(self.0.iter().step_by(2).map(|c| c.sqrt()).collect())
+    }
+
+    /// self^(2^(128·n)) mod `modulus`, n applications of the Frobenius map
+    fn frobenius(&self, n: usize, modulus: &Poly) -> Poly {
+        let mut power = self.div_rem(modulus).1;
+        for _ in 0..n * FIELD_BITS {
+            power = power.mul_mod(&power, modulus);
+        }
+        power
+    }
+
+    /// Factorization in monic irreducible factors with their multiplicities
+    ///
+    /// Square-free factorization, then distinct-degree factorization, then
+    /// Cantor–Zassenhaus splits the factors of equal degree. The factors are
+    /// sorted by degree, the constant factor is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gf128::{Gf128, Poly};
+    ///
+    /// // (x + 3)^2 (x + 5) = (x^2 + 5)(x + 5) = x^3 + 5x^2 + 5x + 17
+    /// let f = Poly::new(vec![Gf128(17), Gf128(5), Gf128(5), Gf128::ONE]);
+    /// let x_plus = |c| Poly::new(vec![Gf128(c), Gf128::ONE]);
+    /// assert_eq!(vec![(x_plus(3), 2), (x_plus(5), 1)], f.factor());
+    /// ```
+    pub fn factor(&self) -> Vec<(Poly, usize)> {
+        let mut rng = rand::thread_rng();
+        let mut factors = Vec::new();
+        for (square_free, multiplicity) in self.monic().square_free_factors() {
+            for (product, degree) in square_free.distinct_degree_factors() {
+                let mut irreducible = Vec::new();
+                product.split_equal_degree(degree, &mut rng, &mut irreducible);
+                factors.extend(irreducible.into_iter().map(|factor| (factor, multiplicity)));
+            }
+        }
+        factors.sort_by(|(a, _), (b, _)| a.degree().cmp(&b.degree()).then_with(|| a.cmp(b)));
+        factors
+    }
+
+    /// Square-free factors of a monic polynomial, with their multiplicities
+    fn square_free_factors(&self) -> Vec<(Poly, usize)> {
+        let mut factors = Vec::new();
+        if self.degree().unwrap_or(0) == 0 {
+            return factors;
+        }
+        // c holds the repeated factors, w the product of the distinct ones
+        let mut c = self.gcd(&self.derivative());
+        let mut w = self.div_rem(&c).0;
+        let mut multiplicity = 1;
+        while !w.is_one() {
+            let y = w.gcd(&c);
+            let factor = w.div_rem(&y).0;
+            if !factor.is_one() {
+                factors.push((factor, multiplicity));
+            }
+            c = c.div_rem(&y).0;
+            w = y;
+            multiplicity += 1;
+        }
+        // What is left is a square, in characteristic 2
+        if !c.is_one() {
+            for (factor, m) in c.sqrt().square_free_factors() {
+                factors.push((factor, 2 * m));
+            }
+        }
+        factors
+    }
+
+    /// Products of the irreducible factors of each degree, of a square-free monic polynomial
+    fn distinct_degree_factors(&self) -> Vec<(Poly, usize)> {
+        let mut factors = Vec::new();
+        let mut rest = self.clone();
+        let mut frobenius = Poly::x();
+        let mut degree = 1;
+        while rest.degree().unwrap_or(0) >= 2 * degree {
+            // x^(q^d) - x is the product of the irreducibles whose degree divides d
+            frobenius = frobenius.frobenius(1, &rest);
+            let product = rest.gcd(&frobenius.add(&Poly::x()));
+            if !product.is_one() {
+                rest = rest.div_rem(&product).0;
+                frobenius = frobenius.div_rem(&rest).1;
+                factors.push((product, degree));
+            }
+            degree += 1;
+        }
+        if let Some(1..) = rest.degree() {
+            let degree = rest.degree().unwrap();
+            factors.push((rest, degree));
+        }
+        factors
+    }
+
+    /// Split a product of distinct monic irreducible factors of the same degree
+    ///
+    /// The trace of a random a, the sum of its 128·degree successive squares,
+    /// is in GF(2) modulo each factor: its gcd with f splits f in two for about
+    /// half the values of a.
+    fn split_equal_degree<R: Rng>(&self, degree: usize, rng: &mut R, factors: &mut Vec<Poly>) {
+        let total = match self.degree() {
+            None | Some(0) => return,
+            Some(total) if total == degree => {
+                factors.push(self.clone());
+                return;
+            }
+            Some(total) => total,
+        };
+        loop {
+            let a = Poly::new((0..total).map(|_| Gf128::random(rng)).collect());
+            let mut term = a.div_rem(self).1;
+            let mut trace = term.clone();
+            for _ in 1..degree * FIELD_BITS {
+                term = term.mul_mod(&term, self);
+                trace = trace.add(&term);
+            }
+            let factor = self.gcd(&trace);
+            if let Some(1..) = factor.degree().filter(|&d| d < total) {
+                factor.split_equal_degree(degree, rng, factors);
+                self.div_rem(&factor).0.split_equal_degree(degree, rng, factors);
+                return;
+            }
+        }
+    }
+
+    /// Distinct roots in GF(2^128), sorted
+    ///
+    /// The roots are those of gcd(f, x^(2^128) - x), the product of the linear
+    /// factors of f, which Cantor–Zassenhaus then splits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gf128::{Gf128, Poly};
+    ///
+    /// // (x + 3)(x + 5) = x^2 + 6x + 15
+    /// let f = Poly::new(vec![Gf128(15), Gf128(6), Gf128::ONE]);
+    /// assert_eq!(vec![Gf128(3), Gf128(5)], f.roots());
+    /// ```
+    pub fn roots(&self) -> Vec<Gf128> {
+        let f = self.monic();
+        if f.degree().unwrap_or(0) == 0 {
+            return Vec::new();
+        }
+        let linear = f.gcd(&Poly::x().frobenius(1, &f).add(&Poly::x()));
+
+        let mut factors = Vec::new();
+        linear.split_equal_degree(1, &mut rand::thread_rng(), &mut factors);
+        // x + c
+        let mut roots: Vec<Gf128> = factors.iter().map(|factor| factor.0[0]).collect();
+        roots.sort();
+        roots
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn block_roundtrip() {
+        let block = *b"YELLOW SUBMARINE";
+        assert_eq!(block, Gf128::from_block(&block).to_block());
+        assert_eq!(Gf128(2), Gf128::from_block(&[0x40]));
+    }
+
+    #[test]
+    fn field_arithmetic() {
+        let mut rng = rand::thread_rng();
+        let a = Gf128::random(&mut rng);
+        let b = Gf128::random(&mut rng);
+        assert_eq!(a * b, b * a);
+        assert_eq!(a, a * Gf128::ONE);
+        assert_eq!(Gf128::ZERO, a + a);
+        // x^127 * x = x^7 + x^2 + x + 1
+        assert_eq!(Gf128(REDUCTION), Gf128(1 << 127) * Gf128(2));
+        assert_eq!(Gf128::ONE, a * a.inverse().unwrap());
+        assert_eq!(None, Gf128::ZERO.inverse());
+    }
+
+    /// Horner's method over the bits of b, reducing at each step
+    fn horner_mul(a: Gf128, b: Gf128) -> Gf128 {
+        let mut result = 0u128;
+        for i in (0..128).rev() {
+            let carry = result >> 127;
+            result <<= 1;
+            if carry == 1 {
+                result ^= REDUCTION;
+            }
+            if (b.0 >> i) & 1 == 1 {
+                result ^= a.0;
+            }
+        }
+        Gf128(result)
+    }
+
+    #[test]
+    fn carry_less_reduction() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let (a, b) = (Gf128::random(&mut rng), Gf128::random(&mut rng));
+            assert_eq!(horner_mul(a, b), a * b);
+        }
+        assert_eq!(Gf128(REDUCTION), reduce(1, 0));
+        // x^255 = x^127 · (x^7 + x^2 + x + 1)
+        assert_eq!(Gf128(1 << 127) * Gf128(REDUCTION), reduce(1 << 127, 0));
+        let a = Gf128::random(&mut rng);
+        assert_eq!(a, (a * a).sqrt());
+    }
+
+    #[test]
+    fn division() {
+        let mut rng = rand::thread_rng();
+        let a = Poly::new((0..5).map(|_| Gf128::random(&mut rng)).collect());
+        let b = Poly::new((0..3).map(|_| Gf128::random(&mut rng)).collect());
+        let (quotient, remainder) = a.div_rem(&b);
+        assert!(remainder.degree() < b.degree());
+        assert_eq!(a, quotient.mul(&b).add(&remainder));
+        assert_eq!(Poly::one(), a.gcd(&Poly::one()));
+    }
+
+    #[test]
+    fn random_roots() {
+        let mut rng = rand::thread_rng();
+        let mut expected: Vec<Gf128> = (0..4).map(|_| Gf128::random(&mut rng)).collect();
+        // x^2 + x + c has no root when the trace of c is 1
+        let irreducible = loop {
+            let c = Gf128::random(&mut rng);
+            let trace = (0..FIELD_BITS).scan(c, |square, _| {
+                let term = *square;
+                *square = term * term;
+                Some(term)
+            }).fold(Gf128::ZERO, |acc, term| acc + term);
+            if trace == Gf128::ONE {
+                break Poly::new(vec![c, Gf128::ONE, Gf128::ONE]);
+            }
+        };
+        // A repeated root and an irreducible factor don't add roots
+        let mut f = irreducible.mul(&Poly::new(vec![expected[0], Gf128::ONE]));
+        for &root in &expected {
+            f = f.mul(&Poly::new(vec![root, Gf128::ONE]));
+        }
+        let repeated = expected[0];
+        expected.sort();
+        assert_eq!(expected, f.roots());
+        assert!(Poly::one().roots().is_empty());
+
+        let mut factors: Vec<(Poly, usize)> = expected.iter()
+            .map(|&root| (Poly::new(vec![root, Gf128::ONE]), if root == repeated { 2 } else { 1 }))
+            .collect();
+        factors.sort();
+        factors.push((irreducible, 1));
+        assert_eq!(factors, f.factor());
+    }
+
+    #[test]
+    fn factor_powers() {
+        let mut rng = rand::thread_rng();
+        let a = Poly::new(vec![Gf128::random(&mut rng), Gf128::ONE]);
+        let b = Poly::new(vec![Gf128::random(&mut rng), Gf128::ONE]);
+        // a^4 · b^3, with a square part that the derivative cannot see
+        let f = a.mul(&a).mul(&a).mul(&a).mul(&b).mul(&b).mul(&b);
+        let mut expected = vec![(a, 4), (b, 3)];
+        expected.sort();
+        assert_eq!(expected, f.factor());
+        assert!(Poly::one().factor().is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Linear algebra over GF(2)
+//!
+//! Bit vectors and matrices packed in 64-bit words, and the matrices of the
+//! GF(2)-linear maps of GF(2^128): multiplication by a constant and squaring.
+
+use crate::gf128::Gf128;
+
+/// Bits of a GF(2^128) element
+const GF128_BITS: usize = 128;
+
+/// Vector of bits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitVec {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitVec {
+    /// Vector of `len` zero bits
+    pub fn zero(len: usize) -> BitVec {
+        BitVec { len, words: vec![0; len.div_ceil(64)] }
+    }
+
+    /// Coefficients of a GF(2^128) element, x^0 first
+    pub fn from_gf128(element: Gf128) -> BitVec {
+        BitVec { len: GF128_BITS, words: vec![element.0 as u64, (element.0 >> 64) as u64] }
+    }
+
+    /// GF(2^128) element of `GF128_BITS` bits starting at `offset`
+    pub fn gf128_at(&self, offset: usize) -> Gf128 {
+        Gf128((0..GF128_BITS).filter(|&i| self.get(offset + i)).fold(0, |acc, i| acc | 1 << i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit index out of range");
+        (self.words[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        assert!(i < self.len, "bit index out of range");
+        if bit {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    /// Add another vector of the same length
+    pub fn xor(&mut self, other: &BitVec) {
+        assert_eq!(self.len, other.len, "bit vectors differ in length");
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a ^= b;
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Scalar product
+    pub fn dot(&self, other: &BitVec) -> bool {
+        self.words.iter().zip(&other.words)
+            .map(|(a, b)| (a & b).count_ones())
+            .sum::<u32>() % 2 == 1
+    }
+}
+
+/// Matrix over GF(2), stored by rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    cols: usize,
+    rows: Vec<BitVec>,
+}
+
+impl Matrix {
+    pub fn zero(rows: usize, cols: usize) -> Matrix {
+        Matrix { cols, rows: vec![BitVec::zero(cols); rows] }
+    }
+
+    pub fn identity(n: usize) -> Matrix {
+        let mut identity = Matrix::zero(n, n);
+        for i in 0..n {
+            identity.set(i, i, true);
+        }
+        identity
+    }
+
+    /// Matrix whose columns are the given vectors of `rows` bits
+    pub fn from_columns(rows: usize, columns: &[BitVec]) -> Matrix {
+        let mut matrix = Matrix::zero(rows, columns.len());
+        for (c, column) in columns.iter().enumerate() {
+            for r in 0..rows {
+                matrix.set(r, c, column.get(r));
+            }
+        }
+        matrix
+    }
+
+    /// Matrix of the multiplication by `c` in GF(2^128)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gf128::Gf128;
+    /// use cryptopals::gf2::{BitVec, Matrix};
+    ///
+    /// let (c, h) = (Gf128(0x1234), Gf128(0xabcd << 100));
+    /// assert_eq!(BitVec::from_gf128(c * h), Matrix::multiplication(c).mul_vec(&BitVec::from_gf128(h)));
+    /// ```
+    pub fn multiplication(c: Gf128) -> Matrix {
+        Self::gf128_linear(|x| c * x)
+    }
+
+    /// Matrix of the squaring in GF(2^128)
+    pub fn squaring() -> Matrix {
+        Self::gf128_linear(|x| x * x)
+    }
+
+    /// Matrix of a GF(2)-linear map of GF(2^128), from the images of the x^j
+    fn gf128_linear<F: Fn(Gf128) -> Gf128>(map: F) -> Matrix {
+        let columns: Vec<BitVec> = (0..GF128_BITS)
+            .map(|j| BitVec::from_gf128(map(Gf128(1 << j))))
+            .collect();
+        Matrix::from_columns(GF128_BITS, &columns)
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, r: usize) -> &BitVec {
+        &self.rows[r]
+    }
+
+    pub fn column(&self, c: usize) -> BitVec {
+        let mut column = BitVec::zero(self.rows.len());
+        for (r, row) in self.rows.iter().enumerate() {
+            column.set(r, row.get(c));
+        }
+        column
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        self.rows[r].get(c)
+    }
+
+    pub fn set(&mut self, r: usize, c: usize, bit: bool) {
+        self.rows[r].set(c, bit)
+    }
+
+    /// Append a row, of `ncols` bits
+    pub fn push_row(&mut self, row: BitVec) {
+        assert_eq!(self.cols, row.len(), "row length differs from the matrix width");
+        self.rows.push(row);
+    }
+
+    pub fn add(&self, other: &Matrix) -> Matrix {
+        let mut sum = self.clone();
+        for (a, b) in sum.rows.iter_mut().zip(&other.rows) {
+            a.xor(b);
+        }
+        sum
+    }
+
+    pub fn mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows.len(), "incompatible matrix sizes");
+        let rows = self.rows.iter().map(|row| {
+            let mut product = BitVec::zero(other.cols);
+            for (k, other_row) in other.rows.iter().enumerate() {
+                if row.get(k) {
+                    product.xor(other_row);
+                }
+            }
+            product
+        }).collect();
+        Matrix { cols: other.cols, rows }
+    }
+
+    pub fn mul_vec(&self, vector: &BitVec) -> BitVec {
+        let mut product = BitVec::zero(self.rows.len());
+        for (r, row) in self.rows.iter().enumerate() {
+            product.set(r, row.dot(vector));
+        }
+        product
+    }
+
+    /// Reduced row echelon form, with the column of each pivot
+    fn echelon(&self) -> (Vec<BitVec>, Vec<usize>) {
+        let mut rows = self.rows.clone();
+        let mut pivots = Vec::new();
+        for c in 0..self.cols {
+            let r = pivots.len();
+            if r == rows.len() {
+                break;
+            }
+            let pivot = match (r..rows.len()).find(|&i| rows[i].get(c)) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+            rows.swap(r, pivot);
+            let pivot_row = rows[r].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                if i != r && row.get(c) {
+                    row.xor(&pivot_row);
+                }
+            }
+            pivots.push(c);
+        }
+        (rows, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.echelon().1.len()
+    }
+
+    /// Basis of the kernel, the vectors v such that M·v = 0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::gf2::{BitVec, Matrix};
+    ///
+    /// let mut m = Matrix::zero(1, 2);
+    /// m.set(0, 0, true);
+    /// m.set(0, 1, true);
+    /// let kernel = m.kernel();
+    /// assert_eq!(1, kernel.len());
+    /// assert!(kernel[0].get(0) && kernel[0].get(1));
+    /// ```
+    pub fn kernel(&self) -> Vec<BitVec> {
+        let (rows, pivots) = self.echelon();
+        let mut basis = Vec::new();
+        let mut pivot_iter = pivots.iter().peekable();
+        for free in 0..self.cols {
+            if pivot_iter.peek() == Some(&&free) {
+                pivot_iter.next();
+                continue;
+            }
+            let mut vector = BitVec::zero(self.cols);
+            vector.set(free, true);
+            for (row, &pivot) in rows.iter().zip(&pivots) {
+                if row.get(free) {
+                    vector.set(pivot, true);
+                }
+            }
+            basis.push(vector);
+        }
+        basis
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    fn random_matrix(rows: usize, cols: usize) -> Matrix {
+        let mut rng = rand::thread_rng();
+        let mut matrix = Matrix::zero(rows, cols);
+        for r in 0..rows {
+            for c in 0..cols {
+                matrix.set(r, c, rng.gen());
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn bits() {
+        let mut v = BitVec::zero(130);
+        v.set(129, true);
+        v.set(3, true);
+        assert!(v.get(129) && v.get(3) && !v.get(4));
+        v.set(3, false);
+        assert!(!v.get(3));
+        assert_eq!(Gf128(1 << 127), v.gf128_at(2));
+        assert_eq!(Gf128(42), BitVec::from_gf128(Gf128(42)).gf128_at(0));
+    }
+
+    #[test]
+    fn products() {
+        let a = random_matrix(5, 7);
+        let b = random_matrix(7, 3);
+        assert_eq!(a, Matrix::identity(5).mul(&a));
+        let v = random_matrix(3, 1).column(0);
+        assert_eq!(a.mul(&b).mul_vec(&v), a.mul_vec(&b.mul_vec(&v)));
+    }
+
+    #[test]
+    fn kernel() {
+        let m = random_matrix(20, 30);
+        let kernel = m.kernel();
+        assert_eq!(30 - m.rank(), kernel.len());
+        assert!(kernel.iter().all(|v| m.mul_vec(v).is_zero()));
+        assert_eq!(kernel.len(), Matrix::from_columns(30, &kernel).rank());
+        assert!(Matrix::identity(4).kernel().is_empty());
+    }
+
+    #[test]
+    fn gf128_maps() {
+        let mut rng = rand::thread_rng();
+        let h = Gf128::random(&mut rng);
+        let s = Matrix::squaring();
+        assert_eq!(BitVec::from_gf128(h * h), s.mul_vec(&BitVec::from_gf128(h)));
+        assert_eq!(BitVec::from_gf128(h.pow(4)), s.mul(&s).mul_vec(&BitVec::from_gf128(h)));
+    }
+}
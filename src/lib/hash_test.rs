@@ -0,0 +1,32 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Checks shared by the tests of the Merkle-Damgård hash functions
+
+/// Check that `resume` forges the digest of a message extended after its padding
+///
+/// `digest`, `padding`, `state` and `resume` are the functions of the same
+/// hash module, e.g. `md4`, whose digests can be extended without the secret.
+pub fn length_extension<S>(digest: fn(&[u8]) -> Vec<u8>, padding: fn(usize) -> Vec<u8>,
+                           state: fn(&[u8]) -> S, resume: fn(S, usize, &[u8]) -> Vec<u8>) {
+    let secret_message = b"secret;comment1=cooking%20MCs";
+    let glue_len = secret_message.len() + padding(secret_message.len()).len();
+    let forged = resume(state(&digest(secret_message)), glue_len, b";admin=true");
+
+    let mut extended = secret_message.to_vec();
+    extended.extend(padding(secret_message.len()));
+    extended.extend_from_slice(b";admin=true");
+    assert_eq!(digest(&extended), forged);
+}
@@ -12,90 +12,521 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Helper functions for displaying titles and subtitles for readability
+//! Helper functions for fetching challenge inputs and displaying blocks
+//!
+//! The inputs are only downloaded and cached with the `net` feature.
 
-use std::{fs, error};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::io::Write;
+#[cfg(feature = "net")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "net")]
+use std::sync::Mutex;
+#[cfg(feature = "net")]
+use std::thread;
+#[cfg(feature = "net")]
+use std::time::Duration;
+#[cfg(feature = "net")]
+use crate::cache::Cache;
+use crate::error::{CryptopalsError, Result};
 
-/// Display a section title
+/// Data files of the solved challenges, and the English corpus
+pub const CHALLENGE_FILES: &[&str] = &[
+    crate::english::GUTENBERG_CORPUS_URL,
+    "https://cryptopals.com/static/challenge-data/4.txt",
+    "https://cryptopals.com/static/challenge-data/6.txt",
+    "https://cryptopals.com/static/challenge-data/7.txt",
+    "https://cryptopals.com/static/challenge-data/8.txt",
+    "https://cryptopals.com/static/challenge-data/25.txt",
+];
+
+/// Whether `read_from_url` is restricted to the cache
+#[cfg(feature = "net")]
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Never download challenge inputs, `read_from_url` then fails on cache misses
+#[cfg(feature = "net")]
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Timeout, retries and user agent of the downloads
+///
+/// Timeouts, connection failures, `429 Too Many Requests` and server errors
+/// are retried, waiting `backoff` before the first retry and twice as long
+/// before each following one.
 ///
 /// # Examples
 ///
 /// ```
+/// use std::time::Duration;
+/// use cryptopals::helper::{self, FetchConfig};
+///
+/// helper::set_fetch_config(FetchConfig::default().with_timeout(Duration::from_secs(5)).with_retries(1));
+/// ```
+#[cfg(feature = "net")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchConfig {
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    user_agent: String,
+}
+
+#[cfg(feature = "net")]
+impl FetchConfig {
+    /// Timeout of a whole request, body included
+    pub fn with_timeout(mut self, timeout: Duration) -> FetchConfig {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of retries after the first attempt
+    pub fn with_retries(mut self, retries: u32) -> FetchConfig {
+        self.retries = retries;
+        self
+    }
+
+    /// Wait before the first retry
+    pub fn with_backoff(mut self, backoff: Duration) -> FetchConfig {
+        self.backoff = backoff;
+        self
+    }
+
+    /// `User-Agent` header of the requests
+    pub fn with_user_agent(mut self, user_agent: &str) -> FetchConfig {
+        self.user_agent = user_agent.to_owned();
+        self
+    }
+
+    /// Wait before retry number `retry`, counting from 0
+    fn delay(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(1 << retry.min(16))
+    }
+
+    fn blocking_client(&self) -> Result<reqwest::blocking::Client> {
+        Ok(reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?)
+    }
+
+    fn async_client(&self) -> Result<reqwest::Client> {
+        Ok(reqwest::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?)
+    }
+}
+
+#[cfg(feature = "net")]
+impl Default for FetchConfig {
+    fn default() -> FetchConfig {
+        FetchConfig {
+            timeout: Duration::from_secs(30),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            user_agent: format!("cryptopals/{} (+https://github.com/Farzy/cryptopals)", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// Configuration of the downloads, `FetchConfig::default` until `set_fetch_config`
+#[cfg(feature = "net")]
+static FETCH_CONFIG: Mutex<Option<FetchConfig>> = Mutex::new(None);
+
+/// Use `config` for all the following downloads
+#[cfg(feature = "net")]
+pub fn set_fetch_config(config: FetchConfig) {
+    *FETCH_CONFIG.lock().unwrap() = Some(config);
+}
+
+#[cfg(feature = "net")]
+fn fetch_config() -> FetchConfig {
+    FETCH_CONFIG.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Whether a download failure may go away by itself
+#[cfg(feature = "net")]
+fn is_transient(error: &CryptopalsError) -> bool {
+    match error {
+        CryptopalsError::Network(e) => e.is_timeout() || e.is_connect() || e.status().map_or(false, |status| {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }),
+        _ => false,
+    }
+}
+
+/// Call `fetch` until it succeeds, fails for good, or the retries of `config` run out
+#[cfg(feature = "net")]
+fn with_retries<T, F: FnMut() -> Result<T>>(config: &FetchConfig, url: &str, mut fetch: F) -> Result<T> {
+    let mut retry = 0;
+    loop {
+        match fetch() {
+            Err(e) if retry < config.retries && is_transient(&e) => {
+                let delay = config.delay(retry);
+                warn!("Download of {} failed, retrying in {:?}: {}", url, delay, e);
+                thread::sleep(delay);
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Path of a local file given as a plain path or a `file://` URL, `None` for other URLs
+fn local_path(url: &str) -> Option<&str> {
+    match url.strip_prefix("file://") {
+        Some(path) => Some(path),
+        None if !url.contains("://") => Some(url),
+        None => None,
+    }
+}
+
+/// Read a text from from an URL and cache it in the directory of `cache::CacheConfig::default`, return the body
+///
+/// Only the cache is read after `set_offline(true)`. Plain paths and `file://`
+/// URLs are read directly from the disk, without the cache, even offline.
+/// Inputs which may not be UTF-8 text are read with `read_bytes_from_url`.
+///
+///# Examples
+///
+/// ```
 /// use cryptopals::helper;
 ///
-/// helper::section("Statistics");
+/// let body = helper::read_from_url("https://httpbin.org/base64/SFRUUEJJTiBpcyBhd2Vzb21l").unwrap();
+/// assert_eq!("HTTPBIN is awesome", body);
 /// ```
+pub fn read_from_url(url: &str) -> Result<String> {
+    Ok(String::from_utf8(read_bytes_from_url(url)?)?)
+}
+
+/// Read the raw bytes of an URL and cache them, see `read_from_url`
 ///
-/// Display:
+/// The body is returned and cached as is, whether it is text or not.
+///
+///# Examples
 ///
-/// ```text
-/// +------------+
-/// | Statistics |
-/// +------------+
 /// ```
-pub fn section(title: &str) {
-    let len = title.len();
-    let dashes = "-".repeat(len);
-    println!("\n+-{}-+", dashes);
-    println!("| {} |", title);
-    println!("+-{}-+", dashes);
+/// use cryptopals::helper;
+///
+/// let body = helper::read_bytes_from_url("https://httpbin.org/base64/3q2+7w==").unwrap();
+/// assert_eq!(vec![0xde, 0xad, 0xbe, 0xef], body);
+/// ```
+#[cfg(feature = "net")]
+pub fn read_bytes_from_url(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = local_path(url) {
+        return Ok(std::fs::read(path)?);
+    }
+    // Read file from the cache or Internet
+    let cache = Cache::default();
+    if let Some(body) = cache.get_bytes(url) {
+        info!("Read body of {} from cache {}", url, cache.dir().display());
+        return Ok(body);
+    }
+    if OFFLINE.load(Ordering::Relaxed) {
+        return Err(CryptopalsError::Offline(url.to_owned()));
+    }
+    let config = fetch_config();
+    let client = config.blocking_client()?;
+    let body = with_retries(&config, url, || {
+        let response = client.get(url).send()?.error_for_status()?;
+        let expected_len = response.content_length();
+        checked_body(url, expected_len, response.bytes()?.to_vec())
+    })?;
+    info!("Write body from {} to cache {}", url, cache.dir().display());
+    cache.put_bytes(url, &body).map_err(CryptopalsError::Cache)?;
+
+    Ok(body)
+}
+
+/// Without the `net` feature there is neither network nor cache, only local files can be read
+#[cfg(not(feature = "net"))]
+pub fn read_bytes_from_url(url: &str) -> Result<Vec<u8>> {
+    match local_path(url) {
+        Some(path) => Ok(std::fs::read(path)?),
+        None => Err(CryptopalsError::Offline(url.to_owned())),
+    }
+}
+
+/// Downloaded body, refusing a download cut short so that it is never cached
+#[cfg(feature = "net")]
+fn checked_body(url: &str, expected_len: Option<u64>, bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if let Some(expected_len) = expected_len.filter(|&n| n != bytes.len() as u64) {
+        return Err(CryptopalsError::Format(format!(
+            "truncated download of {}: {} bytes out of {}", url, bytes.len(), expected_len)));
+    }
+    Ok(bytes)
+}
+
+/// Download a body asynchronously, without the cache, along with its announced length
+#[cfg(feature = "net")]
+pub async fn fetch_async(client: &reqwest::Client, url: &str) -> reqwest::Result<(Option<u64>, Vec<u8>)> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    let expected_len = response.content_length();
+    Ok((expected_len, response.bytes().await?.to_vec()))
 }
 
-/// Display a subsection title
+/// Download every URL missing from the cache concurrently, return the number downloaded
+///
+/// The downloads run on their own runtime, then the bodies are cached one
+/// after the other, so that `read_from_url` finds them all afterwards. Nothing
+/// is downloaded after `set_offline(true)`.
+#[cfg(feature = "net")]
+pub fn prefetch(urls: &[&str]) -> Result<usize> {
+    let cache = Cache::default();
+    let missing: Vec<String> = urls.iter()
+        .filter(|url| local_path(url).is_none() && cache.get_bytes(url).is_none())
+        .map(|url| url.to_string())
+        .collect();
+    if missing.is_empty() || OFFLINE.load(Ordering::Relaxed) {
+        return Ok(0);
+    }
+
+    // Without retries, `read_from_url` retries the failed downloads later
+    let client = fetch_config().async_client()?;
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let downloads = runtime.block_on(async {
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in missing {
+            let client = client.clone();
+            tasks.spawn(async move {
+                let body = fetch_async(&client, &url).await;
+                (url, body)
+            });
+        }
+        let mut downloads = Vec::new();
+        while let Some(download) = tasks.join_next().await {
+            downloads.push(download.expect("download task panicked"));
+        }
+        downloads
+    });
+
+    // Keep the successful downloads even if others failed
+    let mut count = 0;
+    let mut failure = None;
+    for (url, body) in downloads {
+        match body.map_err(CryptopalsError::from)
+            .and_then(|(expected_len, bytes)| checked_body(&url, expected_len, bytes)) {
+            Ok(body) => {
+                info!("Write body from {} to cache {}", url, cache.dir().display());
+                cache.put_bytes(&url, &body).map_err(CryptopalsError::Cache)?;
+                count += 1;
+            }
+            Err(e) => failure = Some(e),
+        }
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+/// Download all the challenge data files and the English corpus, see `prefetch`
+#[cfg(feature = "net")]
+pub fn prefetch_all() -> Result<usize> {
+    prefetch(CHALLENGE_FILES)
+}
+
+/// Render two byte strings in hex, one 16-byte block per row, marking the differences
+///
+/// Each row shows the block number, the bytes of `a`, the bytes of `b`, then a line
+/// with `^^` under every differing byte. Bytes missing from the shorter string
+/// are shown as `--`.
 ///
 /// # Examples
 ///
 /// ```
 /// use cryptopals::helper;
 ///
-/// helper::subsection("Permutations");
+/// print!("{}", helper::hex_diff(b"YELLOW SUBMARINE", b"YELLOW SUBMARINF"));
 /// ```
 ///
 /// Display:
 ///
 /// ```text
-/// Permutations:
-/// -------------
+/// block 0 | 59 45 4c 4c 4f 57 20 53 55 42 4d 41 52 49 4e 45
+///         | 59 45 4c 4c 4f 57 20 53 55 42 4d 41 52 49 4e 46
+///         |                                              ^^
 /// ```
-#[allow(dead_code)]
-pub fn subsection(title: &str) {
-    let len = title.len();
-    let dashes = "-".repeat(len + 1);
-    println!("\n{}:", title);
-    println!("{}\n", dashes);
+pub fn hex_diff(a: &[u8], b: &[u8]) -> String {
+    const BLOCK_SIZE: usize = 16;
+
+    fn cell(bytes: &[u8], index: usize) -> String {
+        bytes.get(index).map(|b| format!("{:02x}", b)).unwrap_or_else(|| "--".to_owned())
+    }
+
+    let len = a.len().max(b.len());
+    let blocks = len.div_ceil(BLOCK_SIZE);
+    let label_width = format!("block {}", blocks.saturating_sub(1)).len();
+    let mut output = String::new();
+    for block in 0..blocks {
+        let range = (block * BLOCK_SIZE)..len.min((block + 1) * BLOCK_SIZE);
+        let row_a: Vec<String> = range.clone().map(|i| cell(a, i)).collect();
+        let row_b: Vec<String> = range.clone().map(|i| cell(b, i)).collect();
+        let markers: Vec<&str> = range.map(|i| if a.get(i) == b.get(i) { "  " } else { "^^" }).collect();
+        output.push_str(&format!("{:width$} | {}\n", format!("block {}", block), row_a.join(" "), width = label_width));
+        output.push_str(&format!("{:width$} | {}\n", "", row_b.join(" "), width = label_width));
+        output.push_str(&format!("{:width$} | {}\n", "", markers.join(" ").trim_end(), width = label_width));
+    }
+    output
 }
 
-/// Read a text from from an URL and cache it in /var/tmp, return the body
+/// Render data in hex as numbered blocks, flagging the blocks seen before
 ///
-///# Examples
+/// Repeated blocks are the signature of ECB mode, and the material of
+/// cut-and-paste attacks.
+///
+/// # Examples
 ///
 /// ```
 /// use cryptopals::helper;
 ///
-/// let body = helper::read_from_url("https://httpbin.org/base64/SFRUUEJJTiBpcyBhd2Vzb21l").unwrap();
-/// assert_eq!("HTTPBIN is awesome", body);
+/// print!("{}", helper::format_blocks(b"YELLOW SUBMARINEYELLOW SUBMARINE!", 16));
 /// ```
-pub fn read_from_url(url: &str) -> Result<String, Box<dyn error::Error>> {
-    // Create filename for the file cache
-    let mut hasher = DefaultHasher::new();
-    url.hash(&mut hasher);
-    let filename = format!("/var/tmp/cryptopals-{:x}.txt", hasher.finish());
+///
+/// Display:
+///
+/// ```text
+/// 0 | 59454c4c4f57205355424d4152494e45
+/// 1 | 59454c4c4f57205355424d4152494e45 <- duplicate of block 0
+/// 2 | 21
+/// ```
+pub fn format_blocks(data: &[u8], block_size: usize) -> String {
+    let blocks: Vec<&[u8]> = data.chunks(block_size).collect();
+    let width = blocks.len().saturating_sub(1).to_string().len();
+    let mut output = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let hex: String = block.iter().map(|b| format!("{:02x}", b)).collect();
+        output.push_str(&format!("{:width$} | {}", index, hex, width = width));
+        if let Some(first) = blocks[..index].iter().position(|b| b == block) {
+            output.push_str(&format!(" <- duplicate of block {}", first));
+        }
+        output.push('\n');
+    }
+    output
+}
 
-    // Read file from the cache or Internet
-    let body: String;
-    if let Ok(body_from_file) = fs::read_to_string(&filename) {
-        info!("Read text of {} from cache file {}", url, filename);
-        body = body_from_file;
-    } else {
-        body = reqwest::blocking::get(url)?
-            .text()?;
-        info!("Write text from {} to cache file {}", url, filename);
-        let mut f = fs::File::create(filename)?;
-        f.write_all(body.as_bytes())?;
+/// Count the blocks which are a repetition of a previous block
+pub fn count_duplicate_blocks(data: &[u8], block_size: usize) -> usize {
+    let blocks: Vec<&[u8]> = data.chunks(block_size).collect();
+    (0..blocks.len())
+        .filter(|&index| blocks[..index].contains(&blocks[index]))
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hex_diff_equal() {
+        assert_eq!("block 0 | 61 62\n        | 61 62\n        | \n", hex_diff(b"ab", b"ab"));
     }
 
-    Ok(body)
+    #[test]
+    fn blocks_duplicates() {
+        let data = b"AAAABBBBAAAACCCCBBBBAA";
+        assert_eq!(
+            "0 | 41414141\n1 | 42424242\n2 | 41414141 <- duplicate of block 0\n3 | 43434343\n4 | 42424242 <- duplicate of block 1\n5 | 4141\n",
+            format_blocks(data, 4)
+        );
+        assert_eq!(2, count_duplicate_blocks(data, 4));
+    }
+
+    #[test]
+    fn blocks_width() {
+        let formatted = format_blocks(&[0; 11], 1);
+        assert!(formatted.starts_with(" 0 | 00\n"));
+        assert!(formatted.ends_with("10 | 00 <- duplicate of block 0\n"));
+        assert_eq!("", format_blocks(b"", 16));
+    }
+
+    #[test]
+    fn local_paths() {
+        assert_eq!(Some("/tmp/4.txt"), local_path("file:///tmp/4.txt"));
+        assert_eq!(Some("data/4.txt"), local_path("data/4.txt"));
+        assert_eq!(None, local_path("https://cryptopals.com/static/challenge-data/4.txt"));
+    }
+
+    #[test]
+    fn read_local_file() {
+        let path = std::env::temp_dir().join(format!("cryptopals-helper-{}.txt", std::process::id()));
+        std::fs::write(&path, "YELLOW SUBMARINE").unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!("YELLOW SUBMARINE", read_from_url(path).unwrap());
+        assert_eq!("YELLOW SUBMARINE", read_from_url(&format!("file://{}", path)).unwrap());
+        std::fs::remove_file(path).unwrap();
+        assert!(matches!(read_from_url(path), Err(CryptopalsError::Io(_))));
+    }
+
+    #[test]
+    fn read_local_binary_file() {
+        let path = std::env::temp_dir().join(format!("cryptopals-helper-binary-{}.bin", std::process::id()));
+        std::fs::write(&path, [0x00, 0xff, 0x80]).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(vec![0x00, 0xff, 0x80], read_bytes_from_url(path).unwrap());
+        assert!(matches!(read_from_url(path), Err(CryptopalsError::Utf8(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn retry_delays() {
+        let config = FetchConfig::default().with_backoff(Duration::from_millis(100));
+        assert_eq!(Duration::from_millis(100), config.delay(0));
+        assert_eq!(Duration::from_millis(400), config.delay(2));
+        assert_eq!(Duration::from_millis(100 << 16), config.delay(1000));
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn retries() {
+        let config = FetchConfig::default().with_retries(2).with_backoff(Duration::from_millis(1));
+        let client = config.blocking_client().unwrap();
+        // Nothing listens on port 1, the connection is refused at each attempt
+        let mut attempts = 0;
+        let result = with_retries(&config, "http://127.0.0.1:1/", || {
+            attempts += 1;
+            Ok(client.get("http://127.0.0.1:1/").send()?)
+        });
+        assert!(matches!(result, Err(CryptopalsError::Network(_))));
+        assert_eq!(3, attempts);
+
+        // Other failures are final
+        let mut attempts = 0;
+        let result: Result<()> = with_retries(&config, "u", || {
+            attempts += 1;
+            Err(CryptopalsError::Format("bad".to_owned()))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, attempts);
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn truncated_body() {
+        assert_eq!(b"abc".to_vec(), checked_body("u", Some(3), b"abc".to_vec()).unwrap());
+        assert_eq!(b"abc".to_vec(), checked_body("u", None, b"abc".to_vec()).unwrap());
+        assert!(checked_body("u", Some(4), b"abc".to_vec()).is_err());
+    }
+
+    #[test]
+    fn hex_diff_empty() {
+        assert_eq!("", hex_diff(b"", b""));
+    }
+
+    #[test]
+    fn hex_diff_blocks_and_lengths() {
+        let a = [0u8; 17];
+        let mut b = [0u8; 18];
+        b[1] = 1;
+        let diff = hex_diff(&a, &b);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(6, lines.len());
+        assert!(lines[0].starts_with("block 0 | 00 00"));
+        assert!(lines[1].starts_with("        | 00 01"));
+        assert_eq!("        |    ^^", lines[2]);
+        assert_eq!("block 1 | 00 --", lines[3]);
+        assert_eq!("        | 00 00", lines[4]);
+        assert_eq!("        |    ^^", lines[5]);
+    }
 }
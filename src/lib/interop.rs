@@ -0,0 +1,107 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Interoperability tests against the `openssl enc` command line
+//!
+//! Random data is encrypted by OpenSSL and decrypted by the crate, then the
+//! other way around, in ECB, CBC and CTR modes. Run them with
+//! `cargo test --features openssl-interop`, they are skipped when the
+//! `openssl` binary cannot be run.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use rand::Rng;
+use crate::cipher::{Aes128Ctr, CtrLayout, StreamCipher, BLOCK_SIZE};
+use crate::crypto::{Aes128Cbc, Aes128Ecb, BytesCrypto};
+use crate::keygen;
+
+/// Run `openssl enc` on `input`, None when openssl is missing
+fn openssl_enc(cipher: &str, key: &[u8], iv: Option<&[u8]>, decrypt: bool, input: &[u8]) -> Option<Vec<u8>> {
+    let mut command = Command::new("openssl");
+    command.args(["enc", cipher, "-K", &key.bytes2hex()]);
+    if let Some(iv) = iv {
+        command.args(["-iv", &iv.bytes2hex()]);
+    }
+    if decrypt {
+        command.arg("-d");
+    }
+    let mut child = match command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            warn!("Skipping the OpenSSL interop test, cannot run openssl: {}", e);
+            return None;
+        }
+    };
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "openssl enc {} failed: {}", cipher, String::from_utf8_lossy(&output.stderr));
+    Some(output.stdout)
+}
+
+/// Random key, IV and data, the length covers the padding edge cases
+fn random_input<R: Rng>(rng: &mut R, len: usize) -> ([u8; BLOCK_SIZE], [u8; BLOCK_SIZE], Vec<u8>) {
+    (keygen::aes_key(rng), keygen::iv(rng), keygen::bytes(rng, len))
+}
+
+/// Lengths of the test messages: empty, a partial block, exactly one block and more
+const LENGTHS: [usize; 5] = [0, 1, 15, 16, 100];
+
+#[test]
+fn ecb() {
+    let mut rng = rand::thread_rng();
+    for &len in &LENGTHS {
+        let (key, _, data) = random_input(&mut rng, len);
+        let ciphertext = match openssl_enc("-aes-128-ecb", &key, None, false, &data) {
+            Some(ciphertext) => ciphertext,
+            None => return,
+        };
+        let cipher = Aes128Ecb::new(&key).unwrap();
+        assert_eq!(data, cipher.decrypt(&ciphertext).unwrap());
+        assert_eq!(Some(data.clone()), openssl_enc("-aes-128-ecb", &key, None, true, &cipher.encrypt(&data)));
+    }
+}
+
+#[test]
+fn cbc() {
+    let mut rng = rand::thread_rng();
+    for &len in &LENGTHS {
+        let (key, iv, data) = random_input(&mut rng, len);
+        let ciphertext = match openssl_enc("-aes-128-cbc", &key, Some(&iv), false, &data) {
+            Some(ciphertext) => ciphertext,
+            None => return,
+        };
+        let cipher = Aes128Cbc::new(&key, &iv).unwrap();
+        assert_eq!(data, cipher.decrypt(&ciphertext).unwrap());
+        assert_eq!(Some(data.clone()), openssl_enc("-aes-128-cbc", &key, Some(&iv), true, &cipher.encrypt(&data)));
+    }
+}
+
+#[test]
+fn ctr() {
+    let mut rng = rand::thread_rng();
+    for &len in &LENGTHS {
+        let extra = rng.gen_range(0..64);
+        let (key, mut iv, data) = random_input(&mut rng, len + extra);
+        // Start close to the wrap around of the low 64 bits to check the carry
+        iv[8..].copy_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe]);
+        let ciphertext = match openssl_enc("-aes-128-ctr", &key, Some(&iv), false, &data) {
+            Some(ciphertext) => ciphertext,
+            None => return,
+        };
+        let ctr = Aes128Ctr::with_layout(&key, CtrLayout::BigEndian128 { iv }).unwrap();
+        assert_eq!(data, ctr.apply_keystream(&ciphertext));
+        assert_eq!(Some(data.clone()), openssl_enc("-aes-128-ctr", &key, Some(&iv), true, &ctr.apply_keystream(&data)));
+    }
+}
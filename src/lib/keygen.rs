@@ -0,0 +1,75 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Random keys, IVs and nonces drawn from a caller-supplied generator
+//!
+//! Oracles draw their secrets here, so that a test can pass a seeded
+//! generator and get the same keys on every run.
+
+use rand::RngCore;
+use crate::cipher::BLOCK_SIZE;
+use crate::gcm;
+
+/// AES-128 key
+///
+/// # Examples
+///
+/// ```
+/// use rand::SeedableRng;
+/// use rand::rngs::StdRng;
+/// use cryptopals::keygen;
+///
+/// let key = keygen::aes_key(&mut StdRng::seed_from_u64(1));
+/// assert_eq!(key, keygen::aes_key(&mut StdRng::seed_from_u64(1)));
+/// assert_ne!(key, keygen::aes_key(&mut rand::thread_rng()));
+/// ```
+pub fn aes_key<R: RngCore + ?Sized>(rng: &mut R) -> [u8; BLOCK_SIZE] {
+    let mut key = [0; BLOCK_SIZE];
+    rng.fill_bytes(&mut key);
+    key
+}
+
+/// RC4 key of 128 bits
+pub fn rc4_key<R: RngCore + ?Sized>(rng: &mut R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.fill_bytes(&mut key);
+    key
+}
+
+/// CBC initialization vector
+pub fn iv<R: RngCore + ?Sized>(rng: &mut R) -> [u8; BLOCK_SIZE] {
+    let mut iv = [0; BLOCK_SIZE];
+    rng.fill_bytes(&mut iv);
+    iv
+}
+
+/// Nonce of `cipher::Aes128Ctr`
+pub fn ctr_nonce<R: RngCore + ?Sized>(rng: &mut R) -> u64 {
+    rng.next_u64()
+}
+
+/// Nonce of `gcm::Aes128Gcm`
+pub fn gcm_nonce<R: RngCore + ?Sized>(rng: &mut R) -> [u8; gcm::NONCE_SIZE] {
+    let mut nonce = [0; gcm::NONCE_SIZE];
+    rng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Any number of random bytes, e.g. a random prefix
+pub fn bytes<R: RngCore + ?Sized>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
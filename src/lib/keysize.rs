@@ -0,0 +1,310 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key size estimators for repeating-key XOR ciphertexts
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use crate::blocks;
+use crate::crypto::{self, BytesCrypto};
+
+/// Relative score margin within which a key size takes the place of its multiples
+pub const MULTIPLE_TOLERANCE: f64 = 0.3;
+
+/// A method guessing the key size of a repeating-key XOR ciphertext
+pub trait KeysizeEstimator {
+    /// Short name used in reports
+    fn name(&self) -> &str;
+
+    /// Score one key size, lower is better
+    ///
+    /// Returns `None` if the ciphertext is too short for this key size.
+    fn score(&self, ciphertext: &[u8], keysize: usize) -> Option<f64>;
+
+    /// Score every key size in the range and sort them, best first
+    fn rank(&self, ciphertext: &[u8], keysizes: Range<usize>) -> Vec<(usize, f64)> {
+        sorted_scores(self, ciphertext, keysizes)
+    }
+}
+
+fn sorted_scores<E: KeysizeEstimator + ?Sized>(estimator: &E, ciphertext: &[u8], keysizes: Range<usize>)
+    -> Vec<(usize, f64)>
+{
+    let mut ranking: Vec<(usize, f64)> = keysizes
+        .filter_map(|keysize| estimator.score(ciphertext, keysize).map(|s| (keysize, s)))
+        .collect();
+    ranking.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    ranking
+}
+
+/// Rank every key size ahead of its multiples which score at most `tolerance` better
+///
+/// A key size gets the best score of its multiples in the ranking when its
+/// own score is within `tolerance`, relative to theirs. Key sizes with the
+/// same score are sorted in increasing order, so that a key size comes before
+/// the multiples scoring as well as it by chance.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::keysize;
+///
+/// let ranking = keysize::collapse_multiples(&[(6, -1.0), (4, -0.8), (3, -0.9)], 0.2);
+/// assert_eq!(vec![(3, -1.0), (6, -1.0), (4, -0.8)], ranking);
+/// ```
+pub fn collapse_multiples(ranking: &[(usize, f64)], tolerance: f64) -> Vec<(usize, f64)> {
+    let mut collapsed: Vec<(usize, f64)> = ranking.iter()
+        .map(|&(keysize, score)| {
+            let best = ranking.iter()
+                .filter(|&&(multiple, multiple_score)| multiple != keysize && multiple % keysize == 0
+                    && score <= multiple_score + tolerance * multiple_score.abs())
+                .map(|&(_, multiple_score)| multiple_score)
+                .fold(score, f64::min);
+            (keysize, best)
+        })
+        .collect();
+    collapsed.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+    collapsed
+}
+
+/// Normalized Hamming distance between the first two blocks
+pub struct FirstBlocksHamming;
+
+impl KeysizeEstimator for FirstBlocksHamming {
+    fn name(&self) -> &str {
+        "hamming-2-blocks"
+    }
+
+    fn score(&self, ciphertext: &[u8], keysize: usize) -> Option<f64> {
+        ciphertext.normalized_hamming(keysize, 2).ok()
+    }
+}
+
+/// Normalized Hamming distance between the first block and the `blocks - 1` next ones, averaged
+pub struct AverageBlocksHamming {
+    pub blocks: usize,
+}
+
+impl KeysizeEstimator for AverageBlocksHamming {
+    fn name(&self) -> &str {
+        "hamming-avg-blocks"
+    }
+
+    fn score(&self, ciphertext: &[u8], keysize: usize) -> Option<f64> {
+        if keysize == 0 || self.blocks < 2 || ciphertext.len() < self.blocks * keysize {
+            return None;
+        }
+        let mut sum = 0.0;
+        for i in 1..self.blocks {
+            sum += ciphertext[0..keysize]
+                .hamming_distance(&ciphertext[(i * keysize)..((i + 1) * keysize)]).ok()? as f64;
+        }
+        Some(sum / ((self.blocks - 1) as f64 * keysize as f64))
+    }
+}
+
+/// Opposite of the mean index of coincidence of the transposed columns
+///
+/// With the right key size, every column is a single-byte XOR of English
+/// text and keeps its index of coincidence of about 0.065, while wrong key
+/// sizes mix key bytes and get closer to 1/256. Multiples of the key size
+/// score as well, so the ranking puts them after their divisors, see
+/// `collapse_multiples`.
+pub struct ColumnCoincidence;
+
+impl KeysizeEstimator for ColumnCoincidence {
+    fn name(&self) -> &str {
+        "coincidence"
+    }
+
+    fn score(&self, ciphertext: &[u8], keysize: usize) -> Option<f64> {
+        // Every column needs a pair of bytes
+        if keysize == 0 || ciphertext.len() < 2 * keysize {
+            return None;
+        }
+        let sum: f64 = blocks::transpose(ciphertext, keysize).iter()
+            .map(|column| crypto::index_of_coincidence(column))
+            .sum();
+        Some(-sum / keysize as f64)
+    }
+
+    fn rank(&self, ciphertext: &[u8], keysizes: Range<usize>) -> Vec<(usize, f64)> {
+        collapse_multiples(&sorted_scores(self, ciphertext, keysizes), MULTIPLE_TOLERANCE)
+    }
+}
+
+/// Positions of every sequence of `len` bytes, in increasing order
+fn sequence_positions(ciphertext: &[u8], len: usize) -> HashMap<&[u8], Vec<usize>> {
+    let mut positions: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    if len == 0 {
+        return positions;
+    }
+    for (position, sequence) in ciphertext.windows(len).enumerate() {
+        positions.entry(sequence).or_default().push(position);
+    }
+    positions
+}
+
+/// Distances between the consecutive occurrences of every repeated sequence of `len` bytes
+///
+/// The same plaintext bytes encrypted by the same key bytes repeat in the
+/// ciphertext at a distance which is a multiple of the key size.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::keysize;
+///
+/// let mut spacings = keysize::repeated_spacings(b"abcXXabcWXYZabc", 3);
+/// spacings.sort();
+/// assert_eq!(vec![5, 7], spacings);
+/// ```
+pub fn repeated_spacings(ciphertext: &[u8], len: usize) -> Vec<usize> {
+    sequence_positions(ciphertext, len).values()
+        .flat_map(|positions| positions.windows(2).map(|pair| pair[1] - pair[0]))
+        .collect()
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Kasiski examination: key sizes proposed by the GCDs of the repeated sequences spacings
+///
+/// Every sequence of `len` bytes seen at least three times proposes the GCD
+/// of its spacings. Returns the proposals in `keysizes` with their number of
+/// votes, most voted first.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::keysize;
+///
+/// assert_eq!(vec![(4, 1)], keysize::kasiski(b"abcXabcYYYYYabcZabc", 3, 2..10));
+/// ```
+pub fn kasiski(ciphertext: &[u8], len: usize, keysizes: Range<usize>) -> Vec<(usize, usize)> {
+    let mut votes: BTreeMap<usize, usize> = BTreeMap::new();
+    for positions in sequence_positions(ciphertext, len).values().filter(|positions| positions.len() >= 3) {
+        let divisor = positions.windows(2).map(|pair| pair[1] - pair[0]).fold(0, gcd);
+        if keysizes.contains(&divisor) {
+            *votes.entry(divisor).or_default() += 1;
+        }
+    }
+    let mut proposals: Vec<(usize, usize)> = votes.into_iter().collect();
+    proposals.sort_by_key(|&(_, votes)| Reverse(votes));
+    proposals
+}
+
+/// Opposite of how much more often the key size divides the repeated sequences spacings than chance
+///
+/// A key size k divides a random spacing once in k, and almost all the
+/// spacings of sequences repeated by the key. Multiples of the key size
+/// score as well, and are ranked after their divisors like with
+/// `ColumnCoincidence`.
+pub struct Kasiski {
+    /// Length of the repeated sequences
+    pub len: usize,
+}
+
+impl KeysizeEstimator for Kasiski {
+    fn name(&self) -> &str {
+        "kasiski"
+    }
+
+    fn score(&self, ciphertext: &[u8], keysize: usize) -> Option<f64> {
+        if keysize == 0 {
+            return None;
+        }
+        let spacings = repeated_spacings(ciphertext, self.len);
+        if spacings.is_empty() {
+            return None;
+        }
+        let divided = spacings.iter().filter(|&&spacing| spacing % keysize == 0).count();
+        Some(-(divided as f64 / spacings.len() as f64) * keysize as f64)
+    }
+
+    fn rank(&self, ciphertext: &[u8], keysizes: Range<usize>) -> Vec<(usize, f64)> {
+        collapse_multiples(&sorted_scores(self, ciphertext, keysizes), MULTIPLE_TOLERANCE)
+    }
+}
+
+/// Return all the available key size estimators
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::keysize;
+///
+/// for estimator in keysize::estimators() {
+///     println!("{}", estimator.name());
+/// }
+/// ```
+pub fn estimators() -> Vec<Box<dyn KeysizeEstimator>> {
+    vec![
+        Box::new(FirstBlocksHamming),
+        Box::new(AverageBlocksHamming { blocks: 4 }),
+        Box::new(ColumnCoincidence),
+        Box::new(Kasiski { len: 3 }),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn too_short() {
+        assert_eq!(None, FirstBlocksHamming.score(b"abc", 2));
+        assert_eq!(None, AverageBlocksHamming { blocks: 4 }.score(b"abcdefg", 2));
+        assert_eq!(None, FirstBlocksHamming.score(b"abc", 0));
+    }
+
+    #[test]
+    fn first_blocks_score() {
+        assert_eq!(Some(37.0 / 14.0), FirstBlocksHamming.score(b"this is a testwokka wokka!!!", 14));
+    }
+
+    #[test]
+    fn rank_sorted() {
+        let ranking = AverageBlocksHamming { blocks: 4 }.rank(&[0u8; 40], 2..11);
+        assert_eq!(9, ranking.len());
+        assert!(ranking.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn coincidence_finds_keysize() {
+        let plaintext = crate::scoring::ENGLISH_SAMPLES.join(" ");
+        let ciphertext = plaintext.as_bytes().xor_repeating(b"Terminator");
+        let ranking = ColumnCoincidence.rank(&ciphertext, 2..40);
+        assert_eq!(10, ranking[0].0);
+        assert_eq!(None, ColumnCoincidence.score(b"abc", 2));
+    }
+
+    #[test]
+    fn kasiski_finds_keysize() {
+        let plaintext = crate::scoring::ENGLISH_SAMPLES.join(" ");
+        let ciphertext = plaintext.as_bytes().xor_repeating(b"ICE");
+        assert_eq!(3, kasiski(&ciphertext, 3, 2..40)[0].0);
+        assert_eq!(3, Kasiski { len: 3 }.rank(&ciphertext, 2..40)[0].0);
+        assert_eq!(None, Kasiski { len: 3 }.score(b"abcdef", 2));
+    }
+
+    #[test]
+    fn average_finds_keysize() {
+        let ciphertext = [b'a'; 40].xor_repeating(b"ICE");
+        let ranking = AverageBlocksHamming { blocks: 4 }.rank(&ciphertext, 2..8);
+        assert_eq!((3, 0.0), ranking[0]);
+    }
+}
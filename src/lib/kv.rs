@@ -0,0 +1,195 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Structured cookie (key=value) parsing and encoding
+
+use std::error;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Parse a `key=value` string with fields separated by `separator`
+///
+/// Keys must be non-empty and made of ASCII alphanumeric characters or
+/// underscores. A plain value may not contain `=` or `"`; a value between
+/// double quotes may contain anything, the separator included, with `\"`
+/// and `\\` standing for a quote and a backslash.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::kv;
+///
+/// let fields = kv::parse("foo=bar&baz=qux&zap=zazzle", '&').unwrap();
+/// assert_eq!(vec![("foo".to_owned(), "bar".to_owned()),
+///                 ("baz".to_owned(), "qux".to_owned()),
+///                 ("zap".to_owned(), "zazzle".to_owned())], fields);
+/// assert!(kv::parse("foo=bar=baz", '&').is_err());
+///
+/// let fields = kv::parse(r#"email="a;b=c";role="say \"hi\"""#, ';').unwrap();
+/// assert_eq!(Some("a;b=c"), kv::get(&fields, "email"));
+/// assert_eq!(Some(r#"say "hi""#), kv::get(&fields, "role"));
+/// ```
+pub fn parse(input: &str, separator: char) -> Result<Vec<(String, String)>> {
+    let mut fields = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && c != separator) {
+            key.push(c);
+        }
+        if chars.next() != Some('=') || !is_valid_key(&key) {
+            return Err(format!("invalid key '{}'", key).into());
+        }
+
+        let value = if chars.next_if_eq(&'"').is_some() {
+            parse_quoted(&mut chars, &key)?
+        } else {
+            let mut value = String::new();
+            while let Some(c) = chars.next_if(|&c| c != separator) {
+                if c == '=' || c == '"' {
+                    return Err(format!("unquoted '{}' in the value of '{}'", c, key).into());
+                }
+                value.push(c);
+            }
+            value
+        };
+        match chars.next() {
+            None => {
+                fields.push((key, value));
+                return Ok(fields);
+            }
+            Some(c) if c == separator => fields.push((key, value)),
+            Some(c) => return Err(format!("unexpected '{}' after the value of '{}'", c, key).into()),
+        }
+    }
+}
+
+/// Read a quoted value up to its closing quote, the opening one already read
+fn parse_quoted<I: Iterator<Item = char>>(chars: &mut I, key: &str) -> Result<String> {
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(value),
+            Some('\\') => match chars.next() {
+                Some(c @ ('"' | '\\')) => value.push(c),
+                Some(c) => return Err(format!("invalid escape '\\{}' in the value of '{}'", c, key).into()),
+                None => return Err(format!("unterminated value of '{}'", key).into()),
+            },
+            Some(c) => value.push(c),
+            None => return Err(format!("unterminated value of '{}'", key).into()),
+        }
+    }
+}
+
+/// Encode fields as `key=value` separated by `separator`, the inverse of `parse`
+///
+/// Values containing the separator, `=`, `"` or `\` are quoted and escaped,
+/// so a value can't inject fields of its own. Keys are written as they are.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::kv;
+///
+/// let fields = vec![("email".to_owned(), "a;role=admin".to_owned()), ("role".to_owned(), "user".to_owned())];
+/// assert_eq!(r#"email="a;role=admin";role=user"#, kv::encode(&fields, ';'));
+/// ```
+pub fn encode(fields: &[(String, String)], separator: char) -> String {
+    fields.iter()
+        .map(|(key, value)| {
+            if value.contains(|c: char| c == separator || c == '=' || c == '"' || c == '\\') {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                format!("{}=\"{}\"", key, escaped)
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&separator.to_string())
+}
+
+/// Look up the value of a key in parsed fields, the last occurrence wins
+pub fn get<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields.iter().rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_semicolons() {
+        let fields = parse("comment1=cooking%20MCs;userdata=x;admin=true", ';').unwrap();
+        assert_eq!(3, fields.len());
+        assert_eq!(Some("true"), get(&fields, "admin"));
+        assert_eq!(None, get(&fields, "role"));
+    }
+
+    #[test]
+    fn parse_empty_value() {
+        assert_eq!(vec![("a".to_owned(), "".to_owned())], parse("a=", '&').unwrap());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("", '&').is_err());
+        assert!(parse("=b", '&').is_err());
+        assert!(parse("a=b;;c=d", ';').is_err());
+        assert!(parse("a b=c", '&').is_err());
+    }
+
+    #[test]
+    fn parse_quoted_values() {
+        let fields = parse(r#"comment1=x;userdata="a;admin=true";comment2="""#, ';').unwrap();
+        assert_eq!(Some("a;admin=true"), get(&fields, "userdata"));
+        assert_eq!(Some(""), get(&fields, "comment2"));
+        assert_eq!(None, get(&fields, "admin"));
+        let fields = parse(r#"a="back\\slash \"quoted\"""#, ';').unwrap();
+        assert_eq!(Some(r#"back\slash "quoted""#), get(&fields, "a"));
+    }
+
+    #[test]
+    fn parse_quoting_errors() {
+        assert!(parse(r#"a="unterminated"#, ';').is_err());
+        assert!(parse(r#"a="ends with \"#, ';').is_err());
+        assert!(parse(r#"a="bad \n escape""#, ';').is_err());
+        assert!(parse(r#"a="x"y;b=c"#, ';').is_err());
+        assert!(parse(r#"a=x"y"#, ';').is_err());
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let fields: Vec<(String, String)> = ["plain", "", "a;b=c", r#"q"uote"#, r"back\slash", "\"", "é"]
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (format!("k{}", index), value.to_string()))
+            .collect();
+        let encoded = encode(&fields, ';');
+        assert!(encoded.starts_with("k0=plain;k1=;k2=\"a;b=c\";"));
+        assert_eq!(fields, parse(&encoded, ';').unwrap());
+    }
+
+    #[test]
+    fn last_wins() {
+        let fields = parse("role=user&role=admin", '&').unwrap();
+        assert_eq!(Some("admin"), get(&fields, "role"));
+    }
+}
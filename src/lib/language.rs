@@ -0,0 +1,124 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Languages of XOR'd plaintexts and their letter frequency tables
+//!
+//! Every language has a Project Gutenberg book as corpus, whose ASCII letter
+//! frequencies are computed like the English ones by `english::calc_frequencies`.
+//! Accented letters are skipped, like any other non-ASCII character.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::english::{self, CorpusSource};
+use crate::error::{CryptopalsError, Result};
+
+/// Language of a plaintext
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+/// All the supported languages
+pub const LANGUAGES: &[Language] = &[Language::English, Language::French, Language::German, Language::Spanish];
+
+impl Language {
+    /// Lowercase English name of the language
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "english",
+            Language::French => "french",
+            Language::German => "german",
+            Language::Spanish => "spanish",
+        }
+    }
+
+    /// URL of the Project Gutenberg book used as corpus
+    pub fn corpus_url(self) -> &'static str {
+        match self {
+            // Alice's Adventures in Wonderland
+            Language::English => english::GUTENBERG_CORPUS_URL,
+            // Les Misérables, tome I
+            Language::French => "https://www.gutenberg.org/cache/epub/17489/pg17489.txt",
+            // Die Verwandlung
+            Language::German => "https://www.gutenberg.org/cache/epub/22367/pg22367.txt",
+            // Don Quijote
+            Language::Spanish => "https://www.gutenberg.org/cache/epub/2000/pg2000.txt",
+        }
+    }
+
+    /// Source of the corpus, for `english::get_english_frequency` and the others
+    pub fn corpus_source(self) -> CorpusSource {
+        CorpusSource::Gutenberg(self.corpus_url().to_owned())
+    }
+
+    /// Letter frequencies of the corpus, the sample of `CRYPTOPALS_CORPUS_SAMPLE` applies
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use cryptopals::language::Language;
+    ///
+    /// let french_frequency: Vec<f64> = Language::French.frequencies().unwrap();
+    /// ```
+    pub fn frequencies(self) -> Result<Vec<f64>> {
+        english::get_english_frequency(&self.corpus_source())
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Language {
+    type Err = CryptopalsError;
+
+    /// Parse the name of a language, in any case
+    fn from_str(name: &str) -> Result<Language> {
+        LANGUAGES.iter()
+            .find(|language| language.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| CryptopalsError::Format(format!("unknown language {}", name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto;
+
+    #[test]
+    fn names() {
+        for &language in LANGUAGES {
+            assert_eq!(Ok(language), language.to_string().parse::<Language>().map_err(|e| e.to_string()));
+        }
+        assert_eq!(Language::German, "German".parse().unwrap());
+        assert!("klingon".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn french_table() {
+        let french = english::calc_frequencies("Longtemps, je me suis couché de bonne heure. Parfois, à peine \
+            ma bougie éteinte, mes yeux se fermaient si vite que je n'avais pas le temps de me dire : « Je m'endors. »");
+        let ciphertext: Vec<u8> = "Une vie pour une vie".bytes().map(|byte| byte ^ 0x3c).collect();
+        let (plaintext, key, _, _) = crypto::decrypt_english(&ciphertext, &french);
+        assert_eq!(0x3c, key);
+        assert_eq!("Une vie pour une vie", plaintext);
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Lattice basis reduction
+//!
+//! LLL turns a basis of a lattice into one of short, nearly orthogonal vectors.
+//! This is the integral version of Cohen's "A Course in Computational Algebraic
+//! Number Theory" (algorithm 2.6.7): the Gram-Schmidt coefficients are kept as
+//! integers d_i and λ_ij scaled by the Gram determinants, so that no rational
+//! arithmetic and no floating point precision is needed.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+
+/// A lattice vector, or a row of a basis
+pub type Vector = Vec<BigInt>;
+
+fn dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// a / b rounded to the nearest integer, with b > 0
+fn round_div(a: &BigInt, b: &BigInt) -> BigInt {
+    // floor((2a + b) / 2b), BigInt division truncating towards zero
+    let numerator = a * 2u32 + b;
+    let denominator = b * 2u32;
+    let quotient = &numerator / &denominator;
+    if numerator.is_negative() && !(&numerator % &denominator).is_zero() {
+        quotient - 1u32
+    } else {
+        quotient
+    }
+}
+
+/// Reduction state: the basis, the Gram determinants d and the scaled coefficients λ
+struct Reduction {
+    basis: Vec<Vector>,
+    // d[0] = 1 and d[i + 1] is the determinant of the Gram matrix of the first i + 1 vectors
+    d: Vec<BigInt>,
+    lambda: Vec<Vec<BigInt>>,
+}
+
+impl Reduction {
+    /// Size-reduce b_k with b_l
+    fn reduce(&mut self, k: usize, l: usize) {
+        if (&self.lambda[k][l] * 2u32).abs() <= self.d[l + 1] {
+            return;
+        }
+        let q = round_div(&self.lambda[k][l], &self.d[l + 1]);
+        for i in 0..self.basis[k].len() {
+            let delta = &q * &self.basis[l][i];
+            self.basis[k][i] -= delta;
+        }
+        let delta = &q * &self.d[l + 1];
+        self.lambda[k][l] -= delta;
+        for i in 0..l {
+            let delta = &q * &self.lambda[l][i];
+            self.lambda[k][i] -= delta;
+        }
+    }
+
+    /// Swap b_k and b_(k-1), updating d and λ
+    fn swap(&mut self, k: usize, k_max: usize) {
+        self.basis.swap(k, k - 1);
+        for j in 0..(k - 1) {
+            let tmp = std::mem::take(&mut self.lambda[k][j]);
+            self.lambda[k][j] = std::mem::replace(&mut self.lambda[k - 1][j], tmp);
+        }
+        let lambda = self.lambda[k][k - 1].clone();
+        let b = (&self.d[k - 1] * &self.d[k + 1] + &lambda * &lambda) / &self.d[k];
+        for i in (k + 1)..=k_max {
+            let t = self.lambda[i][k].clone();
+            self.lambda[i][k] = (&self.d[k + 1] * &self.lambda[i][k - 1] - &lambda * &t) / &self.d[k];
+            self.lambda[i][k - 1] = (&b * &t + &lambda * &self.lambda[i][k]) / &self.d[k + 1];
+        }
+        self.d[k] = b;
+    }
+}
+
+/// LLL-reduce a basis of linearly independent vectors, with δ = 3/4
+///
+/// # Panics:
+///
+/// The function panics if the vectors are linearly dependent.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::lattice;
+/// use num_bigint::BigInt;
+///
+/// let basis: Vec<Vec<BigInt>> = vec![vec![1, 1, 1], vec![-1, 0, 2], vec![3, 5, 6]].into_iter()
+///     .map(|row| row.into_iter().map(BigInt::from).collect())
+///     .collect();
+/// let reduced: Vec<Vec<BigInt>> = vec![vec![0, 1, 0], vec![1, 0, 1], vec![-1, 0, 2]].into_iter()
+///     .map(|row| row.into_iter().map(BigInt::from).collect())
+///     .collect();
+/// assert_eq!(reduced, lattice::lll(basis));
+/// ```
+pub fn lll(basis: Vec<Vector>) -> Vec<Vector> {
+    let n = basis.len();
+    if n < 2 {
+        return basis;
+    }
+    let d0 = dot(&basis[0], &basis[0]);
+    let mut state = Reduction {
+        basis,
+        d: vec![BigInt::from(1), d0],
+        lambda: vec![vec![BigInt::zero(); n]; n],
+    };
+    state.d.resize(n + 1, BigInt::zero());
+
+    let mut k = 1;
+    let mut k_max = 0;
+    while k < n {
+        if k > k_max {
+            // Incremental Gram-Schmidt
+            k_max = k;
+            for j in 0..=k {
+                let mut u = dot(&state.basis[k], &state.basis[j]);
+                for i in 0..j {
+                    u = (&state.d[i + 1] * u - &state.lambda[k][i] * &state.lambda[j][i]) / &state.d[i];
+                }
+                if j < k {
+                    state.lambda[k][j] = u;
+                } else {
+                    assert!(!u.is_zero(), "the basis vectors are linearly dependent");
+                    state.d[k + 1] = u;
+                }
+            }
+        }
+        state.reduce(k, k - 1);
+        // Lovász condition 4 d_k d_(k-2) >= 3 d_(k-1)^2 - 4 λ_(k,k-1)^2, shifted by one
+        let lambda = &state.lambda[k][k - 1];
+        if &state.d[k + 1] * &state.d[k - 1] * 4u32 < &state.d[k] * &state.d[k] * 3u32 - lambda * lambda * 4u32 {
+            state.swap(k, k_max);
+            k = (k - 1).max(1);
+        } else {
+            for l in (0..(k - 1)).rev() {
+                state.reduce(k, l);
+            }
+            k += 1;
+        }
+    }
+    state.basis
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vector(values: &[i64]) -> Vector {
+        values.iter().map(|&v| BigInt::from(v)).collect()
+    }
+
+    #[test]
+    fn rounding() {
+        let round = |a: i64, b: i64| round_div(&BigInt::from(a), &BigInt::from(b));
+        assert_eq!(BigInt::from(2), round(7, 3));
+        assert_eq!(BigInt::from(-2), round(-7, 3));
+        assert_eq!(BigInt::from(3), round(5, 2));
+        assert_eq!(BigInt::from(-2), round(-5, 2));
+        assert_eq!(BigInt::from(0), round(0, 5));
+    }
+
+    #[test]
+    fn already_reduced() {
+        let basis = vec![vector(&[1, 0]), vector(&[0, 1])];
+        assert_eq!(basis.clone(), lll(basis));
+    }
+
+    #[test]
+    fn finds_short_vector() {
+        // The lattice of (x, y) with x = 9y mod 1009 contains (9, 1)
+        let reduced = lll(vec![vector(&[1009, 0]), vector(&[-1000, 1])]);
+        assert_eq!(BigInt::from(82), dot(&reduced[0], &reduced[0]));
+    }
+
+    #[test]
+    #[should_panic = "linearly dependent"]
+    fn dependent_vectors() {
+        lll(vec![vector(&[1, 2]), vector(&[2, 4])]);
+    }
+}
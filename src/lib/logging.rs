@@ -0,0 +1,74 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Logging of the library, through `env_logger`
+//!
+//! `RUST_LOG` is always honored, and a verbosity, e.g. the number of `-v`
+//! on the command line, raises the level of the `cryptopals` messages on top
+//! of it.
+
+use log::LevelFilter;
+
+/// Module whose level the verbosity sets
+const LOG_MODULE: &str = "cryptopals";
+
+/// Level of the `cryptopals` messages for a verbosity, `None` to leave it to `RUST_LOG`
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::logging;
+/// use log::LevelFilter;
+///
+/// assert_eq!(None, logging::level(0));
+/// assert_eq!(Some(LevelFilter::Debug), logging::level(2));
+/// ```
+pub fn level(verbosity: u8) -> Option<LevelFilter> {
+    match verbosity {
+        0 => None,
+        1 => Some(LevelFilter::Info),
+        2 => Some(LevelFilter::Debug),
+        _ => Some(LevelFilter::Trace),
+    }
+}
+
+/// Log to the standard error with `RUST_LOG`, showing more messages as `verbosity` grows
+///
+/// 1 displays the info messages, 2 the debug messages, e.g. the candidates
+/// of `decrypt_text`, and 3 or more the trace messages. Only the first call
+/// has an effect.
+pub fn init(verbosity: u8) {
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(level) = level(verbosity) {
+        logger.filter_module(LOG_MODULE, level);
+    }
+    // A logger is already set, e.g. by an earlier call
+    let _ = logger.try_init();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(Some(LevelFilter::Info), level(1));
+        assert_eq!(Some(LevelFilter::Trace), level(3));
+        assert_eq!(Some(LevelFilter::Trace), level(u8::MAX));
+        // Initializing twice is harmless
+        init(0);
+        init(2);
+    }
+}
@@ -0,0 +1,708 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Number theory: exponentiation, extended GCD, inverses, CRT, roots and primes
+//!
+//! The RSA, DH and elliptic curve code all build on these functions.
+//!
+//! Montgomery multiplication replaces the division of each modular reduction
+//! by shifts and masks, which pays off when thousands of multiplications are
+//! performed with the same modulus, as in an exponentiation.
+
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use num_traits::{One, Signed, Zero};
+use rand::Rng;
+
+/// Multiply two u128 into a 256 bits result, returned as (high, low)
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (middle << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Add two numbers modulo `modulus` without overflowing
+fn addmod_u128(a: u128, b: u128, modulus: u128) -> u128 {
+    if a >= modulus - b {
+        a - (modulus - b)
+    } else {
+        a + b
+    }
+}
+
+/// Multiply two numbers modulo `modulus` by doubling and adding, without overflowing
+pub fn mulmod_u128(a: u128, b: u128, modulus: u128) -> u128 {
+    let mut a = a % modulus;
+    let mut b = b % modulus;
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = addmod_u128(result, a, modulus);
+        }
+        a = addmod_u128(a, a, modulus);
+        b >>= 1;
+    }
+    result
+}
+
+/// Compute `base^exp mod modulus` with square and multiply
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+///
+/// assert_eq!(445, math::modexp_u128(4, 13, 497));
+/// ```
+pub fn modexp_u128(base: u128, exp: u128, modulus: u128) -> u128 {
+    if modulus == 1 {
+        return 0;
+    }
+    let mut base = base % modulus;
+    let mut exp = exp;
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mulmod_u128(result, base, modulus);
+        }
+        base = mulmod_u128(base, base, modulus);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Compute `base^exp mod modulus` with square and multiply
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigUint;
+///
+/// let result = math::modexp(&BigUint::from(4u32), &BigUint::from(13u32), &BigUint::from(497u32));
+/// assert_eq!(BigUint::from(445u32), result);
+/// ```
+pub fn modexp(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    if modulus.is_one() {
+        return BigUint::zero();
+    }
+    let mut base = base % modulus;
+    let mut result = BigUint::one();
+    for i in 0..exp.bits() {
+        if exp.bit(i) {
+            result = result * &base % modulus;
+        }
+        base = &base * &base % modulus;
+    }
+    result
+}
+
+/// Extended Euclid: (g, x, y) with g = gcd(a, b) = a·x + b·y and g ≥ 0
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigInt;
+///
+/// let (g, x, y) = math::egcd(&BigInt::from(240), &BigInt::from(46));
+/// assert_eq!(BigInt::from(2), g);
+/// assert_eq!(g, 240 * x + 46 * y);
+/// ```
+pub fn egcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_x, mut x) = (BigInt::one(), BigInt::zero());
+    let (mut old_y, mut y) = (BigInt::zero(), BigInt::one());
+    while !r.is_zero() {
+        let quotient = &old_r / &r;
+        let next_r = &old_r - &quotient * &r;
+        old_r = std::mem::replace(&mut r, next_r);
+        let next_x = &old_x - &quotient * &x;
+        old_x = std::mem::replace(&mut x, next_x);
+        let next_y = &old_y - &quotient * &y;
+        old_y = std::mem::replace(&mut y, next_y);
+    }
+    if old_r.is_negative() {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Modular inverse of `a`, if `a` and `modulus` are coprime
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigUint;
+///
+/// assert_eq!(Some(BigUint::from(2753u32)), math::modinv(&BigUint::from(17u32), &BigUint::from(3120u32)));
+/// assert_eq!(None, math::modinv(&BigUint::from(6u32), &BigUint::from(9u32)));
+/// ```
+pub fn modinv(a: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    let modulus = BigInt::from(modulus.clone());
+    let (g, x, _) = egcd(&(BigInt::from(a.clone()) % &modulus), &modulus);
+    if !g.is_one() {
+        return None;
+    }
+    (((x % &modulus) + &modulus) % &modulus).to_biguint()
+}
+
+/// Integer k-th root: the largest r with r^k ≤ n, and whether r^k = n
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigUint;
+///
+/// assert_eq!((BigUint::from(3u32), true), math::nth_root(&BigUint::from(27u32), 3));
+/// assert_eq!((BigUint::from(3u32), false), math::nth_root(&BigUint::from(63u32), 3));
+/// ```
+///
+/// # Panics:
+///
+/// The function panics if `k` is 0.
+pub fn nth_root(n: &BigUint, k: u32) -> (BigUint, bool) {
+    let root = n.nth_root(k);
+    let exact = root.pow(k) == *n;
+    (root, exact)
+}
+
+/// Solve `x = residue mod modulus` for all the (residue, modulus) pairs
+///
+/// Returns x and the product of the moduli, or `None` if the moduli are not
+/// pairwise coprime.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigUint;
+///
+/// let congruences: Vec<(BigUint, BigUint)> = [(2u32, 3u32), (3, 5), (2, 7)].iter()
+///     .map(|&(r, m)| (BigUint::from(r), BigUint::from(m)))
+///     .collect();
+/// assert_eq!(Some((BigUint::from(23u32), BigUint::from(105u32))), math::crt(&congruences));
+/// ```
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<(BigUint, BigUint)> {
+    let mut x = BigUint::zero();
+    let mut product = BigUint::one();
+    for (residue, modulus) in congruences {
+        // x + product * k = residue mod modulus
+        let inverse = modinv(&(&product % modulus), modulus)?;
+        let delta = (residue % modulus + modulus - &x % modulus) % modulus;
+        x += &product * (delta * inverse % modulus);
+        product *= modulus;
+    }
+    Some((x, product))
+}
+
+/// Square root of `a` modulo an odd prime `p`, with Tonelli-Shanks
+///
+/// Returns one of the two roots, or `None` if `a` is not a quadratic residue.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::math;
+/// use num_bigint::BigUint;
+///
+/// let p = BigUint::from(13u32);
+/// let root = math::sqrt_mod(&BigUint::from(10u32), &p).unwrap();
+/// assert_eq!(BigUint::from(10u32), &root * &root % &p);
+/// assert_eq!(None, math::sqrt_mod(&BigUint::from(5u32), &p));
+/// ```
+pub fn sqrt_mod(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if a.is_zero() {
+        return Some(a);
+    }
+    let one = BigUint::one();
+    let p_minus_1 = p - 1u32;
+    let half = &p_minus_1 >> 1;
+    // Euler's criterion
+    if a.modpow(&half, p) != one {
+        return None;
+    }
+
+    // p - 1 = q * 2^s with q odd, and z any quadratic non-residue
+    let s = p_minus_1.trailing_zeros().unwrap();
+    let q = &p_minus_1 >> s;
+    let mut z = BigUint::from(2u32);
+    while z.modpow(&half, p) != p_minus_1 {
+        z += 1u32;
+    }
+
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = a.modpow(&q, p);
+    let mut root = a.modpow(&((&q + 1u32) >> 1), p);
+    while t != one {
+        // Smallest i with t^(2^i) = 1
+        let mut i = 0;
+        let mut t_square = t.clone();
+        while t_square != one {
+            t_square = &t_square * &t_square % p;
+            i += 1;
+        }
+        let b = c.modpow(&(BigUint::one() << (m - i - 1)), p);
+        m = i;
+        c = &b * &b % p;
+        t = t * &c % p;
+        root = root * b % p;
+    }
+    Some(root)
+}
+
+/// All the primes below `bound`, with the sieve of Eratosthenes
+pub fn primes_below(bound: u64) -> Vec<u64> {
+    let mut is_prime = vec![true; bound as usize];
+    let mut primes = Vec::new();
+    for n in 2..bound {
+        if is_prime[n as usize] {
+            primes.push(n);
+            for multiple in (n * n..bound).step_by(n as usize) {
+                is_prime[multiple as usize] = false;
+            }
+        }
+    }
+    primes
+}
+
+/// Distinct prime factors of `n` below `bound`, found by trial division
+pub fn small_factors(n: &BigUint, bound: u64) -> Vec<u64> {
+    primes_below(bound).into_iter()
+        .filter(|&prime| (n % prime).is_zero())
+        .collect()
+}
+
+/// Miller-Rabin primality test with `rounds` random bases
+///
+/// A composite number passes each round with a probability of at most 1/4.
+pub fn is_probable_prime(n: &BigUint, rounds: usize) -> bool {
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    for prime in primes_below(100) {
+        if (n % prime).is_zero() {
+            return *n == BigUint::from(prime);
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_1 = n - 1u32;
+    let s = n_minus_1.trailing_zeros().unwrap();
+    let d = &n_minus_1 >> s;
+    let mut rng = rand::thread_rng();
+    'witness: for _ in 0..rounds {
+        let a = rng.gen_biguint_range(&two, &n_minus_1);
+        let mut x = a.modpow(&d, n);
+        if x.is_one() || x == n_minus_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Random prime of exactly `bits` bits
+///
+/// # Panics:
+///
+/// The function panics if `bits` is smaller than 2.
+pub fn random_prime(bits: u64) -> BigUint {
+    assert!(bits >= 2, "primes have at least 2 bits");
+    let mut rng = rand::thread_rng();
+    loop {
+        let mut candidate = rng.gen_biguint(bits);
+        candidate.set_bit(bits - 1, true);
+        if is_probable_prime(&candidate, 40) {
+            return candidate;
+        }
+    }
+}
+
+/// Random prime p of exactly `bits` bits with p - 1 twice a product of distinct primes below `factor_bound`
+///
+/// The primes in `avoid` are not used, so that two such primes only share the
+/// factor 2. Returns p and the odd prime factors of p - 1.
+///
+/// # Panics:
+///
+/// The function panics if there are no odd primes below `factor_bound` outside `avoid`.
+pub fn random_smooth_prime(bits: u64, factor_bound: u64, avoid: &[u64]) -> (BigUint, Vec<u64>) {
+    let primes: Vec<u64> = primes_below(factor_bound).into_iter()
+        .filter(|prime| *prime != 2 && !avoid.contains(prime))
+        .collect();
+    assert!(!primes.is_empty(), "no primes left to build p - 1");
+    let mut rng = rand::thread_rng();
+    loop {
+        let mut factors = Vec::new();
+        let mut smooth = BigUint::from(2u32);
+        while smooth.bits() < bits {
+            let prime = primes[rng.gen_range(0..primes.len())];
+            if !factors.contains(&prime) {
+                factors.push(prime);
+                smooth *= prime;
+            }
+        }
+        let p = smooth + 1u32;
+        if p.bits() == bits && is_probable_prime(&p, 40) {
+            return (p, factors);
+        }
+    }
+}
+
+/// Montgomery arithmetic modulo an odd u128, with R = 2^128
+#[derive(Debug, Clone, Copy)]
+pub struct MontgomeryU128 {
+    modulus: u128,
+    /// -modulus^-1 mod R
+    modulus_inv: u128,
+    /// R^2 mod modulus
+    r2: u128,
+}
+
+impl MontgomeryU128 {
+    /// Prepare the Montgomery constants of an odd modulus
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the modulus is even or equal to 1.
+    pub fn new(modulus: u128) -> MontgomeryU128 {
+        assert!(modulus & 1 == 1 && modulus > 1, "modulus must be odd and greater than 1");
+        // Newton iteration, each step doubles the number of correct low bits
+        let mut inv = modulus;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(modulus.wrapping_mul(inv)));
+        }
+        let r = (u128::MAX % modulus + 1) % modulus;
+        MontgomeryU128 { modulus, modulus_inv: inv.wrapping_neg(), r2: mulmod_u128(r, r, modulus) }
+    }
+
+    /// Montgomery reduction of a 256 bits number: T * R^-1 mod modulus
+    fn reduce(&self, high: u128, low: u128) -> u128 {
+        let m = low.wrapping_mul(self.modulus_inv);
+        let (mn_high, mn_low) = mul_wide(m, self.modulus);
+        // low + mn_low is 0 mod R by construction, only its carry matters
+        let carry = (low.overflowing_add(mn_low).1) as u128;
+        let (t, overflow1) = high.overflowing_add(mn_high);
+        let (t, overflow2) = t.overflowing_add(carry);
+        if overflow1 || overflow2 || t >= self.modulus {
+            t.wrapping_sub(self.modulus)
+        } else {
+            t
+        }
+    }
+
+    /// Convert a number to its Montgomery form a*R mod modulus
+    pub fn to_montgomery(&self, a: u128) -> u128 {
+        let (high, low) = mul_wide(a % self.modulus, self.r2);
+        self.reduce(high, low)
+    }
+
+    /// Convert a number back from its Montgomery form
+    pub fn from_montgomery(&self, a: u128) -> u128 {
+        self.reduce(0, a)
+    }
+
+    /// Multiply two numbers in Montgomery form
+    pub fn mul(&self, a: u128, b: u128) -> u128 {
+        let (high, low) = mul_wide(a, b);
+        self.reduce(high, low)
+    }
+
+    /// Compute `base^exp mod modulus`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::math::MontgomeryU128;
+    ///
+    /// assert_eq!(445, MontgomeryU128::new(497).modexp(4, 13));
+    /// ```
+    pub fn modexp(&self, base: u128, exp: u128) -> u128 {
+        let mut base = self.to_montgomery(base);
+        let mut result = self.to_montgomery(1);
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        self.from_montgomery(result)
+    }
+}
+
+/// Montgomery arithmetic modulo an odd big integer, with R a power of 2^64
+///
+/// The temporary `BigUint` allocations eat most of the gain here: `BigUint::modpow`,
+/// which works on the limbs directly, stays faster. See `cargo bench --bench modexp`.
+#[derive(Debug, Clone)]
+pub struct Montgomery {
+    modulus: BigUint,
+    /// -modulus^-1 mod R
+    modulus_inv: BigUint,
+    /// R^2 mod modulus
+    r2: BigUint,
+    /// R - 1
+    mask: BigUint,
+    r_bits: u64,
+}
+
+impl Montgomery {
+    /// Prepare the Montgomery constants of an odd modulus
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the modulus is even or equal to 1.
+    pub fn new(modulus: &BigUint) -> Montgomery {
+        assert!(modulus.bit(0) && !modulus.is_one(), "modulus must be odd and greater than 1");
+        let r_bits = modulus.bits().div_ceil(64) * 64;
+        let r = BigUint::one() << r_bits;
+        let mask = &r - 1u32;
+        // Newton iteration, each step doubles the number of correct low bits
+        let mut inv = BigUint::one();
+        let mut correct_bits = 1;
+        while correct_bits < r_bits {
+            let product = (modulus * &inv) & &mask;
+            inv = (&inv * ((&r + 2u32 - product) & &mask)) & &mask;
+            correct_bits *= 2;
+        }
+        let modulus_inv = (&r - inv) & &mask;
+        let r2 = (&r * &r) % modulus;
+        Montgomery { modulus: modulus.clone(), modulus_inv, r2, mask, r_bits }
+    }
+
+    /// Montgomery reduction: t * R^-1 mod modulus
+    fn reduce(&self, t: BigUint) -> BigUint {
+        let m = ((&t & &self.mask) * &self.modulus_inv) & &self.mask;
+        let result = (t + m * &self.modulus) >> self.r_bits;
+        if result >= self.modulus {
+            result - &self.modulus
+        } else {
+            result
+        }
+    }
+
+    /// Convert a number to its Montgomery form a*R mod modulus
+    pub fn to_montgomery(&self, a: &BigUint) -> BigUint {
+        self.reduce((a % &self.modulus) * &self.r2)
+    }
+
+    /// Convert a number back from its Montgomery form
+    pub fn from_montgomery(&self, a: &BigUint) -> BigUint {
+        self.reduce(a.clone())
+    }
+
+    /// Multiply two numbers in Montgomery form
+    pub fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        self.reduce(a * b)
+    }
+
+    /// Compute `base^exp mod modulus`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::math::Montgomery;
+    /// use num_bigint::BigUint;
+    ///
+    /// let montgomery = Montgomery::new(&BigUint::from(497u32));
+    /// assert_eq!(BigUint::from(445u32), montgomery.modexp(&BigUint::from(4u32), &BigUint::from(13u32)));
+    /// ```
+    pub fn modexp(&self, base: &BigUint, exp: &BigUint) -> BigUint {
+        let mut base = self.to_montgomery(base);
+        let mut result = self.to_montgomery(&BigUint::one());
+        for i in 0..exp.bits() {
+            if exp.bit(i) {
+                result = self.mul(&result, &base);
+            }
+            base = self.mul(&base, &base);
+        }
+        self.from_montgomery(&result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const P64: u128 = 0xffff_ffff_ffff_ffc5;
+    const P127: u128 = (1 << 127) - 1;
+
+    #[test]
+    fn wide_multiplication() {
+        assert_eq!((0, 6), mul_wide(2, 3));
+        assert_eq!((1, 0), mul_wide(1 << 64, 1 << 64));
+        assert_eq!((u128::MAX - 1, 1), mul_wide(u128::MAX, u128::MAX));
+    }
+
+    #[test]
+    fn mulmod_large() {
+        assert_eq!(1, mulmod_u128(u128::MAX - 1, u128::MAX - 1, u128::MAX));
+        assert_eq!(P64 - 1, mulmod_u128(P64 - 1, 1, P64));
+    }
+
+    #[test]
+    fn modexp_u128_fermat() {
+        assert_eq!(1, modexp_u128(3, P64 - 1, P64));
+        assert_eq!(1, modexp_u128(3, P127 - 1, P127));
+        assert_eq!(0, modexp_u128(3, 5, 1));
+        assert_eq!(1, modexp_u128(3, 0, 7));
+    }
+
+    #[test]
+    fn montgomery_u128_matches_naive() {
+        for &modulus in &[3, 497, P64, P127, u128::MAX] {
+            let montgomery = MontgomeryU128::new(modulus);
+            for &(base, exp) in &[(0, 5), (1, 0), (2, 1), (12345, 678), (u128::MAX - 7, P64), (P127 + 11, u128::MAX)] {
+                assert_eq!(modexp_u128(base, exp, modulus), montgomery.modexp(base, exp),
+                           "{}^{} mod {}", base, exp, modulus);
+            }
+        }
+    }
+
+    #[test]
+    fn montgomery_biguint_matches_modpow() {
+        let modulus = crate::params::dh::nist_1536();
+        let montgomery = Montgomery::new(&modulus);
+        let base = BigUint::from(2u32);
+        let exp = BigUint::parse_bytes(b"123456789abcdef0123456789abcdef0123456789abcdef", 16).unwrap();
+        assert_eq!(base.modpow(&exp, &modulus), montgomery.modexp(&base, &exp));
+        assert_eq!(base.modpow(&exp, &modulus), modexp(&base, &exp, &modulus));
+        assert_eq!(BigUint::one(), montgomery.modexp(&base, &BigUint::zero()));
+    }
+
+    #[test]
+    fn montgomery_biguint_small() {
+        let modulus = BigUint::from(P64);
+        let montgomery = Montgomery::new(&modulus);
+        let a = BigUint::from(P64 - 2);
+        assert_eq!(a.modpow(&a, &modulus), montgomery.modexp(&a, &a));
+    }
+
+    fn congruences(pairs: &[(u32, u32)]) -> Vec<(BigUint, BigUint)> {
+        pairs.iter().map(|&(r, m)| (BigUint::from(r), BigUint::from(m))).collect()
+    }
+
+    #[test]
+    fn modinv_values() {
+        assert_eq!(Some(BigUint::from(4u32)), modinv(&BigUint::from(3u32), &BigUint::from(11u32)));
+        assert_eq!(Some(BigUint::zero()), modinv(&BigUint::from(5u32), &BigUint::one()));
+        assert_eq!(None, modinv(&BigUint::zero(), &BigUint::from(7u32)));
+        let p = BigUint::from(P127);
+        let a = BigUint::from(123456789u32);
+        assert_eq!(BigUint::one(), &a * modinv(&a, &p).unwrap() % &p);
+    }
+
+    #[test]
+    fn egcd_signs() {
+        for &(a, b) in &[(0, 5), (5, 0), (-12, 18), (12, -18), (17, 3120), (0, 0)] {
+            let (a, b) = (BigInt::from(a), BigInt::from(b));
+            let (g, x, y) = egcd(&a, &b);
+            assert!(!g.is_negative());
+            assert_eq!(g, &a * x + &b * y);
+        }
+        assert_eq!(BigInt::from(6), egcd(&BigInt::from(-12), &BigInt::from(18)).0);
+    }
+
+    #[test]
+    fn nth_roots() {
+        let big = BigUint::from(12345678901234567890u64);
+        assert_eq!((big.clone(), true), nth_root(&big.pow(3), 3));
+        assert_eq!((big.clone(), false), nth_root(&(big.pow(3) + 1u32), 3));
+        assert_eq!((&big - 1u32, false), nth_root(&(big.pow(3) - 1u32), 3));
+        assert_eq!((BigUint::zero(), true), nth_root(&BigUint::zero(), 5));
+    }
+
+    #[test]
+    fn crt_values() {
+        assert_eq!(Some((BigUint::zero(), BigUint::one())), crt(&[]));
+        assert_eq!(Some((BigUint::from(39u32), BigUint::from(60u32))), crt(&congruences(&[(3, 4), (4, 5), (0, 3)])));
+        assert_eq!(None, crt(&congruences(&[(1, 4), (2, 6)])));
+    }
+
+    #[test]
+    fn square_roots() {
+        // p = 5 mod 8 and p = 1 mod 16, where Tonelli-Shanks loops
+        for &p in &[13u32, 17, 97, 1_000_000_007, 998_244_353] {
+            let p = BigUint::from(p);
+            for a in 0..50u32 {
+                let a = BigUint::from(a);
+                let residue = a.modpow(&((&p - 1u32) >> 1), &p) != &p - 1u32;
+                match sqrt_mod(&a, &p) {
+                    Some(root) => assert_eq!(&a % &p, &root * &root % &p),
+                    None => assert!(!residue, "{} has a root modulo {}", a, p),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_primes() {
+        assert_eq!(vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], primes_below(30));
+        assert_eq!(Vec::<u64>::new(), primes_below(2));
+        assert_eq!(vec![2, 3, 5], small_factors(&BigUint::from(2u32 * 2 * 3 * 5 * 1009), 1000));
+    }
+
+    #[test]
+    fn primality() {
+        assert!(is_probable_prime(&BigUint::from(P64), 20));
+        assert!(is_probable_prime(&BigUint::from(P127), 20));
+        assert!(is_probable_prime(&BigUint::from(97u32), 20));
+        // Carmichael number
+        assert!(!is_probable_prime(&BigUint::from(561u32), 20));
+        assert!(!is_probable_prime(&(BigUint::from(P64) * BigUint::from(P127)), 20));
+        assert!(!is_probable_prime(&BigUint::one(), 20));
+        let prime = random_prime(80);
+        assert_eq!(80, prime.bits());
+    }
+
+    #[test]
+    fn smooth_primes() {
+        let (p, factors) = random_smooth_prime(64, 1 << 8, &[3, 5, 7]);
+        assert_eq!(64, p.bits());
+        assert!(is_probable_prime(&p, 20));
+        assert!(factors.iter().all(|factor| ![2, 3, 5, 7].contains(factor)));
+        assert_eq!(p - 1u32, factors.iter().fold(BigUint::from(2u32), |product, factor| product * factor));
+    }
+
+    #[test]
+    #[should_panic]
+    fn montgomery_even_modulus() {
+        MontgomeryU128::new(10);
+    }
+}
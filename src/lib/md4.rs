@@ -0,0 +1,200 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! MD4 hash function, as described in RFC 1320
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// MD4 block size in bytes
+pub const BLOCK_SIZE: usize = 64;
+
+/// MD4 digest size in bytes
+pub const DIGEST_SIZE: usize = 16;
+
+/// Initial state, in the (A, B, C, D) order of the RFC
+pub const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Round 2 additive constant
+pub const ROUND2_CONSTANT: u32 = 0x5a827999;
+/// Round 3 additive constant
+pub const ROUND3_CONSTANT: u32 = 0x6ed9eba1;
+
+/// Rotation amounts of the four steps of each round
+pub const ROUND1_SHIFTS: [u32; 4] = [3, 7, 11, 19];
+pub const ROUND2_SHIFTS: [u32; 4] = [3, 5, 9, 13];
+pub const ROUND3_SHIFTS: [u32; 4] = [3, 9, 11, 15];
+
+const ROUND2_ORDER: [usize; 16] = [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+const ROUND3_ORDER: [usize; 16] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+/// Round 1 boolean function, selects `y` or `z` depending on `x`
+pub fn f(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (!x & z)
+}
+
+/// Round 2 boolean function, majority of the three bits
+pub fn g(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (x & z) | (y & z)
+}
+
+/// Round 3 boolean function, parity of the three bits
+pub fn h(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+/// Decode a 64-byte block into little-endian words
+///
+/// # Panics:
+///
+/// The function panics if the block is not 64 bytes long.
+pub fn words(block: &[u8]) -> [u32; 16] {
+    assert_eq!(BLOCK_SIZE, block.len(), "MD4 blocks are 64 bytes long");
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Encode words into a little-endian block
+pub fn block(words: &[u32; 16]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect()
+}
+
+/// Apply the compression function to one block of words
+pub fn compress(state: [u32; 4], words: &[u32; 16]) -> [u32; 4] {
+    let [mut a, mut b, mut c, mut d] = state;
+
+    for i in (0..16).step_by(4) {
+        a = (a.wrapping_add(f(b, c, d)).wrapping_add(words[i])).rotate_left(ROUND1_SHIFTS[0]);
+        d = (d.wrapping_add(f(a, b, c)).wrapping_add(words[i + 1])).rotate_left(ROUND1_SHIFTS[1]);
+        c = (c.wrapping_add(f(d, a, b)).wrapping_add(words[i + 2])).rotate_left(ROUND1_SHIFTS[2]);
+        b = (b.wrapping_add(f(c, d, a)).wrapping_add(words[i + 3])).rotate_left(ROUND1_SHIFTS[3]);
+    }
+    for i in (0..16).step_by(4) {
+        let m = |j: usize| words[ROUND2_ORDER[i + j]].wrapping_add(ROUND2_CONSTANT);
+        a = (a.wrapping_add(g(b, c, d)).wrapping_add(m(0))).rotate_left(ROUND2_SHIFTS[0]);
+        d = (d.wrapping_add(g(a, b, c)).wrapping_add(m(1))).rotate_left(ROUND2_SHIFTS[1]);
+        c = (c.wrapping_add(g(d, a, b)).wrapping_add(m(2))).rotate_left(ROUND2_SHIFTS[2]);
+        b = (b.wrapping_add(g(c, d, a)).wrapping_add(m(3))).rotate_left(ROUND2_SHIFTS[3]);
+    }
+    for i in (0..16).step_by(4) {
+        let m = |j: usize| words[ROUND3_ORDER[i + j]].wrapping_add(ROUND3_CONSTANT);
+        a = (a.wrapping_add(h(b, c, d)).wrapping_add(m(0))).rotate_left(ROUND3_SHIFTS[0]);
+        d = (d.wrapping_add(h(a, b, c)).wrapping_add(m(1))).rotate_left(ROUND3_SHIFTS[1]);
+        c = (c.wrapping_add(h(d, a, b)).wrapping_add(m(2))).rotate_left(ROUND3_SHIFTS[2]);
+        b = (b.wrapping_add(h(c, d, a)).wrapping_add(m(3))).rotate_left(ROUND3_SHIFTS[3]);
+    }
+
+    [
+        state[0].wrapping_add(a),
+        state[1].wrapping_add(b),
+        state[2].wrapping_add(c),
+        state[3].wrapping_add(d),
+    ]
+}
+
+/// Padding appended to a message of `message_len` bytes before hashing
+pub fn padding(message_len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    padding.resize(BLOCK_SIZE - (message_len + 8) % BLOCK_SIZE, 0);
+    padding.extend_from_slice(&((message_len as u64).wrapping_mul(8)).to_le_bytes());
+    padding
+}
+
+/// State of the function after hashing a message, read back from its digest
+///
+/// # Panics:
+///
+/// The function panics if the digest is not 16 bytes long.
+pub fn state(digest: &[u8]) -> [u32; 4] {
+    assert_eq!(DIGEST_SIZE, digest.len(), "MD4 digests are 16 bytes long");
+    let mut state = [0u32; 4];
+    for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    state
+}
+
+/// Continue hashing from `state`, after `prefix_len` bytes of padded message
+///
+/// `resume(INITIAL_STATE, 0, message)` is the digest of `message`, see
+/// `sha256::resume` for length extensions.
+///
+/// # Panics:
+///
+/// The function panics if `prefix_len` is not a multiple of the block size.
+pub fn resume(state: [u32; 4], prefix_len: usize, suffix: &[u8]) -> Vec<u8> {
+    assert!(prefix_len.is_multiple_of(BLOCK_SIZE), "MD4 resumes after whole blocks");
+    let mut padded = suffix.to_vec();
+    padded.extend(padding(prefix_len + suffix.len()));
+
+    let state = padded.chunks(BLOCK_SIZE)
+        .fold(state, |state, block| compress(state, &words(block)));
+    state.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// Compute the MD4 digest of a message
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::md4;
+/// use cryptopals::crypto::BytesCrypto;
+///
+/// assert_eq!("a448017aaf21d8525fc10ae87aa6729d", md4::digest(b"abc").bytes2hex());
+/// ```
+pub fn digest(message: &[u8]) -> Vec<u8> {
+    resume(INITIAL_STATE, 0, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives::BytesCrypto;
+
+    #[test]
+    fn rfc1320_vectors() {
+        assert_eq!("31d6cfe0d16ae931b73c59d7e0c089c0", digest(b"").bytes2hex());
+        assert_eq!("bde52cb31de33e46245e05fbdbd6fb24", digest(b"a").bytes2hex());
+        assert_eq!("d9130a8164549fe818874806e1c7014b", digest(b"message digest").bytes2hex());
+        assert_eq!(
+            "e33b4ddc9c38f2199c3e7b164fcc0536",
+            digest(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890").bytes2hex()
+        );
+    }
+
+    #[test]
+    fn length_extension() {
+        crate::hash_test::length_extension(digest, padding, state, resume);
+    }
+
+    #[test]
+    fn padding_length() {
+        for len in 0..200 {
+            assert_eq!(0, (len + padding(len).len()) % BLOCK_SIZE);
+        }
+        assert_eq!(72, padding(56).len());
+        assert_eq!(9, padding(55).len());
+    }
+
+    #[test]
+    fn words_roundtrip() {
+        let data: Vec<u8> = (0..64).collect();
+        assert_eq!(data, block(&words(&data)));
+        assert_eq!(0x03020100, words(&data)[0]);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! MD5 hash function, as described in RFC 1321
+//!
+//! Like `md4` and `sha256`, the state, the compression function and the
+//! padding are public, and `resume` hashes from the state of a published
+//! digest, for length extension and collision experiments.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// MD5 block size in bytes
+pub const BLOCK_SIZE: usize = 64;
+
+/// MD5 digest size in bytes
+pub const DIGEST_SIZE: usize = 16;
+
+/// Initial state, in the (A, B, C, D) order of the RFC, the same as MD4
+pub const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Additive constants, `floor(abs(sin(i + 1)) * 2^32)`
+const T: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Rotation amounts of the four steps of each round
+pub const SHIFTS: [[u32; 4]; 4] = [[7, 12, 17, 22], [5, 9, 14, 20], [4, 11, 16, 23], [6, 10, 15, 21]];
+
+/// Round 1 boolean function, selects `y` or `z` depending on `x`
+pub fn f(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (!x & z)
+}
+
+/// Round 2 boolean function, selects `x` or `y` depending on `z`
+pub fn g(x: u32, y: u32, z: u32) -> u32 {
+    (x & z) | (y & !z)
+}
+
+/// Round 3 boolean function, parity of the three bits
+pub fn h(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+/// Round 4 boolean function
+pub fn i(x: u32, y: u32, z: u32) -> u32 {
+    y ^ (x | !z)
+}
+
+/// Decode a 64-byte block into little-endian words
+///
+/// # Panics:
+///
+/// The function panics if the block is not 64 bytes long.
+pub fn words(block: &[u8]) -> [u32; 16] {
+    assert_eq!(BLOCK_SIZE, block.len(), "MD5 blocks are 64 bytes long");
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Apply the compression function to one block of words
+pub fn compress(state: [u32; 4], words: &[u32; 16]) -> [u32; 4] {
+    let [mut a, mut b, mut c, mut d] = state;
+
+    for step in 0..64 {
+        let round = step / 16;
+        let (mixed, index) = match round {
+            0 => (f(b, c, d), step),
+            1 => (g(b, c, d), (5 * step + 1) % 16),
+            2 => (h(b, c, d), (3 * step + 5) % 16),
+            _ => (i(b, c, d), (7 * step) % 16),
+        };
+        let sum = a.wrapping_add(mixed).wrapping_add(words[index]).wrapping_add(T[step]);
+        // Rotate the registers, the new B is computed from the old A
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(sum.rotate_left(SHIFTS[round][step % 4]));
+    }
+
+    [
+        state[0].wrapping_add(a),
+        state[1].wrapping_add(b),
+        state[2].wrapping_add(c),
+        state[3].wrapping_add(d),
+    ]
+}
+
+/// Padding appended to a message of `message_len` bytes before hashing
+pub fn padding(message_len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    padding.resize(BLOCK_SIZE - (message_len + 8) % BLOCK_SIZE, 0);
+    padding.extend_from_slice(&((message_len as u64).wrapping_mul(8)).to_le_bytes());
+    padding
+}
+
+/// State of the function after hashing a message, read back from its digest
+///
+/// # Panics:
+///
+/// The function panics if the digest is not 16 bytes long.
+pub fn state(digest: &[u8]) -> [u32; 4] {
+    assert_eq!(DIGEST_SIZE, digest.len(), "MD5 digests are 16 bytes long");
+    let mut state = [0u32; 4];
+    for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    state
+}
+
+/// Continue hashing from `state`, after `prefix_len` bytes of padded message
+///
+/// `resume(INITIAL_STATE, 0, message)` is the digest of `message`, see
+/// `sha256::resume` for length extensions.
+///
+/// # Panics:
+///
+/// The function panics if `prefix_len` is not a multiple of the block size.
+pub fn resume(state: [u32; 4], prefix_len: usize, suffix: &[u8]) -> Vec<u8> {
+    assert!(prefix_len.is_multiple_of(BLOCK_SIZE), "MD5 resumes after whole blocks");
+    let mut padded = suffix.to_vec();
+    padded.extend(padding(prefix_len + suffix.len()));
+
+    let state = padded.chunks(BLOCK_SIZE)
+        .fold(state, |state, block| compress(state, &words(block)));
+    state.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// Compute the MD5 digest of a message
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::md5;
+/// use cryptopals::crypto::BytesCrypto;
+///
+/// assert_eq!("900150983cd24fb0d6963f7d28e17f72", md5::digest(b"abc").bytes2hex());
+/// ```
+pub fn digest(message: &[u8]) -> Vec<u8> {
+    resume(INITIAL_STATE, 0, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives::BytesCrypto;
+
+    #[test]
+    fn rfc1321_vectors() {
+        assert_eq!("d41d8cd98f00b204e9800998ecf8427e", digest(b"").bytes2hex());
+        assert_eq!("0cc175b9c0f1b6a831c399e269772661", digest(b"a").bytes2hex());
+        assert_eq!("f96b697d7cb7938d525a2f31aaf161d0", digest(b"message digest").bytes2hex());
+        assert_eq!("c3fcd3d76192e4007dfb496cca67e13b", digest(b"abcdefghijklmnopqrstuvwxyz").bytes2hex());
+        assert_eq!(
+            "57edf4a22be3c955ac49da2e2107b67a",
+            digest(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890").bytes2hex()
+        );
+    }
+
+    #[test]
+    fn length_extension() {
+        crate::hash_test::length_extension(digest, padding, state, resume);
+    }
+}
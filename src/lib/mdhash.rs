@@ -0,0 +1,553 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Toy Merkle-Damgård hash functions and multicollisions
+//!
+//! The compression function encrypts the message block with AES, keyed by the
+//! current state, and keeps only the first bytes. With a state of a few bytes,
+//! collisions are cheap enough to play with.
+
+use std::collections::{HashMap, HashSet};
+use aes::Aes128;
+use block_modes::{BlockMode, Ecb};
+use block_modes::block_padding::NoPadding;
+use rand::RngCore;
+
+/// Size of the message blocks
+pub const BLOCK_SIZE: usize = 16;
+
+/// Merkle-Damgård hash with a truncated AES compression function
+#[derive(Debug, Clone, Copy)]
+pub struct MdHash {
+    state_len: usize,
+}
+
+impl MdHash {
+    /// Create a hash with a state of `state_len` bytes, from 1 to 16
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the state length is not in the 1..=16 range.
+    pub fn new(state_len: usize) -> MdHash {
+        assert!(state_len > 0 && state_len <= BLOCK_SIZE, "state length must be between 1 and 16");
+        MdHash { state_len }
+    }
+
+    pub fn state_len(&self) -> usize {
+        self.state_len
+    }
+
+    /// Initial state of the hash
+    pub fn initial_state(&self) -> Vec<u8> {
+        (0..self.state_len as u8).collect()
+    }
+
+    /// Compress one block into the state
+    pub fn compress(&self, state: &[u8], block: &[u8]) -> Vec<u8> {
+        let mut key = [0u8; BLOCK_SIZE];
+        key[..state.len()].copy_from_slice(state);
+        let mut buffer = [0u8; BLOCK_SIZE];
+        buffer.copy_from_slice(block);
+        // The key and the block always have the right size
+        let ecb = Ecb::<Aes128, NoPadding>::new_var(&key, &[]).unwrap();
+        ecb.encrypt(&mut buffer, BLOCK_SIZE).unwrap();
+        buffer[..self.state_len].to_vec()
+    }
+
+    /// Compress full blocks into the state, without any padding
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the data is not made of full blocks.
+    pub fn compress_blocks(&self, state: &[u8], blocks: &[u8]) -> Vec<u8> {
+        assert_eq!(0, blocks.len() % BLOCK_SIZE, "data must be made of full blocks");
+        blocks.chunks(BLOCK_SIZE)
+            .fold(state.to_vec(), |state, block| self.compress(&state, block))
+    }
+
+    /// Hash a message, padded with its length
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::mdhash::MdHash;
+    ///
+    /// let hash = MdHash::new(2);
+    /// assert_eq!(2, hash.hash(b"YELLOW SUBMARINE").len());
+    /// assert_ne!(hash.hash(b"YELLOW SUBMARINE"), hash.hash(b"YELLOW SUBMARINF"));
+    /// ```
+    pub fn hash(&self, message: &[u8]) -> Vec<u8> {
+        self.compress_blocks(&self.initial_state(), &md_pad(message, message.len()))
+    }
+}
+
+/// Pad a message with a 1 bit, zeros and the total message length in bits
+///
+/// `total_len` is the length in bytes of the whole message, which differs from
+/// the length of `message` when hashing its last blocks only.
+pub fn md_pad(message: &[u8], total_len: usize) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % BLOCK_SIZE != BLOCK_SIZE - 8 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((total_len as u64) * 8).to_be_bytes());
+    padded
+}
+
+/// Two different blocks compressing to the same state
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub block1: Vec<u8>,
+    pub block2: Vec<u8>,
+    pub state: Vec<u8>,
+}
+
+/// Find two blocks colliding from a given state with a birthday search
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::mdhash::{self, MdHash};
+///
+/// let hash = MdHash::new(2);
+/// let state = hash.initial_state();
+/// let collision = mdhash::find_collision(&hash, &state);
+/// assert_eq!(hash.compress(&state, &collision.block1), hash.compress(&state, &collision.block2));
+/// ```
+pub fn find_collision(hash: &MdHash, state: &[u8]) -> Collision {
+    let mut seen: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut block = vec![0u8; BLOCK_SIZE];
+    loop {
+        rand::thread_rng().fill_bytes(&mut block);
+        let next = hash.compress(state, &block);
+        match seen.get(&next) {
+            Some(other) if *other != block => {
+                return Collision { block1: other.clone(), block2: block, state: next };
+            }
+            _ => {
+                seen.insert(next, block.clone());
+            }
+        }
+    }
+}
+
+/// Chain `n` collisions, giving 2^n messages of `n` blocks with the same hash
+pub fn multicollisions(hash: &MdHash, state: &[u8], n: usize) -> Vec<Collision> {
+    let mut collisions: Vec<Collision> = Vec::with_capacity(n);
+    for _ in 0..n {
+        let state = collisions.last().map(|c| c.state.clone()).unwrap_or_else(|| state.to_vec());
+        collisions.push(find_collision(hash, &state));
+    }
+    collisions
+}
+
+/// Build the message selected by the bits of `index` from chained collisions
+pub fn collision_message(collisions: &[Collision], index: u64) -> Vec<u8> {
+    collisions.iter().enumerate()
+        .flat_map(|(i, c)| if index >> i & 1 == 0 { c.block1.clone() } else { c.block2.clone() })
+        .collect()
+}
+
+/// Find a collision of the cascade `f(m) || g(m)`, with `f` cheap and `g` stronger
+///
+/// Generate 2^(b/2) messages colliding in `f`, with `b` the bit size of `g`, and look
+/// for a collision in `g` among them. Add one more `f` collision, doubling the number
+/// of messages, until `g` collides too. Returns the two colliding messages.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::mdhash::{self, MdHash};
+///
+/// let (f, g) = (MdHash::new(1), MdHash::new(2));
+/// let (m1, m2) = mdhash::cascade_collision(&f, &g);
+/// assert_ne!(m1, m2);
+/// assert_eq!(f.hash(&m1), f.hash(&m2));
+/// assert_eq!(g.hash(&m1), g.hash(&m2));
+/// ```
+pub fn cascade_collision(f: &MdHash, g: &MdHash) -> (Vec<u8>, Vec<u8>) {
+    let mut collisions = multicollisions(f, &f.initial_state(), g.state_len() * 4);
+    loop {
+        debug!("Looking for a g collision among 2^{} messages", collisions.len());
+        let mut seen: HashMap<Vec<u8>, u64> = HashMap::new();
+        for index in 0..(1u64 << collisions.len()) {
+            let message = collision_message(&collisions, index);
+            let state = g.compress_blocks(&g.initial_state(), &message);
+            if let Some(&other) = seen.get(&state) {
+                return (collision_message(&collisions, other), message);
+            }
+            seen.insert(state, index);
+        }
+        let state = collisions.last().unwrap().state.clone();
+        collisions.push(find_collision(f, &state));
+    }
+}
+
+/// Kelsey-Schneier expandable message
+///
+/// Made of `k` collisions between a single block and a chunk of 2^i + 1 blocks,
+/// for i from `k - 1` down to 0. Picking the short or the long side of each
+/// collision gives messages of any length from `k` to `k + 2^k - 1` blocks,
+/// all leading to the same final state.
+#[derive(Debug, Clone)]
+pub struct ExpandableMessage {
+    /// (short, long) sides of each collision, the longest first
+    pieces: Vec<(Vec<u8>, Vec<u8>)>,
+    state: Vec<u8>,
+}
+
+impl ExpandableMessage {
+    /// Build an expandable message of parameter `k` starting from a given state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::mdhash::{ExpandableMessage, MdHash};
+    ///
+    /// let hash = MdHash::new(2);
+    /// let initial = hash.initial_state();
+    /// let expandable = ExpandableMessage::build(&hash, &initial, 3);
+    /// assert_eq!((3, 10), (expandable.min_blocks(), expandable.max_blocks()));
+    /// for blocks in 3..=10 {
+    ///     let message = expandable.message(blocks).unwrap();
+    ///     assert_eq!(expandable.final_state(), &hash.compress_blocks(&initial, &message)[..]);
+    /// }
+    /// ```
+    pub fn build(hash: &MdHash, state: &[u8], k: usize) -> ExpandableMessage {
+        let mut pieces = Vec::with_capacity(k);
+        let mut state = state.to_vec();
+        for i in (0..k).rev() {
+            let dummy = vec![0u8; (1 << i) * BLOCK_SIZE];
+            let dummy_state = hash.compress_blocks(&state, &dummy);
+            let (short, long_last, next) = find_collision_from(hash, &state, &dummy_state);
+            let mut long = dummy;
+            long.extend(long_last);
+            pieces.push((short, long));
+            state = next;
+        }
+        ExpandableMessage { pieces, state }
+    }
+
+    pub fn k(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Shortest message length, in blocks
+    pub fn min_blocks(&self) -> usize {
+        self.k()
+    }
+
+    /// Longest message length, in blocks
+    pub fn max_blocks(&self) -> usize {
+        self.k() + (1 << self.k()) - 1
+    }
+
+    /// State reached after any of the messages
+    pub fn final_state(&self) -> &[u8] {
+        &self.state
+    }
+
+    /// Produce the message of exactly `blocks` blocks, if in range
+    pub fn message(&self, blocks: usize) -> Option<Vec<u8>> {
+        if blocks < self.min_blocks() || blocks > self.max_blocks() {
+            return None;
+        }
+        let extra = blocks - self.k();
+        let k = self.k();
+        Some(self.pieces.iter().enumerate()
+            .flat_map(|(index, (short, long))| {
+                if extra >> (k - 1 - index) & 1 == 1 { long.clone() } else { short.clone() }
+            })
+            .collect())
+    }
+}
+
+/// Find a block from `state1` and a block from `state2` compressing to the same state
+fn find_collision_from(hash: &MdHash, state1: &[u8], state2: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let mut seen1: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut seen2: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut block = vec![0u8; BLOCK_SIZE];
+    loop {
+        rand::thread_rng().fill_bytes(&mut block);
+        let next1 = hash.compress(state1, &block);
+        if let Some(block2) = seen2.get(&next1) {
+            return (block.clone(), block2.clone(), next1);
+        }
+        seen1.insert(next1, block.clone());
+
+        rand::thread_rng().fill_bytes(&mut block);
+        let next2 = hash.compress(state2, &block);
+        if let Some(block1) = seen1.get(&next2) {
+            return (block1.clone(), block.clone(), next2);
+        }
+        seen2.insert(next2, block.clone());
+    }
+}
+
+/// Find a second preimage of a long message with an expandable message
+///
+/// The message must be longer than `k + 1` blocks and at most `k + 2^k` blocks long.
+/// A bridge block links the expandable message's final state to one of the states
+/// the hash goes through while processing the original message; the expandable
+/// message is then sized so that the forgery keeps the original length, and thus
+/// the same padding. Returns `None` if the message has an unsuitable length.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::mdhash::{self, MdHash};
+///
+/// let hash = MdHash::new(2);
+/// let message: Vec<u8> = (0..(64 * 16)).map(|i| i as u8).collect();
+/// let forged = mdhash::second_preimage(&hash, &message, 6).unwrap();
+/// assert_ne!(message, forged);
+/// assert_eq!(hash.hash(&message), hash.hash(&forged));
+/// ```
+pub fn second_preimage(hash: &MdHash, message: &[u8], k: usize) -> Option<Vec<u8>> {
+    let full_blocks = message.len() / BLOCK_SIZE;
+    let expandable = ExpandableMessage::build(hash, &hash.initial_state(), k);
+
+    // State after each number of full blocks, usable when the prefix length fits
+    let mut states: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut state = hash.initial_state();
+    for (index, block) in message.chunks_exact(BLOCK_SIZE).enumerate() {
+        state = hash.compress(&state, block);
+        let blocks = index + 1;
+        if blocks > expandable.min_blocks() && blocks <= expandable.max_blocks() + 1 {
+            states.entry(state.clone()).or_insert(blocks);
+        }
+    }
+    if states.is_empty() {
+        return None;
+    }
+
+    let mut bridge = vec![0u8; BLOCK_SIZE];
+    loop {
+        rand::thread_rng().fill_bytes(&mut bridge);
+        let next = hash.compress(expandable.final_state(), &bridge);
+        if let Some(&blocks) = states.get(&next) {
+            debug!("Bridge found to the state after block {} of {}", blocks, full_blocks);
+            let mut forged = expandable.message(blocks - 1)?;
+            forged.extend_from_slice(&bridge);
+            forged.extend_from_slice(&message[(blocks * BLOCK_SIZE)..]);
+            return Some(forged);
+        }
+    }
+}
+
+/// Diamond structure for the Nostradamus (herding) attack
+///
+/// A binary tree of collisions: 2^depth leaf states are paired, and each pair is
+/// sent to a common state by a collision, halving the number of states at each
+/// level until a single one remains. From any leaf, `depth` blocks lead to it.
+#[derive(Debug, Clone)]
+pub struct Diamond {
+    /// States of each level, from the 2^depth leaves down to the root
+    states: Vec<Vec<Vec<u8>>>,
+    /// Block leading each state to its parent, for each level but the root
+    blocks: Vec<Vec<Vec<u8>>>,
+}
+
+impl Diamond {
+    /// Build a diamond from 2^depth distinct random leaf states
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the hash state is too small to hold 2^depth distinct states.
+    pub fn build(hash: &MdHash, depth: usize) -> Diamond {
+        assert!(depth < 8 * hash.state_len().min(4), "too many leaves for the hash state size");
+        let mut leaves: HashSet<Vec<u8>> = HashSet::new();
+        let mut state = vec![0u8; hash.state_len()];
+        while leaves.len() < 1 << depth {
+            rand::thread_rng().fill_bytes(&mut state);
+            leaves.insert(state.clone());
+        }
+
+        let mut states = vec![leaves.into_iter().collect::<Vec<_>>()];
+        let mut blocks = Vec::with_capacity(depth);
+        for level in 0..depth {
+            debug!("Building diamond level {}", level);
+            let mut next_states = Vec::new();
+            let mut level_blocks = Vec::new();
+            for pair in states[level].chunks(2) {
+                let (block1, block2, next) = find_collision_from(hash, &pair[0], &pair[1]);
+                level_blocks.push(block1);
+                level_blocks.push(block2);
+                next_states.push(next);
+            }
+            states.push(next_states);
+            blocks.push(level_blocks);
+        }
+        Diamond { states, blocks }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn leaves(&self) -> &[Vec<u8>] {
+        &self.states[0]
+    }
+
+    /// State reached from every leaf
+    pub fn final_state(&self) -> &[u8] {
+        &self.states[self.depth()][0]
+    }
+
+    /// Blocks leading from a leaf to the final state
+    pub fn path(&self, leaf: usize) -> Vec<u8> {
+        (0..self.depth())
+            .flat_map(|level| self.blocks[level][leaf >> level].clone())
+            .collect()
+    }
+
+    /// Hash of any herded message whose prefix is `prefix_blocks` long
+    ///
+    /// The herded message is made of the prefix, a glue block and the path to
+    /// the final state, its length is known in advance and so is its padding.
+    pub fn prediction(&self, hash: &MdHash, prefix_blocks: usize) -> Vec<u8> {
+        let total_len = (prefix_blocks + 1 + self.depth()) * BLOCK_SIZE;
+        hash.compress_blocks(self.final_state(), &md_pad(&[], total_len))
+    }
+
+    /// Herd a prefix into the predicted hash
+    ///
+    /// The prefix is padded with spaces to `prefix_blocks` blocks, then a glue block
+    /// leading to one of the leaves is searched. Returns `None` if the prefix is too long.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::mdhash::{Diamond, MdHash};
+    ///
+    /// let hash = MdHash::new(2);
+    /// let diamond = Diamond::build(&hash, 4);
+    /// let prediction = diamond.prediction(&hash, 2);
+    ///
+    /// let message = diamond.herd(&hash, b"Red Sox 4, Yankees 2", 2).unwrap();
+    /// assert!(message.starts_with(b"Red Sox 4, Yankees 2"));
+    /// assert_eq!(prediction, hash.hash(&message));
+    /// ```
+    pub fn herd(&self, hash: &MdHash, prefix: &[u8], prefix_blocks: usize) -> Option<Vec<u8>> {
+        if prefix.len() > prefix_blocks * BLOCK_SIZE {
+            return None;
+        }
+        let mut message = prefix.to_vec();
+        message.resize(prefix_blocks * BLOCK_SIZE, b' ');
+        let state = hash.compress_blocks(&hash.initial_state(), &message);
+
+        let leaves: HashMap<&[u8], usize> = self.leaves().iter().enumerate()
+            .map(|(index, leaf)| (&leaf[..], index))
+            .collect();
+        let mut glue = vec![0u8; BLOCK_SIZE];
+        loop {
+            rand::thread_rng().fill_bytes(&mut glue);
+            if let Some(&leaf) = leaves.get(&hash.compress(&state, &glue)[..]) {
+                message.extend_from_slice(&glue);
+                message.extend(self.path(leaf));
+                return Some(message);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pad_lengths() {
+        assert_eq!(16, md_pad(b"", 0).len());
+        assert_eq!(16, md_pad(&[0; 7], 7).len());
+        assert_eq!(32, md_pad(&[0; 8], 8).len());
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 0x38], &md_pad(&[0; 7], 7)[8..]);
+    }
+
+    #[test]
+    fn state_size() {
+        let hash = MdHash::new(3);
+        assert_eq!(3, hash.initial_state().len());
+        assert_eq!(3, hash.hash(b"").len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_state_size() {
+        MdHash::new(17);
+    }
+
+    #[test]
+    fn multicollision_messages() {
+        let hash = MdHash::new(2);
+        let collisions = multicollisions(&hash, &hash.initial_state(), 3);
+        let expected = hash.hash(&collision_message(&collisions, 0));
+        for index in 1..8 {
+            let message = collision_message(&collisions, index);
+            assert_eq!(3 * BLOCK_SIZE, message.len());
+            assert_eq!(expected, hash.hash(&message));
+        }
+    }
+
+    #[test]
+    fn expandable_lengths() {
+        let hash = MdHash::new(1);
+        let expandable = ExpandableMessage::build(&hash, &hash.initial_state(), 4);
+        assert_eq!(4, expandable.k());
+        assert_eq!(None, expandable.message(3));
+        assert_eq!(None, expandable.message(20));
+        for blocks in 4..=19 {
+            let message = expandable.message(blocks).unwrap();
+            assert_eq!(blocks * BLOCK_SIZE, message.len());
+            assert_eq!(expandable.final_state(), &hash.compress_blocks(&hash.initial_state(), &message)[..]);
+        }
+    }
+
+    #[test]
+    fn second_preimage_keeps_tail() {
+        let hash = MdHash::new(2);
+        let message: Vec<u8> = (0..(40 * BLOCK_SIZE + 5)).map(|i| (i * 7) as u8).collect();
+        let forged = second_preimage(&hash, &message, 5).unwrap();
+        assert_eq!(message.len(), forged.len());
+        assert!(forged.ends_with(&message[(40 * BLOCK_SIZE)..]));
+        assert_eq!(hash.hash(&message), hash.hash(&forged));
+    }
+
+    #[test]
+    fn diamond_paths() {
+        let hash = MdHash::new(2);
+        let diamond = Diamond::build(&hash, 3);
+        assert_eq!(3, diamond.depth());
+        assert_eq!(8, diamond.leaves().len());
+        for (index, leaf) in diamond.leaves().iter().enumerate() {
+            let path = diamond.path(index);
+            assert_eq!(3 * BLOCK_SIZE, path.len());
+            assert_eq!(diamond.final_state(), &hash.compress_blocks(leaf, &path)[..]);
+        }
+    }
+
+    #[test]
+    fn herd_too_long() {
+        let hash = MdHash::new(1);
+        let diamond = Diamond::build(&hash, 2);
+        assert!(diamond.herd(&hash, &[0; 17], 1).is_none());
+    }
+
+    #[test]
+    fn second_preimage_too_short() {
+        let hash = MdHash::new(1);
+        assert!(second_preimage(&hash, &[0; 3 * BLOCK_SIZE], 4).is_none());
+    }
+}
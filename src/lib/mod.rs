@@ -12,10 +12,114 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[macro_use]
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+#[cfg_attr(feature = "std", macro_use)]
 extern crate log;
 
+pub mod error;
+#[cfg(feature = "std")]
 pub mod helper;
+#[cfg(feature = "std")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub mod challenge;
+#[cfg(feature = "net")]
+pub mod cache;
+#[cfg(feature = "core")]
+pub mod bits;
+#[cfg(feature = "core")]
+pub mod blocks;
+#[cfg(feature = "core")]
+pub mod primitives;
+#[cfg(feature = "std")]
 pub mod crypto;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "std")]
+pub mod base64;
+#[cfg(feature = "std")]
 pub mod english;
+#[cfg(feature = "std")]
+pub mod language;
+#[cfg(feature = "std")]
 pub mod stats;
+#[cfg(feature = "std")]
+pub mod scoring;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod keysize;
+#[cfg(feature = "std")]
+pub mod attacks;
+#[cfg(feature = "std")]
+pub mod cbcmac;
+#[cfg(feature = "std")]
+pub mod kv;
+#[cfg(feature = "std")]
+pub mod validator;
+#[cfg(feature = "std")]
+pub mod cipher;
+#[cfg(feature = "std")]
+pub mod ctredit;
+#[cfg(feature = "std")]
+pub mod gf128;
+#[cfg(feature = "std")]
+pub mod gf2;
+#[cfg(feature = "std")]
+pub mod gcm;
+#[cfg(feature = "std")]
+pub mod bias;
+#[cfg(feature = "std")]
+pub mod mdhash;
+#[cfg(feature = "core")]
+pub mod md4;
+#[cfg(feature = "core")]
+pub mod md5;
+#[cfg(feature = "core")]
+pub mod sha256;
+#[cfg(feature = "std")]
+pub mod wang;
+#[cfg(feature = "std")]
+pub mod math;
+#[cfg(feature = "std")]
+pub mod params;
+#[cfg(feature = "std")]
+pub mod dh;
+#[cfg(feature = "std")]
+pub mod dlog;
+#[cfg(feature = "std")]
+pub mod ec;
+#[cfg(feature = "std")]
+pub mod ecdsa;
+#[cfg(feature = "std")]
+pub mod rsa;
+#[cfg(feature = "std")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod lattice;
+#[cfg(feature = "std")]
+pub mod keygen;
+#[cfg(feature = "std")]
+pub mod oracle;
+#[cfg(feature = "std")]
+pub mod bruteforce;
+#[cfg(feature = "std")]
+pub mod mt19937;
+#[cfg(feature = "std")]
+pub mod timebox;
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod rc4bias;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(all(test, feature = "openssl-interop"))]
+mod interop;
+#[cfg(all(test, feature = "core"))]
+mod hash_test;
@@ -0,0 +1,181 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! MT19937 Mersenne Twister, and the recovery of its seed
+//!
+//! Challenge 22 finds a seed taken from the clock, challenge 24 the 16-bit
+//! seed of a stream cipher built on the generator. Both search the seeds with
+//! `bruteforce::WorkerPool`.
+
+use std::ops::Range;
+use crate::bruteforce::WorkerPool;
+use crate::cipher::StreamCipher;
+
+const N: usize = 624;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908_b0df;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7fff_ffff;
+
+/// 32-bit MT19937 generator
+#[derive(Clone)]
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u32) -> Mt19937 {
+        let mut state = [0u32; N];
+        state[0] = seed;
+        for i in 1..N {
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Mt19937 { state, index: N }
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mut next = self.state[(i + M) % N] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= MATRIX_A;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    /// Next tempered output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::mt19937::Mt19937;
+    ///
+    /// // Reference implementation, default seed
+    /// let mut mt = Mt19937::new(5489);
+    /// assert_eq!(3499211612, mt.next_u32());
+    /// assert_eq!(581869302, mt.next_u32());
+    /// ```
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^ (y >> 18)
+    }
+}
+
+/// Stream cipher of challenge 24, keyed by a 16-bit seed
+///
+/// Each output of the generator gives four keystream bytes, in little endian
+/// order.
+pub struct Mt19937Cipher {
+    seed: u16,
+}
+
+impl Mt19937Cipher {
+    pub fn new(seed: u16) -> Mt19937Cipher {
+        Mt19937Cipher { seed }
+    }
+}
+
+impl StreamCipher for Mt19937Cipher {
+    fn keystream(&self, len: usize) -> Vec<u8> {
+        let mut mt = Mt19937::new(self.seed as u32);
+        let mut stream: Vec<u8> = (0..len.div_ceil(4)).flat_map(|_| mt.next_u32().to_le_bytes()).collect();
+        stream.truncate(len);
+        stream
+    }
+}
+
+/// Challenge 22: seed of a generator seeded with a Unix time in `times`, from its first output
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::mt19937::{self, Mt19937};
+///
+/// let output = Mt19937::new(1_600_000_123).next_u32();
+/// assert_eq!(Some(1_600_000_123), mt19937::crack_time_seed(output, 1_600_000_000..1_600_001_000));
+/// ```
+pub fn crack_time_seed(first_output: u32, times: Range<u32>) -> Option<u32> {
+    WorkerPool::new()
+        .find_first(times.start as u64..times.end as u64, |seed| Mt19937::new(seed as u32).next_u32() == first_output)
+        .map(|seed| seed as u32)
+}
+
+/// Challenge 24: seed of `Mt19937Cipher` from a ciphertext whose plaintext ends with `known_suffix`
+pub fn crack_stream_seed(ciphertext: &[u8], known_suffix: &[u8]) -> Option<u16> {
+    if known_suffix.len() > ciphertext.len() {
+        return None;
+    }
+    let offset = ciphertext.len() - known_suffix.len();
+    WorkerPool::new()
+        .find_first(0..1 << 16, |seed| {
+            let keystream = Mt19937Cipher::new(seed as u16).keystream(ciphertext.len());
+            ciphertext[offset..].iter().zip(&keystream[offset..]).zip(known_suffix)
+                .all(|((c, k), p)| c ^ k == *p)
+        })
+        .map(|seed| seed as u16)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, RngCore};
+    use crate::stats::ByteHistogram;
+
+    #[test]
+    fn reference_outputs() {
+        // The C++ standard requires the 10000th output of a default mt19937 to be 4123659995
+        let mut mt = Mt19937::new(5489);
+        for _ in 0..9999 {
+            mt.next_u32();
+        }
+        assert_eq!(4123659995, mt.next_u32());
+    }
+
+    #[test]
+    fn uniform_bytes() {
+        let mut mt = Mt19937::new(5489);
+        let bytes: Vec<u8> = (0..1 << 14).flat_map(|_| mt.next_u32().to_le_bytes()).collect();
+        // The 99.9% quantile of the chi-squared distribution with 255 degrees
+        // of freedom is about 330, i.e. 1.29 once reduced
+        assert!(ByteHistogram::from_bytes(&bytes).uniformity() < 1.29);
+        let keystream = Mt19937Cipher::new(4242).keystream(1 << 16);
+        assert!(ByteHistogram::from_bytes(&keystream).uniformity() < 1.29);
+    }
+
+    #[test]
+    fn stream_seed() {
+        let mut rng = rand::thread_rng();
+        let seed: u16 = rng.gen();
+        let mut plaintext = vec![0u8; rng.gen_range(5..20)];
+        rng.fill_bytes(&mut plaintext);
+        plaintext.extend_from_slice(&[b'A'; 14]);
+        let ciphertext = Mt19937Cipher::new(seed).apply_keystream(&plaintext);
+        assert_eq!(plaintext, Mt19937Cipher::new(seed).apply_keystream(&ciphertext));
+        assert_eq!(Some(seed), crack_stream_seed(&ciphertext, &[b'A'; 14]));
+        assert_eq!(None, crack_stream_seed(b"short", &[b'A'; 14]));
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Oracles queried by the attacks, and a builder of encryption oracles
+
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use aes::Aes128;
+use block_modes::{BlockMode, Cbc, Ecb};
+use block_modes::block_padding::{Pkcs7, ZeroPadding};
+use rand::{Rng, RngCore, SeedableRng};
+use rand::rngs::StdRng;
+use crate::cipher::BLOCK_SIZE;
+use crate::keygen;
+
+/// Fan out independent queries to a thread-safe oracle over several threads
+///
+/// Responses are returned in the order of the queries, whatever the order in
+/// which the threads complete them.
+pub struct ParallelOracle<F> {
+    oracle: F,
+    concurrency: usize,
+}
+
+impl<F> ParallelOracle<F> {
+    /// Wrap an oracle, with as many threads as there are CPUs
+    pub fn new(oracle: F) -> ParallelOracle<F> {
+        let concurrency = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        ParallelOracle { oracle, concurrency }
+    }
+
+    /// Limit the number of queries in flight, 1 queries sequentially
+    pub fn with_concurrency(mut self, concurrency: usize) -> ParallelOracle<F> {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Send a single query
+    pub fn query<Q, R>(&self, query: &Q) -> R
+        where F: Fn(&Q) -> R
+    {
+        (self.oracle)(query)
+    }
+
+    /// Send all queries, return the responses in the same order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::oracle::ParallelOracle;
+    ///
+    /// let oracle = ParallelOracle::new(|x: &u64| x * x).with_concurrency(4);
+    /// assert_eq!(vec![0, 1, 4, 9, 16], oracle.query_all(&[0, 1, 2, 3, 4]));
+    /// ```
+    pub fn query_all<Q, R>(&self, queries: &[Q]) -> Vec<R>
+        where F: Fn(&Q) -> R + Sync, Q: Sync, R: Send
+    {
+        let threads = self.concurrency.min(queries.len());
+        if threads <= 1 {
+            return queries.iter().map(|q| (self.oracle)(q)).collect();
+        }
+
+        let next = AtomicUsize::new(0);
+        let responses: Mutex<Vec<Option<R>>> = Mutex::new((0..queries.len()).map(|_| None).collect());
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= queries.len() {
+                        break;
+                    }
+                    let response = (self.oracle)(&queries[index]);
+                    responses.lock().unwrap()[index] = Some(response);
+                });
+            }
+        });
+        responses.into_inner().unwrap()
+            .into_iter()
+            .map(|response| response.unwrap())
+            .collect()
+    }
+}
+
+/// Oracle drawing its randomness from a seeded generator
+///
+/// Random-key oracles expose a variant taking the generator, wrapping it
+/// makes an attack reproducible in tests. Queries are answered in turn, so
+/// the answers only depend on the seed if they are sent in a fixed order.
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use cryptopals::oracle::{DeterministicOracle, EncryptionOracle};
+/// use cryptopals::ctredit::EditOracle;
+///
+/// let oracle = || DeterministicOracle::new(25, |rng: &mut StdRng, input: &[u8]| EditOracle::with_rng(rng).encrypt(input));
+/// assert_eq!(oracle().encrypt(b"input"), oracle().encrypt(b"input"));
+/// ```
+pub struct DeterministicOracle<F> {
+    oracle: F,
+    rng: Mutex<StdRng>,
+}
+
+impl<F> DeterministicOracle<F> {
+    pub fn new(seed: u64, oracle: F) -> DeterministicOracle<F> {
+        DeterministicOracle { oracle, rng: Mutex::new(StdRng::seed_from_u64(seed)) }
+    }
+
+    /// Send a query with the next random numbers of the generator
+    pub fn query<Q: ?Sized, R>(&self, query: &Q) -> R
+        where F: Fn(&mut StdRng, &Q) -> R
+    {
+        (self.oracle)(&mut self.rng.lock().unwrap(), query)
+    }
+}
+
+impl<F: Fn(&mut StdRng, &[u8]) -> Vec<u8>> EncryptionOracle for DeterministicOracle<F> {
+    fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        self.query(input)
+    }
+}
+
+/// Encrypt attacker controlled data, the target of the chosen plaintext attacks
+pub trait EncryptionOracle {
+    fn encrypt(&self, input: &[u8]) -> Vec<u8>;
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> EncryptionOracle for F {
+    fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        self(input)
+    }
+}
+
+/// AES-128 block cipher modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc,
+}
+
+/// Padding of the last block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// PKCS#7, always adds between 1 and 16 bytes
+    Pkcs7,
+    /// Zero bytes up to the block boundary, none if already aligned
+    Zero,
+}
+
+/// Build an `EncryptionOracle` around AES-128
+///
+/// The oracle encrypts `prefix || input || suffix` under a key fixed when it
+/// is built. By default the key is random, there is neither prefix nor suffix,
+/// and the mode is ECB with PKCS#7 padding. In CBC mode the IV is random and
+/// fixed too. Random values are drawn when the oracle is built.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::oracle::{EncryptionOracle, OracleBuilder};
+///
+/// // Challenge 14: a random prefix and an unknown suffix
+/// let oracle = OracleBuilder::new()
+///     .with_random_prefix(5..=10)
+///     .with_suffix(b"secret")
+///     .build();
+/// let ciphertext = oracle.encrypt(&[b'A'; 48]);
+/// // At least two identical blocks of 'A'
+/// assert!(ciphertext.chunks(16).zip(ciphertext.chunks(16).skip(1)).any(|(a, b)| a == b));
+/// ```
+pub struct OracleBuilder {
+    key: Option<Vec<u8>>,
+    prefix: Vec<u8>,
+    /// Range of the random prefix length, which replaces `prefix`
+    prefix_lengths: Option<RangeInclusive<usize>>,
+    suffix: Vec<u8>,
+    mode: Mode,
+    padding: Padding,
+}
+
+impl OracleBuilder {
+    pub fn new() -> OracleBuilder {
+        OracleBuilder {
+            key: None,
+            prefix: Vec::new(),
+            prefix_lengths: None,
+            suffix: Vec::new(),
+            mode: Mode::Ecb,
+            padding: Padding::Pkcs7,
+        }
+    }
+
+    /// Use a known key instead of a random one
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the key is not 16 bytes long.
+    pub fn with_key(mut self, key: &[u8]) -> OracleBuilder {
+        assert_eq!(BLOCK_SIZE, key.len(), "invalid AES-128 key length");
+        self.key = Some(key.to_vec());
+        self
+    }
+
+    /// Prepend fixed bytes to the input
+    pub fn with_prefix(mut self, prefix: &[u8]) -> OracleBuilder {
+        self.prefix = prefix.to_vec();
+        self.prefix_lengths = None;
+        self
+    }
+
+    /// Prepend random bytes to the input, their number is drawn from `lengths`
+    pub fn with_random_prefix(mut self, lengths: RangeInclusive<usize>) -> OracleBuilder {
+        self.prefix_lengths = Some(lengths);
+        self
+    }
+
+    /// Append the secret bytes to the input
+    pub fn with_suffix(mut self, suffix: &[u8]) -> OracleBuilder {
+        self.suffix = suffix.to_vec();
+        self
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> OracleBuilder {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: Padding) -> OracleBuilder {
+        self.padding = padding;
+        self
+    }
+
+    /// Draw the missing random key and IV
+    pub fn build(self) -> AesOracle {
+        self.build_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Draw the missing key, the IV and the random prefix from `rng`, e.g. a seeded generator in tests
+    pub fn build_with_rng<R: RngCore + ?Sized>(self, rng: &mut R) -> AesOracle {
+        let key = self.key.unwrap_or_else(|| keygen::aes_key(rng).to_vec());
+        let iv = keygen::iv(rng);
+        let prefix = match self.prefix_lengths {
+            Some(lengths) => {
+                let len = rng.gen_range(lengths);
+                keygen::bytes(rng, len)
+            }
+            None => self.prefix,
+        };
+        AesOracle { key, iv, prefix, suffix: self.suffix, mode: self.mode, padding: self.padding }
+    }
+}
+
+impl Default for OracleBuilder {
+    fn default() -> Self {
+        OracleBuilder::new()
+    }
+}
+
+/// Oracle made by `OracleBuilder`
+pub struct AesOracle {
+    key: Vec<u8>,
+    iv: [u8; BLOCK_SIZE],
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+    mode: Mode,
+    padding: Padding,
+}
+
+impl AesOracle {
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+}
+
+impl EncryptionOracle for AesOracle {
+    fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        let plaintext = [&self.prefix[..], input, &self.suffix[..]].concat();
+        // The key length was checked by the builder
+        match (self.mode, self.padding) {
+            (Mode::Ecb, Padding::Pkcs7) =>
+                Ecb::<Aes128, Pkcs7>::new_var(&self.key, &[]).unwrap().encrypt_vec(&plaintext),
+            (Mode::Ecb, Padding::Zero) =>
+                Ecb::<Aes128, ZeroPadding>::new_var(&self.key, &[]).unwrap().encrypt_vec(&plaintext),
+            (Mode::Cbc, Padding::Pkcs7) =>
+                Cbc::<Aes128, Pkcs7>::new_var(&self.key, &self.iv).unwrap().encrypt_vec(&plaintext),
+            (Mode::Cbc, Padding::Zero) =>
+                Cbc::<Aes128, ZeroPadding>::new_var(&self.key, &self.iv).unwrap().encrypt_vec(&plaintext),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use crate::crypto::{Aes128Cbc, Aes128Ecb};
+
+    #[test]
+    fn ordered_responses() {
+        // Early queries take longer, so that they complete last
+        let oracle = ParallelOracle::new(|x: &u64| {
+            thread::sleep(Duration::from_millis(20 - *x));
+            x + 1
+        }).with_concurrency(8);
+        let queries: Vec<u64> = (0..20).collect();
+        assert_eq!((1..21).collect::<Vec<u64>>(), oracle.query_all(&queries));
+    }
+
+    #[test]
+    fn concurrency_limit() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let oracle = ParallelOracle::new(|_: &u8| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+        }).with_concurrency(3);
+        oracle.query_all(&[0; 30]);
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn sequential_and_empty() {
+        let oracle = ParallelOracle::new(|s: &&str| s.len()).with_concurrency(0);
+        assert_eq!(1, oracle.concurrency());
+        assert_eq!(vec![1, 3], oracle.query_all(&["a", "abc"]));
+        assert_eq!(Vec::<usize>::new(), oracle.query_all(&[]));
+        assert_eq!(2, oracle.query(&"ab"));
+    }
+
+    #[test]
+    fn oracle_layout() {
+        let key = b"YELLOW SUBMARINE";
+        let oracle = OracleBuilder::new().with_key(key).with_prefix(b"pre").with_suffix(b"suffix").build();
+        let ciphertext = oracle.encrypt(b"input");
+        let plaintext = Aes128Ecb::new(key).unwrap().decrypt(&ciphertext).unwrap();
+        assert_eq!(b"preinputsuffix".to_vec(), plaintext);
+        // A full block of PKCS#7 padding
+        assert_eq!(32, oracle.encrypt(b"0123456").len());
+    }
+
+    #[test]
+    fn oracle_modes() {
+        let key = b"YELLOW SUBMARINE";
+        let input = [b'A'; 32];
+        let ecb = OracleBuilder::new().with_key(key).with_padding(Padding::Zero).build();
+        let ciphertext = ecb.encrypt(&input);
+        assert_eq!(32, ciphertext.len());
+        assert_eq!(ciphertext[..16], ciphertext[16..]);
+
+        let cbc = OracleBuilder::new().with_key(key).with_mode(Mode::Cbc).build();
+        assert_eq!(Mode::Cbc, cbc.mode());
+        let ciphertext = cbc.encrypt(&input);
+        assert_ne!(ciphertext[..16], ciphertext[16..32]);
+        let plaintext = Aes128Cbc::new(key, &cbc.iv).unwrap().decrypt(&ciphertext).unwrap();
+        assert_eq!(input.to_vec(), plaintext);
+    }
+
+    #[test]
+    fn seeded_oracle() {
+        let build = || OracleBuilder::new().with_mode(Mode::Cbc).with_random_prefix(0..=32)
+            .build_with_rng(&mut StdRng::seed_from_u64(59));
+        assert_eq!(build().encrypt(b"input"), build().encrypt(b"input"));
+    }
+
+    #[test]
+    fn deterministic_oracle() {
+        let oracle = || DeterministicOracle::new(48, |rng: &mut StdRng, input: &[u8]| {
+            OracleBuilder::new().with_random_prefix(0..=16).build_with_rng(rng).encrypt(input)
+        });
+        let (first, second) = (oracle(), oracle());
+        let answers: Vec<Vec<u8>> = (0..4).map(|_| first.encrypt(b"input")).collect();
+        assert_eq!(answers, (0..4).map(|_| second.encrypt(b"input")).collect::<Vec<_>>());
+        // Each query draws a new key
+        assert_ne!(answers[0], answers[1]);
+        assert_eq!(5, DeterministicOracle::new(0, |_: &mut StdRng, q: &str| q.len()).query("bytes"));
+    }
+
+    #[test]
+    fn closure_oracle() {
+        let oracle = |input: &[u8]| input.iter().rev().copied().collect::<Vec<u8>>();
+        assert_eq!(vec![3, 2, 1], oracle.encrypt(&[1, 2, 3]));
+    }
+}
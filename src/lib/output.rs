@@ -0,0 +1,410 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Output sinks for the challenge narration
+//!
+//! The challenges write their titles and results to the `Output` of their
+//! `Context`, which goes to the terminal by default. The `CRYPTOPALS_OUTPUT`
+//! environment variable selects another sink:
+//!
+//! - `terminal`: standard output
+//! - `null`: nothing
+//! - `text:<path>`: plain text file
+//! - `jsonl` or `jsonl:<path>`: one JSON object per line, on standard output or in a file
+//!
+//! Besides free text, challenges report named values and binary data, which
+//! the JSON sink keeps apart and `Capture` records for the tests.
+
+use std::cell::RefCell;
+use std::error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::rc::Rc;
+use serde_json::json;
+use crate::crypto::BytesCrypto;
+use crate::helper;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Environment variable selecting the output sink
+pub const OUTPUT_ENV: &str = "CRYPTOPALS_OUTPUT";
+
+/// Render a section title in a box
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::output;
+///
+/// assert_eq!("\n+------------+\n| Statistics |\n+------------+", output::section_title("Statistics"));
+/// ```
+pub fn section_title(title: &str) -> String {
+    let dashes = "-".repeat(title.len());
+    format!("\n+-{}-+\n| {} |\n+-{}-+", dashes, title, dashes)
+}
+
+/// Render an underlined subsection title
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::output;
+///
+/// assert_eq!("\nPermutations:\n-------------\n", output::subsection_title("Permutations"));
+/// ```
+pub fn subsection_title(title: &str) -> String {
+    format!("\n{}:\n{}\n", title, "-".repeat(title.len() + 1))
+}
+
+/// Destination of the narration
+pub trait Output {
+    /// Write a line, which may hold several lines of text
+    fn line(&mut self, text: &str);
+
+    fn section(&mut self, title: &str) {
+        self.line(&section_title(title));
+    }
+
+    fn subsection(&mut self, title: &str) {
+        self.line(&subsection_title(title));
+    }
+
+    /// Write a named result, e.g. a recovered key
+    fn value(&mut self, key: &str, value: &str) {
+        self.line(&format!("{}: {}", key, value));
+    }
+
+    /// Write binary data as numbered 16-byte blocks
+    fn hexdump(&mut self, label: &str, bytes: &[u8]) {
+        self.line(&format!("{}:\n{}", label, helper::format_blocks(bytes, 16).trim_end()));
+    }
+}
+
+/// Standard output
+pub struct Terminal;
+
+impl Output for Terminal {
+    fn line(&mut self, text: &str) {
+        println!("{}", text);
+    }
+}
+
+/// Nothing, for tests and benchmarks
+pub struct Null;
+
+impl Output for Null {
+    fn line(&mut self, _text: &str) {}
+}
+
+/// Plain text, as on the terminal
+pub struct Text<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Text<W> {
+    pub fn new(writer: W) -> Text<W> {
+        Text { writer }
+    }
+}
+
+impl Text<BufWriter<File>> {
+    /// Create or truncate a text file
+    pub fn create(path: &str) -> Result<Self> {
+        Ok(Text::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Output for Text<W> {
+    fn line(&mut self, text: &str) {
+        if let Err(e) = writeln!(self.writer, "{}", text) {
+            warn!("cannot write the output: {}", e);
+        }
+    }
+}
+
+/// JSON lines with the kind of each record: `section`, `subsection`, `line`,
+/// `value` or `hexdump`, the last two with a `key`
+pub struct JsonLines<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> JsonLines<W> {
+        JsonLines { writer }
+    }
+
+    fn record(&mut self, kind: &str, text: &str) {
+        self.write(json!({ "kind": kind, "text": text }));
+    }
+
+    fn write(&mut self, record: serde_json::Value) {
+        if let Err(e) = writeln!(self.writer, "{}", record) {
+            warn!("cannot write the output: {}", e);
+        }
+    }
+}
+
+impl JsonLines<BufWriter<File>> {
+    /// Create or truncate a JSON lines file
+    pub fn create(path: &str) -> Result<Self> {
+        Ok(JsonLines::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Output for JsonLines<W> {
+    fn line(&mut self, text: &str) {
+        self.record("line", text);
+    }
+
+    fn section(&mut self, title: &str) {
+        self.record("section", title);
+    }
+
+    fn subsection(&mut self, title: &str) {
+        self.record("subsection", title);
+    }
+
+    fn value(&mut self, key: &str, value: &str) {
+        self.write(json!({ "kind": "value", "key": key, "text": value }));
+    }
+
+    fn hexdump(&mut self, label: &str, bytes: &[u8]) {
+        self.write(json!({ "kind": "hexdump", "key": label, "text": bytes.bytes2hex() }));
+    }
+}
+
+/// What a challenge reported, as recorded by `Capture`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    Section(String),
+    Subsection(String),
+    Line(String),
+    Value(String, String),
+    Hexdump(String, Vec<u8>),
+}
+
+/// Records kept in memory, to check what a challenge reported
+///
+/// Clones share the same records, so a clone can be given to a `Context`
+/// and the original read afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::output::{Capture, Context, Record};
+///
+/// let capture = Capture::new();
+/// let ctx = Context::new(Box::new(capture.clone()));
+/// ctx.section("Set 1 / Challenge 5");
+/// ctx.value("Key", "ICE");
+/// assert_eq!(Some("ICE".to_owned()), capture.value("Key"));
+/// assert_eq!(Record::Section("Set 1 / Challenge 5".to_owned()), capture.records()[0]);
+/// ```
+#[derive(Clone, Default)]
+pub struct Capture {
+    records: Rc<RefCell<Vec<Record>>>,
+}
+
+impl Capture {
+    pub fn new() -> Capture {
+        Capture::default()
+    }
+
+    pub fn records(&self) -> Vec<Record> {
+        self.records.borrow().clone()
+    }
+
+    /// Last value reported under `key`
+    pub fn value(&self, key: &str) -> Option<String> {
+        self.records.borrow().iter().rev().find_map(|record| match record {
+            Record::Value(k, value) if k == key => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    fn push(&self, record: Record) {
+        self.records.borrow_mut().push(record);
+    }
+}
+
+impl Output for Capture {
+    fn line(&mut self, text: &str) {
+        self.push(Record::Line(text.to_owned()));
+    }
+
+    fn section(&mut self, title: &str) {
+        self.push(Record::Section(title.to_owned()));
+    }
+
+    fn subsection(&mut self, title: &str) {
+        self.push(Record::Subsection(title.to_owned()));
+    }
+
+    fn value(&mut self, key: &str, value: &str) {
+        self.push(Record::Value(key.to_owned(), value.to_owned()));
+    }
+
+    fn hexdump(&mut self, label: &str, bytes: &[u8]) {
+        self.push(Record::Hexdump(label.to_owned(), bytes.to_vec()));
+    }
+}
+
+/// Build the sink described by a `CRYPTOPALS_OUTPUT` value
+pub fn from_spec(spec: &str) -> Result<Box<dyn Output>> {
+    let (kind, path) = match spec.split_once(':') {
+        Some((kind, path)) => (kind, Some(path)),
+        None => (spec, None),
+    };
+    match (kind, path) {
+        ("terminal", None) => Ok(Box::new(Terminal)),
+        ("null", None) => Ok(Box::new(Null)),
+        ("text", Some(path)) => Ok(Box::new(Text::create(path)?)),
+        ("jsonl", None) => Ok(Box::new(JsonLines::new(std::io::stdout()))),
+        ("jsonl", Some(path)) => Ok(Box::new(JsonLines::create(path)?)),
+        _ => Err(format!("invalid output '{}', expected terminal, null, text:<path> or jsonl[:<path>]", spec).into()),
+    }
+}
+
+/// State shared by the challenges of a run
+pub struct Context {
+    output: RefCell<Box<dyn Output>>,
+}
+
+impl Default for Context {
+    /// Narrate to the terminal
+    fn default() -> Context {
+        Context::new(Box::new(Terminal))
+    }
+}
+
+impl Context {
+    pub fn new(output: Box<dyn Output>) -> Context {
+        Context { output: RefCell::new(output) }
+    }
+
+    /// Context with the output selected by `CRYPTOPALS_OUTPUT`, the terminal if unset
+    pub fn from_env() -> Result<Context> {
+        match std::env::var(OUTPUT_ENV) {
+            Ok(spec) => Ok(Context::new(from_spec(&spec)?)),
+            Err(_) => Ok(Context::default()),
+        }
+    }
+
+    pub fn line(&self, text: &str) {
+        self.output.borrow_mut().line(text);
+    }
+
+    pub fn section(&self, title: &str) {
+        self.output.borrow_mut().section(title);
+    }
+
+    pub fn subsection(&self, title: &str) {
+        self.output.borrow_mut().subsection(title);
+    }
+
+    pub fn value(&self, key: &str, value: &str) {
+        self.output.borrow_mut().value(key, value);
+    }
+
+    pub fn hexdump(&self, label: &str, bytes: &[u8]) {
+        self.output.borrow_mut().hexdump(label, bytes);
+    }
+}
+
+/// Write a formatted line to the output of a `Context`, like `println!`
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::outln;
+/// use cryptopals::output::{Context, Null};
+///
+/// let ctx = Context::new(Box::new(Null));
+/// outln!(ctx, "Key: {}", 42);
+/// outln!(ctx);
+/// ```
+#[macro_export]
+macro_rules! outln {
+    ($ctx:expr) => {
+        $ctx.line("")
+    };
+    ($ctx:expr, $($arg:tt)*) => {
+        $ctx.line(&format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text() {
+        let mut buffer = Vec::new();
+        let mut text = Text::new(&mut buffer);
+        text.section("Set 1");
+        text.line("key = 42");
+        assert_eq!("\n+-------+\n| Set 1 |\n+-------+\nkey = 42\n", String::from_utf8(buffer).unwrap());
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut buffer = Vec::new();
+        let mut jsonl = JsonLines::new(&mut buffer);
+        jsonl.subsection("Keys");
+        jsonl.line("two\nlines");
+        assert_eq!(
+            "{\"kind\":\"subsection\",\"text\":\"Keys\"}\n{\"kind\":\"line\",\"text\":\"two\\nlines\"}\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn values_and_hexdumps() {
+        let mut buffer = Vec::new();
+        let mut text = Text::new(&mut buffer);
+        text.value("Key", "ICE");
+        text.hexdump("Ciphertext", b"YELLOW SUBMARINE!");
+        assert_eq!(
+            "Key: ICE\nCiphertext:\n0 | 59454c4c4f57205355424d4152494e45\n1 | 21\n",
+            String::from_utf8(buffer).unwrap()
+        );
+
+        let mut buffer = Vec::new();
+        let mut jsonl = JsonLines::new(&mut buffer);
+        jsonl.hexdump("IV", &[0, 255]);
+        assert_eq!("{\"key\":\"IV\",\"kind\":\"hexdump\",\"text\":\"00ff\"}\n", String::from_utf8(buffer).unwrap());
+    }
+
+    #[test]
+    fn capture() {
+        let capture = Capture::new();
+        let ctx = Context::new(Box::new(capture.clone()));
+        ctx.value("Key", "first");
+        ctx.hexdump("IV", &[1, 2]);
+        ctx.value("Key", "second");
+        assert_eq!(Some("second".to_owned()), capture.value("Key"));
+        assert_eq!(None, capture.value("IV"));
+        assert_eq!(Record::Hexdump("IV".to_owned(), vec![1, 2]), capture.records()[1]);
+    }
+
+    #[test]
+    fn specs() {
+        assert!(from_spec("null").is_ok());
+        assert!(from_spec("terminal").is_ok());
+        assert!(from_spec("text").is_err());
+        assert!(from_spec("null:/tmp/x").is_err());
+        assert!(from_spec("html:out.html").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Named parameters of the challenges
+//!
+//! Every group and curve the attacks use is defined once here, with where it
+//! comes from. The constants hold the numbers as text, the functions parse
+//! them into the types of the crate.
+
+use num_bigint::BigUint;
+
+fn parse(digits: &str, radix: u32) -> BigUint {
+    BigUint::parse_bytes(digits.as_bytes(), radix).expect("invalid parameter")
+}
+
+/// Finite field Diffie-Hellman groups
+pub mod dh {
+    use num_bigint::BigUint;
+    use crate::dh::DhParams;
+    use super::parse;
+
+    /// 1536-bit MODP prime of RFC 3526, section 2, used from challenge 33 on
+    /// as the "NIST prime", in hexadecimal
+    pub const NIST_1536: &str = "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f14374fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7edee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf0598da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb9ed529077096966d670c354e4abc9804f1746c08ca237327ffffffffffffffff";
+    /// Generator of the RFC 3526 group
+    pub const NIST_1536_G: u32 = 2;
+
+    /// p, g and q of challenge 57, in decimal: (p - 1) / q has many small factors
+    pub const CHALLENGE57_P: &str = "7199773997391911030609999317773941274322764333428698921736339643928346453700085358802973900485592910475480089726140708102474957429903531369589969318716771";
+    pub const CHALLENGE57_G: &str = "4565356397095740655436854503483826832136106141639563487732438195343690437606117828318042418238184896212352329118608100083187535033402010599512641674644143";
+    pub const CHALLENGE57_Q: &str = "236234353446506858198510045061214171961";
+
+    /// p, g and q of challenge 58, in decimal: (p - 1) / q only has a few small factors
+    pub const CHALLENGE58_P: &str = "11470374874925275658116663507232161402086650258453896274534991676898999262641581519101074740642369848233294239851519212341844337347119899874391456329785623";
+    pub const CHALLENGE58_G: &str = "622952335333961296978159266084741085889881358738459939978290179936063635566740258555167783009058567397963466103140082647486611657350811560630587013183357";
+    pub const CHALLENGE58_Q: &str = "335062023296420808191071248367701059461";
+
+    pub fn nist_1536() -> BigUint {
+        parse(NIST_1536, 16)
+    }
+
+    pub fn challenge57() -> DhParams {
+        DhParams { p: parse(CHALLENGE57_P, 10), g: parse(CHALLENGE57_G, 10), q: parse(CHALLENGE57_Q, 10) }
+    }
+
+    pub fn challenge58() -> DhParams {
+        DhParams { p: parse(CHALLENGE58_P, 10), g: parse(CHALLENGE58_G, 10), q: parse(CHALLENGE58_Q, 10) }
+    }
+}
+
+/// Elliptic curves of set 8
+pub mod ec {
+    use num_bigint::{BigInt, BigUint};
+    use crate::ec::{EcParams, MontgomeryCurve, MontgomeryParams, Point, WeierstrassCurve};
+    use super::parse;
+
+    /// Field prime of the curves of challenges 59 and 60, in decimal
+    pub const CHALLENGE59_P: &str = "233970423115425145524320034830162017933";
+    /// y^2 = x^3 + a·x + b of challenge 59
+    pub const CHALLENGE59_A: i64 = -95051;
+    pub const CHALLENGE59_B: i64 = 11279326;
+    /// Base point of challenge 59, its y coordinate in decimal
+    pub const CHALLENGE59_BASE_X: u32 = 182;
+    pub const CHALLENGE59_BASE_Y: &str = "85518893674295321206118380980485522083";
+    /// Prime order of the base point, shared by the Montgomery form of challenge 60
+    pub const CHALLENGE59_ORDER: &str = "29246302889428143187362802287225875743";
+    /// b of the bogus curves of challenge 59 with the orders of their groups
+    pub const CHALLENGE59_BOGUS: [(i64, &str); 3] = [
+        (210, "233970423115425145550826547352470124412"),
+        (504, "233970423115425145544350131142039591210"),
+        (727, "233970423115425145545378039958152057148"),
+    ];
+
+    /// B·v^2 = u^3 + A·u^2 + u of challenge 60
+    pub const CHALLENGE60_A: u32 = 534;
+    pub const CHALLENGE60_B: u32 = 1;
+    /// u coordinate of the base point of challenge 60
+    pub const CHALLENGE60_BASE_U: u32 = 4;
+    /// Number of points of the curve of challenge 60, in decimal
+    pub const CHALLENGE60_CURVE_ORDER: &str = "233970423115425145498902418297807005944";
+
+    pub fn challenge59() -> EcParams {
+        EcParams {
+            curve: WeierstrassCurve::new(&parse(CHALLENGE59_P, 10), &BigInt::from(CHALLENGE59_A), &BigInt::from(CHALLENGE59_B)),
+            base: Point::new(BigUint::from(CHALLENGE59_BASE_X), parse(CHALLENGE59_BASE_Y, 10)),
+            order: parse(CHALLENGE59_ORDER, 10),
+        }
+    }
+
+    /// Curves of challenge 59 differing only by b, with their orders
+    pub fn challenge59_bogus_curves() -> Vec<(WeierstrassCurve, BigUint)> {
+        let curve = challenge59().curve;
+        CHALLENGE59_BOGUS.iter()
+            .map(|&(b, order)| (curve.with_b(&BigInt::from(b)), parse(order, 10)))
+            .collect()
+    }
+
+    pub fn challenge60() -> MontgomeryParams {
+        MontgomeryParams {
+            curve: MontgomeryCurve::new(&parse(CHALLENGE59_P, 10), &BigUint::from(CHALLENGE60_A), &BigUint::from(CHALLENGE60_B)),
+            base: BigUint::from(CHALLENGE60_BASE_U),
+            order: parse(CHALLENGE59_ORDER, 10),
+            curve_order: parse(CHALLENGE60_CURVE_ORDER, 10),
+        }
+    }
+}
+
+/// DSA domain parameters
+pub mod dsa {
+    use num_bigint::BigUint;
+    use super::parse;
+
+    /// p, q and g of challenge 43, in hexadecimal: q is a 160-bit prime
+    /// dividing p - 1, and g generates the subgroup of order q
+    pub const CHALLENGE43_P: &str = "800000000000000089e1855218a0e7dac38136ffafa72eda7859f2171e25e65eac698c1702578b07dc2a1076da241c76c62d374d8389ea5aeffd3226a0530cc565f3bf6b50929139ebeac04f48c3c84afb796d61e5a4f9a8fda812ab59494232c7d2b4deb50aa18ee9e132bfa85ac4374d7f9091abc3d015efc871a584471bb1";
+    pub const CHALLENGE43_Q: &str = "f4f47f05794b256174bba6e9b396a7707e563c5b";
+    pub const CHALLENGE43_G: &str = "5958c9d3898b224b12672c0b98e06c60df923cb8bc999d119458fef538b8fa4046c8db53039db620c094c9fa077ef389b5322a559946a71903f990f1f7e0e025e2d7f7cf494aff1a0470f5b64c36b625a097f1651fe775323556fe00b3608c887892878480e99041be601a62166ca6894bdd41a7054ec89f756ba9fc95302291";
+
+    /// (p, q, g) of challenge 43
+    pub fn challenge43() -> (BigUint, BigUint, BigUint) {
+        (parse(CHALLENGE43_P, 16), parse(CHALLENGE43_Q, 16), parse(CHALLENGE43_G, 16))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use num_traits::{One, Zero};
+    use crate::math;
+
+    /// q is prime and g generates a subgroup of order q
+    fn check_group(p: &BigUint, q: &BigUint, g: &BigUint) {
+        assert!(math::is_probable_prime(p, 20));
+        assert!(math::is_probable_prime(q, 20));
+        assert!(((p - 1u32) % q).is_zero());
+        assert!(g > &BigUint::one());
+        assert_eq!(BigUint::one(), g.modpow(q, p));
+    }
+
+    #[test]
+    fn nist_prime() {
+        let p = super::dh::nist_1536();
+        assert_eq!(1536, p.bits());
+        assert!(math::is_probable_prime(&p, 20));
+        // A safe prime: (p - 1) / 2 is prime too
+        assert!(math::is_probable_prime(&((&p - 1u32) >> 1), 20));
+    }
+
+    #[test]
+    fn dsa_group() {
+        let (p, q, g) = super::dsa::challenge43();
+        assert_eq!(1024, p.bits());
+        assert_eq!(160, q.bits());
+        check_group(&p, &q, &g);
+    }
+
+    #[test]
+    fn curves() {
+        let params = super::ec::challenge59();
+        assert!(params.curve.contains(&params.base));
+        assert!(math::is_probable_prime(&params.order, 20));
+        assert_eq!(3, super::ec::challenge59_bogus_curves().len());
+        let montgomery = super::ec::challenge60();
+        assert!(((&montgomery.curve_order) % (&montgomery.order)).is_zero());
+    }
+}
@@ -0,0 +1,791 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Hex, Base64, XOR and Hamming distance of byte strings, and AES-128
+//!
+//! These primitives only need `alloc`: they build without std with the
+//! `core` feature, e.g. for embedded experiments, and are re-exported by
+//! `crypto`. The hashes of `md4`, `md5` and `sha256`, and `bits` and `blocks`, also
+//! build without std.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::char;
+use core::fmt::Write;
+use aes::Aes128;
+use block_modes::{BlockMode, Cbc, Ecb};
+use block_modes::block_padding::{NoPadding, Pkcs7};
+use crate::blocks;
+use crate::error::{CryptopalsError, Result};
+#[cfg(feature = "simd")]
+use crate::simd;
+
+const BASE64_ALPHABET: [char; 65] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '+', '/', '='
+];
+
+const BASE64_URL_ALPHABET: [char; 65] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '-', '_', '='
+];
+
+/// Width of the Base64 lines of MIME bodies, see `BytesCrypto::base64_encode_wrapped`
+pub const MIME_LINE_WIDTH: usize = 76;
+
+/// Base64 alphabets, they only differ by their last two characters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alphabet {
+    /// RFC 4648 section 4, with `+` and `/`
+    #[default]
+    Standard,
+    /// RFC 4648 section 5, with `-` and `_`, used in URLs and tokens
+    UrlSafe,
+}
+
+impl Alphabet {
+    /// Characters of the sextets 0 to 63, followed by the padding
+    fn table(self) -> &'static [char; 65] {
+        match self {
+            Alphabet::Standard => &BASE64_ALPHABET,
+            Alphabet::UrlSafe => &BASE64_URL_ALPHABET,
+        }
+    }
+
+    /// Value of a Base64 character, `None` for padding and invalid characters
+    pub(crate) fn sextet(self, byte: u8) -> Option<u8> {
+        match (byte, self) {
+            // A to Z => 0 to 25
+            (65..=90, _) => Some(byte - 65),
+            // a to z => 26 to 51
+            (97..=122, _) => Some(byte - 97 + 26),
+            // 0 to 9 => 52 to 61
+            (48..=57, _) => Some(byte + 4),
+            // + or - => 62
+            (b'+', Alphabet::Standard) | (b'-', Alphabet::UrlSafe) => Some(62),
+            // / or _ => 63
+            (b'/', Alphabet::Standard) | (b'_', Alphabet::UrlSafe) => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// Options of `HexString::base64_decode_config`
+///
+/// The default decodes standard, padded Base64 like `HexString::base64_decode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeConfig {
+    pub alphabet: Alphabet,
+    /// Reject text whose length is not a multiple of 4
+    pub require_padding: bool,
+}
+
+impl Default for DecodeConfig {
+    fn default() -> Self {
+        DecodeConfig { alphabet: Alphabet::Standard, require_padding: true }
+    }
+}
+
+impl DecodeConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Accept text without its trailing `=`, like JWTs and many web APIs produce
+    pub fn with_optional_padding(mut self) -> Self {
+        self.require_padding = false;
+        self
+    }
+}
+
+/// Add hexadecimal string manipulation to strings.
+pub trait HexString {
+    fn hex2bytes(&self) -> Result<Vec<u8>>;
+    fn hex2bytes_lenient(&self) -> Result<Vec<u8>>;
+    fn hex2string(&self) -> Result<String>;
+    fn base64_decode(&self) -> Result<Vec<u8>>;
+    fn base64_decode_with(&self, alphabet: Alphabet) -> Result<Vec<u8>>;
+    fn base64_decode_config(&self, config: &DecodeConfig) -> Result<Vec<u8>>;
+}
+
+impl HexString for str {
+    /// Convert a hex string to an array of bytes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// assert_eq!(vec![65], "41".hex2bytes().unwrap());
+    /// assert_eq!(vec![16, 32, 48], "102030".hex2bytes().unwrap());
+    /// assert!("1020ZZ".hex2bytes().is_err());
+    /// assert!("+a".hex2bytes().is_err());
+    /// ```
+    fn hex2bytes(&self) -> Result<Vec<u8>> {
+        let l = self.len();
+        // Pairs of bytes are sliced, they must be whole characters, and
+        // from_str_radix would accept a sign ("+a")
+        if l == 0 || (l & 0b1) == 1 || !self.is_ascii() || self.contains('+') {
+            return Err(CryptopalsError::Hex(None));
+        }
+        (0..l)
+            .step_by(2)
+            .map(|i|
+                u8::from_str_radix(&self[i..i + 2], 16)
+                    .map_err(|e| e.into()))
+            .collect()
+    }
+
+    /// Convert a hex string pasted from another tool to an array of bytes
+    ///
+    /// Whitespace and line breaks separate groups of bytes, which may start
+    /// with `0x` and be split by colons. Every group and every part between
+    /// colons must hold whole bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// let bytes = vec![0xde, 0xad, 0xbe, 0xef];
+    /// assert_eq!(bytes, "de:ad:be:ef".hex2bytes_lenient().unwrap());
+    /// assert_eq!(bytes, "0xDEADBEEF".hex2bytes_lenient().unwrap());
+    /// assert_eq!(bytes, "dead\n  beef\n".hex2bytes_lenient().unwrap());
+    /// assert_eq!(bytes, "0xde 0xad 0xbe 0xef".hex2bytes_lenient().unwrap());
+    /// assert!("0xd 0xe".hex2bytes_lenient().is_err());
+    /// ```
+    fn hex2bytes_lenient(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.len() / 2);
+        for group in self.split_whitespace() {
+            let group = group.strip_prefix("0x").or_else(|| group.strip_prefix("0X")).unwrap_or(group);
+            // hex2bytes slices pairs of bytes, which must be characters
+            if !group.is_ascii() {
+                return Err(CryptopalsError::Hex(None));
+            }
+            for digits in group.split(':') {
+                bytes.extend(digits.hex2bytes()?);
+            }
+        }
+        if bytes.is_empty() {
+            return Err(CryptopalsError::Hex(None));
+        }
+        Ok(bytes)
+    }
+
+    /// Convert a hex string to a string
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// assert_eq!("A".to_owned(), "41".hex2string().unwrap());
+    /// assert_eq!("the kid don't play", "746865206b696420646f6e277420706c6179".hex2string().unwrap());
+    /// assert!("1020ZZ".hex2string().is_err());
+    /// ```
+    fn hex2string(&self) -> Result<String> {
+        let l = self.len();
+        if l == 0 || (l & 0b1) == 1 || !self.is_ascii() || self.contains('+') {
+            return Err(CryptopalsError::Hex(None));
+        }
+        let mut s = String::with_capacity(l / 2);
+        for i in (0..l).step_by(2) {
+            let c = u8::from_str_radix(&self[i..i + 2], 16)? as char;
+            s.push(c);
+        }
+        Ok(s)
+    }
+
+    /// Decode a Base64 string to a byte array
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::HexString;
+    ///
+    /// assert_eq!(String::from("QUJD").base64_decode().unwrap(), vec![65, 66, 67]);
+    /// assert_eq!("QUJD".base64_decode().unwrap(), &[65, 66, 67]);
+    /// assert_eq!("SGVsbG8sIHdvcmxkIQ==".base64_decode().unwrap(), "Hello, world!".as_bytes());
+    /// ```
+    ///
+    /// # References
+    ///
+    /// This code is inspired by [this article](https://levelup.gitconnected.com/implementing-base64-in-rust-34ef6db1e73a).
+    fn base64_decode(&self) -> Result<Vec<u8>> {
+        self.base64_decode_with(Alphabet::Standard)
+    }
+
+    /// Decode a Base64 string to a byte array, with the options of `config`
+    ///
+    /// Without required padding, a text of 4n + 2 or 4n + 3 characters is
+    /// decoded as if it ended with the missing `=`. A text of 4n + 1
+    /// characters is still invalid, since one character is not a whole byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::{Alphabet, DecodeConfig, HexString};
+    ///
+    /// let config = DecodeConfig::new().with_alphabet(Alphabet::UrlSafe).with_optional_padding();
+    /// assert_eq!(b"Hello, world!".to_vec(), "SGVsbG8sIHdvcmxkIQ".base64_decode_config(&config).unwrap());
+    /// assert_eq!(b"Hello, world!".to_vec(), "SGVsbG8sIHdvcmxkIQ==".base64_decode_config(&config).unwrap());
+    /// assert!("SGVsbG8sIHdvcmxkIQ".base64_decode_config(&DecodeConfig::new()).is_err());
+    /// ```
+    fn base64_decode_config(&self, config: &DecodeConfig) -> Result<Vec<u8>> {
+        if config.require_padding {
+            return self.base64_decode_with(config.alphabet);
+        }
+        let mut padded: String = self.chars().filter(|&c| c != '\n' && c != '\r').collect();
+        match padded.len() % 4 {
+            2 => padded.push_str("=="),
+            3 => padded.push('='),
+            _ => (),
+        }
+        padded.base64_decode_with(config.alphabet)
+    }
+
+    /// Decode a Base64 string written with `alphabet` to a byte array
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::{Alphabet, HexString};
+    ///
+    /// assert_eq!(vec![0xfb, 0xff], "-_8=".base64_decode_with(Alphabet::UrlSafe).unwrap());
+    /// assert!("+/8=".base64_decode_with(Alphabet::UrlSafe).is_err());
+    /// ```
+    fn base64_decode_with(&self, alphabet: Alphabet) -> Result<Vec<u8>> {
+        // Remove return chars first, so that the length is known when checking the padding
+        let b64: Vec<u8> = self.bytes().filter(|&b| b != b'\n' && b != b'\r').collect();
+        let mut padding_count = 0;
+        let b64_bytes = b64.iter()
+            .enumerate()
+            .map(|(index, &byte)| {
+                if let Some(sextet) = alphabet.sextet(byte) {
+                    // Nothing but padding after the padding
+                    if padding_count > 0 {
+                        return Err(CryptopalsError::Base64(format!("invalid byte '=' at position {} in Base64 string", index - 1)));
+                    }
+                    return Ok(sextet);
+                }
+                match byte {
+                    // Equal sign only authorized in the last two positions
+                    b'=' if index + 2 >= b64.len() => {
+                        padding_count += 1;
+                        Ok(0)
+                    }
+                    b'=' => Err(CryptopalsError::Base64(format!("invalid byte '=' at position {} in Base64 string", index))),
+                    _ => Err(CryptopalsError::Base64(format!("invalid byte '{}' (0x{:X}) at position {} in Base64 string", byte as char, byte, index)))
+                }
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        if b64.len() % 4 != 0 {
+            return Err(CryptopalsError::Base64(format!("invalid Base64 length: {}", b64.len())));
+        }
+        let mut bytes = b64_bytes
+            .chunks(4)
+            .map(|quartet| {
+                let b1 = quartet[0] << 2                | (quartet[1] & 0b00110000) >> 4;
+                let b2 = (quartet[1] & 0b00001111) << 4 | (quartet[2] & 0b00111100) >> 2;
+                let b3 = (quartet[2] & 0b00000011) << 6 | quartet[3];
+                vec![b1, b2, b3]
+            })
+            .flatten()
+            .collect::<Vec<u8>>();
+        // Remove extra bytes created by the padding
+        bytes.resize(bytes.len() - padding_count, 0);
+        Ok(bytes)
+    }
+}
+
+/// Options of `BytesCrypto::bytes2hex_with`, to compare with the vectors of papers and RFCs
+///
+/// The default is the lowercase, ungrouped output of `BytesCrypto::bytes2hex`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HexFormat {
+    pub uppercase: bool,
+    /// Write `0x` before every group
+    pub prefix: bool,
+    /// Bytes per group, 0 for a single group
+    pub group: usize,
+    /// Written between the groups
+    pub separator: &'static str,
+}
+
+impl HexFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_uppercase(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    pub fn with_prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+
+    /// Split the output in groups of `group` bytes, e.g. 1 and `":"` for `de:ad:be:ef`
+    pub fn with_groups(mut self, group: usize, separator: &'static str) -> Self {
+        self.group = group;
+        self.separator = separator;
+        self
+    }
+}
+
+/// Add hexadecimal strings, base64 and xor functions to arrays of bytes.
+pub trait BytesCrypto {
+    fn bytes2hex(&self) -> String;
+    fn bytes2hex_with(&self, format: &HexFormat) -> String;
+    fn base64_encode(&self) -> String;
+    fn base64_encode_with(&self, alphabet: Alphabet) -> String;
+    fn base64_encode_wrapped(&self, width: usize) -> String;
+    fn xor(&self, other: &[u8]) -> Vec<u8>;
+    fn xor_truncating(&self, other: &[u8]) -> Vec<u8>;
+    fn xor_strict(&self, other: &[u8]) -> Result<Vec<u8>>;
+    fn xor_repeating(&self, key: &[u8]) -> Vec<u8>;
+    fn xor_in_place(&mut self, other: &[u8]);
+    fn hamming_distance(&self, other: &[u8]) -> Result<u32>;
+    fn normalized_hamming(&self, keysize: usize, n_blocks: usize) -> Result<f64>;
+}
+
+impl BytesCrypto for [u8] {
+    /// Convert an array of bytes to a hex string
+     ///
+     /// # Examples
+     ///
+     /// ```
+     /// use cryptopals::crypto::BytesCrypto;
+     ///
+     /// assert_eq!("41".to_owned(), vec![65].bytes2hex());
+     /// assert_eq!(
+     ///    "48656c6c6f2c20776f726c6421".to_owned(),
+     ///    vec![72, 101, 108, 108, 111, 44, 32, 119, 111, 114, 108, 100, 33].bytes2hex());
+     /// ```
+    fn bytes2hex(&self) -> String {
+        self.bytes2hex_with(&HexFormat::default())
+    }
+
+    /// Convert an array of bytes to a hex string formatted by `format`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::{BytesCrypto, HexFormat};
+    ///
+    /// let bytes = [0xde, 0xad, 0xbe, 0xef];
+    /// assert_eq!("0xDEADBEEF", bytes.bytes2hex_with(&HexFormat::new().with_uppercase().with_prefix()));
+    /// assert_eq!("de:ad:be:ef", bytes.bytes2hex_with(&HexFormat::new().with_groups(1, ":")));
+    /// assert_eq!("dead beef", bytes.bytes2hex_with(&HexFormat::new().with_groups(2, " ")));
+    /// ```
+    fn bytes2hex_with(&self, format: &HexFormat) -> String {
+        // Empty arrays have no group, even with a prefix
+        let group = if format.group == 0 { self.len().max(1) } else { format.group };
+        let mut s = String::with_capacity(self.len() * 3);
+        for (index, chunk) in self.chunks(group).enumerate() {
+            if index > 0 {
+                s.push_str(format.separator);
+            }
+            if format.prefix {
+                s.push_str("0x");
+            }
+            for b in chunk {
+                if format.uppercase {
+                    write!(&mut s, "{:02X}", b).unwrap();
+                } else {
+                    write!(&mut s, "{:02x}", b).unwrap();
+                }
+            }
+        }
+        s
+    }
+
+    /// Convert an array of bytes to Base64
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(String::from("QUJD"), [65, 66, 67].base64_encode());
+    /// assert_eq!(String::from("QUJD"), vec![65, 66, 67].base64_encode());
+    /// assert_eq!(String::from("SGVsbG8sIHdvcmxkIQ=="), "Hello, world!".as_bytes().base64_encode());
+    /// ```
+    ///
+    /// # References
+    ///
+    /// This code is inspired by [this article](https://levelup.gitconnected.com/implementing-base64-in-rust-34ef6db1e73a).
+    fn base64_encode(&self) -> String {
+        self.base64_encode_with(Alphabet::Standard)
+    }
+
+    /// Convert an array of bytes to Base64 with `alphabet`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::{Alphabet, BytesCrypto};
+    ///
+    /// assert_eq!("+/8=", [0xfb, 0xff].base64_encode_with(Alphabet::Standard));
+    /// assert_eq!("-_8=", [0xfb, 0xff].base64_encode_with(Alphabet::UrlSafe));
+    /// ```
+    fn base64_encode_with(&self, alphabet: Alphabet) -> String {
+        let table = alphabet.table();
+        self
+            .chunks(3)
+            .map(|chunk| {
+                match chunk.len() {
+                    1 => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4, 64, 64],
+                    2 => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4 | (chunk[1] & 0b11110000) >> 4, (chunk[1] & 0b00001111) << 2, 64],
+                    _ => [chunk[0] >> 2, (chunk[0] & 0b00000011) << 4 | (chunk[1] & 0b11110000) >> 4, (chunk[1] & 0b00001111) << 2 | (chunk[2] & 0b11000000) >> 6, chunk[2] & 0b00111111],
+                }.iter()
+                    .map(|x| table[*x as usize])
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("")
+    }
+
+    /// Convert an array of bytes to Base64 lines of `width` characters, separated by CRLF
+    ///
+    /// MIME bodies use lines of `MIME_LINE_WIDTH` characters. The last line
+    /// has no line break, and a zero `width` does not wrap at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::{self, BytesCrypto};
+    ///
+    /// assert_eq!("SGVsbG8s\r\nIHdvcmxk\r\nIQ==", "Hello, world!".as_bytes().base64_encode_wrapped(8));
+    /// let wrapped = [0; 60].base64_encode_wrapped(crypto::MIME_LINE_WIDTH);
+    /// assert_eq!(vec![76, 4], wrapped.split("\r\n").map(str::len).collect::<Vec<_>>());
+    /// ```
+    fn base64_encode_wrapped(&self, width: usize) -> String {
+        let encoded = self.base64_encode();
+        if width == 0 {
+            return encoded;
+        }
+        encoded.as_bytes()
+            .chunks(width)
+            // Base64 is ASCII, every chunk is valid UTF-8
+            .map(|line| core::str::from_utf8(line).unwrap())
+            .collect::<Vec<&str>>()
+            .join("\r\n")
+    }
+
+    /// XOR two equal length arrays of bytes
+    ///
+    /// Arrays of different lengths are truncated to the shorter one, see
+    /// `xor_truncating`, `xor_strict` and `xor_repeating` to choose.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(vec![0], vec![0].xor(&vec![0]));
+    /// assert_eq!(
+    ///    vec![0b11111111, 0b01101100],
+    ///    vec![0b10101010, 0b11111111].xor(&vec![0b01010101, 0b10010011])
+    /// );
+    /// ```
+    fn xor(&self, other: &[u8]) -> Vec<u8> {
+        self.xor_truncating(other)
+    }
+
+    /// XOR two arrays of bytes, up to the length of the shorter one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(vec![3], [1, 2, 3].xor_truncating(&[2]));
+    /// ```
+    fn xor_truncating(&self, other: &[u8]) -> Vec<u8> {
+        #[cfg(feature = "simd")]
+        {
+            simd::xor(self, other)
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            self.iter().zip(other.iter())
+                .map(|(&x, &y)| x ^ y)
+                .collect()
+        }
+    }
+
+    /// XOR two arrays of bytes, which must have the same length
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(vec![3, 3], [1, 2].xor_strict(&[2, 1]).unwrap());
+    /// assert!([1, 2, 3].xor_strict(&[2]).is_err());
+    /// ```
+    fn xor_strict(&self, other: &[u8]) -> Result<Vec<u8>> {
+        if self.len() != other.len() {
+            return Err(CryptopalsError::Format(format!("cannot XOR {} bytes with {} bytes", self.len(), other.len())));
+        }
+        Ok(self.xor_truncating(other))
+    }
+
+    /// XOR an array of bytes with a repeating key, like challenge 5
+    ///
+    /// The output has the length of the array, and is empty with an empty key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(vec![0, 0, 0, 0, 0], b"ICEIC".xor_repeating(b"ICE"));
+    /// assert_eq!(vec![3], [1].xor_repeating(&[2, 3]));
+    /// ```
+    fn xor_repeating(&self, key: &[u8]) -> Vec<u8> {
+        self.iter().zip(key.iter().cycle())
+            .map(|(&x, &y)| x ^ y)
+            .collect()
+    }
+
+    /// XOR an array of bytes with another one without allocating, up to the shorter one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// let mut bytes = [1, 2, 3];
+    /// bytes.xor_in_place(&[1, 1]);
+    /// assert_eq!([0, 3, 3], bytes);
+    /// ```
+    fn xor_in_place(&mut self, other: &[u8]) {
+        for (x, y) in self.iter_mut().zip(other) {
+            *x ^= y;
+        }
+    }
+
+    /// Compute the Hamming distance between two byte arrays of the same length
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// assert_eq!(
+    ///            37,
+    ///            "this is a test".as_bytes()
+    ///                .hamming_distance("wokka wokka!!!".as_bytes()).unwrap());
+    /// assert!(b"this is a test".hamming_distance(b"wokka").is_err());
+    /// ```
+    fn hamming_distance(&self, other: &[u8]) -> Result<u32> {
+        if self.len() != other.len() {
+            return Err(CryptopalsError::Format(format!("bytes arrays differ in size: {} and {}", self.len(), other.len())));
+        }
+
+        #[cfg(feature = "simd")]
+        {
+            Ok(simd::hamming_distance(self, other))
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            Ok(crate::bits::hamming_weight(&self.xor(other)))
+        }
+    }
+
+    /// Mean Hamming distance per byte between the adjacent blocks of `keysize` bytes
+    ///
+    /// The first `n_blocks` blocks give `n_blocks - 1` pairs, which must all
+    /// fit in the array. Challenge 6 guesses the key size with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::crypto::BytesCrypto;
+    ///
+    /// // 'a' ^ 'b' = 3 has two bits set, 'b' ^ 'b' none
+    /// assert_eq!(1.0, b"aabbbb".normalized_hamming(2, 3).unwrap());
+    /// assert!(b"aabbbb".normalized_hamming(2, 4).is_err());
+    /// ```
+    fn normalized_hamming(&self, keysize: usize, n_blocks: usize) -> Result<f64> {
+        if keysize == 0 || n_blocks < 2 || self.len() < keysize * n_blocks {
+            return Err(CryptopalsError::Format(format!(
+                "{} blocks of {} bytes need at least two blocks in {} bytes", n_blocks, keysize, self.len())));
+        }
+        let mut distance = 0;
+        for i in 1..n_blocks {
+            distance += self[((i - 1) * keysize)..(i * keysize)]
+                .hamming_distance(&self[(i * keysize)..((i + 1) * keysize)])?;
+        }
+        Ok(distance as f64 / ((n_blocks - 1) * keysize) as f64)
+    }
+}
+
+/// XOR `input` with a repeating `key` into `output`, reusing its allocation
+///
+/// `output` is cleared first and gets the length of `input`, or stays empty
+/// with an empty key. Attacks trying thousands of keys can keep a single
+/// buffer instead of allocating a new plaintext per key.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::crypto;
+///
+/// let mut plaintext = Vec::new();
+/// for key in 0..=255 {
+///     crypto::xor_with_key_into(b"ICE", &[key], &mut plaintext);
+/// }
+/// assert_eq!(vec![b'I' ^ 255, b'C' ^ 255, b'E' ^ 255], plaintext);
+/// ```
+pub fn xor_with_key_into(input: &[u8], key: &[u8], output: &mut Vec<u8>) {
+    output.clear();
+    output.extend(input.iter().zip(key.iter().cycle()).map(|(&x, &y)| x ^ y));
+}
+
+/// Compare two byte arrays in a time independent of their content
+///
+/// Every byte is compared, so the time only reveals the lengths, which are
+/// public for MACs and tags.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::crypto;
+///
+/// assert!(crypto::ct_eq(b"tag", b"tag"));
+/// assert!(!crypto::ct_eq(b"tag", b"tap"));
+/// assert!(!crypto::ct_eq(b"tag", b"tags"));
+/// ```
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y));
+    // Keep the compiler from turning the fold into an early exit
+    core::hint::black_box(difference) == 0
+}
+
+
+/// AES block size
+pub const AES_BLOCK_SIZE: usize = 16;
+
+/// Check the length of an AES-128 key or IV
+fn aes_block(bytes: &[u8], name: &str) -> Result<[u8; AES_BLOCK_SIZE]> {
+    let mut block = [0u8; AES_BLOCK_SIZE];
+    if bytes.len() != AES_BLOCK_SIZE {
+        return Err(CryptopalsError::Format(format!("invalid AES-128 {} length: {}", name, bytes.len())));
+    }
+    block.copy_from_slice(bytes);
+    Ok(block)
+}
+
+/// Decrypt whole AES blocks in place, then remove the PKCS#7 padding
+fn aes_unpad<M: BlockMode<Aes128, NoPadding>>(mode: M, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let mut buffer = ciphertext.to_vec();
+    mode.decrypt(&mut buffer)
+        .map_err(|_| CryptopalsError::Padding(format!("{} bytes are not whole AES blocks", ciphertext.len())))?;
+    Ok(blocks::pkcs7_unpad(&buffer, AES_BLOCK_SIZE)?.to_vec())
+}
+
+/// AES-128 in ECB mode with PKCS#7 padding
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::crypto::Aes128Ecb;
+///
+/// let ecb = Aes128Ecb::new(b"YELLOW SUBMARINE").unwrap();
+/// let ciphertext = ecb.encrypt(b"I'm back and I'm ringin' the bell");
+/// assert_eq!(48, ciphertext.len());
+/// assert_eq!(b"I'm back and I'm ringin' the bell".to_vec(), ecb.decrypt(&ciphertext).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct Aes128Ecb {
+    key: [u8; AES_BLOCK_SIZE],
+}
+
+impl Aes128Ecb {
+    /// Create an ECB cipher, the key must be 16 bytes long
+    pub fn new(key: &[u8]) -> Result<Aes128Ecb> {
+        Ok(Aes128Ecb { key: aes_block(key, "key")? })
+    }
+
+    /// Pad and encrypt a plaintext
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        // The key length was checked by the constructor
+        Ecb::<Aes128, Pkcs7>::new_var(&self.key, &[]).unwrap().encrypt_vec(plaintext)
+    }
+
+    /// Decrypt a ciphertext and remove its padding
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        aes_unpad(Ecb::<Aes128, NoPadding>::new_var(&self.key, &[]).unwrap(), ciphertext)
+    }
+}
+
+/// AES-128 in CBC mode with PKCS#7 padding
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::crypto::Aes128Cbc;
+///
+/// let cbc = Aes128Cbc::new(b"YELLOW SUBMARINE", &[0; 16]).unwrap();
+/// let ciphertext = cbc.encrypt(b"comment1=cooking%20MCs");
+/// assert_eq!(32, ciphertext.len());
+/// assert_eq!(b"comment1=cooking%20MCs".to_vec(), cbc.decrypt(&ciphertext).unwrap());
+/// assert!(cbc.decrypt(&ciphertext[..20]).is_err());
+/// ```
+#[derive(Clone)]
+pub struct Aes128Cbc {
+    key: [u8; AES_BLOCK_SIZE],
+    iv: [u8; AES_BLOCK_SIZE],
+}
+
+impl Aes128Cbc {
+    /// Create a CBC cipher, the key and the IV must be 16 bytes long
+    pub fn new(key: &[u8], iv: &[u8]) -> Result<Aes128Cbc> {
+        Ok(Aes128Cbc { key: aes_block(key, "key")?, iv: aes_block(iv, "IV")? })
+    }
+
+    /// Initialization vector
+    pub fn iv(&self) -> &[u8] {
+        &self.iv
+    }
+
+    /// Pad and encrypt a plaintext
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        // The key and IV lengths were checked by the constructor
+        Cbc::<Aes128, Pkcs7>::new_var(&self.key, &self.iv).unwrap().encrypt_vec(plaintext)
+    }
+
+    /// Decrypt a ciphertext and remove its padding
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        aes_unpad(Cbc::<Aes128, NoPadding>::new_var(&self.key, &self.iv).unwrap(), ciphertext)
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! RC4 single-byte bias attack on an encrypted cookie
+//!
+//! The 16th and 32nd bytes of the RC4 keystream are biased towards 240 and 224
+//! (AlFardan et al., "On the Security of RC4 in TLS", 2013). Aligning a secret
+//! byte on one of these positions and encrypting it under many random keys
+//! reveals it: the most frequent ciphertext byte is the secret XOR the bias.
+
+use std::error::Error;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::cipher::{Rc4, StreamCipher};
+use crate::keygen;
+use crate::oracle::ParallelOracle;
+use crate::stats::ByteHistogram;
+use crate::timebox::{Checkpoint, Deadline, NoProgress, Outcome, Progress};
+
+/// Base64 encoded cookie of challenge 56
+pub const COOKIE: &str = "QkUgU1VSRSBUTyBEUklOSyBZT1VSIE9WQUxUSU5F";
+
+/// Keystream position of the Z16 bias, and its favourite byte
+pub const Z16: (usize, u8) = (15, 240);
+/// Keystream position of the Z32 bias, and its favourite byte
+pub const Z32: (usize, u8) = (31, 224);
+
+/// Encryptions per parallel query
+const SAMPLES_PER_QUERY: usize = 1 << 14;
+
+/// Oracle encrypting a request followed by a secret cookie
+pub struct CookieOracle {
+    cookie: Vec<u8>,
+}
+
+impl CookieOracle {
+    pub fn new(cookie: &[u8]) -> CookieOracle {
+        CookieOracle { cookie: cookie.to_vec() }
+    }
+
+    /// Encrypt `request || cookie` with RC4 under a fresh random 128-bit key
+    pub fn encrypt(&self, request: &[u8]) -> Vec<u8> {
+        self.encrypt_with_rng(request, &mut rand::thread_rng())
+    }
+
+    /// Same as `encrypt`, drawing the key from `rng`
+    pub fn encrypt_with_rng<R: RngCore + ?Sized>(&self, request: &[u8], rng: &mut R) -> Vec<u8> {
+        let key = keygen::rc4_key(rng);
+        let mut plaintext = request.to_vec();
+        plaintext.extend_from_slice(&self.cookie);
+        // A 16 byte key is always valid
+        Rc4::new(&key).unwrap().apply_keystream(&plaintext)
+    }
+}
+
+/// Statistics of the cookie attack, which can be stopped and resumed
+///
+/// A request of `p` bytes, for `p` in 0..16, puts cookie byte `15 - p` on Z16 and
+/// cookie byte `31 - p` on Z32. The ciphertext bytes seen there are counted for
+/// every request length.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CookieAttack {
+    cookie_len: usize,
+    /// Encryptions of each request so far
+    samples: usize,
+    /// Z16 and Z32 histograms, indexed by request length
+    histograms: Vec<(ByteHistogram, ByteHistogram)>,
+}
+
+impl CookieAttack {
+    /// Start an attack on a cookie of at most 32 bytes
+    pub fn new(cookie_len: usize) -> CookieAttack {
+        CookieAttack {
+            cookie_len: cookie_len.min(Z32.0 + 1),
+            samples: 0,
+            histograms: vec![(ByteHistogram::new(), ByteHistogram::new()); Z16.0 + 1],
+        }
+    }
+
+    /// Length of the attacked cookie
+    pub fn cookie_len(&self) -> usize {
+        self.cookie_len
+    }
+
+    /// Number of encryptions of each request so far
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Best guess of the cookie with the statistics so far
+    pub fn guess(&self) -> Vec<u8> {
+        let mut cookie = vec![0u8; self.cookie_len];
+        for (prefix_len, (z16, z32)) in self.histograms.iter().enumerate() {
+            if let Some(byte) = cookie.get_mut(Z16.0 - prefix_len) {
+                *byte = z16.most_frequent() ^ Z16.1;
+            }
+            if let Some(byte) = cookie.get_mut(Z32.0 - prefix_len) {
+                *byte = z32.most_frequent() ^ Z32.1;
+            }
+        }
+        cookie
+    }
+
+    /// Send each request until it has been encrypted `samples` times in total, or the deadline
+    ///
+    /// Requests are sent in parallel over all the CPUs, the deadline is checked
+    /// between batches of `SAMPLES_PER_QUERY` encryptions per CPU.
+    pub fn run<F>(self, oracle: F, samples: usize, deadline: &Deadline) -> Outcome<Vec<u8>, CookieAttack>
+        where F: Fn(&[u8]) -> Vec<u8> + Sync
+    {
+        self.run_with_progress(oracle, samples, deadline, &NoProgress)
+    }
+
+    /// Same as `run`, reporting the number of encryptions of each request after every batch
+    pub fn run_with_progress<F, P>(mut self, oracle: F, samples: usize, deadline: &Deadline, progress: &P)
+        -> Outcome<Vec<u8>, CookieAttack>
+        where F: Fn(&[u8]) -> Vec<u8> + Sync, P: Progress + ?Sized
+    {
+        let sampler = ParallelOracle::new(|&(prefix_len, count): &(usize, usize)| {
+            let request = vec![b'A'; prefix_len];
+            let mut z16 = ByteHistogram::new();
+            let mut z32 = ByteHistogram::new();
+            for _ in 0..count {
+                let ciphertext = oracle(&request);
+                if let Some(&byte) = ciphertext.get(Z16.0) {
+                    z16.add(byte);
+                }
+                if let Some(&byte) = ciphertext.get(Z32.0) {
+                    z32.add(byte);
+                }
+            }
+            (prefix_len, z16, z32)
+        });
+
+        while self.samples < samples {
+            if deadline.expired() {
+                return Outcome::Partial { best: Some(self.guess()), state: self };
+            }
+            // One query is a number of encryptions of a given request length
+            let batch = (samples - self.samples).min(SAMPLES_PER_QUERY * sampler.concurrency());
+            let mut queries = Vec::new();
+            for prefix_len in 0..=Z16.0 {
+                let mut remaining = batch;
+                while remaining > 0 {
+                    let chunk = remaining.min(SAMPLES_PER_QUERY);
+                    queries.push((prefix_len, chunk));
+                    remaining -= chunk;
+                }
+            }
+            for (prefix_len, z16, z32) in sampler.query_all(&queries) {
+                self.histograms[prefix_len].0.merge(&z16);
+                self.histograms[prefix_len].1.merge(&z32);
+            }
+            self.samples += batch;
+            progress.step(self.samples as u64, Some(samples as u64));
+        }
+        Outcome::Complete(self.guess())
+    }
+}
+
+impl Checkpoint for CookieAttack {
+    const KIND: &'static str = "rc4-cookie";
+
+    /// A cookie of at most 32 bytes, and a pair of histograms per request length
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.cookie_len > Z32.0 + 1 {
+            return Err(format!("cookie of {} bytes in the checkpoint, at most {} are supported",
+                               self.cookie_len, Z32.0 + 1).into());
+        }
+        if self.histograms.len() != Z16.0 + 1 {
+            return Err(format!("{} histograms in the checkpoint, expected {}", self.histograms.len(), Z16.0 + 1).into());
+        }
+        Ok(())
+    }
+}
+
+/// Recover a cookie of at most 32 bytes from an oracle encrypting `request || cookie`
+///
+/// Each request is sent `samples` times, see `CookieAttack`.
+pub fn recover_cookie<F>(oracle: F, samples: usize) -> Vec<u8>
+    where F: Fn(&[u8]) -> Vec<u8> + Sync
+{
+    let cookie_len = oracle(b"").len();
+    // Without a deadline the attack always completes
+    CookieAttack::new(cookie_len).run(oracle, samples, &Deadline::never()).best().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use crate::timebox::ProgressCounter;
+
+    /// Keystream with the Z16 and Z32 biases made much stronger than in RC4
+    fn biased_keystream(len: usize) -> Vec<u8> {
+        let mut keystream = vec![0u8; len];
+        rand::thread_rng().fill_bytes(&mut keystream);
+        for &(position, bias) in &[Z16, Z32] {
+            if position < len && rand::thread_rng().next_u32() & 3 == 0 {
+                keystream[position] = bias;
+            }
+        }
+        keystream
+    }
+
+    fn biased_oracle(cookie: &[u8]) -> impl Fn(&[u8]) -> Vec<u8> + Sync + '_ {
+        move |request: &[u8]| {
+            let mut plaintext = request.to_vec();
+            plaintext.extend_from_slice(cookie);
+            plaintext.iter().zip(biased_keystream(plaintext.len()))
+                .map(|(a, b)| a ^ b)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn recover_with_strong_bias() {
+        let cookie = b"BE SURE TO DRINK YOUR OVALTINE";
+        assert_eq!(cookie.to_vec(), recover_cookie(biased_oracle(cookie), 1000));
+    }
+
+    #[test]
+    fn recover_short_cookie() {
+        let cookie = b"secret";
+        assert_eq!(cookie.to_vec(), recover_cookie(biased_oracle(cookie), 1000));
+    }
+
+    #[test]
+    fn stop_and_resume() {
+        let cookie = b"BE SURE TO DRINK YOUR OVALTINE";
+        let oracle = biased_oracle(cookie);
+        let expired = Deadline::after(Duration::from_secs(0));
+        let attack = match CookieAttack::new(cookie.len()).run(&oracle, 1000, &expired) {
+            Outcome::Partial { best, state } => {
+                assert_eq!(Some(cookie.len()), best.map(|guess| guess.len()));
+                state
+            }
+            Outcome::Complete(_) => panic!("the deadline was ignored"),
+        };
+        assert_eq!(0, attack.samples());
+        assert_eq!(Some(cookie.to_vec()), attack.run(&oracle, 1000, &Deadline::never()).best());
+    }
+
+    #[test]
+    fn progress_reports() {
+        let cookie = b"secret";
+        let counter = ProgressCounter::default();
+        let outcome = CookieAttack::new(cookie.len()).run_with_progress(biased_oracle(cookie), 1000, &Deadline::never(), &counter);
+        assert_eq!(Some(cookie.to_vec()), outcome.best());
+        // A single batch is enough for 1000 samples
+        assert_eq!(1, counter.steps());
+        assert_eq!(Some((1000, Some(1000))), counter.last_step());
+    }
+
+    #[test]
+    fn checkpoint() {
+        let cookie = b"secret cookie";
+        let oracle = biased_oracle(cookie);
+
+        // Stop an attack right away, then save and reload it
+        let expired = Deadline::after(Duration::from_secs(0));
+        let state = match CookieAttack::new(cookie.len()).run(&oracle, 300, &expired) {
+            Outcome::Partial { state, .. } => state,
+            Outcome::Complete(_) => panic!("the deadline was ignored"),
+        };
+        let path = std::env::temp_dir().join(format!("cryptopals-rc4bias-{}.json", std::process::id()));
+        state.save_checkpoint(&path).unwrap();
+        let resumed = CookieAttack::resume_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.samples(), resumed.samples());
+        assert_eq!(state.guess(), resumed.guess());
+        assert_eq!(Some(cookie.to_vec()), resumed.run(&oracle, 1000, &Deadline::never()).best());
+    }
+
+    #[test]
+    fn invalid_checkpoint() {
+        let path = std::env::temp_dir().join(format!("cryptopals-rc4bias-invalid-{}.json", std::process::id()));
+        let mut state = CookieAttack::new(13);
+        state.histograms.truncate(3);
+        state.save_checkpoint(&path).unwrap();
+        assert!(CookieAttack::resume_from(&path).is_err());
+
+        let mut state = CookieAttack::new(13);
+        state.cookie_len = 40;
+        state.save_checkpoint(&path).unwrap();
+        assert!(CookieAttack::resume_from(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oracle_length() {
+        let oracle = CookieOracle::new(b"cookie");
+        assert_eq!(10, oracle.encrypt(b"test").len());
+        assert_ne!(oracle.encrypt(b""), oracle.encrypt(b""));
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! RSA signatures and duplicate signature key selection
+//!
+//! A verifier checks that s^e = pad(m) mod N. Knowing s and m, a new modulus
+//! N' = p * q with smooth p - 1 and q - 1 makes the discrete logarithm of pad(m)
+//! to the base s easy with Pohlig-Hellman, and gives an e' under which the
+//! signature verifies.
+
+use num_bigint::BigUint;
+use num_traits::One;
+use sha2::{Digest, Sha256};
+use crate::{dlog, math};
+
+/// Public exponent of the generated keys
+pub const E: u32 = 65537;
+
+/// Upper bound of the factors of p - 1 and q - 1 in forged keys
+pub const FACTOR_BOUND: u64 = 1 << 16;
+
+/// DER encoded DigestInfo prefix of a SHA-256 digest
+const SHA256_DIGEST_INFO: &[u8] = &[
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+];
+
+/// PKCS#1 v1.5 encoding of the SHA-256 digest of a message, as long as the modulus
+///
+/// 00 01 FF ... FF 00 DigestInfo digest
+///
+/// # Panics:
+///
+/// The function panics if the modulus is too short to hold the encoding.
+pub fn pkcs1_pad(message: &[u8], n: &BigUint) -> BigUint {
+    let len = n.bits().div_ceil(8) as usize;
+    let digest = Sha256::digest(message);
+    let suffix_len = SHA256_DIGEST_INFO.len() + digest.len();
+    assert!(len >= suffix_len + 11, "modulus too short for a PKCS#1 signature");
+    let mut encoded = vec![0x00, 0x01];
+    encoded.resize(len - suffix_len - 1, 0xff);
+    encoded.push(0x00);
+    encoded.extend_from_slice(SHA256_DIGEST_INFO);
+    encoded.extend_from_slice(&digest);
+    BigUint::from_bytes_be(&encoded)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub n: BigUint,
+    pub e: BigUint,
+}
+
+impl PublicKey {
+    pub fn verify(&self, message: &[u8], signature: &BigUint) -> bool {
+        *signature < self.n && signature.modpow(&self.e, &self.n) == pkcs1_pad(message, &self.n)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateKey {
+    public: PublicKey,
+    d: BigUint,
+}
+
+impl PrivateKey {
+    /// Generate a key with a modulus made of two primes of `bits / 2` bits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::rsa::PrivateKey;
+    ///
+    /// let key = PrivateKey::generate(512);
+    /// let signature = key.sign(b"hi mom");
+    /// assert!(key.public_key().verify(b"hi mom", &signature));
+    /// assert!(!key.public_key().verify(b"hi dad", &signature));
+    /// ```
+    pub fn generate(bits: u64) -> PrivateKey {
+        let e = BigUint::from(E);
+        loop {
+            let p = math::random_prime(bits / 2);
+            let q = math::random_prime(bits - bits / 2);
+            if p == q {
+                continue;
+            }
+            let phi = (&p - 1u32) * (&q - 1u32);
+            if let Some(d) = math::modinv(&e, &phi) {
+                return PrivateKey { public: PublicKey { n: p * q, e }, d };
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn sign(&self, message: &[u8]) -> BigUint {
+        pkcs1_pad(message, &self.public.n).modpow(&self.d, &self.public.n)
+    }
+}
+
+/// Exponent e_p with s^e_p = m mod p, invertible modulo p - 1
+fn exponent_mod(p: &BigUint, factors: &[u64], signature: &BigUint, padded: &BigUint) -> Option<BigUint> {
+    let mut all_factors = vec![2];
+    all_factors.extend_from_slice(factors);
+    let e = dlog::pohlig_hellman(p, signature, padded, &all_factors)?;
+    math::modinv(&e, &(p - 1u32))?;
+    Some(e)
+}
+
+/// Random smooth prime of `bits` bits with its exponent, see `exponent_mod`
+fn smooth_part(bits: u64, factor_bound: u64, avoid: &[u64], signature: &BigUint, padded: &BigUint)
+               -> (BigUint, Vec<u64>, BigUint) {
+    loop {
+        let (p, factors) = math::random_smooth_prime(bits, factor_bound, avoid);
+        if let Some(e) = exponent_mod(&p, &factors, signature, padded) {
+            return (p, factors, e);
+        }
+    }
+}
+
+/// Craft a key pair under which an existing signature verifies
+///
+/// p and q have p - 1 and q - 1 built from distinct primes below `factor_bound`,
+/// and share no factor but 2. The exponents e_p and e_q with s^e = pad(m) are
+/// invertible, so odd, and combine with the CRT into e' modulo lcm(p - 1, q - 1).
+/// N' keeps the length of N, so that pad(m) does not change. Returns `None` if
+/// the signature does not verify in the first place.
+pub fn duplicate_key(public: &PublicKey, message: &[u8], signature: &BigUint, factor_bound: u64)
+                     -> Option<PrivateKey> {
+    if !public.verify(message, signature) {
+        return None;
+    }
+    let padded = pkcs1_pad(message, &public.n);
+    let bits = public.n.bits();
+    loop {
+        let (p, p_factors, e_p) = smooth_part(bits / 2, factor_bound, &[], signature, &padded);
+        let (q, _, e_q) = smooth_part(bits - bits / 2, factor_bound, &p_factors, signature, &padded);
+        let n = &p * &q;
+        // The signature must be a residue modulo N', encoded on as many bytes.
+        // A small p may rule out every q, so both are drawn again.
+        if n <= *signature || n.bits().div_ceil(8) != bits.div_ceil(8) {
+            continue;
+        }
+        let a: BigUint = (&p - 1u32) >> 1;
+        let b: BigUint = (&q - 1u32) >> 1;
+        let (e, _) = math::crt(&[
+            (&e_p % &a, a.clone()),
+            (&e_q % &b, b.clone()),
+            (BigUint::one(), BigUint::from(2u32)),
+        ])?;
+        let d = math::modinv(&e, &(a * b * 2u32))?;
+        return Some(PrivateKey { public: PublicKey { n, e }, d });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+    #[test]
+    fn padding() {
+        let n = BigUint::one() << 1023;
+        let padded = pkcs1_pad(MESSAGE, &n).to_bytes_be();
+        // The leading 00 is dropped
+        assert_eq!(127, padded.len());
+        assert_eq!(0x01, padded[0]);
+        assert!(padded[1..75].iter().all(|&b| b == 0xff));
+        assert_eq!(0x00, padded[75]);
+        assert_eq!(Sha256::digest(MESSAGE).to_vec(), padded[95..].to_vec());
+    }
+
+    #[test]
+    fn duplicate_signature_key() {
+        let key = PrivateKey::generate(512);
+        let signature = key.sign(MESSAGE);
+        let forged = duplicate_key(key.public_key(), MESSAGE, &signature, 1 << 10).unwrap();
+        assert_ne!(key.public_key(), forged.public_key());
+        assert!(forged.public_key().verify(MESSAGE, &signature));
+        // A valid key: it signs other messages too
+        let other = forged.sign(b"another message");
+        assert!(forged.public_key().verify(b"another message", &other));
+    }
+
+    #[test]
+    fn duplicate_needs_valid_signature() {
+        let key = PrivateKey::generate(512);
+        let signature = key.sign(MESSAGE);
+        assert_eq!(None, duplicate_key(key.public_key(), b"another message", &signature, 1 << 10));
+    }
+}
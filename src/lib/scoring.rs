@@ -0,0 +1,541 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! English plaintext scorers and their calibration
+//!
+//! Each scorer rates how English a text looks, higher is better. Any of them
+//! can break a single-byte XOR with `crypto::decrypt_text`, and
+//! `crypto::decrypt_english` combines them with the weights of the
+//! configuration file, which the `calibrate` command fits on bundled samples
+//! of English and non-English text.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::{english, stats};
+use crate::english::FrequencyModel;
+use crate::stats::RunningStats;
+use crate::error::Result;
+
+/// English sentences, labeled positive
+pub const ENGLISH_SAMPLES: &[&str] = &[
+    "It was the best of times, it was the worst of times",
+    "Alice was beginning to get very tired of sitting by her sister on the bank",
+    "Call me Ishmael. Some years ago, never mind how long precisely",
+    "The quick brown fox jumps over the lazy dog",
+    "I'm back and I'm ringin' the bell",
+    "Now that the party is jumping",
+    "Cooking MC's like a pound of bacon",
+    "Burning 'em, if you ain't quick and nimble",
+    "In a hole in the ground there lived a hobbit",
+    "All happy families are alike; each unhappy family is unhappy in its own way",
+    "There was nothing so very remarkable in that",
+    "The sun shone, having no alternative, on the nothing new",
+    "Happy is the man who has a friend to share his sorrows",
+    "We shall fight on the beaches, we shall fight on the landing grounds",
+    "Four score and seven years ago our fathers brought forth on this continent",
+    "To be, or not to be, that is the question",
+    "Whether the weather be fine, or whether the weather be not",
+    "She sells sea shells by the sea shore",
+    "A journey of a thousand miles begins with a single step",
+    "The rain in Spain stays mainly in the plain",
+    "Please send the report to the office before noon tomorrow",
+    "He opened the door and walked into the cold night air",
+];
+
+/// Non-English and garbage strings, labeled negative
+pub const GARBAGE_SAMPLES: &[&str] = &[
+    "Longtemps, je me suis couché de bonne heure",
+    "Im Anfang war das Wort, und das Wort war bei Gott",
+    "En un lugar de la Mancha, de cuyo nombre no quiero acordarme",
+    "Nel mezzo del cammin di nostra vita mi ritrovai per una selva oscura",
+    "SSBtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t",
+    "49276d206b696c6c696e6720796f757220627261696e",
+    "zxqv wkjp yyrt bnnz qqxw plmk",
+    "!@#$%^&*()_+{}|:<>?~`-=[]\\;',./",
+    "1234567890 0987654321 1122334455",
+    "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXX",
+    "aaaa bbbb cccc dddd eeee ffff",
+    "qwertyuiop asdfghjkl zxcvbnm",
+];
+
+/// Keys XORed with the English samples to make more negatives
+const XOR_KEYS: &[u8] = &[0x01, 0x20, 0x2a, 0x55, 0x7f];
+
+/// A way to rate how English a plaintext looks, higher is better
+///
+/// Scorers hold their reference frequencies and rate raw bytes, so that
+/// `crypto::decrypt_text` can try any heuristic on candidate plaintexts.
+/// Closures rating bytes are scorers too.
+pub trait Scorer {
+    /// Short name used in reports and in the configuration file
+    fn name(&self) -> &str;
+
+    /// Score a plaintext, higher is more English
+    fn score(&self, bytes: &[u8]) -> f64;
+}
+
+impl<F: Fn(&[u8]) -> f64> Scorer for F {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        self(bytes)
+    }
+}
+
+/// Score the text of valid UTF-8 bytes, invalid UTF-8 scores minus infinity
+fn score_text<F: Fn(&str) -> f64>(bytes: &[u8], score: F) -> f64 {
+    std::str::from_utf8(bytes).map_or(f64::NEG_INFINITY, score)
+}
+
+/// Opposite of the Euclidean distance to the corpus frequencies
+pub struct Euclidean {
+    corpus_freq: Vec<f64>,
+}
+
+impl Euclidean {
+    pub fn new(corpus_freq: &[f64]) -> Euclidean {
+        Euclidean { corpus_freq: corpus_freq.to_vec() }
+    }
+}
+
+impl Scorer for Euclidean {
+    fn name(&self) -> &str {
+        "euclidean"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| -english::euclidean_distance(&self.corpus_freq, &english::calc_frequencies(text)))
+    }
+}
+
+/// Pearson correlation with the corpus frequencies, -1 when undefined
+pub struct Pearson {
+    corpus_freq: Vec<f64>,
+}
+
+impl Pearson {
+    pub fn new(corpus_freq: &[f64]) -> Pearson {
+        Pearson { corpus_freq: corpus_freq.to_vec() }
+    }
+}
+
+impl Scorer for Pearson {
+    fn name(&self) -> &str {
+        "pearson"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| {
+            stats::pearson_correlation(&self.corpus_freq, &english::calc_frequencies(text)).unwrap_or(-1.0)
+        })
+    }
+}
+
+/// Share of letters and spaces among the characters
+pub struct Letters;
+
+impl Scorer for Letters {
+    fn name(&self) -> &str {
+        "letters"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| share(text, |c| c.is_ascii_alphabetic() || c == ' '))
+    }
+}
+
+/// Share of printable ASCII characters, including whitespace
+pub struct Printable;
+
+impl Scorer for Printable {
+    fn name(&self) -> &str {
+        "printable"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| share(text, |c| c.is_ascii_graphic() || c.is_ascii_whitespace()))
+    }
+}
+
+/// Opposite of the chi-squared statistic against the corpus frequencies
+///
+/// The statistic is divided by the number of characters and taken as a
+/// logarithm, so that the scores of texts of any length, or of garbage
+/// orders of magnitude away from English, stay comparable. Empty texts score
+/// minus infinity.
+pub struct ChiSquared {
+    corpus_freq: Vec<f64>,
+}
+
+impl ChiSquared {
+    pub fn new(corpus_freq: &[f64]) -> ChiSquared {
+        ChiSquared { corpus_freq: corpus_freq.to_vec() }
+    }
+}
+
+impl Scorer for ChiSquared {
+    fn name(&self) -> &str {
+        "chi-squared"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| {
+            let len = text.chars().count();
+            if len == 0 {
+                return f64::NEG_INFINITY;
+            }
+            -(english::chi_squared(&self.corpus_freq, text) / len as f64).ln_1p()
+        })
+    }
+}
+
+/// Share of common English words, see `english::common_word_share`
+///
+/// A tie-breaker for the frequency scorers: letter frequencies cannot tell
+/// plaintexts apart when they only differ by the case of their letters, or by
+/// a few rare characters.
+pub struct Words;
+
+impl Scorer for Words {
+    fn name(&self) -> &str {
+        "words"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, english::common_word_share)
+    }
+}
+
+fn share<F: Fn(char) -> bool>(text: &str, accept: F) -> f64 {
+    let total = text.chars().count();
+    if total == 0 {
+        return 0.0;
+    }
+    text.chars().filter(|&c| accept(c)).count() as f64 / total as f64
+}
+
+/// Bigram and trigram log likelihood, which ranks short texts better than letter frequencies
+///
+/// Clones share the tables.
+#[derive(Clone)]
+pub struct Ngrams {
+    bigrams: Arc<FrequencyModel>,
+    trigrams: Arc<FrequencyModel>,
+}
+
+impl Ngrams {
+    /// Count the bigrams and trigrams of a corpus
+    pub fn new(corpus: &str) -> Ngrams {
+        Ngrams::from_models(FrequencyModel::build("corpus", corpus, 2), FrequencyModel::build("corpus", corpus, 3))
+    }
+
+    /// Use precomputed tables, e.g. imported by `FrequencyModel::import_json`
+    ///
+    /// # Panics:
+    ///
+    /// The function panics if the models are not of bigrams and trigrams.
+    pub fn from_models(bigrams: FrequencyModel, trigrams: FrequencyModel) -> Ngrams {
+        assert_eq!((2, 3), (bigrams.n, trigrams.n), "expected bigram and trigram models");
+        Ngrams { bigrams: Arc::new(bigrams), trigrams: Arc::new(trigrams) }
+    }
+
+    /// Tables of the standard English corpus
+    pub fn english() -> Result<Ngrams> {
+        Ok(Ngrams::from_models(english::get_english_model(2)?, english::get_english_model(3)?))
+    }
+}
+
+impl Scorer for Ngrams {
+    fn name(&self) -> &str {
+        "ngrams"
+    }
+
+    /// Sum of the mean log10 frequencies of the bigrams and trigrams
+    ///
+    /// Invalid UTF-8 and texts shorter than a bigram score minus infinity,
+    /// texts shorter than a trigram only get the bigram score.
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| match self.bigrams.log_likelihood(text) {
+            Some(bigrams) => bigrams + self.trigrams.log_likelihood(text).unwrap_or(0.0),
+            None => f64::NEG_INFINITY,
+        })
+    }
+}
+
+/// Weighted sum of the `scorers`, like `crypto::decrypt_english` uses
+///
+/// The scorers are built once, to rate many candidate plaintexts.
+pub struct Ensemble {
+    scorers: Vec<(f64, Box<dyn Scorer + Send + Sync>)>,
+}
+
+impl Ensemble {
+    /// Keep the scorers with a weight, the others are skipped
+    ///
+    /// A weight of the n-grams is only used if their tables are given.
+    pub fn new(weights: &BTreeMap<String, f64>, corpus_freq: &[f64], ngrams: Option<&Ngrams>) -> Ensemble {
+        let scorers = scorers(corpus_freq, ngrams).into_iter()
+            .filter_map(|scorer| weights.get(scorer.name()).map(|&weight| (weight, scorer)))
+            .collect();
+        Ensemble { scorers }
+    }
+
+    /// Weighted sum of the scores of a text
+    pub fn score_str(&self, text: &str) -> f64 {
+        self.scorers.iter()
+            .map(|(weight, scorer)| weight * scorer.score(text.as_bytes()))
+            .sum()
+    }
+}
+
+impl Scorer for Ensemble {
+    fn name(&self) -> &str {
+        "ensemble"
+    }
+
+    fn score(&self, bytes: &[u8]) -> f64 {
+        score_text(bytes, |text| self.score_str(text))
+    }
+}
+
+/// Return all the scorers weighted by the configuration, on a frequency table
+///
+/// The n-grams are only included when their tables are given, since building
+/// them from a corpus takes much longer than its letter frequencies.
+pub fn scorers(corpus_freq: &[f64], ngrams: Option<&Ngrams>) -> Vec<Box<dyn Scorer + Send + Sync>> {
+    let mut scorers: Vec<Box<dyn Scorer + Send + Sync>> = vec![
+        Box::new(Euclidean::new(corpus_freq)),
+        Box::new(Pearson::new(corpus_freq)),
+        Box::new(Letters),
+        Box::new(Printable),
+        Box::new(ChiSquared::new(corpus_freq)),
+        Box::new(Words),
+    ];
+    if let Some(ngrams) = ngrams {
+        scorers.push(Box::new(ngrams.clone()));
+    }
+    scorers
+}
+
+/// Weighted sum of the scores, scorers without a weight are skipped
+///
+/// The scorers are built for this text only, an `Ensemble` rates many texts
+/// and may include the n-grams.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::{english, scoring};
+/// use cryptopals::config::Config;
+///
+/// let corpus_freq = english::calc_frequencies("the quick brown fox jumps over the lazy dog");
+/// let weights = Config::default().scoring_weights;
+/// assert!(scoring::ensemble_score(&weights, &corpus_freq, "a lazy fox")
+///     > scoring::ensemble_score(&weights, &corpus_freq, "#@!&&#!"));
+/// ```
+pub fn ensemble_score(weights: &BTreeMap<String, f64>, corpus_freq: &[f64], text: &str) -> f64 {
+    Ensemble::new(weights, corpus_freq, None).score_str(text)
+}
+
+/// Samples labeled English (`true`) or not
+///
+/// The bundled samples, plus the English samples XORed with a few single-byte
+/// keys, which is what a wrong key of `decrypt_english` produces.
+pub fn labeled_samples() -> Vec<(String, bool)> {
+    let mut samples: Vec<(String, bool)> = ENGLISH_SAMPLES.iter().map(|s| (s.to_string(), true)).collect();
+    samples.extend(GARBAGE_SAMPLES.iter().map(|s| (s.to_string(), false)));
+    for &key in XOR_KEYS {
+        for sample in ENGLISH_SAMPLES {
+            let xored: Vec<u8> = sample.bytes().map(|b| b ^ key).collect();
+            if let Ok(text) = String::from_utf8(xored) {
+                samples.push((text, false));
+            }
+        }
+    }
+    samples
+}
+
+/// Precision and recall of a scorer at its best threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScorerReport {
+    pub name: String,
+    /// Texts scoring at least this are classified English
+    pub threshold: f64,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+/// Threshold with the best F1 score, with its precision and recall
+fn best_threshold(scored: &[(f64, bool)]) -> (f64, f64, f64) {
+    let positives = scored.iter().filter(|(_, english)| *english).count();
+    let mut best = (f64::INFINITY, 0.0, 0.0);
+    let mut best_f1 = -1.0;
+    for &(threshold, _) in scored {
+        let selected: Vec<bool> = scored.iter().filter(|(s, _)| *s >= threshold).map(|(_, e)| *e).collect();
+        let true_positives = selected.iter().filter(|&&english| english).count() as f64;
+        let precision = true_positives / selected.len() as f64;
+        let recall = if positives == 0 { 0.0 } else { true_positives / positives as f64 };
+        let f1 = if precision + recall > 0.0 { 2.0 * precision * recall / (precision + recall) } else { 0.0 };
+        if f1 > best_f1 {
+            best_f1 = f1;
+            best = (threshold, precision, recall);
+        }
+    }
+    best
+}
+
+fn report(name: &str, scored: &[(f64, bool)]) -> ScorerReport {
+    let (threshold, precision, recall) = best_threshold(scored);
+    ScorerReport { name: name.to_owned(), threshold, precision, recall }
+}
+
+/// Calibration of every scorer and of their ensemble
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub scorers: Vec<ScorerReport>,
+    /// Suggested weights, normalized so that their absolute values sum to 1
+    pub weights: BTreeMap<String, f64>,
+    pub ensemble: ScorerReport,
+}
+
+/// Run every scorer over labeled samples and fit the ensemble weights
+///
+/// The weight of a scorer is the difference between its mean scores on
+/// English and non-English samples, divided by its pooled variance: a linear
+/// discriminant assuming independent scorers. The n-grams are only
+/// calibrated when their tables are given, see `scorers`.
+pub fn calibrate(corpus_freq: &[f64], ngrams: Option<&Ngrams>, samples: &[(String, bool)]) -> Calibration {
+    let mut reports = Vec::new();
+    let mut weights = BTreeMap::new();
+    for scorer in scorers(corpus_freq, ngrams) {
+        let scored: Vec<(f64, bool)> = samples.iter()
+            .map(|(text, english)| (scorer.score(text.as_bytes()), *english))
+            .collect();
+        reports.push(report(scorer.name(), &scored));
+
+        let english: RunningStats = scored.iter().filter(|(_, e)| *e).map(|(s, _)| *s).collect();
+        let other: RunningStats = scored.iter().filter(|(_, e)| !*e).map(|(s, _)| *s).collect();
+        let variance = (english.variance() + other.variance()) / 2.0;
+        let weight = if variance > 0.0 { (english.mean() - other.mean()) / variance } else { 0.0 };
+        weights.insert(scorer.name().to_owned(), weight);
+    }
+    let total: f64 = weights.values().map(|w| w.abs()).sum();
+    if total > 0.0 {
+        for weight in weights.values_mut() {
+            *weight /= total;
+        }
+    }
+
+    let ensemble = Ensemble::new(&weights, corpus_freq, ngrams);
+    let scored: Vec<(f64, bool)> = samples.iter()
+        .map(|(text, english)| (ensemble.score_str(text), *english))
+        .collect();
+    Calibration { scorers: reports, ensemble: report("ensemble", &scored), weights }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto;
+
+    fn corpus() -> Vec<f64> {
+        english::calc_frequencies(&ENGLISH_SAMPLES.join(" "))
+    }
+
+    #[test]
+    fn scorer_names() {
+        let names: Vec<String> = scorers(&corpus(), None).iter().map(|s| s.name().to_owned()).collect();
+        assert_eq!(vec!["euclidean", "pearson", "letters", "printable", "chi-squared", "words"], names);
+        let ngrams = Ngrams::new(&ENGLISH_SAMPLES.join(" "));
+        assert_eq!("ngrams", scorers(&corpus(), Some(&ngrams)).last().unwrap().name());
+    }
+
+    #[test]
+    fn shares() {
+        assert_eq!(0.5, Letters.score(b"ab12"));
+        assert_eq!(0.0, Printable.score(b""));
+        assert_eq!(-1.0, Pearson::new(&corpus()).score("éèà".as_bytes()));
+    }
+
+    #[test]
+    fn byte_scorers() {
+        let corpus = corpus();
+        let scorers: Vec<Box<dyn Scorer>> = vec![
+            Box::new(Euclidean::new(&corpus)),
+            Box::new(Pearson::new(&corpus)),
+            Box::new(ChiSquared::new(&corpus)),
+        ];
+        for scorer in &scorers {
+            assert!(scorer.score(b"the lazy dog") > scorer.score(b"zq#x!kw@ v"));
+            assert_eq!(f64::NEG_INFINITY, scorer.score(b"\xff\xfe"));
+        }
+        assert_eq!(1.0, Words.score(b"It was the time"));
+        assert_eq!(0.5, Words.score(b"ti saw eht time"));
+        let closure = |bytes: &[u8]| bytes.len() as f64;
+        assert_eq!(3.0, closure.score(b"abc"));
+    }
+
+    /// Number of the English samples cut to `len` bytes whose XOR key `scorer` finds
+    fn keys_found(scorer: &dyn Scorer, len: usize, key: u8) -> usize {
+        ENGLISH_SAMPLES.iter()
+            .map(|sample| &sample[..len])
+            .filter(|short| {
+                let ciphertext: Vec<u8> = short.bytes().map(|byte| byte ^ key).collect();
+                crypto::decrypt_text(&ciphertext, scorer).1 == key
+            })
+            .count()
+    }
+
+    #[test]
+    fn chi_squared_short_texts() {
+        let corpus = corpus();
+        let found = |scorer: &dyn Scorer| keys_found(scorer, 12, 0x2a);
+        let (chi_squared, euclidean) = (found(&ChiSquared::new(&corpus)), found(&Euclidean::new(&corpus)));
+        assert!(chi_squared > euclidean, "chi-squared found {} keys, Euclidean {}", chi_squared, euclidean);
+    }
+
+    #[test]
+    fn ngrams_short_texts() {
+        let ngrams = Ngrams::new(&ENGLISH_SAMPLES.join(" "));
+        let found = |scorer: &dyn Scorer| keys_found(scorer, 8, 0x55);
+        let (ngrams, euclidean) = (found(&ngrams), found(&Euclidean::new(&corpus())));
+        assert!(ngrams > euclidean, "n-grams found {} keys, Euclidean {}", ngrams, euclidean);
+        assert_eq!(f64::NEG_INFINITY, Ngrams::new("abc").score(b"a"));
+    }
+
+    #[test]
+    fn threshold_separates() {
+        let scored = vec![(0.9, true), (0.8, true), (0.3, false), (0.1, false)];
+        assert_eq!((0.8, 1.0, 1.0), best_threshold(&scored));
+    }
+
+    #[test]
+    fn calibrated_ensemble() {
+        let samples = labeled_samples();
+        assert!(samples.len() > ENGLISH_SAMPLES.len() + GARBAGE_SAMPLES.len());
+        let calibration = calibrate(&corpus(), None, &samples);
+        assert_eq!(6, calibration.weights.len());
+        assert!((calibration.weights.values().map(|w| w.abs()).sum::<f64>() - 1.0).abs() < 1e-9);
+        let best_single = calibration.scorers.iter().map(|r| r.precision + r.recall).fold(0.0, f64::max);
+        assert!(calibration.ensemble.precision + calibration.ensemble.recall >= best_single - 0.1);
+
+        let ngrams = Ngrams::new(&ENGLISH_SAMPLES.join(" "));
+        let calibration = calibrate(&corpus(), Some(&ngrams), &samples);
+        assert_eq!(7, calibration.weights.len());
+        assert!(calibration.weights["ngrams"] > 0.0);
+    }
+}
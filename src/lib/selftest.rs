@@ -0,0 +1,206 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Quick self-test of the primitives and attacks, after a build on a new platform
+//!
+//! Every check compares a primitive with a published test vector, or runs an
+//! attack against a synthetic target, in well under a second and without
+//! downloading anything.
+
+use std::error;
+use std::time::{Duration, Instant};
+use aes::Aes128;
+use block_modes::{BlockMode, Ecb};
+use block_modes::block_padding::NoPadding;
+use crate::{attacks, cbcmac, crypto, english, scoring, sha256};
+use crate::cipher::{Aes128Ctr, CtrLayout, StreamCipher};
+use crate::crypto::{BytesCrypto, HexString};
+use crate::gcm::Aes128Gcm;
+use crate::mt19937::{self, Mt19937, Mt19937Cipher};
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// One check of the self-test
+pub struct Check {
+    /// Area of the check, the row of the matrix
+    pub area: &'static str,
+    /// What is checked
+    pub name: &'static str,
+    run: fn() -> Result<()>,
+}
+
+/// Outcome of a check
+pub struct Report {
+    pub area: &'static str,
+    pub name: &'static str,
+    /// Error message of a failed check
+    pub failure: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Fail with `message` unless `condition` holds
+fn ensure(condition: bool, message: &str) -> Result<()> {
+    if condition { Ok(()) } else { Err(message.into()) }
+}
+
+/// Compare bytes with an expected hexadecimal string
+fn ensure_hex(actual: &[u8], expected: &str) -> Result<()> {
+    let actual = actual.bytes2hex();
+    ensure(actual == expected, &format!("got {}, expected {}", actual, expected))
+}
+
+/// FIPS-197 appendix C.1
+fn aes_block() -> Result<()> {
+    let key = "000102030405060708090a0b0c0d0e0f".hex2bytes()?;
+    let mut block = "00112233445566778899aabbccddeeff".hex2bytes()?;
+    let ecb = Ecb::<Aes128, NoPadding>::new_var(&key, &[])?;
+    ecb.encrypt(&mut block, 16)?;
+    ensure_hex(&block, "69c4e0d86a7b0430d8cdb78070b4c55a")
+}
+
+/// NIST SP 800-38A F.5.1, first block
+fn aes_ctr() -> Result<()> {
+    let mut iv = [0; 16];
+    iv.copy_from_slice(&"f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff".hex2bytes()?);
+    let ctr = Aes128Ctr::with_layout(&"2b7e151628aed2a6abf7158809cf4f3c".hex2bytes()?, CtrLayout::BigEndian128 { iv })?;
+    ensure_hex(&ctr.apply_keystream(&"6bc1bee22e409f96e93d7e117393172a".hex2bytes()?),
+               "874d6191b620e3261bef6864990db6ce")
+}
+
+/// GCM specification test case 2
+fn aes_gcm() -> Result<()> {
+    let gcm = Aes128Gcm::new(&[0; 16])?;
+    let (ciphertext, tag) = gcm.encrypt(&[0; 12], &[0; 16], b"");
+    ensure_hex(&ciphertext, "0388dace60b6a392f328c2b971b2fe78")?;
+    ensure_hex(&tag, "ab6e47d42cec13bdf53a67b21257bddf")?;
+    ensure(gcm.decrypt(&[0; 12], &ciphertext, b"", &tag)? == [0; 16], "decryption differs")
+}
+
+/// FIPS 180-2 appendix B.1
+fn sha256() -> Result<()> {
+    ensure_hex(&sha256::digest(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+}
+
+/// RFC 4231 test case 2
+fn hmac_sha256() -> Result<()> {
+    ensure_hex(&sha256::hmac(b"Jefe", b"what do ya want for nothing?"), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+}
+
+/// RFC 4648 section 10
+fn base64() -> Result<()> {
+    for (text, encoded) in [("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+        ensure(text.as_bytes().base64_encode() == encoded, &format!("wrong encoding of {}", text))?;
+        ensure(encoded.base64_decode()? == text.as_bytes(), &format!("wrong decoding of {}", encoded))?;
+    }
+    Ok(())
+}
+
+/// PKCS#7 always adds padding
+fn pkcs7() -> Result<()> {
+    ensure(cbcmac::pkcs7_pad(b"YELLOW SUB") == b"YELLOW SUB\x06\x06\x06\x06\x06\x06", "wrong partial block")?;
+    ensure(cbcmac::pkcs7_pad(b"YELLOW SUBMARINE")[16..] == [16; 16], "wrong full block")
+}
+
+/// The mt19937ar reference output
+fn mt19937() -> Result<()> {
+    let mut mt = Mt19937::new(5489);
+    let output = (0..10000).map(|_| mt.next_u32()).last();
+    ensure(output == Some(4_123_659_995), "wrong 10000th output")
+}
+
+/// Letter frequencies of the bundled English samples, instead of the downloaded corpus
+fn sample_frequencies() -> Vec<f64> {
+    english::calc_frequencies(&scoring::ENGLISH_SAMPLES.join(" "))
+}
+
+/// Challenge 3 against a random key
+fn single_byte_xor() -> Result<()> {
+    let key = rand::random::<u8>() | 0x80;
+    let plaintext = scoring::ENGLISH_SAMPLES[0];
+    let ciphertext: Vec<u8> = plaintext.bytes().map(|byte| byte ^ key).collect();
+    let (text, found, _, _) = crypto::decrypt_english(&ciphertext, &sample_frequencies());
+    ensure(found == key && text == plaintext, &format!("found key {:#04x} instead of {:#04x}", found, key))
+}
+
+/// Challenge 6 with a known key size
+fn repeating_xor() -> Result<()> {
+    let key = b"Terminator X";
+    let ciphertext = attacks::repeating_xor(scoring::ENGLISH_SAMPLES.join(" ").as_bytes(), key);
+    let found = attacks::break_repeating_xor(&ciphertext, key.len(), &sample_frequencies(), true);
+    ensure(found == key, &format!("found key {:?}", String::from_utf8_lossy(&found)))
+}
+
+/// Challenges 22 and 24 on small seed ranges
+fn mt19937_seeds() -> Result<()> {
+    let output = Mt19937::new(1_600_000_321).next_u32();
+    ensure(mt19937::crack_time_seed(output, 1_600_000_000..1_600_001_000) == Some(1_600_000_321), "time seed not found")?;
+    let ciphertext = Mt19937Cipher::new(4242).apply_keystream(b"xyzAAAAAAAAAAAAAA");
+    ensure(mt19937::crack_stream_seed(&ciphertext, b"AAAAAAAAAAAAAA") == Some(4242), "stream seed not found")
+}
+
+/// All the checks, primitives first
+pub fn checks() -> Vec<Check> {
+    vec![
+        Check { area: "AES", name: "FIPS-197 block", run: aes_block },
+        Check { area: "AES", name: "SP 800-38A CTR", run: aes_ctr },
+        Check { area: "AES", name: "GCM test case 2", run: aes_gcm },
+        Check { area: "SHA", name: "SHA-256 abc", run: sha256 },
+        Check { area: "HMAC", name: "RFC 4231 case 2", run: hmac_sha256 },
+        Check { area: "Base64", name: "RFC 4648", run: base64 },
+        Check { area: "Padding", name: "PKCS#7", run: pkcs7 },
+        Check { area: "PRNG", name: "MT19937 reference", run: mt19937 },
+        Check { area: "Attacks", name: "single-byte XOR", run: single_byte_xor },
+        Check { area: "Attacks", name: "repeating-key XOR", run: repeating_xor },
+        Check { area: "Attacks", name: "MT19937 seeds", run: mt19937_seeds },
+    ]
+}
+
+/// Run every check, a panic counts as a failure
+pub fn run_all() -> Vec<Report> {
+    checks().into_iter()
+        .map(|check| {
+            let start = Instant::now();
+            let failure = match std::panic::catch_unwind(check.run) {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some("panicked".to_owned()),
+            };
+            Report { area: check.area, name: check.name, failure, elapsed: start.elapsed() }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn all_pass() {
+        for report in run_all() {
+            assert!(report.passed(), "{} / {}: {:?}", report.area, report.name, report.failure);
+        }
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        assert!(ensure_hex(b"\x01", "02").unwrap_err().to_string().contains("expected 02"));
+    }
+}
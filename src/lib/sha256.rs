@@ -0,0 +1,239 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! SHA-256 hash function, as described in FIPS 180-4, and HMAC-SHA256
+//!
+//! Like `md4`, the state, the compression function and the padding are
+//! public, and `resume` hashes from the state of a published digest, for
+//! length extension attacks.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// SHA-256 block size in bytes
+pub const BLOCK_SIZE: usize = 64;
+
+/// SHA-256 digest size in bytes
+pub const DIGEST_SIZE: usize = 32;
+
+/// Initial state, the first 32 bits of the fractional parts of the square
+/// roots of the first 8 primes
+pub const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Round constants, the first 32 bits of the fractional parts of the cube
+/// roots of the first 64 primes
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Decode a 64-byte block into big-endian words
+///
+/// # Panics:
+///
+/// The function panics if the block is not 64 bytes long.
+pub fn words(block: &[u8]) -> [u32; 16] {
+    assert_eq!(BLOCK_SIZE, block.len(), "SHA-256 blocks are 64 bytes long");
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Apply the compression function to one block of words
+pub fn compress(state: [u32; 8], words: &[u32; 16]) -> [u32; 8] {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(words);
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    let mut next = state;
+    for (word, value) in next.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+    next
+}
+
+/// Padding appended to a message of `message_len` bytes before hashing
+pub fn padding(message_len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    padding.resize(BLOCK_SIZE - (message_len + 8) % BLOCK_SIZE, 0);
+    padding.extend_from_slice(&((message_len as u64).wrapping_mul(8)).to_be_bytes());
+    padding
+}
+
+/// State of the function after hashing a message, read back from its digest
+///
+/// # Panics:
+///
+/// The function panics if the digest is not 32 bytes long.
+pub fn state(digest: &[u8]) -> [u32; 8] {
+    assert_eq!(DIGEST_SIZE, digest.len(), "SHA-256 digests are 32 bytes long");
+    let mut state = [0u32; 8];
+    for (word, bytes) in state.iter_mut().zip(digest.chunks(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    state
+}
+
+/// Continue hashing from `state`, after `prefix_len` bytes of padded message
+///
+/// `resume(INITIAL_STATE, 0, message)` is the digest of `message`. With the
+/// state of the digest of a secret-prefixed message and the length of the
+/// message and its padding, it is the digest of the message, its padding and
+/// `suffix`, without knowing the secret.
+///
+/// # Panics:
+///
+/// The function panics if `prefix_len` is not a multiple of the block size.
+pub fn resume(state: [u32; 8], prefix_len: usize, suffix: &[u8]) -> Vec<u8> {
+    assert!(prefix_len.is_multiple_of(BLOCK_SIZE), "SHA-256 resumes after whole blocks");
+    let mut padded = suffix.to_vec();
+    padded.extend(padding(prefix_len + suffix.len()));
+
+    let state = padded.chunks(BLOCK_SIZE)
+        .fold(state, |state, block| compress(state, &words(block)));
+    state.iter().flat_map(|word| word.to_be_bytes()).collect()
+}
+
+/// Compute the SHA-256 digest of a message
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::sha256;
+/// use cryptopals::crypto::BytesCrypto;
+///
+/// assert_eq!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", sha256::digest(b"abc").bytes2hex());
+/// ```
+pub fn digest(message: &[u8]) -> Vec<u8> {
+    resume(INITIAL_STATE, 0, message)
+}
+
+/// Compute the HMAC-SHA256 of a message, as described in RFC 2104
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::sha256;
+/// use cryptopals::crypto::BytesCrypto;
+///
+/// assert_eq!(
+///     "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+///     sha256::hmac(b"Jefe", b"what do ya want for nothing?").bytes2hex()
+/// );
+/// ```
+pub fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = if key.len() > BLOCK_SIZE { digest(key) } else { key.to_vec() };
+    block.resize(BLOCK_SIZE, 0);
+
+    let mut inner: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+    outer.extend(digest(&inner));
+    digest(&outer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+    use crate::primitives::BytesCrypto;
+
+    #[test]
+    fn fips180_vectors() {
+        assert_eq!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", digest(b"").bytes2hex());
+        assert_eq!(
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").bytes2hex()
+        );
+        assert_eq!(
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+            digest(&[b'a'; 1_000_000]).bytes2hex()
+        );
+    }
+
+    #[test]
+    fn rfc4231_vectors() {
+        assert_eq!(
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            hmac(&[0x0b; 20], b"Hi There").bytes2hex()
+        );
+        assert_eq!(
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            hmac(&[0xaa; 20], &[0xdd; 50]).bytes2hex()
+        );
+        // Keys longer than a block are hashed first
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            hmac(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First").bytes2hex()
+        );
+    }
+
+    #[test]
+    fn same_as_sha2() {
+        let message: Vec<u8> = (0..200).collect();
+        for len in 0..message.len() {
+            assert_eq!(Sha256::digest(&message[..len]).to_vec(), digest(&message[..len]));
+            let mut mac = Hmac::<Sha256>::new_from_slice(&message[len..]).unwrap();
+            mac.update(&message[..len]);
+            assert_eq!(mac.finalize().into_bytes().to_vec(), hmac(&message[len..], &message[..len]));
+        }
+    }
+
+    #[test]
+    fn length_extension() {
+        crate::hash_test::length_extension(digest, padding, state, resume);
+    }
+
+    #[test]
+    fn padding_length() {
+        for len in 0..200 {
+            assert_eq!(0, (len + padding(len).len()) % BLOCK_SIZE);
+        }
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 0x01, 0xb8], &padding(55)[1..]);
+    }
+}
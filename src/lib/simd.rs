@@ -0,0 +1,203 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! SSE2 and AVX2 fast paths of `BytesCrypto::xor` and `BytesCrypto::hamming_distance`
+//!
+//! Enabled by the `simd` feature. AVX2 is detected at run time and SSE2 is
+//! part of every x86_64 target; other architectures XOR 8 bytes at a time.
+//! The bytes past the last whole vector are handled one by one.
+
+#[cfg(not(target_arch = "x86_64"))]
+use std::convert::TryInto;
+
+/// XOR two arrays of bytes, up to the length of the shorter one
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::simd;
+///
+/// assert_eq!(vec![1; 40], simd::xor(&[0x55; 40], &[0x54; 41]));
+/// ```
+pub fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    let mut out = vec![0; len];
+    let done = xor_vectors(a, b, &mut out);
+    for ((o, x), y) in out[done..].iter_mut().zip(&a[done..]).zip(&b[done..]) {
+        *o = x ^ y;
+    }
+    out
+}
+
+/// Number of differing bits of two arrays of bytes, up to the length of the shorter one
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::simd;
+///
+/// assert_eq!(37, simd::hamming_distance(b"this is a test", b"wokka wokka!!!"));
+/// ```
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    let (distance, done) = hamming_vectors(a, b);
+    distance + a[done..].iter().zip(&b[done..]).map(|(x, y)| (x ^ y).count_ones()).sum::<u32>()
+}
+
+/// XOR the whole vectors of equal length arrays, return the bytes done
+#[cfg(target_arch = "x86_64")]
+fn xor_vectors(a: &[u8], b: &[u8], out: &mut [u8]) -> usize {
+    if is_x86_feature_detected!("avx2") {
+        // AVX2 was just detected
+        unsafe { x86::xor_avx2(a, b, out) }
+    } else {
+        x86::xor_sse2(a, b, out)
+    }
+}
+
+/// Count the differing bits of the whole vectors of equal length arrays, return them and the bytes done
+#[cfg(target_arch = "x86_64")]
+fn hamming_vectors(a: &[u8], b: &[u8]) -> (u32, usize) {
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("popcnt") {
+        // AVX2 and POPCNT were just detected
+        unsafe { x86::hamming_avx2(a, b) }
+    } else {
+        x86::hamming_sse2(a, b)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn xor_vectors(a: &[u8], b: &[u8], out: &mut [u8]) -> usize {
+    let words = out.len() / 8;
+    for i in 0..words {
+        let range = i * 8..(i + 1) * 8;
+        let x = u64::from_ne_bytes(a[range.clone()].try_into().unwrap());
+        let y = u64::from_ne_bytes(b[range.clone()].try_into().unwrap());
+        out[range].copy_from_slice(&(x ^ y).to_ne_bytes());
+    }
+    words * 8
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn hamming_vectors(a: &[u8], b: &[u8]) -> (u32, usize) {
+    let words = a.len() / 8;
+    let distance = a.chunks_exact(8).zip(b.chunks_exact(8))
+        .map(|(x, y)| (u64::from_ne_bytes(x.try_into().unwrap()) ^ u64::from_ne_bytes(y.try_into().unwrap())).count_ones())
+        .sum();
+    (distance, words * 8)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    /// XOR 32 bytes at a time
+    ///
+    /// Callers must check that AVX2 is available, and pass arrays of `out`'s length.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn xor_avx2(a: &[u8], b: &[u8], out: &mut [u8]) -> usize {
+        let done = out.len() / 32 * 32;
+        for i in (0..done).step_by(32) {
+            let x = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+            let y = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            _mm256_storeu_si256(out.as_mut_ptr().add(i) as *mut __m256i, _mm256_xor_si256(x, y));
+        }
+        done
+    }
+
+    /// XOR 16 bytes at a time
+    pub fn xor_sse2(a: &[u8], b: &[u8], out: &mut [u8]) -> usize {
+        assert!(a.len() >= out.len() && b.len() >= out.len());
+        let done = out.len() / 16 * 16;
+        for i in (0..done).step_by(16) {
+            // SSE2 is part of x86_64, and the loads and stores stay within the arrays
+            unsafe {
+                let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+                let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+                _mm_storeu_si128(out.as_mut_ptr().add(i) as *mut __m128i, _mm_xor_si128(x, y));
+            }
+        }
+        done
+    }
+
+    /// XOR 32 bytes at a time and count the bits set of their four 64-bit lanes
+    ///
+    /// Callers must check that AVX2 and POPCNT are available, and pass arrays of the same length.
+    #[target_feature(enable = "avx2,popcnt")]
+    pub unsafe fn hamming_avx2(a: &[u8], b: &[u8]) -> (u32, usize) {
+        let done = a.len() / 32 * 32;
+        let mut distance = 0;
+        let mut lanes = [0u64; 4];
+        for i in (0..done).step_by(32) {
+            let x = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+            let y = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, _mm256_xor_si256(x, y));
+            distance += lanes.iter().map(|lane| lane.count_ones()).sum::<u32>();
+        }
+        (distance, done)
+    }
+
+    /// XOR 16 bytes at a time and count the bits set of their two 64-bit lanes
+    pub fn hamming_sse2(a: &[u8], b: &[u8]) -> (u32, usize) {
+        assert!(b.len() >= a.len());
+        let done = a.len() / 16 * 16;
+        let mut distance = 0;
+        let mut lanes = [0u64; 2];
+        for i in (0..done).step_by(16) {
+            // SSE2 is part of x86_64, and the loads and stores stay within the arrays
+            unsafe {
+                let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+                let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+                _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, _mm_xor_si128(x, y));
+            }
+            distance += lanes[0].count_ones() + lanes[1].count_ones();
+        }
+        (distance, done)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bits;
+
+    #[test]
+    fn matches_scalar() {
+        let a: Vec<u8> = (0..200u32).map(|i| (i * 37 % 251) as u8).collect();
+        let b: Vec<u8> = (0..200u32).map(|i| (i * 101 % 241) as u8).collect();
+        for len in [0, 1, 15, 16, 17, 31, 32, 33, 64, 100, 200] {
+            for offset in [0, 1, 3] {
+                let (a, b) = (&a[offset..len.max(offset)], &b[..len]);
+                let expected: Vec<u8> = a.iter().zip(b).map(|(x, y)| x ^ y).collect();
+                assert_eq!(expected, xor(a, b), "length {} offset {}", len, offset);
+                assert_eq!(bits::hamming_weight(&expected), hamming_distance(a, b), "length {} offset {}", len, offset);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn fallbacks_match() {
+        let a = [0xa5; 70];
+        let b = [0x0f; 70];
+        let mut out = [0; 70];
+        assert_eq!(64, x86::xor_sse2(&a, &b, &mut out));
+        assert_eq!([0xaa; 64], out[..64]);
+        assert_eq!((64 * 4, 64), x86::hamming_sse2(&a, &b));
+    }
+}
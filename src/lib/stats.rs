@@ -14,6 +14,9 @@
 
 //! Statistical functions
 
+use std::iter::FromIterator;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
 
 /// Compute the mean of a series
 pub fn mean(values: &[f64]) -> f64 {
@@ -29,6 +32,56 @@ pub fn std_dev(values: &[f64]) -> f64 {
         / (values.len() as f64)).sqrt()
 }
 
+/// Compute the median of a series, NaN if it is empty
+///
+/// Unlike the mean, it ignores a few outliers, e.g. the timings of requests
+/// delayed by the scheduler.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// assert_eq!(2.0, stats::median(&[3.0, 1.0, 2.0]));
+/// assert_eq!(2.5, stats::median(&[1.0, 2.0, 3.0, 1000.0]));
+/// ```
+pub fn median(values: &[f64]) -> f64 {
+    percentile(values, 50.0)
+}
+
+/// Compute the `p`-th percentile of a series, NaN if it is empty
+///
+/// The percentile is interpolated linearly between the two closest ranks, so
+/// that the 0th is the minimum, the 100th the maximum and the 50th the median.
+///
+/// # Panics:
+///
+/// The function panics if `p` is not between 0 and 100.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// let values = [15.0, 20.0, 35.0, 40.0, 50.0];
+/// assert_eq!(15.0, stats::percentile(&values, 0.0));
+/// assert_eq!(20.0, stats::percentile(&values, 25.0));
+/// assert_eq!(29.0, stats::percentile(&values, 40.0));
+/// assert_eq!(50.0, stats::percentile(&values, 100.0));
+/// ```
+pub fn percentile(values: &[f64], p: f64) -> f64 {
+    assert!((0.0..=100.0).contains(&p), "Percentile must be between 0 and 100");
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
 /// Compute the covariance of two equal length series
 ///
 /// # Panics:
@@ -43,3 +96,427 @@ pub fn covariance(values_x: &[f64], values_y: &[f64]) -> f64 {
         .map(|(x, y)| (*x - mean_x) * (*y - mean_y))
         .sum::<f64>() / values_x.len() as f64
 }
+
+/// Compute the Pearson correlation coefficient of two equal length series
+///
+/// The coefficient is undefined when a series has a zero variance, e.g. the
+/// letter frequencies of a text without any ASCII character, so `None` is
+/// returned instead of a NaN which would compare as neither better nor worse.
+///
+/// # Panics:
+///
+/// The function panics if the series are not of equal length.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// assert_eq!(Some(-1.0), stats::pearson_correlation(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]));
+/// assert_eq!(None, stats::pearson_correlation(&[1.0, 2.0, 3.0], &[0.0, 0.0, 0.0]));
+/// ```
+pub fn pearson_correlation(values_x: &[f64], values_y: &[f64]) -> Option<f64> {
+    // Checked before the variances, which may return early
+    assert_eq!(values_x.len(), values_y.len(), "Both arrays must be the same size");
+    let std_dev_x = std_dev(values_x);
+    let std_dev_y = std_dev(values_y);
+    // Also catches the NaN of empty series
+    if !(std_dev_x > 0.0 && std_dev_y > 0.0) {
+        return None;
+    }
+    Some(covariance(values_x, values_y) / std_dev_x / std_dev_y)
+}
+
+/// Compute Pearson's chi-squared statistic of observed counts against expected ones
+///
+/// # Panics:
+///
+/// The function panics if the series are not of equal length.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// assert_eq!(0.0, stats::chi_squared(&[2.0, 8.0], &[2.0, 8.0]));
+/// assert_eq!(2.0 + 0.5, stats::chi_squared(&[4.0, 6.0], &[2.0, 8.0]));
+/// ```
+pub fn chi_squared(observed: &[f64], expected: &[f64]) -> f64 {
+    assert_eq!(observed.len(), expected.len(), "Both arrays must be the same size");
+
+    observed.iter().zip(expected)
+        .map(|(o, e)| (o - e).powi(2) / e)
+        .sum()
+}
+
+/// Chi-squared statistic divided by its degrees of freedom, one less than the categories
+///
+/// Close to 1 when the observed counts follow the expected distribution,
+/// whatever the number of categories, and much larger when they do not. A
+/// single category has no degree of freedom and gets NaN.
+///
+/// # Panics:
+///
+/// The function panics if the series are not of equal length.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// assert_eq!(2.5 / 1.0, stats::reduced_chi_squared(&[4.0, 6.0], &[2.0, 8.0]));
+/// ```
+pub fn reduced_chi_squared(observed: &[f64], expected: &[f64]) -> f64 {
+    let degrees_of_freedom = observed.len() as f64 - 1.0;
+    if degrees_of_freedom <= 0.0 {
+        return f64::NAN;
+    }
+    chi_squared(observed, expected) / degrees_of_freedom
+}
+
+/// Shannon entropy of the bytes, in bits per byte
+///
+/// From 0 for a repeated byte to 8 for uniformly random bytes. A ciphertext
+/// is close to 8 unless it is short or, like ECB of a repetitive plaintext,
+/// repeats blocks. Empty inputs return 0.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats;
+///
+/// assert_eq!(0.0, stats::entropy(b"aaaa"));
+/// assert_eq!(1.0, stats::entropy(b"abab"));
+/// assert_eq!(8.0, stats::entropy(&(0..=255).collect::<Vec<u8>>()));
+/// ```
+pub fn entropy(bytes: &[u8]) -> f64 {
+    ByteHistogram::from_bytes(bytes).entropy()
+}
+
+/// Count, mean, variance and extremes of a stream of values, without keeping them
+///
+/// Welford's online algorithm updates the mean and the sum of squared
+/// differences to it with each value, which is numerically stable, unlike
+/// summing the squares. The mean and the variance are NaN before the first
+/// value, like `mean` and `std_dev` of an empty series, and the variance is
+/// the population variance, like `std_dev`.
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::stats::RunningStats;
+///
+/// let mut running = RunningStats::new();
+/// for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+///     running.add(value);
+/// }
+/// assert_eq!(8, running.count());
+/// assert_eq!(5.0, running.mean());
+/// assert_eq!(2.0, running.std_dev());
+/// assert_eq!((Some(2.0), Some(9.0)), (running.min(), running.max()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunningStats {
+    count: u64,
+    mean: f64,
+    /// Sum of the squared differences to the mean
+    m2: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl RunningStats {
+    pub fn new() -> RunningStats {
+        RunningStats { count: 0, mean: 0.0, m2: 0.0, min: None, max: None }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 { f64::NAN } else { self.mean }
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.m2 / self.count as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    /// Add the values of another accumulator, e.g. of another thread
+    ///
+    /// The result is the same as adding all the values to one accumulator,
+    /// up to rounding errors (Chan et al., 1979).
+    pub fn merge(&mut self, other: &RunningStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count as f64 * other.count as f64 / count as f64);
+        self.count = count;
+        self.min = self.min.zip(other.min).map(|(a, b)| a.min(b));
+        self.max = self.max.zip(other.max).map(|(a, b)| a.max(b));
+    }
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        RunningStats::new()
+    }
+}
+
+impl Extend<f64> for RunningStats {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for value in values {
+            self.add(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for RunningStats {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut running = RunningStats::new();
+        running.extend(values);
+        running
+    }
+}
+
+/// Byte counts over the 256 possible values
+#[derive(Clone)]
+pub struct ByteHistogram {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl ByteHistogram {
+    pub fn new() -> ByteHistogram {
+        ByteHistogram { counts: [0; 256], total: 0 }
+    }
+
+    /// Count every byte of a slice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::stats::ByteHistogram;
+    ///
+    /// let histogram = ByteHistogram::from_bytes(b"abca");
+    /// assert_eq!(2, histogram.count(b'a'));
+    /// assert_eq!(0.25, histogram.frequency(b'b'));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> ByteHistogram {
+        let mut histogram = ByteHistogram::new();
+        for &byte in bytes {
+            histogram.add(byte);
+        }
+        histogram
+    }
+
+    pub fn add(&mut self, byte: u8) {
+        self.counts[byte as usize] += 1;
+        self.total += 1;
+    }
+
+    pub fn count(&self, byte: u8) -> u64 {
+        self.counts[byte as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Frequency of a byte, 0 if nothing was counted
+    pub fn frequency(&self, byte: u8) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.counts[byte as usize] as f64 / self.total as f64
+        }
+    }
+
+    /// Frequencies of the 256 byte values
+    pub fn frequencies(&self) -> Vec<f64> {
+        (0..=255u8).map(|byte| self.frequency(byte)).collect()
+    }
+
+    /// The byte counted most often, the smallest one in case of a tie
+    pub fn most_frequent(&self) -> u8 {
+        (0..=255u8).rev().max_by_key(|&byte| self.count(byte)).unwrap()
+    }
+
+    /// Shannon entropy of the counted bytes, see `entropy`
+    pub fn entropy(&self) -> f64 {
+        let entropy: f64 = (0..=255u8)
+            .map(|byte| self.frequency(byte))
+            .filter(|&frequency| frequency > 0.0)
+            .map(|frequency| -frequency * frequency.log2())
+            .sum();
+        // Avoid -0.0 for a single byte value
+        entropy.max(0.0)
+    }
+
+    /// Reduced chi-squared statistic of the counts against uniform bytes
+    ///
+    /// About 1 for random bytes, see `reduced_chi_squared`. NaN if nothing
+    /// was counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::stats::ByteHistogram;
+    ///
+    /// let bytes: Vec<u8> = (0..=255).cycle().take(256 * 4).collect();
+    /// assert_eq!(0.0, ByteHistogram::from_bytes(&bytes).uniformity());
+    /// assert!(ByteHistogram::from_bytes(b"aaaa").uniformity() > 1.0);
+    /// ```
+    pub fn uniformity(&self) -> f64 {
+        let observed: Vec<f64> = self.counts.iter().map(|&count| count as f64).collect();
+        let expected = vec![self.total as f64 / 256.0; 256];
+        reduced_chi_squared(&observed, &expected)
+    }
+
+    /// Add the counts of another histogram
+    pub fn merge(&mut self, other: &ByteHistogram) {
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+        self.total += other.total;
+    }
+}
+
+/// Serialized as the list of the 256 counts
+impl Serialize for ByteHistogram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.counts.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteHistogram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ByteHistogram, D::Error> {
+        let counts = Vec::<u64>::deserialize(deserializer)?;
+        if counts.len() != 256 {
+            return Err(D::Error::invalid_length(counts.len(), &"256 counts"));
+        }
+        let mut histogram = ByteHistogram::new();
+        histogram.counts.copy_from_slice(&counts);
+        histogram.total = counts.iter().sum();
+        Ok(histogram)
+    }
+}
+
+impl Default for ByteHistogram {
+    fn default() -> Self {
+        ByteHistogram::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pearson_values() {
+        assert_eq!(Some(1.0), pearson_correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]));
+        let r = pearson_correlation(&[1.0, 2.0, 3.0, 4.0], &[1.0, 3.0, 2.0, 4.0]).unwrap();
+        assert!((r - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pearson_zero_variance() {
+        assert_eq!(None, pearson_correlation(&[1.0, 1.0], &[1.0, 2.0]));
+        assert_eq!(None, pearson_correlation(&[1.0, 2.0], &[5.0, 5.0]));
+        assert_eq!(None, pearson_correlation(&[], &[]));
+        assert_eq!(None, pearson_correlation(&[f64::NAN, 1.0], &[1.0, 2.0]));
+    }
+
+    #[test]
+    fn entropy_of_ecb() {
+        assert_eq!(0.0, entropy(b""));
+        // Four distinct blocks of distinct bytes, then the same four again
+        let blocks: Vec<u8> = (0..64).chain(0..64).collect();
+        assert_eq!(6.0, entropy(&blocks));
+        assert!((entropy(b"ab") - entropy(b"aabb")).abs() < 1e-12);
+    }
+
+    #[test]
+    fn percentiles() {
+        assert!(median(&[]).is_nan());
+        assert_eq!(7.0, median(&[7.0]));
+        assert_eq!(7.0, percentile(&[7.0], 90.0));
+        let values: Vec<f64> = (1..=101).rev().map(f64::from).collect();
+        assert_eq!(51.0, median(&values));
+        assert_eq!(91.0, percentile(&values, 90.0));
+        assert_eq!(1.0, percentile(&values, 0.0));
+    }
+
+    #[test]
+    #[should_panic = "Percentile must be between 0 and 100"]
+    fn percentile_out_of_range() {
+        percentile(&[1.0], 101.0);
+    }
+
+    #[test]
+    fn running_stats() {
+        let values = [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0];
+        let running: RunningStats = values.iter().copied().collect();
+        assert_eq!(mean(&values), running.mean());
+        // Summing the squares would lose the digits of the differences
+        assert!((std_dev(&values) - running.std_dev()).abs() < 1e-6);
+        assert!((22.5 - running.variance()).abs() < 1e-6);
+
+        let empty = RunningStats::new();
+        assert!(empty.mean().is_nan() && empty.variance().is_nan());
+        assert_eq!((0, None, None), (empty.count(), empty.min(), empty.max()));
+    }
+
+    #[test]
+    fn running_stats_merge() {
+        let values: Vec<f64> = (0..100).map(|i| (i * i % 37) as f64).collect();
+        let all: RunningStats = values.iter().copied().collect();
+        let mut merged: RunningStats = values[..30].iter().copied().collect();
+        merged.merge(&values[30..].iter().copied().collect());
+        merged.merge(&RunningStats::new());
+        assert_eq!(all.count(), merged.count());
+        assert!((all.mean() - merged.mean()).abs() < 1e-12);
+        assert!((all.variance() - merged.variance()).abs() < 1e-9);
+        assert_eq!((all.min(), all.max()), (merged.min(), merged.max()));
+    }
+
+    #[test]
+    #[should_panic = "Both arrays must be the same size"]
+    fn pearson_bad_size() {
+        pearson_correlation(&[1.0, 2.0], &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    #[should_panic = "Both arrays must be the same size"]
+    fn pearson_constant_bad_size() {
+        pearson_correlation(&[1.0, 1.0], &[1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Deadlines and checkpoints for long-running attacks
+//!
+//! An attack given a deadline stops when it is reached and returns its best
+//! result so far with its state, which can be run again to resume the attack.
+//! Saving the state as a checkpoint lets the attack survive a restart.
+//!
+//! Long-running attacks also report their progress to a `Progress`, which
+//! can log it or count the steps.
+
+use std::error;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// Point in time where an attack should stop, if any
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// Run until the end
+    pub fn never() -> Deadline {
+        Deadline { at: None }
+    }
+
+    /// Stop after `duration` from now
+    pub fn after(duration: Duration) -> Deadline {
+        Deadline { at: Some(Instant::now() + duration) }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+}
+
+/// Result of an attack run under a deadline
+#[derive(Debug)]
+pub enum Outcome<T, S> {
+    /// The attack ran to the end
+    Complete(T),
+    /// The attack was stopped: best result so far, and the state to resume from
+    Partial { best: Option<T>, state: S },
+}
+
+impl<T, S> Outcome<T, S> {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Outcome::Complete(_))
+    }
+
+    /// The final result, or the best partial one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cryptopals::timebox::Outcome;
+    ///
+    /// let outcome: Outcome<u8, ()> = Outcome::Partial { best: Some(3), state: () };
+    /// assert_eq!(Some(3), outcome.best());
+    /// ```
+    pub fn best(self) -> Option<T> {
+        match self {
+            Outcome::Complete(result) => Some(result),
+            Outcome::Partial { best, .. } => best,
+        }
+    }
+}
+
+/// Receiver of the progress of an attack
+///
+/// Attacks may report from several threads. Both methods do nothing by default.
+pub trait Progress: Sync {
+    /// `done` steps out of `total`, if known, e.g. encryptions or oracle queries
+    fn step(&self, _done: u64, _total: Option<u64>) {}
+
+    /// Bytes of the secret recovered at `position`
+    fn recovered(&self, _position: usize, _bytes: &[u8]) {}
+}
+
+/// Ignore the progress
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
+
+/// Log the progress at the info level, every percent when the total is known
+pub struct LogProgress {
+    name: String,
+    last_percent: AtomicU64,
+}
+
+impl LogProgress {
+    pub fn new(name: &str) -> LogProgress {
+        LogProgress { name: name.to_owned(), last_percent: AtomicU64::new(u64::MAX) }
+    }
+}
+
+impl Progress for LogProgress {
+    fn step(&self, done: u64, total: Option<u64>) {
+        match total {
+            Some(total) if total > 0 => {
+                let percent = done.min(total) * 100 / total;
+                if self.last_percent.swap(percent, Ordering::Relaxed) != percent {
+                    info!("{}: {}% ({}/{})", self.name, percent, done, total);
+                }
+            }
+            _ => debug!("{}: {} steps", self.name, done),
+        }
+    }
+
+    fn recovered(&self, position: usize, bytes: &[u8]) {
+        info!("{}: recovered {:02x?} at {}", self.name, bytes, position);
+    }
+}
+
+/// Record the progress, so that tests can check the budget of an attack
+///
+/// # Examples
+///
+/// ```
+/// use cryptopals::timebox::{Progress, ProgressCounter};
+///
+/// let counter = ProgressCounter::default();
+/// counter.step(10, Some(100));
+/// counter.step(20, Some(100));
+/// counter.recovered(0, b"A");
+/// assert_eq!(2, counter.steps());
+/// assert_eq!(Some((20, Some(100))), counter.last_step());
+/// assert_eq!(vec![(0, b"A".to_vec())], counter.recovered_bytes());
+/// ```
+#[derive(Default)]
+pub struct ProgressCounter {
+    steps: AtomicUsize,
+    last_step: Mutex<Option<(u64, Option<u64>)>>,
+    recovered: Mutex<Vec<(usize, Vec<u8>)>>,
+}
+
+impl ProgressCounter {
+    /// Number of step reports
+    pub fn steps(&self) -> usize {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    pub fn last_step(&self) -> Option<(u64, Option<u64>)> {
+        *self.last_step.lock().unwrap()
+    }
+
+    /// Recovered bytes with their position, in the order of the reports
+    pub fn recovered_bytes(&self) -> Vec<(usize, Vec<u8>)> {
+        self.recovered.lock().unwrap().clone()
+    }
+}
+
+impl Progress for ProgressCounter {
+    fn step(&self, done: u64, total: Option<u64>) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+        *self.last_step.lock().unwrap() = Some((done, total));
+    }
+
+    fn recovered(&self, position: usize, bytes: &[u8]) {
+        self.recovered.lock().unwrap().push((position, bytes.to_vec()));
+    }
+}
+
+/// Checkpoint file content, the kind protects against loading another attack's state
+#[derive(Serialize, Deserialize)]
+struct CheckpointFile<S> {
+    kind: String,
+    state: S,
+}
+
+/// Attack state which can be saved to a JSON file and loaded back
+pub trait Checkpoint: Serialize + DeserializeOwned {
+    /// Name of the attack, stored in the checkpoint
+    const KIND: &'static str;
+
+    /// Save the state to a file
+    ///
+    /// The state is written next to the file then renamed over it, so that an
+    /// interruption never leaves a truncated checkpoint.
+    fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string(&CheckpointFile { kind: Self::KIND.to_owned(), state: self })?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, json)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Check a loaded state, `resume_from` rejects the states failing it
+    ///
+    /// The default accepts every state which deserializes.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Load a state saved by `save_checkpoint`
+    fn resume_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let checkpoint: CheckpointFile<Self> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if checkpoint.kind != Self::KIND {
+            return Err(format!("checkpoint of a {} attack, expected {}", checkpoint.kind, Self::KIND).into());
+        }
+        checkpoint.state.validate()?;
+        Ok(checkpoint.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deadlines() {
+        assert!(!Deadline::never().expired());
+        assert!(Deadline::after(Duration::from_secs(0)).expired());
+        assert!(!Deadline::after(Duration::from_secs(3600)).expired());
+    }
+
+    #[test]
+    fn outcome_best() {
+        assert_eq!(Some(1), Outcome::<u8, ()>::Complete(1).best());
+        assert_eq!(None, Outcome::<u8, ()>::Partial { best: None, state: () }.best());
+        assert!(!Outcome::<u8, ()>::Partial { best: None, state: () }.is_complete());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        count: u64,
+    }
+
+    impl Checkpoint for Counter {
+        const KIND: &'static str = "counter";
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Other {
+        count: u64,
+    }
+
+    impl Checkpoint for Other {
+        const KIND: &'static str = "other";
+    }
+
+    #[test]
+    fn checkpoint_roundtrip() {
+        let path = std::env::temp_dir().join(format!("cryptopals-timebox-{}.json", std::process::id()));
+        Counter { count: 42 }.save_checkpoint(&path).unwrap();
+        assert_eq!(Counter { count: 42 }, Counter::resume_from(&path).unwrap());
+        assert!(Other::resume_from(&path).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(Counter::resume_from(&path).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Admin checks run by the bitflipping targets on decrypted plaintexts
+//!
+//! The validators go from the naive substring search of challenges 16 and 26
+//! to a MAC-protected check, so the same attack can be run against
+//! progressively hardened targets.
+
+use crate::{cbcmac, kv};
+use crate::crypto::TagComparison;
+
+/// Decide whether a decrypted plaintext grants admin access
+pub trait PlaintextValidator {
+    /// Return `true` if the plaintext is accepted as an admin profile
+    fn is_admin(&self, plaintext: &[u8]) -> bool;
+}
+
+/// Look for `;admin=true;` anywhere in the plaintext, like the challenges do
+pub struct SubstringValidator;
+
+impl PlaintextValidator for SubstringValidator {
+    fn is_admin(&self, plaintext: &[u8]) -> bool {
+        const ADMIN: &[u8] = b";admin=true;";
+        plaintext.windows(ADMIN.len()).any(|w| w == ADMIN)
+    }
+}
+
+/// Parse the whole plaintext as `key=value` fields separated by `;`
+///
+/// Any invalid UTF-8 or malformed field, such as a block scrambled by
+/// a bit flip, makes the whole plaintext rejected.
+pub struct StrictParserValidator;
+
+impl PlaintextValidator for StrictParserValidator {
+    fn is_admin(&self, plaintext: &[u8]) -> bool {
+        match std::str::from_utf8(plaintext) {
+            Ok(text) => match kv::parse(text, ';') {
+                Ok(fields) => kv::get(&fields, "admin") == Some("true"),
+                Err(_) => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// Check a CBC-MAC appended to the plaintext before running another validator
+///
+/// The plaintext is expected to be `message || CBC-MAC(key, message)`.
+pub struct MacValidator<V: PlaintextValidator> {
+    pub key: Vec<u8>,
+    pub iv: Vec<u8>,
+    pub inner: V,
+    /// Leaky for the timing attacks, constant time otherwise
+    pub comparison: TagComparison,
+}
+
+impl<V: PlaintextValidator> PlaintextValidator for MacValidator<V> {
+    fn is_admin(&self, plaintext: &[u8]) -> bool {
+        if plaintext.len() < cbcmac::BLOCK_SIZE {
+            return false;
+        }
+        let (message, mac) = plaintext.split_at(plaintext.len() - cbcmac::BLOCK_SIZE);
+        match cbcmac::cbc_mac(&self.key, &self.iv, message) {
+            Ok(expected) if self.comparison.verify(&expected, mac) => self.inner.is_admin(message),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+
+    fn with_mac(message: &[u8]) -> Vec<u8> {
+        let mut data = message.to_vec();
+        data.extend(cbcmac::cbc_mac(KEY, &[0; 16], message).unwrap());
+        data
+    }
+
+    fn mac_validator() -> MacValidator<StrictParserValidator> {
+        MacValidator {
+            key: KEY.to_vec(),
+            iv: vec![0; 16],
+            inner: StrictParserValidator,
+            comparison: TagComparison::ConstantTime,
+        }
+    }
+
+    #[test]
+    fn substring() {
+        assert!(SubstringValidator.is_admin(b"comment1=x;admin=true;comment2=y"));
+        assert!(SubstringValidator.is_admin(b"\xff\x00garbage;admin=true;"));
+        assert!(!SubstringValidator.is_admin(b"comment1=x;admin=false;"));
+    }
+
+    #[test]
+    fn strict_parser() {
+        assert!(StrictParserValidator.is_admin(b"comment1=x;admin=true;comment2=y"));
+        assert!(!StrictParserValidator.is_admin(b"\xff\x00garbage;admin=true;comment2=y"));
+        assert!(!StrictParserValidator.is_admin(b"comment1=x;admin=true;comment2=y=z"));
+        assert!(!StrictParserValidator.is_admin(b"comment1=x;admin=false"));
+        // A quoted value can't smuggle a field in
+        assert!(!StrictParserValidator.is_admin(b"comment1=x;userdata=\"a;admin=true;\";comment2=y"));
+    }
+
+    #[test]
+    fn mac_protected() {
+        assert!(mac_validator().is_admin(&with_mac(b"comment1=x;admin=true")));
+        assert!(!mac_validator().is_admin(&with_mac(b"comment1=x;admin=false")));
+        assert!(!mac_validator().is_admin(b"comment1=x;admin=true;0123456789abcdef"));
+        assert!(!mac_validator().is_admin(b"short"));
+    }
+
+    #[test]
+    fn mac_detects_bitflip() {
+        let mut data = with_mac(b"comment1=x;admin=false;padding=y");
+        data[0] ^= 1;
+        assert!(!mac_validator().is_admin(&data));
+    }
+}
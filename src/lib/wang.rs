@@ -0,0 +1,338 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Wang's MD4 collision attack
+//!
+//! The differential of Wang et al. ("Cryptanalysis of the Hash Functions MD4 and
+//! RIPEMD", 2005) holds if the intermediate states meet a list of bit conditions.
+//! Message modification forces all the round 1 conditions and some of round 2,
+//! the rest is left to chance.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::md4;
+use crate::timebox::{Checkpoint, Deadline, Outcome};
+
+/// A condition on one bit of an intermediate state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    /// The bit is 0
+    Zero(u32),
+    /// The bit is 1
+    One(u32),
+    /// The bit is equal to the same bit of a reference state
+    Equal(u32),
+}
+
+use Condition::*;
+
+impl Condition {
+    /// Check the condition on `value`, `reference` being the state compared to by `Equal`
+    pub fn holds(&self, value: u32, reference: u32) -> bool {
+        match *self {
+            Zero(bit) => value >> bit & 1 == 0,
+            One(bit) => value >> bit & 1 == 1,
+            Equal(bit) => (value ^ reference) >> bit & 1 == 0,
+        }
+    }
+
+    /// Modify `value` so that the condition holds
+    pub fn enforce(&self, value: u32, reference: u32) -> u32 {
+        match *self {
+            Zero(bit) => value & !(1 << bit),
+            One(bit) => value | 1 << bit,
+            Equal(bit) => value ^ ((value ^ reference) & 1 << bit),
+        }
+    }
+}
+
+/// Round 1 conditions on a1, d1, c1, b1, a2, ..., b4, with bits numbered from 0
+///
+/// `Equal` refers to the state computed by the previous step.
+pub const ROUND1_CONDITIONS: [&[Condition]; 16] = [
+    &[Equal(6)],
+    &[Zero(6), Equal(7), Equal(10)],
+    &[One(6), One(7), Zero(10), Equal(25)],
+    &[One(6), Zero(7), Zero(10), Zero(25)],
+    &[One(7), One(10), Zero(25), Equal(13)],
+    &[Zero(13), Equal(18), Equal(19), Equal(20), Equal(21), One(25)],
+    &[Equal(12), Zero(13), Equal(14), Zero(18), Zero(19), One(20), Zero(21)],
+    &[One(12), One(13), Zero(14), Equal(16), Zero(18), Zero(19), Zero(20), Zero(21)],
+    &[One(12), One(13), One(14), Zero(16), Zero(18), Zero(19), Zero(20), One(21), Equal(22), Equal(25)],
+    &[One(12), One(13), One(14), Zero(16), Zero(19), One(20), One(21), Zero(22), One(25), Equal(29)],
+    &[One(16), Zero(19), Zero(20), Zero(21), Zero(22), Zero(25), One(29), Equal(31)],
+    &[Zero(19), One(20), One(21), Equal(22), One(25), Zero(29), Zero(31)],
+    &[Zero(22), Zero(25), Equal(26), Equal(28), One(29), Zero(31)],
+    &[Zero(22), Zero(25), One(26), One(28), Zero(29), One(31)],
+    &[Equal(18), One(22), One(25), Zero(26), Zero(28), Zero(29)],
+    &[Zero(18), One(25), One(26), One(28), Zero(29)],
+];
+
+/// Round 2 conditions on a5, `Equal` refers to c4
+///
+/// In the numbering of the paper, from 1: a5,19 = c4,19, a5,26 = 1, a5,27 = 0,
+/// a5,29 = 1 and a5,32 = 1. The published collision has a5,32 = 1 and c4,32 = 0.
+pub const A5_CONDITIONS: &[Condition] = &[Equal(18), One(25), Zero(26), One(28), One(31)];
+
+/// Round 1 states, in computation order: a0, d0, c0, b0, a1, d1, c1, b1, ..., b4
+///
+/// Step `i` updates state `i` into state `i + 4`, reading states `i + 1` to `i + 3`.
+pub fn round1_states(words: &[u32; 16]) -> [u32; 20] {
+    let [a0, b0, c0, d0] = md4::INITIAL_STATE;
+    let mut states = [0u32; 20];
+    states[..4].copy_from_slice(&[a0, d0, c0, b0]);
+    for i in 0..16 {
+        states[i + 4] = round1_step(&states, i, words[i]);
+    }
+    states
+}
+
+fn round1_step(states: &[u32; 20], i: usize, word: u32) -> u32 {
+    states[i]
+        .wrapping_add(md4::f(states[i + 3], states[i + 2], states[i + 1]))
+        .wrapping_add(word)
+        .rotate_left(md4::ROUND1_SHIFTS[i % 4])
+}
+
+/// The message word making step `i` produce the state already in `states[i + 4]`
+fn round1_word(states: &[u32; 20], i: usize) -> u32 {
+    states[i + 4]
+        .rotate_right(md4::ROUND1_SHIFTS[i % 4])
+        .wrapping_sub(states[i])
+        .wrapping_sub(md4::f(states[i + 3], states[i + 2], states[i + 1]))
+}
+
+/// Compute a5, the first state of round 2
+fn a5(states: &[u32; 20], words: &[u32; 16]) -> u32 {
+    let (a4, d4, c4, b4) = (states[16], states[17], states[18], states[19]);
+    a4.wrapping_add(md4::g(b4, c4, d4))
+        .wrapping_add(words[0])
+        .wrapping_add(md4::ROUND2_CONSTANT)
+        .rotate_left(md4::ROUND2_SHIFTS[0])
+}
+
+/// List the violated round 1 conditions, as (step, condition) pairs
+pub fn round1_violations(words: &[u32; 16]) -> Vec<(usize, Condition)> {
+    let states = round1_states(words);
+    let mut violations = Vec::new();
+    for (i, conditions) in ROUND1_CONDITIONS.iter().enumerate() {
+        for condition in conditions.iter() {
+            if !condition.holds(states[i + 4], states[i + 3]) {
+                violations.push((i, *condition));
+            }
+        }
+    }
+    violations
+}
+
+/// Check the a5 conditions
+pub fn a5_holds(words: &[u32; 16]) -> bool {
+    let states = round1_states(words);
+    let a5 = a5(&states, words);
+    A5_CONDITIONS.iter().all(|condition| condition.holds(a5, states[18]))
+}
+
+/// Modify a message so that it meets all the round 1 conditions, and a5's if possible
+///
+/// Round 1 is fixed step by step: each state is corrected, then the message word is
+/// solved back from it. A wrong a5 bit is fixed by flipping the same bit of a1, which
+/// shifts m0 by the right amount, and solving m1 to m4 again so that round 1 is
+/// unchanged. Carries can make this fail, so the a5 conditions are not guaranteed.
+pub fn massage(words: &mut [u32; 16]) {
+    let mut states = round1_states(words);
+    for i in 0..16 {
+        states[i + 4] = round1_step(&states, i, words[i]);
+        for condition in ROUND1_CONDITIONS[i] {
+            states[i + 4] = condition.enforce(states[i + 4], states[i + 3]);
+        }
+        words[i] = round1_word(&states, i);
+    }
+
+    for condition in A5_CONDITIONS {
+        let a5 = a5(&states, words);
+        if !condition.holds(a5, states[18]) {
+            let bit = match *condition {
+                Zero(bit) | One(bit) | Equal(bit) => bit,
+            };
+            states[4] ^= 1 << bit;
+            for (i, word) in words.iter_mut().enumerate().take(5) {
+                *word = round1_word(&states, i);
+            }
+        }
+    }
+}
+
+/// Apply Wang's message differential
+///
+/// Δm1 = 2^31, Δm2 = 2^31 - 2^28 and Δm12 = -2^16.
+pub fn differential(words: &[u32; 16]) -> [u32; 16] {
+    let mut twin = *words;
+    twin[1] = twin[1].wrapping_add(1 << 31);
+    twin[2] = twin[2].wrapping_add(1 << 31).wrapping_sub(1 << 28);
+    twin[12] = twin[12].wrapping_sub(1 << 16);
+    twin
+}
+
+/// Two different one-block messages with the same MD4 hash
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub message1: Vec<u8>,
+    pub message2: Vec<u8>,
+    /// Number of random messages tried
+    pub attempts: u64,
+}
+
+/// Random messages tried between two deadline checks
+const ATTEMPTS_PER_CHECK: u64 = 1 << 12;
+
+/// Collision search, which can be stopped and resumed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollisionSearch {
+    /// Random messages tried so far
+    pub attempts: u64,
+}
+
+impl CollisionSearch {
+    pub fn new() -> CollisionSearch {
+        CollisionSearch { attempts: 0 }
+    }
+
+    /// Try random messages until a collision, `max_attempts` in total, or the deadline
+    ///
+    /// Both messages are one block long, so a collision of the compression function
+    /// from the initial state is a full MD4 collision: the padding blocks are the same.
+    /// There is no partial collision, so a stopped search has no best result.
+    pub fn run<R: Rng>(mut self, rng: &mut R, max_attempts: u64, deadline: &Deadline)
+                       -> Outcome<Collision, CollisionSearch>
+    {
+        let start = self.attempts;
+        while self.attempts < max_attempts {
+            if (self.attempts - start) & (ATTEMPTS_PER_CHECK - 1) == 0 && deadline.expired() {
+                break;
+            }
+            self.attempts += 1;
+            let mut words = [0u32; 16];
+            rng.fill(&mut words[..]);
+            massage(&mut words);
+            let twin = differential(&words);
+            if md4::compress(md4::INITIAL_STATE, &words) == md4::compress(md4::INITIAL_STATE, &twin) {
+                return Outcome::Complete(Collision {
+                    message1: md4::block(&words),
+                    message2: md4::block(&twin),
+                    attempts: self.attempts,
+                });
+            }
+        }
+        Outcome::Partial { best: None, state: self }
+    }
+}
+
+impl Checkpoint for CollisionSearch {
+    const KIND: &'static str = "md4-collision";
+}
+
+/// Search for an MD4 collision, trying at most `max_attempts` random messages
+pub fn find_collision<R: Rng>(rng: &mut R, max_attempts: u64) -> Option<Collision> {
+    CollisionSearch::new().run(rng, max_attempts, &Deadline::never()).best()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn random_words() -> [u32; 16] {
+        let mut words = [0u32; 16];
+        rand::thread_rng().fill(&mut words[..]);
+        words
+    }
+
+    #[test]
+    fn condition_enforce() {
+        for &condition in &[Zero(3), One(3), Equal(3)] {
+            for &(value, reference) in &[(0u32, 0u32), (0, 8), (8, 0), (8, 8), (0xffff_fff7, 0xffff_ffff)] {
+                let fixed = condition.enforce(value, reference);
+                assert!(condition.holds(fixed, reference));
+                assert_eq!(value & !8, fixed & !8);
+            }
+        }
+    }
+
+    #[test]
+    fn random_message_violates_round1() {
+        // 95 conditions: a random message meets them all with probability 2^-95
+        assert!(!round1_violations(&random_words()).is_empty());
+    }
+
+    #[test]
+    fn massage_meets_round1() {
+        for _ in 0..100 {
+            let mut words = random_words();
+            massage(&mut words);
+            assert_eq!(Vec::<(usize, Condition)>::new(), round1_violations(&words));
+        }
+    }
+
+    #[test]
+    fn massage_mostly_meets_a5() {
+        let met = (0..100)
+            .filter(|_| {
+                let mut words = random_words();
+                massage(&mut words);
+                a5_holds(&words)
+            })
+            .count();
+        assert!(met > 50, "a5 conditions met only {} times out of 100", met);
+    }
+
+    #[test]
+    fn search_stops() {
+        // 100 attempts find a collision with a very low probability
+        let outcome = CollisionSearch::new().run(&mut rand::thread_rng(), 100, &Deadline::never());
+        if let Outcome::Partial { best: None, state } = outcome {
+            assert_eq!(100, state.attempts);
+            let expired = Deadline::after(Duration::from_secs(0));
+            match state.run(&mut rand::thread_rng(), 1000, &expired) {
+                Outcome::Partial { best: None, state } => assert_eq!(100, state.attempts),
+                _ => panic!("the deadline was ignored"),
+            }
+        }
+    }
+
+    /// First collision published by Wang et al., whose MD4 hash is 5f5c1a0d71b360461b5435da9b0d807a
+    const WANG_MESSAGE: [u32; 16] = [
+        0x4d7a9c83, 0x56cb927a, 0xb9d5a578, 0x57a7a5ee, 0xde748a3c, 0xdcc366b3, 0xb683a020, 0x3b2a5d9f,
+        0xc69d71b3, 0xf9e99198, 0xd79f805e, 0xa63bb2e8, 0x45dd8e31, 0x97e31fe5, 0x2794bf08, 0xb9e8c3e9,
+    ];
+
+    #[test]
+    fn published_collision() {
+        // The published pair meets the conditions of the paper, so it checks the tables
+        assert_eq!(Vec::<(usize, Condition)>::new(), round1_violations(&WANG_MESSAGE));
+        assert!(a5_holds(&WANG_MESSAGE));
+        let twin = differential(&WANG_MESSAGE);
+        assert_eq!(0xd6cb927a, twin[1]);
+        assert_eq!(0x29d5a578, twin[2]);
+        assert_eq!(0x45dc8e31, twin[12]);
+        assert_eq!(md4::digest(&md4::block(&WANG_MESSAGE)), md4::digest(&md4::block(&twin)));
+    }
+
+    #[test]
+    fn differential_words() {
+        let words = [0u32; 16];
+        let twin = differential(&words);
+        assert_eq!([0, 0x8000_0000, 0x7000_0000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xffff_0000, 0, 0, 0], twin);
+    }
+}
@@ -15,10 +15,218 @@
 #[macro_use]
 extern crate log;
 
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use cryptopals::challenge::{ChallengeOutcome, Registration};
+use cryptopals::helper::{self, FetchConfig};
+use cryptopals::output::{self, Context, Null};
+use serde::Serialize;
+use std::error::Error;
+use std::process;
+use std::time::Duration;
+
 mod set1;
+mod set2;
+mod set4;
+mod set7;
+mod set8;
+mod analyze;
+
+/// Solutions to the cryptopals crypto challenges
+#[derive(Parser)]
+#[command(name = "cryptopals")]
+struct Cli {
+    /// Display info messages, -vv for debug messages and -vvv for trace messages
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Only read challenge inputs from the cache, never download them
+    #[arg(long, global = true)]
+    offline: bool,
+    /// Timeout of each download, in seconds
+    #[arg(long, global = true, default_value_t = 30)]
+    timeout: u64,
+    /// Retries of a download after a timeout, a connection failure or a server error
+    #[arg(long, global = true, default_value_t = 3)]
+    retries: u32,
+    /// Run every challenge when no command is given
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the selected challenges
+    Run(RunArgs),
+    /// List the solved challenges with their titles
+    List,
+    /// Compare the key size estimators on a ciphertext file
+    AnalyzeKeysize { file: String },
+    /// Measure the keystream byte biases of AES-CTR under random keys
+    AnalyzeBias {
+        #[arg(default_value_t = 1 << 16)]
+        samples: usize,
+    },
+    /// Export the n-gram model of the English corpus as JSON
+    ExportModel {
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+    /// Compare the frequencies of corpus samples with the whole corpus
+    AnalyzeSample,
+    /// Fit the weights of the English scorers and save them in the configuration
+    Calibrate,
+    /// Check the primitives against test vectors and run quick attacks
+    SelfTest,
+    /// Inspect or empty the download cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show the number and size of the cached files
+    Stats,
+    /// Remove the cached files
+    Clean,
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// `all` runs every challenge, like giving no selection
+    #[arg(value_parser = ["all"], conflicts_with_all = ["set", "challenge"])]
+    all: Option<String>,
+    /// Run the challenges of this set
+    #[arg(long)]
+    set: Option<u32>,
+    /// Run only this challenge
+    #[arg(long)]
+    challenge: Option<u32>,
+    /// Print the narration, or only the outcomes as JSON
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+/// Output format of the runner
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// Outcome of one challenge in the JSON output
+#[derive(Serialize)]
+struct Report {
+    set: u32,
+    challenge: u32,
+    #[serde(flatten)]
+    outcome: Option<ChallengeOutcome>,
+    error: Option<String>,
+}
+
+/// The solved sets, with their challenges
+const SETS: [&[Registration]; 5] = [
+    set1::CHALLENGES,
+    set2::CHALLENGES,
+    set4::CHALLENGES,
+    set7::CHALLENGES,
+    set8::CHALLENGES,
+];
+
+/// Every solved challenge, in order
+fn registry() -> impl Iterator<Item = &'static Registration> {
+    SETS.iter().flat_map(|challenges| challenges.iter())
+}
+
+/// Challenges matching the selection, in order
+fn select(set: Option<u32>, challenge: Option<u32>) -> Result<Vec<&'static Registration>, String> {
+    if let Some(set) = set {
+        if !registry().any(|registration| registration.set == set) {
+            return Err(format!("set {} is not solved", set));
+        }
+    }
+    let selected: Vec<&Registration> = registry()
+        .filter(|registration| set.is_none_or(|set| set == registration.set))
+        .filter(|registration| challenge.is_none_or(|challenge| challenge == registration.number))
+        .collect();
+    match (set, challenge) {
+        (Some(set), Some(challenge)) if selected.is_empty() =>
+            Err(format!("challenge {} is not part of set {}", challenge, set)),
+        (None, Some(challenge)) if selected.is_empty() =>
+            Err(format!("challenge {} is not solved", challenge)),
+        _ => Ok(selected),
+    }
+}
+
+fn run(ctx: &Context, set: Option<u32>, challenge: Option<u32>, format: Format) -> Result<(), Box<dyn Error>> {
+    let selected = select(set, challenge)?;
+    // Download the missing inputs all at once, the challenges retry on failure
+    match helper::prefetch_all() {
+        Ok(0) => {}
+        Ok(count) => info!("Prefetched {} challenge files", count),
+        Err(error) => warn!("Prefetching the challenge files failed: {}", error),
+    }
+    let mut reports = Vec::new();
+    for registration in selected {
+        match (registration.run(ctx), format) {
+            (Err(error), Format::Text) => eprintln!("An error happened: {}", error),
+            (Ok(_), Format::Text) => {}
+            (result, Format::Json) => {
+                let (outcome, error) = match result {
+                    Ok(outcome) => (Some(outcome), None),
+                    Err(error) => (None, Some(error.to_string())),
+                };
+                reports.push(Report { set: registration.set, challenge: registration.number, outcome, error });
+            }
+        }
+    }
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+    Ok(())
+}
 
 fn main() {
-    env_logger::init();
+    let cli = Cli::parse();
+
+    cryptopals::logging::init(cli.verbose);
+    helper::set_offline(cli.offline);
+    helper::set_fetch_config(FetchConfig::default()
+        .with_timeout(Duration::from_secs(cli.timeout))
+        .with_retries(cli.retries));
+
+    // The JSON outcomes go to the standard output, the narration only where asked
+    let json = matches!(&cli.command, Some(Command::Run(RunArgs { format: Format::Json, .. })));
+    let ctx = match Context::from_env() {
+        Ok(_) if json && std::env::var(output::OUTPUT_ENV).is_err() => Context::new(Box::new(Null)),
+        Ok(ctx) => ctx,
+        Err(error) => {
+            eprintln!("An error happened: {}", error);
+            process::exit(1);
+        }
+    };
 
-    set1::main();
+    let result = match cli.command {
+        None => run(&ctx, None, None, Format::Text),
+        Some(Command::Run(args)) => run(&ctx, args.set, args.challenge, args.format),
+        Some(Command::List) => {
+            for registration in registry() {
+                println!("{:>2}/{:<2} {}", registration.set, registration.number, registration.title);
+            }
+            Ok(())
+        }
+        Some(Command::AnalyzeKeysize { file }) => analyze::keysize(&ctx, &file),
+        Some(Command::AnalyzeBias { samples }) => analyze::bias(&ctx, samples),
+        Some(Command::ExportModel { n: 0 }) => Err("n-grams must be at least one character long".into()),
+        Some(Command::ExportModel { n }) => analyze::export_model(n),
+        Some(Command::AnalyzeSample) => analyze::corpus_sample(&ctx),
+        Some(Command::Calibrate) => analyze::calibrate(&ctx),
+        Some(Command::SelfTest) => analyze::self_test(&ctx),
+        Some(Command::Cache { action: CacheAction::Stats }) => analyze::cache_stats(&ctx),
+        Some(Command::Cache { action: CacheAction::Clean }) => analyze::cache_clean(&ctx),
+    };
+    if let Err(error) = result {
+        eprintln!("An error happened: {}", error);
+        process::exit(1);
+    }
 }
@@ -12,27 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cryptopals::helper;
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
 use cryptopals::crypto::{HexString, BytesCrypto};
+use std::error::Error;
 
-// Set 1 / Challenge 1
-pub fn main() {
-    helper::section("Set 1 / Challenge 1");
-    println!("Solving https://cryptopals.com/sets/1/challenges/1:\nConvert hex to base64\n");
-
-    let input = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
-    let output = "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t";
-
-    match input.hex2bytes() {
-        Ok(bytes) => {
-            let x = bytes.base64_encode();
-
-            println!("Base64({}) = {}", input, x);
-            println!("String translation: {}", input.hex2string().unwrap());
-            assert_eq!(output, x);
-        }
-        Err(e) => {
-            println!("'{}' is an invalid hex string: {}", input, e)
-        }
+pub struct Challenge1;
+
+impl Challenge for Challenge1 {
+    fn number(&self) -> u32 {
+        1
+    }
+
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 1");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/1:\nConvert hex to base64\n");
+
+        let input = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
+        let output = "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t";
+
+        let bytes = input.hex2bytes()?;
+        let x = bytes.base64_encode();
+
+        outln!(ctx, "Base64({}) = {}", input, x);
+        ctx.value("String translation", &input.hex2string()?);
+        assert_eq!(output, x);
+
+        Ok(ChallengeOutcome::default().with_plaintext(&bytes).with_answer(&x))
     }
 }
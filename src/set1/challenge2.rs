@@ -12,23 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cryptopals::helper;
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
 use cryptopals::crypto::{HexString, BytesCrypto};
+use std::error::Error;
 
-// Set 1 / Challenge 2
-pub fn main() {
-    helper::section("Set 1 / Challenge 2");
-    println!("Solving https://cryptopals.com/sets/1/challenges/2:\nFixed XOR\n");
+pub struct Challenge2;
 
-    let input1 = "1c0111001f010100061a024b53535009181c";
-    let input2 = "686974207468652062756c6c277320657965";
-    let expected_output = "746865206b696420646f6e277420706c6179";
+impl Challenge for Challenge2 {
+    fn number(&self) -> u32 {
+        2
+    }
 
-    let output = input1.hex2bytes().unwrap().xor(
-        &input2.hex2bytes().unwrap(),
-    ).bytes2hex();
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 2");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/2:\nFixed XOR\n");
 
-    println!("{} ^ {} = {}", input1, input2, output);
-    println!("String translation = {}", output.hex2string().unwrap());
-    assert_eq!(expected_output, output);
+        let input1 = "1c0111001f010100061a024b53535009181c";
+        let input2 = "686974207468652062756c6c277320657965";
+        let expected_output = "746865206b696420646f6e277420706c6179";
+
+        let xored = input1.hex2bytes()?.xor(&input2.hex2bytes()?);
+        let output = xored.bytes2hex();
+
+        outln!(ctx, "{} ^ {} = {}", input1, input2, output);
+        outln!(ctx, "String translation = {}", output.hex2string()?);
+        assert_eq!(expected_output, output);
+
+        Ok(ChallengeOutcome::default().with_plaintext(&xored).with_answer(&output))
+    }
 }
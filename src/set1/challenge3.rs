@@ -14,22 +14,33 @@
 
 extern crate reqwest;
 
-use cryptopals::{helper, english, crypto};
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::{english, crypto};
 use cryptopals::crypto::HexString;
 use std::error::Error;
 
-pub fn main() -> Result<(), Box<dyn Error>> {
-    helper::section("Set 1 / Challenge 3");
-    println!("Solving https://cryptopals.com/sets/1/challenges/3:\nSingle-byte XOR cipher\n");
+pub struct Challenge3;
 
-    let input = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
+impl Challenge for Challenge3 {
+    fn number(&self) -> u32 {
+        3
+    }
 
-    let corpus_freq = english::get_english_frequency()?;
-    let input_bytes = input.hex2bytes().unwrap();
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 3");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/3:\nSingle-byte XOR cipher\n");
 
-    let (solution, key, _, _) = crypto::decrypt_text(&input_bytes, &corpus_freq);
+        let input = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
 
-    println!("XOR character = '{}', string = '{}'", key as char, solution);
+        let corpus_freq = english::get_english_frequency(&english::CorpusSource::default())?;
+        let input_bytes = input.hex2bytes().unwrap();
 
-    Ok(())
+        let (solution, key, _, pearson) = crypto::decrypt_english(&input_bytes, &corpus_freq);
+
+        outln!(ctx, "XOR character = '{}', string = '{}'", key as char, solution);
+
+        Ok(ChallengeOutcome::default().with_key(&[key]).with_plaintext(solution.as_bytes()).with_score(pearson))
+    }
 }
@@ -14,50 +14,89 @@
 
 extern crate reqwest;
 
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
 use cryptopals::{helper, english, crypto};
-use cryptopals::crypto::HexString;
+use cryptopals::crypto::{Candidate, HexString};
+use cryptopals::scoring::{Ngrams, Scorer};
 use std::error::Error;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 const CHALLENGE4_FILE: &str = "https://cryptopals.com/static/challenge-data/4.txt";
+/// Best keys of every line compared with the n-grams
+const LINE_CANDIDATES: usize = 3;
 
-pub fn main() -> Result<(), Box<dyn Error>> {
-    helper::section("Set 1 / Challenge 4");
-    println!("Solving https://cryptopals.com/sets/1/challenges/4:\nDetect single-character XOR\n");
+pub struct Challenge4;
 
-    let corpus_freq = english::get_english_frequency()?;
+/// A line of the input file with its best keys
+type RankedLine<'a> = (&'a str, Vec<Candidate>);
 
-    let inputs = helper::read_from_url(CHALLENGE4_FILE)?;
+/// Best keys of every hexadecimal line, on all the cores with the `parallel` feature
+fn rank_lines<'a>(inputs: &'a str, corpus_freq: &[f64]) -> Result<Vec<RankedLine<'a>>, Box<dyn Error>> {
+    let lines: Vec<&str> = inputs.lines().collect();
+    #[cfg(not(feature = "parallel"))]
+    let lines = lines.into_iter();
+    #[cfg(feature = "parallel")]
+    let lines = lines.into_par_iter();
+    // Errors are sent between threads as messages
+    let ranked: Result<Vec<_>, String> = lines
+        .map(|input| {
+            let input_bytes = input.hex2bytes().map_err(|e| e.to_string())?;
+            let mut candidates = crypto::rank_candidates(&input_bytes, corpus_freq);
+            candidates.truncate(LINE_CANDIDATES);
+            Ok((input, candidates))
+        })
+        .collect();
+    Ok(ranked?)
+}
 
-    let mut best_euclidean_score = f64::INFINITY;
-    let mut best_pearson_score = f64::NEG_INFINITY;
-    let mut best_xor = 0;
-    let mut best_input = String::new();
-    let mut best_string = String::new();
+impl Challenge for Challenge4 {
+    fn number(&self) -> u32 {
+        4
+    }
 
-    for input in inputs.lines() {
-        debug!("Analyzing candidate '{}…", input);
-        let input_bytes = input.hex2bytes().unwrap();
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 4");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/4:\nDetect single-character XOR\n");
 
-        let (xored_string, xor, euclidean_score, pearson_score) =
-            crypto::decrypt_text(&input_bytes, &corpus_freq);
+        let corpus_freq = english::get_english_frequency(&english::CorpusSource::default())?;
 
-        if euclidean_score < best_euclidean_score {
-            best_euclidean_score = euclidean_score;
-            best_xor = xor;
-            best_input = input.into();
-            best_string = xored_string;
-            debug!(" - Best Euclidean score!");
-        }
-        if pearson_score > best_pearson_score {
-            best_pearson_score = pearson_score;
-            debug!(" - Best Pearson score!");
-        }
-    }
+        let inputs = helper::read_from_url(CHALLENGE4_FILE)?;
 
-    println!("Input = '{}', XOR character = '{}'.", best_input, best_xor as char);
-    println!("Output = {}", best_string);
+        // Candidates are only 30 characters long, too short for their letter
+        // frequencies to tell the English line from the others
+        let ngrams = Ngrams::english()?;
 
-    Ok(())
-}
+        let mut best_ngram_score = f64::NEG_INFINITY;
+        let mut best_xor = 0;
+        let mut best_input = String::new();
+        let mut best_string = String::new();
 
+        for (input, candidates) in rank_lines(&inputs, &corpus_freq)? {
+            debug!("Analyzing candidate '{}…", input);
 
+            // The n-grams also decide between the best keys of the line
+            for candidate in candidates {
+                let ngram_score = ngrams.score(candidate.plaintext.as_bytes());
+                if ngram_score > best_ngram_score {
+                    best_ngram_score = ngram_score;
+                    best_xor = candidate.key;
+                    best_input = input.into();
+                    best_string = candidate.plaintext;
+                    debug!(" - Best n-gram score!");
+                }
+            }
+        }
+
+        outln!(ctx, "Input = '{}', XOR character = '{}'.", best_input, best_xor as char);
+        outln!(ctx, "Output = {}", best_string);
+
+        Ok(ChallengeOutcome::default()
+            .with_key(&[best_xor])
+            .with_plaintext(best_string.as_bytes())
+            .with_answer(&best_input)
+            .with_score(best_ngram_score))
+    }
+}
@@ -13,23 +13,33 @@
 // limitations under the License.
 
 
-use cryptopals::helper;
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
 use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
 
-pub fn main() {
-    helper::section("Set 1 / Challenge 5");
-    println!("Solving https://cryptopals.com/sets/1/challenges/5:\nImplement repeating-key XOR\n");
+pub struct Challenge5;
 
-    let input = "Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
-    let xor = "ICE";
-    let expected_output = "0b3637272a2b2e63622c2e69692a23693a2a3c6324202d623d63343c2a26226324272765272a282b2f20430a652e2c652a3124333a653e2b2027630c692b20283165286326302e27282f";
+impl Challenge for Challenge5 {
+    fn number(&self) -> u32 {
+        5
+    }
 
-    let output = input.bytes().zip(xor.bytes().cycle())
-        .map(|(a, b)| a ^ b)
-        .collect::<Vec<u8>>()
-        .bytes2hex();
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 5");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/5:\nImplement repeating-key XOR\n");
 
-    println!("Input:\n{}", input);
-    println!("ICE xored output:\n{}", output);
-    assert_eq!(expected_output, output);
+        let input = "Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
+        let xor = "ICE";
+        let expected_output = "0b3637272a2b2e63622c2e69692a23693a2a3c6324202d623d63343c2a26226324272765272a282b2f20430a652e2c652a3124333a653e2b2027630c692b20283165286326302e27282f";
+
+        let output = input.as_bytes().xor_repeating(xor.as_bytes()).bytes2hex();
+
+        outln!(ctx, "Input:\n{}", input);
+        outln!(ctx, "ICE xored output:\n{}", output);
+        assert_eq!(expected_output, output);
+
+        Ok(ChallengeOutcome::default().with_key(xor.as_bytes()).with_answer(&output))
+    }
 }
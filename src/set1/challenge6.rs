@@ -13,110 +13,55 @@
 // limitations under the License.
 
 
-use cryptopals::{helper, english, crypto};
-use cryptopals::crypto::{HexString, BytesCrypto};
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::{helper, english, attacks};
+use cryptopals::crypto::HexString;
+use cryptopals::keysize::{AverageBlocksHamming, FirstBlocksHamming, KeysizeEstimator};
 use std::error::Error;
 use std::ops::Range;
-use std::collections::HashSet;
 
 const CHALLENGE6_FILE: &str = "https://cryptopals.com/static/challenge-data/6.txt";
 const KEYSIZE_RANGE: Range<usize> = 2..42;
+/// Confidence below which a decrypted byte is not shown
+const MIN_CONFIDENCE: f64 = 0.1;
 
-pub fn main() -> Result<(), Box<dyn Error>> {
-    helper::section("Set 1 / Challenge 6");
-    println!("Solving https://cryptopals.com/sets/1/challenges/6:\nBreak repeating-key XOR\n");
+pub struct Challenge6;
 
-    let input = helper::read_from_url(CHALLENGE6_FILE)?.base64_decode()?;
-
-    // let raw_output = String::from_utf8(input)?;
-    // println!("Encrypted input:\n{:?}", raw_output);
-
-    // Guess Key size
-    let mut keysize_distances: Vec<(usize, f64)> = Vec::with_capacity(KEYSIZE_RANGE.len());
-    let mut keysize_distances2: Vec<(usize, f64)> = Vec::with_capacity(KEYSIZE_RANGE.len());
-    for keysize in KEYSIZE_RANGE {
-        // Compute hamming distance between the first 2 blocks of length "keysize"
-        keysize_distances.push(
-            (keysize,
-             (input[0..keysize].hamming_distance(&input[keysize..(2 * keysize)])) as f64
-                 / (keysize as f64)));
-
-        // Compute hamming distance between the first 4 blocks of length "keysize", take the average
-        let mut sum = 0.0;
-        for i in 0..3 {
-            sum += input[0..keysize]
-                .hamming_distance(&input[((i + 1) * keysize)..((i + 2) * keysize)]) as f64;
-        }
-        sum /= 3.0 * (keysize as f64);
-        keysize_distances2.push((keysize, sum));
-    }
-    // We now have the hamming distances for a variety of key sizes, computed by two different methods
-    keysize_distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-    keysize_distances2.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-    println!("Keysize scores 1: {:?}",
-             keysize_distances.iter()
-                 .map(|(k, v)| format!("({}: {:.3})", *k, *v))
-                 .collect::<Vec<_>>().join(", "));
-    println!("Keysize scores 2: {:?}",
-             keysize_distances2.iter()
-                 .map(|(k, v)| format!("({}: {:.3})", *k, *v))
-                 .collect::<Vec<_>>().join(", "));
-
-    // Keep union for best keysizes
-    let mut keysize_set: HashSet<usize> = keysize_distances[0..3].iter().map(|k| k.0).collect();
-    for keysize in &keysize_distances2[0..3] {
-        keysize_set.insert(keysize.0);
+impl Challenge for Challenge6 {
+    fn number(&self) -> u32 {
+        6
     }
-    let keysizes: Vec<_> = keysize_set.iter().cloned().collect();
-    println!("Most popular key sizes from first 2*3 entries: {:?}", keysizes);
 
-    // Prepare the english corpus frequency table
-    let corpus_freq = english::get_english_frequency()?;
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 6");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/6:\nBreak repeating-key XOR\n");
 
-    let mut best_euclidean_score = f64::INFINITY;
-    let mut best_key = String::new();
-    let mut best_text = String::new();
+        let input = helper::read_from_url(CHALLENGE6_FILE)?.base64_decode()?;
 
-    // Now try to:
-    // - guess a key for each key size
-    // - decrypt input
-    // - compute the euclidean distance of the decrypted text's frequency table to the English corpus
-    // - keep the key / key size with the best result
-    for keysize in keysizes {
-        println!("Trying keysize = {}", keysize);
-        let mut transposed_strings: Vec<String> = vec![String::with_capacity(input.len() / keysize); keysize];
-        for idx_char in input.iter().cloned().enumerate() {
-            transposed_strings[idx_char.0 % keysize].push(idx_char.1 as char);
+        // Show the key sizes the Hamming distance estimators prefer
+        for estimator in [&FirstBlocksHamming as &dyn KeysizeEstimator, &AverageBlocksHamming { blocks: 4 }] {
+            outln!(ctx, "Keysize scores {}: {:?}", estimator.name(),
+                     estimator.rank(&input, KEYSIZE_RANGE).iter()
+                         .map(|(k, v)| format!("({}: {:.3})", *k, *v))
+                         .collect::<Vec<_>>().join(", "));
         }
 
-        let mut full_key = String::new();
-        for string in transposed_strings {
-            let (_, key, _, _) =
-                crypto::decrypt_text(string.as_bytes(), &corpus_freq);
-            full_key.push(key as char);
-        }
-        println!("Candidate key found: '{}'", full_key);
+        // Prepare the english corpus frequency table
+        let corpus_freq = english::get_english_frequency(&english::CorpusSource::default())?;
 
-        // Now decode all text
-        let output = input.iter()
-            .zip(full_key.bytes().cycle())
-            .map(|(a, b)| a ^ b)
-            .collect::<Vec<u8>>();
-        let text = String::from_utf8(output)?;
+        // Break each column of the best key sizes as a single-byte XOR, then
+        // fix the bytes which make the whole text less English
+        let (best_key, best_text) = attacks::break_repeating_key_xor(
+            &input, KEYSIZE_RANGE, &attacks::english_scorer(&corpus_freq));
 
-        let euclidean_distance = english::euclidean_distance(
-            &english::calc_frequencies(&text),
-            &corpus_freq
-        );
-        if euclidean_distance < best_euclidean_score {
-            best_euclidean_score = euclidean_distance;
-            best_key = full_key;
-            best_text = text;
-        }
-    }
+        outln!(ctx, "Best key: '{}'", String::from_utf8_lossy(&best_key));
+        // Bytes decrypted by a doubtful key byte are shown as '?'
+        let partial = attacks::repeating_xor_confidence(&input, &best_key, &corpus_freq);
+        outln!(ctx, "{:.0}% of the text decrypted with confidence", 100.0 * partial.coverage(MIN_CONFIDENCE));
+        outln!(ctx, "Full text:\n{}", partial.render(MIN_CONFIDENCE));
 
-    println!("Best key: '{}'", best_key);
-    println!("Full text:\n{}", best_text);
-
-    Ok(())
+        Ok(ChallengeOutcome::default().with_key(&best_key).with_plaintext(&best_text))
+    }
 }
@@ -13,30 +13,35 @@
 // limitations under the License.
 
 
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
 use cryptopals::helper;
-use cryptopals::crypto::HexString;
+use cryptopals::crypto::{Aes128Ecb, HexString};
 use std::error::Error;
-use aes::Aes128;
-use block_modes::{BlockMode, Ecb};
-use block_modes::block_padding::Pkcs7;
-
-// create an alias for convenience
-type Aes128Ecb = Ecb<Aes128, Pkcs7>;
 
 const CHALLENGE7_FILE: &str = "https://cryptopals.com/static/challenge-data/7.txt";
 const KEY: &str = "YELLOW SUBMARINE";
 
-pub fn main() -> Result<(), Box<dyn Error>> {
-    helper::section("Set 1 / Challenge 7");
-    println!("Solving https://cryptopals.com/sets/1/challenges/7:\nAES in ECB mode\n");
+pub struct Challenge7;
+
+impl Challenge for Challenge7 {
+    fn number(&self) -> u32 {
+        7
+    }
+
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 7");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/7:\nAES in ECB mode\n");
 
-    let key = KEY.as_bytes();
-    let ciphertext = helper::read_from_url(CHALLENGE7_FILE)?.base64_decode()?;
+        let key = KEY.as_bytes();
+        let ciphertext = helper::read_from_url(CHALLENGE7_FILE)?.base64_decode()?;
 
-    let cipher = Aes128Ecb::new_var(key, Default::default())?;
-    let decrypted_ciphertext = cipher.decrypt_vec(&ciphertext)?;
+        let cipher = Aes128Ecb::new(key)?;
+        let decrypted_ciphertext = cipher.decrypt(&ciphertext)?;
 
-    println!("Decrypted AES EBC ciphertext:\n{}", String::from_utf8(decrypted_ciphertext)?);
+        outln!(ctx, "Decrypted AES EBC ciphertext:\n{}", String::from_utf8(decrypted_ciphertext.clone())?);
 
-    Ok(())
+        Ok(ChallengeOutcome::default().with_key(key).with_plaintext(&decrypted_ciphertext))
+    }
 }
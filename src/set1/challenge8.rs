@@ -0,0 +1,66 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::{helper, stats};
+use cryptopals::crypto::HexString;
+use std::error::Error;
+
+const CHALLENGE8_FILE: &str = "https://cryptopals.com/static/challenge-data/8.txt";
+const BLOCK_SIZE: usize = 16;
+
+pub struct Challenge8;
+
+impl Challenge for Challenge8 {
+    fn number(&self) -> u32 {
+        8
+    }
+
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 1 / Challenge 8");
+        outln!(ctx, "Solving https://cryptopals.com/sets/1/challenges/8:\nDetect AES in ECB mode\n");
+
+        let inputs = helper::read_from_url(CHALLENGE8_FILE)?;
+
+        // ECB encrypts identical plaintext blocks to identical ciphertext blocks
+        let mut best_line = 0;
+        let mut best_duplicates = 0;
+        let mut best_ciphertext = Vec::new();
+        for (line, input) in inputs.lines().enumerate() {
+            let ciphertext = input.hex2bytes()?;
+            let duplicates = helper::count_duplicate_blocks(&ciphertext, BLOCK_SIZE);
+            debug!("Line {}: {} duplicate blocks", line + 1, duplicates);
+            if duplicates > best_duplicates {
+                best_line = line + 1;
+                best_duplicates = duplicates;
+                best_ciphertext = ciphertext;
+            }
+        }
+
+        if best_duplicates == 0 {
+            outln!(ctx, "No ciphertext with repeated blocks found.");
+            return Ok(ChallengeOutcome::default());
+        }
+        outln!(ctx, "Line {} has {} repeated blocks, it is probably ECB encrypted:", best_line, best_duplicates);
+        outln!(ctx, "{}", helper::format_blocks(&best_ciphertext, BLOCK_SIZE).trim_end());
+        // Repeated blocks also lower the entropy, random bytes are close to 8 bits
+        outln!(ctx, "Entropy: {:.2} bits per byte", stats::entropy(&best_ciphertext));
+
+        Ok(ChallengeOutcome::default().with_answer(&best_line.to_string()))
+    }
+}
@@ -14,28 +14,53 @@
 
 // Set 1
 
-mod challenge1;
-mod challenge2;
-mod challenge3;
-mod challenge4;
-mod challenge5;
-mod challenge6;
-mod challenge7;
-
-pub fn main() {
-    challenge1::main();
-    challenge2::main();
-    if let Err(error) = challenge3::main() {
-        eprintln!("An error happened: {}", error);
+cryptopals::register_challenges! {
+    set 1;
+    1 => challenge1::Challenge1, "Convert hex to base64";
+    2 => challenge2::Challenge2, "Fixed XOR";
+    3 => challenge3::Challenge3, "Single-byte XOR cipher";
+    4 => challenge4::Challenge4, "Detect single-character XOR";
+    5 => challenge5::Challenge5, "Implement repeating-key XOR";
+    6 => challenge6::Challenge6, "Break repeating-key XOR";
+    7 => challenge7::Challenge7, "AES in ECB mode";
+    8 => challenge8::Challenge8, "Detect AES in ECB mode";
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cryptopals::challenge::{ChallengeOutcome, Registration};
+    use cryptopals::output::{Capture, Context, Null};
+
+    fn registration(challenge: u32) -> &'static Registration {
+        CHALLENGES.iter().find(|registration| registration.number == challenge).unwrap()
+    }
+
+    fn outcome(challenge: u32) -> ChallengeOutcome {
+        registration(challenge).run(&Context::new(Box::new(Null))).unwrap()
     }
-    if let Err(error) = challenge4::main() {
-        eprintln!("An error happened: {}", error);
+
+    #[test]
+    fn challenge1() {
+        let outcome = outcome(1);
+        assert_eq!(Some("SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t"), outcome.answer.as_deref());
+        assert_eq!(Some("I'm killing your brain like a poisonous mushroom"), outcome.text());
     }
-    challenge5::main();
-    if let Err(error) = challenge6::main() {
-        eprintln!("An error happened: {}", error);
+
+    #[test]
+    fn challenge1_report() {
+        let capture = Capture::new();
+        registration(1).run(&Context::new(Box::new(capture.clone()))).unwrap();
+        assert_eq!(Some("I'm killing your brain like a poisonous mushroom".to_owned()), capture.value("String translation"));
     }
-    if let Err(error) = challenge7::main() {
-        eprintln!("An error happened: {}", error);
+
+    #[test]
+    fn challenge2() {
+        assert_eq!(Some("the kid don't play"), outcome(2).text());
+    }
+
+    #[test]
+    fn challenge5() {
+        assert_eq!(Some(b"ICE".to_vec()), outcome(5).key);
     }
 }
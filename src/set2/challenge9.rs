@@ -0,0 +1,43 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::blocks;
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+
+pub struct Challenge9;
+
+impl Challenge for Challenge9 {
+    fn number(&self) -> u32 {
+        9
+    }
+
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 2 / Challenge 9");
+        outln!(ctx, "Solving https://cryptopals.com/sets/2/challenges/9:\nImplement PKCS#7 padding\n");
+
+        let input = b"YELLOW SUBMARINE";
+        let expected_output = b"YELLOW SUBMARINE\x04\x04\x04\x04";
+
+        let padded = blocks::pkcs7_pad(input, 20);
+
+        outln!(ctx, "\"{}\" padded to 20 bytes = {}", String::from_utf8_lossy(input), padded.bytes2hex());
+        assert_eq!(expected_output.to_vec(), padded);
+
+        Ok(ChallengeOutcome::default().with_plaintext(&padded).with_answer(&padded.bytes2hex()))
+    }
+}
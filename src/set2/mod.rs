@@ -0,0 +1,32 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Set 2
+
+cryptopals::register_challenges! {
+    set 2;
+    9 => challenge9::Challenge9, "Implement PKCS#7 padding";
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cryptopals::output::{Context, Null};
+
+    #[test]
+    fn challenge9() {
+        let outcome = CHALLENGES[0].run(&Context::new(Box::new(Null))).unwrap();
+        assert_eq!(Some(b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec()), outcome.plaintext);
+    }
+}
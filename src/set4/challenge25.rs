@@ -0,0 +1,53 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::challenge::{Challenge, ChallengeOutcome};
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::helper;
+use cryptopals::crypto::{Aes128Ecb, HexString};
+use cryptopals::ctredit::{self, EditOracle};
+use std::error::Error;
+
+const CHALLENGE25_FILE: &str = "https://cryptopals.com/static/challenge-data/25.txt";
+/// Key of the ECB encrypted file, the same as challenge 7
+const ECB_KEY: &[u8] = b"YELLOW SUBMARINE";
+
+pub struct Challenge25;
+
+impl Challenge for Challenge25 {
+    fn number(&self) -> u32 {
+        25
+    }
+
+    fn run(&self, ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+        ctx.section("Set 4 / Challenge 25");
+        outln!(ctx, "Solving https://cryptopals.com/sets/4/challenges/25:\nBreak \"random access read/write\" AES CTR\n");
+
+        let ecb_ciphertext = helper::read_from_url(CHALLENGE25_FILE)?.base64_decode()?;
+        let plaintext = Aes128Ecb::new(ECB_KEY)?.decrypt(&ecb_ciphertext)?;
+
+        let oracle = EditOracle::new();
+        let ciphertext = oracle.encrypt(&plaintext);
+        let recovered = ctredit::recover_plaintext(&ciphertext, |c, offset, newtext| oracle.edit(c, offset, newtext))?;
+        if recovered != plaintext {
+            return Err("the recovered plaintext does not match".into());
+        }
+
+        outln!(ctx, "Recovered plaintext through edit():\n{}", String::from_utf8_lossy(&recovered));
+
+        Ok(ChallengeOutcome::default().with_plaintext(&recovered))
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::cbcmac;
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+
+const KEY: &str = "YELLOW SUBMARINE";
+const SNIPPET: &str = "alert('MZA who was that?');\n";
+const FORGED_SNIPPET: &str = "alert('Ayo, the Wu is back!');//";
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 50");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/50:\nHashing with CBC-MAC\n");
+
+    let key = KEY.as_bytes();
+    let iv = [0u8; cbcmac::BLOCK_SIZE];
+
+    let hash = cbcmac::cbc_mac(key, &iv, SNIPPET.as_bytes())?;
+    outln!(ctx, "CBC-MAC({:?}) = {}", SNIPPET, hash.bytes2hex());
+
+    // The padding and the stitched block must not contain a newline, or the
+    // rest of the snippet would escape the JavaScript comment. The tail of
+    // the original snippet ends with one, so only these bytes are checked.
+    // Add spaces to the prefix until we get a usable block.
+    let mut prefix = FORGED_SNIPPET.to_owned();
+    let forged = loop {
+        let prefix_mac = cbcmac::cbc_mac(key, &iv, prefix.as_bytes())?;
+        let forged = cbcmac::stitch(prefix.as_bytes(), &prefix_mac, SNIPPET.as_bytes());
+        let stitched_end = cbcmac::pkcs7_pad(prefix.as_bytes()).len() + cbcmac::BLOCK_SIZE;
+        if !forged[prefix.len()..stitched_end].iter().any(|&b| b == b'\n' || b == b'\r') {
+            break forged;
+        }
+        debug!("Stitched block contains a newline, retrying");
+        prefix.insert(0, ' ');
+    };
+
+    let forged_hash = cbcmac::cbc_mac(key, &iv, &forged)?;
+    outln!(ctx, "Forged snippet: {}", String::from_utf8_lossy(&forged));
+    outln!(ctx, "CBC-MAC(forged snippet) = {}", forged_hash.bytes2hex());
+    assert_eq!(hash, forged_hash);
+
+    Ok(ChallengeOutcome::default().with_plaintext(&forged).with_answer(&forged_hash.bytes2hex()))
+}
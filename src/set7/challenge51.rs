@@ -0,0 +1,37 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::compression::{self, CompressionOracle, Mode};
+use std::error::Error;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 51");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/51:\nCompression Ratio Side-Channel Attacks\n");
+
+    let mut recovered = String::new();
+    for &mode in &[Mode::Ctr, Mode::Cbc] {
+        let oracle = CompressionOracle::new(mode, compression::SESSION_ID);
+        let session_id = compression::recover_session_id(|payload| oracle.length(payload))?;
+        outln!(ctx, "{:?} mode, recovered session id: {}", mode, session_id);
+        assert_eq!(compression::SESSION_ID, session_id);
+        recovered = session_id;
+    }
+
+    Ok(ChallengeOutcome::default().with_answer(&recovered))
+}
@@ -0,0 +1,56 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::mdhash;
+use cryptopals::mdhash::MdHash;
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+
+/// Cheap hash state, in bytes
+const F_STATE_LEN: usize = 2;
+/// Stronger hash state, in bytes
+const G_STATE_LEN: usize = 3;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 52");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/52:\nIterated Hash Function Multicollisions\n");
+
+    let f = MdHash::new(F_STATE_LEN);
+    let g = MdHash::new(G_STATE_LEN);
+
+    let n = 4;
+    let collisions = mdhash::multicollisions(&f, &f.initial_state(), n);
+    outln!(ctx, "{} collisions in f give {} messages with the same hash:", n, 1 << n);
+    for index in 0..(1 << n) {
+        let message = mdhash::collision_message(&collisions, index);
+        outln!(ctx, "f({}…) = {}", message[..8].bytes2hex(), f.hash(&message).bytes2hex());
+    }
+
+    outln!(ctx, "\nLooking for a collision in f || g, with f on {} bits and g on {} bits…",
+             F_STATE_LEN * 8, G_STATE_LEN * 8);
+    let (m1, m2) = mdhash::cascade_collision(&f, &g);
+    let h1 = [f.hash(&m1), g.hash(&m1)].concat();
+    let h2 = [f.hash(&m2), g.hash(&m2)].concat();
+    outln!(ctx, "Message 1 ({} blocks): f || g = {}", m1.len() / mdhash::BLOCK_SIZE, h1.bytes2hex());
+    outln!(ctx, "Message 2 ({} blocks): f || g = {}", m2.len() / mdhash::BLOCK_SIZE, h2.bytes2hex());
+    assert_ne!(m1, m2);
+    assert_eq!(h1, h2);
+
+    Ok(ChallengeOutcome::default().with_answer(&h1.bytes2hex()))
+}
@@ -0,0 +1,50 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::mdhash;
+use cryptopals::mdhash::MdHash;
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+
+/// Hash state, in bytes
+const STATE_LEN: usize = 2;
+/// The original message is 2^K blocks long
+const K: usize = 10;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 53");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/53:\nKelsey and Schneier's Expandable Messages\n");
+
+    let hash = MdHash::new(STATE_LEN);
+    let message: Vec<u8> = (0..((1 << K) * mdhash::BLOCK_SIZE))
+        .map(|i| b"Rollin' in my 5.0 "[i % 18])
+        .collect();
+    outln!(ctx, "Original message: {} blocks, hash {}", message.len() / mdhash::BLOCK_SIZE, hash.hash(&message).bytes2hex());
+
+    let forged = mdhash::second_preimage(&hash, &message, K)
+        .ok_or("message length unsuitable for the expandable message")?;
+    let differing = message.chunks(mdhash::BLOCK_SIZE).zip(forged.chunks(mdhash::BLOCK_SIZE))
+        .filter(|(a, b)| a != b)
+        .count();
+    outln!(ctx, "Second preimage: {} blocks, {} of them different, hash {}",
+             forged.len() / mdhash::BLOCK_SIZE, differing, hash.hash(&forged).bytes2hex());
+    assert_eq!(hash.hash(&message), hash.hash(&forged));
+
+    Ok(ChallengeOutcome::default().with_plaintext(&forged).with_answer(&hash.hash(&forged).bytes2hex()))
+}
@@ -0,0 +1,48 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::mdhash::{Diamond, MdHash};
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+
+/// Hash state, in bytes
+const STATE_LEN: usize = 2;
+/// Depth of the diamond structure
+const DEPTH: usize = 8;
+/// Length of the predictions, in blocks
+const PREFIX_BLOCKS: usize = 4;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 54");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/54:\nKelsey and Kohno's Nostradamus Attack\n");
+
+    let hash = MdHash::new(STATE_LEN);
+    let diamond = Diamond::build(&hash, DEPTH);
+    let prediction = diamond.prediction(&hash, PREFIX_BLOCKS);
+    outln!(ctx, "Diamond of depth {} built, published prediction hash: {}", DEPTH, prediction.bytes2hex());
+
+    let results = "Baseball results of the season: Red Sox 102, Yankees 60.";
+    let message = diamond.herd(&hash, results.as_bytes(), PREFIX_BLOCKS)
+        .ok_or("the results are too long for the prediction")?;
+    outln!(ctx, "Herded message: {:?}", String::from_utf8_lossy(&message));
+    outln!(ctx, "Hash of the herded message: {}", hash.hash(&message).bytes2hex());
+    assert_eq!(prediction, hash.hash(&message));
+
+    Ok(ChallengeOutcome::default().with_plaintext(&message).with_answer(&prediction.bytes2hex()))
+}
@@ -0,0 +1,45 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::{helper, md4, wang};
+use cryptopals::crypto::BytesCrypto;
+use std::error::Error;
+use std::time::Instant;
+
+/// Give up after this many random messages
+const MAX_ATTEMPTS: u64 = 1 << 32;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 55");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/55:\nMD4 Collisions\n");
+
+    let start = Instant::now();
+    let collision = wang::find_collision(&mut rand::thread_rng(), MAX_ATTEMPTS)
+        .ok_or("no collision found")?;
+    outln!(ctx, "Collision found after {} attempts in {:.1?}", collision.attempts, start.elapsed());
+    outln!(ctx, "{}", helper::hex_diff(&collision.message1, &collision.message2).trim_end());
+
+    let hash1 = md4::digest(&collision.message1);
+    let hash2 = md4::digest(&collision.message2);
+    outln!(ctx, "MD4 of message 1: {}", hash1.bytes2hex());
+    outln!(ctx, "MD4 of message 2: {}", hash2.bytes2hex());
+    assert_ne!(collision.message1, collision.message2);
+    assert_eq!(hash1, hash2);
+
+    Ok(ChallengeOutcome::default().with_plaintext(&collision.message2).with_answer(&hash2.bytes2hex()))
+}
@@ -0,0 +1,77 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::crypto::HexString;
+use cryptopals::rc4bias::{self, CookieAttack, CookieOracle};
+use cryptopals::timebox::{Checkpoint, Deadline, LogProgress, Outcome};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Encryptions of each request, the biases need about 2^24 to stand out
+const SAMPLES: usize = 1 << 24;
+/// Show the current guess and save a checkpoint at this interval
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
+/// Checkpoint file name, in the temporary directory
+const CHECKPOINT: &str = "cryptopals-challenge56.json";
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 7 / Challenge 56");
+    outln!(ctx, "Solving https://cryptopals.com/sets/7/challenges/56:\nRC4 Single-Byte Biases\n");
+
+    let cookie = rc4bias::COOKIE.base64_decode()?;
+    let oracle = CookieOracle::new(&cookie);
+
+    outln!(ctx, "Sending {} requests of each length, this takes a few minutes in release mode...", SAMPLES);
+    let start = Instant::now();
+    let encrypt = |request: &[u8]| oracle.encrypt(request);
+    let checkpoint = env::temp_dir().join(CHECKPOINT);
+    let cookie_len = encrypt(b"").len();
+    let mut attack = if checkpoint.exists() {
+        let attack = CookieAttack::resume_from(&checkpoint)
+            .map_err(|e| format!("invalid checkpoint {}, remove it to start over: {}", checkpoint.display(), e))?;
+        if attack.cookie_len() != cookie_len.min(rc4bias::Z32.0 + 1) {
+            return Err(format!("checkpoint {} is for a cookie of {} bytes, remove it to start over",
+                               checkpoint.display(), attack.cookie_len()).into());
+        }
+        outln!(ctx, "Resuming from {} after {} samples", checkpoint.display(), attack.samples());
+        attack
+    } else {
+        CookieAttack::new(cookie_len)
+    };
+    let progress = LogProgress::new("RC4 cookie");
+    let recovered = loop {
+        match attack.run_with_progress(encrypt, SAMPLES, &Deadline::after(PROGRESS_INTERVAL), &progress) {
+            Outcome::Complete(cookie) => break cookie,
+            Outcome::Partial { best, state } => {
+                outln!(ctx, "{:>9} samples: {:?}", state.samples(), String::from_utf8_lossy(&best.unwrap_or_default()));
+                state.save_checkpoint(&checkpoint)?;
+                attack = state;
+            }
+        }
+    };
+    // Nothing to resume any more
+    let _ = fs::remove_file(&checkpoint);
+    outln!(ctx, "Recovered cookie in {:.1?}: {:?}", start.elapsed(), String::from_utf8_lossy(&recovered));
+
+    let correct = recovered.iter().zip(cookie.iter()).filter(|(a, b)| a == b).count();
+    outln!(ctx, "{} bytes out of {} are right", correct, cookie.len());
+
+    Ok(ChallengeOutcome::default().with_plaintext(&recovered).with_score(correct as f64 / cookie.len() as f64))
+}
@@ -0,0 +1,29 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Set 7
+// Challenge 51 needs the compression feature
+
+cryptopals::register_challenges! {
+    set 7;
+    50 => challenge50::main(), "Hashing with CBC-MAC";
+    #[cfg(feature = "compression")]
+    51 => challenge51::main(), "Compression Ratio Side-Channel Attacks";
+    52 => challenge52::main(), "Iterated Hash Function Multicollisions";
+    53 => challenge53::main(), "Kelsey and Schneier's Expandable Messages";
+    54 => challenge54::main(), "Kelsey and Kohno's Nostradamus Attack";
+    55 => challenge55::main(), "MD4 Collisions";
+    56 => challenge56::main(), "RC4 Single-Byte Biases";
+}
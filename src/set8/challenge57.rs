@@ -0,0 +1,37 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::dh::{self, Bob, DhParams};
+use std::error::Error;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 57");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/57:\nDiffie-Hellman Revisited: Small Subgroup Confinement\n");
+
+    let params = DhParams::challenge57();
+    let bob = Bob::new(&params);
+    outln!(ctx, "Bob's public key: {}", bob.public_key());
+
+    let recovered = dh::small_subgroup_attack(&params, |h| bob.respond(h))
+        .ok_or("not enough small factors to recover the key")?;
+    ctx.value("Recovered private key", &recovered.to_string());
+    outln!(ctx, "Bob's private key:     {}", bob.private_key());
+    assert_eq!(bob.private_key(), &recovered);
+
+    Ok(ChallengeOutcome::default().with_answer(&recovered.to_string()))
+}
@@ -0,0 +1,57 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::dh::{self, Bob, DhParams};
+use cryptopals::dlog::Kangaroo;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::error::Error;
+use std::time::Instant;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 58");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/58:\nPollard's Method for Catching Kangaroos\n");
+
+    let params = DhParams::challenge58();
+
+    ctx.subsection("Catching indexes in an interval");
+    let indexes: [(&[u8], u32); 2] = [
+        (b"7760073848032689505395005705677365876654629189298052775754597607446617558600394076764814236081991643094239886772481052254010323780165093955236429914607119", 20),
+        (b"9388897478013399550694114614498790691034187453089355259602614074132918843899833277397448144245883225611726912025846772975325932794909655215329941809013733", 40),
+    ];
+    for (y, bits) in indexes.iter() {
+        let y = BigUint::parse_bytes(y, 10).ok_or("invalid number")?;
+        let start = Instant::now();
+        let kangaroo = Kangaroo::new(&BigUint::zero(), &(BigUint::from(1u32) << *bits));
+        match kangaroo.catch(&params.p, &params.g, &y) {
+            Some(x) => outln!(ctx, "Index in [0, 2^{}]: {} ({:.1?})", bits, x, start.elapsed()),
+            None => outln!(ctx, "Index in [0, 2^{}] not found ({:.1?})", bits, start.elapsed()),
+        }
+    }
+
+    ctx.subsection("Confining Bob's key, then catching the rest");
+    let bob = Bob::new(&params);
+    let start = Instant::now();
+    let recovered = dh::confine_and_catch(&params, &bob.public_key(), |h| bob.respond(h))
+        .ok_or("the kangaroo missed Bob's key")?;
+    outln!(ctx, "Recovered private key: {} ({:.1?})", recovered, start.elapsed());
+    outln!(ctx, "Bob's private key:     {}", bob.private_key());
+    assert_eq!(bob.private_key(), &recovered);
+
+    Ok(ChallengeOutcome::default().with_answer(&recovered.to_string()))
+}
@@ -0,0 +1,45 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::ec::{self, LadderBob, MontgomeryParams};
+use cryptopals::math;
+use std::error::Error;
+use std::time::Instant;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 60");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/60:\nSingle-Coordinate Ladders and Insecure Twists\n");
+
+    let params = MontgomeryParams::challenge60();
+    outln!(ctx, "Twist order: {}", params.twist_order());
+    outln!(ctx, "Small twist factors: {:?}", math::small_factors(&params.twist_order(), ec::TWIST_FACTOR_BOUND));
+
+    let bob = LadderBob::new(&params);
+    let public = bob.public_key();
+    outln!(ctx, "Bob's public u: {}", public);
+
+    let start = Instant::now();
+    let recovered = ec::twist_attack(&params, &public, ec::TWIST_FACTOR_BOUND, |h| bob.respond(h))
+        .ok_or("the kangaroo missed the private key")?;
+    outln!(ctx, "Recovered private key: {} ({:.1?})", recovered, start.elapsed());
+    outln!(ctx, "Bob's private key:     {}", bob.private_key());
+    // x and -x give the same u coordinates
+    assert!(*bob.private_key() == recovered || *bob.private_key() == &params.order - &recovered);
+
+    Ok(ChallengeOutcome::default().with_answer(&recovered.to_string()))
+}
@@ -0,0 +1,60 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::{ecdsa, rsa};
+use cryptopals::ec::EcParams;
+use std::error::Error;
+use std::time::Instant;
+
+const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 61");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/61:\nDuplicate-Signature Key Selection in ECDSA (and RSA)\n");
+
+    ctx.subsection("ECDSA");
+    let params = EcParams::challenge59();
+    let alice = params.generate_private();
+    let alice_public = params.public_key(&alice);
+    let signature = ecdsa::sign(&params, &alice, MESSAGE);
+    outln!(ctx, "Alice's signature: {:?}", signature);
+    assert!(ecdsa::verify(&params, &alice_public, MESSAGE, &signature));
+
+    let (forged, eve, eve_public) = ecdsa::duplicate_key(&params, &alice_public, MESSAGE, &signature)
+        .ok_or("Alice's signature does not verify")?;
+    outln!(ctx, "Eve's generator:   {:?}", forged.base);
+    outln!(ctx, "Eve's private key: {}", eve);
+    outln!(ctx, "Verifies under Eve's key: {}", ecdsa::verify(&forged, &eve_public, MESSAGE, &signature));
+    assert!(ecdsa::verify(&forged, &eve_public, MESSAGE, &signature));
+
+    ctx.subsection("RSA");
+    let key = rsa::PrivateKey::generate(1024);
+    let signature = key.sign(MESSAGE);
+    outln!(ctx, "Alice's modulus: {}", key.public_key().n);
+    assert!(key.public_key().verify(MESSAGE, &signature));
+
+    let start = Instant::now();
+    let forged = rsa::duplicate_key(key.public_key(), MESSAGE, &signature, rsa::FACTOR_BOUND)
+        .ok_or("Alice's signature does not verify")?;
+    outln!(ctx, "Eve's modulus:   {} ({:.1?})", forged.public_key().n, start.elapsed());
+    outln!(ctx, "Eve's exponent:  {}", forged.public_key().e);
+    outln!(ctx, "Verifies under Eve's key: {}", forged.public_key().verify(MESSAGE, &signature));
+    assert!(forged.public_key().verify(MESSAGE, &signature));
+
+    Ok(ChallengeOutcome::default().with_answer(&eve.to_string()))
+}
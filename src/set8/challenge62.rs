@@ -0,0 +1,54 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::ecdsa;
+use cryptopals::ec::EcParams;
+use std::error::Error;
+use std::time::Instant;
+
+/// Low bits of the nonces always set to zero
+const ZERO_BITS: u64 = 8;
+/// Enough signatures for a 128 bits order with 8 known bits per nonce
+const SIGNATURES: usize = 22;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 62");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/62:\nKey-Recovery Attacks on ECDSA with Biased Nonces\n");
+
+    let params = EcParams::challenge59();
+    let private = params.generate_private();
+    let public = params.public_key(&private);
+
+    let signatures: Vec<(Vec<u8>, ecdsa::Signature)> = (0..SIGNATURES)
+        .map(|i| {
+            let message = format!("message number {}", i).into_bytes();
+            let signature = ecdsa::sign_biased(&params, &private, &message, ZERO_BITS);
+            (message, signature)
+        })
+        .collect();
+    outln!(ctx, "Collected {} signatures with {} zero low bits in each nonce", SIGNATURES, ZERO_BITS);
+
+    let start = Instant::now();
+    let recovered = ecdsa::biased_nonce_attack(&params, &public, &signatures, ZERO_BITS)
+        .ok_or("no short vector revealed the private key")?;
+    outln!(ctx, "Recovered private key: {} ({:.1?})", recovered, start.elapsed());
+    outln!(ctx, "Private key:           {}", private);
+    assert_eq!(private, recovered);
+
+    Ok(ChallengeOutcome::default().with_answer(&recovered.to_string()))
+}
@@ -0,0 +1,65 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::crypto::BytesCrypto;
+use cryptopals::gcm::{self, Aes128Gcm, Sealed};
+use cryptopals::keygen;
+use std::error::Error;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 63");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/63:\nKey-Recovery Attacks on GCM with Repeated Nonces\n");
+
+    let mut rng = rand::thread_rng();
+    let gcm = Aes128Gcm::new(&keygen::aes_key(&mut rng))?;
+    let nonce = keygen::gcm_nonce(&mut rng);
+
+    // The server forgets to change its nonce
+    let plaintexts: [(&[u8], &[u8]); 3] = [
+        (b"Transfer 100 dollars to Alice", b"from=bank"),
+        (b"Transfer 42 dollars to Bob, for the pizza", b"from=bank"),
+        (b"Good morning!", b"from=bank"),
+    ];
+    let messages: Vec<Sealed> = plaintexts.iter().map(|(plaintext, aad)| {
+        let (ciphertext, tag) = gcm.encrypt(&nonce, plaintext, aad);
+        outln!(ctx, "Sealed {:?}, tag {}", String::from_utf8_lossy(plaintext), tag.bytes2hex());
+        Sealed { aad: aad.to_vec(), ciphertext, tag }
+    }).collect();
+
+    let candidates = gcm::forbidden_attack(&messages[..2]);
+    outln!(ctx, "\nCandidate keys from the first two messages: {}", candidates.len());
+    let candidates = gcm::forbidden_attack(&messages);
+    let h = match candidates[..] {
+        [h] => h,
+        _ => return Err(format!("{} candidate keys left", candidates.len()).into()),
+    };
+    ctx.value("Recovered authentication key", &h.to_block().bytes2hex());
+    outln!(ctx, "Actual authentication key:    {}", gcm.auth_key().to_block().bytes2hex());
+
+    // Flip the amount of the first transfer, CTR mode is malleable
+    let forged: Vec<u8> = messages[0].ciphertext.iter()
+        .zip(b"Transfer 100".iter().zip(b"Transfer 999").map(|(a, b)| a ^ b).chain(std::iter::repeat(0)))
+        .map(|(c, d)| c ^ d)
+        .collect();
+    let tag = gcm::forge(h, &messages[0], b"from=bank", &forged);
+    let plaintext = gcm.decrypt(&nonce, &forged, b"from=bank", &tag)?;
+    outln!(ctx, "Forged message accepted: {:?}", String::from_utf8_lossy(&plaintext));
+
+    Ok(ChallengeOutcome::default().with_key(&h.to_block()).with_plaintext(&plaintext))
+}
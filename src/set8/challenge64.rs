@@ -0,0 +1,42 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+
+use cryptopals::outln;
+use cryptopals::output::Context;
+use cryptopals::challenge::ChallengeOutcome;
+use cryptopals::crypto::BytesCrypto;
+use cryptopals::gcm::{self, TruncatedMacOracle};
+use std::error::Error;
+use std::time::Instant;
+
+/// The message has 2^17 blocks, whose 17 blocks multiplied by h^(2^i) can be changed
+const MESSAGE_BLOCKS: usize = 1 << 17;
+
+pub fn main(ctx: &Context) -> Result<ChallengeOutcome, Box<dyn Error>> {
+    ctx.section("Set 8 / Challenge 64");
+    outln!(ctx, "Solving https://cryptopals.com/sets/8/challenges/64:\nKey-Recovery Attacks on GCM with a Truncated MAC\n");
+
+    let oracle = TruncatedMacOracle::new(MESSAGE_BLOCKS)?;
+    outln!(ctx, "Message of {} blocks, truncated tag {}", MESSAGE_BLOCKS, oracle.tag().bytes2hex());
+
+    let start = Instant::now();
+    let h = gcm::truncated_mac_attack(&oracle).ok_or("no forgery was accepted")?;
+    outln!(ctx, "Recovered authentication key: {} ({:.1?})", h.to_block().bytes2hex(), start.elapsed());
+    outln!(ctx, "Actual authentication key:    {}", oracle.auth_key().to_block().bytes2hex());
+    assert_eq!(oracle.auth_key(), h);
+
+    Ok(ChallengeOutcome::default().with_key(&h.to_block()))
+}
@@ -0,0 +1,29 @@
+// Copyright 2020 Farzad FARID <farzy@farzy.org>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Set 8
+
+
+cryptopals::register_challenges! {
+    set 8;
+    57 => challenge57::main(), "Diffie-Hellman Revisited: Small Subgroup Confinement";
+    58 => challenge58::main(), "Pollard's Method for Catching Kangaroos";
+    59 => challenge59::main(), "Elliptic Curve Diffie-Hellman and Invalid-Curve Attacks";
+    60 => challenge60::main(), "Single-Coordinate Ladders and Insecure Twists";
+    61 => challenge61::main(), "Duplicate-Signature Key Selection in ECDSA (and RSA)";
+    62 => challenge62::main(), "Key-Recovery Attacks on ECDSA with Biased Nonces";
+    63 => challenge63::main(), "Key-Recovery Attacks on GCM with Repeated Nonces";
+    64 => challenge64::main(), "Key-Recovery Attacks on GCM with a Truncated MAC";
+}